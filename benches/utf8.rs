@@ -0,0 +1,108 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Compares decoding a Metadata response made of many strings with and without checking that
+//! they are UTF-8, see `Decoder::trusted_utf8`
+//!
+//! Run with `cargo bench --bench utf8`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use kafka_serde::Decoder;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Metadata<'a> {
+    #[serde(borrow)]
+    brokers: Vec<Broker<'a>>,
+    #[serde(borrow)]
+    topics: Vec<Topic<'a>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Broker<'a> {
+    node_id: i32,
+    host: &'a str,
+    port: i32,
+    rack: &'a str,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Topic<'a> {
+    error_code: i16,
+    name: &'a str,
+    partitions: Vec<Partition>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Partition {
+    error_code: i16,
+    index: i32,
+    leader_id: i32,
+}
+
+/// How many times payloads are decoded for each measure
+const ITERATIONS: u32 = 2_000;
+
+fn payload(names: &[String]) -> Vec<u8> {
+    let brokers = (0..32)
+        .map(|id| Broker {
+            node_id: id,
+            host: "kafka-broker.us-east-1.internal.example.com",
+            port: 9092,
+            rack: "us-east-1a",
+        })
+        .collect();
+    let topics = names
+        .iter()
+        .map(|name| Topic {
+            error_code: 0,
+            name,
+            partitions: (0..4)
+                .map(|index| Partition {
+                    error_code: 0,
+                    index,
+                    leader_id: index % 32,
+                })
+                .collect(),
+        })
+        .collect();
+    kafka_serde::to_vec(&Metadata { brokers, topics }).unwrap()
+}
+
+fn measure(decoder: &Decoder, payload: &[u8]) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let metadata: Metadata<'_> = decoder.decode(black_box(payload)).unwrap();
+        black_box(metadata);
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let ascii: Vec<_> = (0..2_000)
+        .map(|i| format!("service.events.partitioned-by-customer.v{}", i))
+        .collect();
+    let unicode: Vec<_> = (0..2_000)
+        .map(|i| format!("événements.clients.partitionnés.версия.{}", i))
+        .collect();
+
+    let strict = Decoder::new();
+    // SAFETY: the payloads are encoded from Rust strings, which are UTF-8
+    let trusted = unsafe { Decoder::new().trusted_utf8(true) };
+    for (name, names) in [("ascii", ascii), ("unicode", unicode)] {
+        let payload = payload(&names);
+        let checked = measure(&strict, &payload);
+        let unchecked = measure(&trusted, &payload);
+        println!(
+            "{:<8} {:>7} bytes   checked {:>10.2?}   trusted {:>10.2?}   {:.2}x",
+            name,
+            payload.len(),
+            checked,
+            unchecked,
+            checked.as_secs_f64() / unchecked.as_secs_f64()
+        );
+    }
+}
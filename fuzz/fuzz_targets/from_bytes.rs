@@ -0,0 +1,95 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Deserializes arbitrary bytes into structs shaped like Kafka messages: errors are expected,
+//! panics and runaway allocations are bugs
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde::Deserialize;
+
+/// Shaped like a Metadata response: nested arrays, nullable strings and every integer width
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    throttle_time_ms: i32,
+    brokers: Vec<Broker>,
+    cluster_id: Option<String>,
+    controller_id: i32,
+    topics: Vec<Topic>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Broker {
+    node_id: i32,
+    host: String,
+    port: i32,
+    rack: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Topic {
+    error_code: i16,
+    name: String,
+    is_internal: bool,
+    partitions: Vec<Partition>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Partition {
+    error_code: i16,
+    index: i32,
+    leader_id: i32,
+    replica_nodes: Vec<i32>,
+    isr_nodes: Vec<i32>,
+}
+
+/// Shaped like the body of a request with a record set and borrowed fields
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Produce<'a> {
+    transactional_id: Option<&'a str>,
+    acks: i16,
+    timeout_ms: i32,
+    topics: Vec<(&'a str, Vec<ProducePartition<'a>>)>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct ProducePartition<'a> {
+    index: i32,
+    records: Option<&'a [u8]>,
+}
+
+/// Shaped like a consumer group member: enums, unsigned integers and floats
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Member {
+    member_id: String,
+    state: State,
+    generation: u32,
+    offset: u64,
+    lag: f64,
+    flags: u8,
+    metadata: Option<Vec<u8>>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+enum State {
+    Empty,
+    Stable,
+    Dead,
+}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = kafka_serde::from_bytes::<Metadata>(data);
+    let _ = kafka_serde::from_bytes::<Produce<'_>>(data);
+    let _ = kafka_serde::from_bytes::<Member>(data);
+    let _ = kafka_serde::from_bytes::<Vec<Vec<String>>>(data);
+});
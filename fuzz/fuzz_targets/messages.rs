@@ -0,0 +1,40 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Decodes arbitrary bytes with the decoders of the messages the crate knows: responses
+//! following runtime schemas, in the version given by the first two bytes, consumer protocol
+//! payloads and the keys and values of internal topics. Errors are expected, panics and
+//! runaway allocations are bugs
+#![no_main]
+
+use kafka_serde::consumer::{Assignment, CooperativeStickyUserData, StickyUserData, Subscription};
+use kafka_serde::fetch::FetchResponse;
+use kafka_serde::internal::{
+    GroupMetadataValue, MetadataRecord, OffsetCommitValue, OffsetsKey, TransactionLogKey,
+    TransactionLogValue,
+};
+use kafka_serde::metadata::MetadataResponse;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() >= 2 {
+        let version = i16::from_be_bytes([data[0], data[1]]);
+        let body = &data[2..];
+        let _ = FetchResponse::decode(version, body);
+        let _ = MetadataResponse::decode(version, body);
+    }
+
+    let _ = Subscription::decode(data);
+    let _ = Assignment::decode(data);
+    let _ = StickyUserData::decode(data);
+    let _ = CooperativeStickyUserData::decode(data);
+
+    let _ = OffsetsKey::decode(data);
+    let _ = OffsetCommitValue::decode(data);
+    let _ = GroupMetadataValue::decode(data);
+    let _ = TransactionLogKey::decode(data);
+    let _ = TransactionLogValue::decode(data);
+    let _ = MetadataRecord::decode(data);
+});
@@ -0,0 +1,26 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Decodes arbitrary bytes as record sets, eagerly and lazily, then as legacy message sets and
+//! record headers: errors are expected, panics and runaway allocations are bugs
+#![no_main]
+
+use kafka_serde::records::{BatchDecoder, Headers, MessageSet};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // checksums would fail almost all inputs before the records are read
+    let decoder = BatchDecoder::new().verify_crc(false);
+    let _ = decoder.decode_all(data);
+    if let Ok(batch) = decoder.decode_lazy(data) {
+        for record in batch.records() {
+            if record.is_err() {
+                break;
+            }
+        }
+    }
+    let _ = MessageSet::decode_unverified(data);
+    let _ = Headers::decode(data);
+});
@@ -0,0 +1,492 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Requests and responses of the APIs
+//!
+//! [`KafkaRequest`] ties the type of a request body to the API it calls, the versions it can be
+//! sent in, and the type of its response, so generic code can send any request and get the
+//! right response back. It is implemented by the request messages [generated](crate::codegen)
+//! from the definitions of Apache Kafka, and can be implemented for hand-written ones.
+//!
+//! A [`ResponseDispatcher`] goes the other way, for proxies and mock brokers that handle the
+//! responses of many APIs: it decodes a response frame into the response type of the API and
+//! version of the request it answers, usually a variant of an enum over the supported responses.
+//!
+//! [`ApiVersions`] holds the versions a broker supports, as listed in its ApiVersions response,
+//! and picks the highest version of each API that both the client and the broker support.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::iter::FromIterator;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorKind, Result};
+use crate::{header, Decoder};
+
+/// The API key of ApiVersions, whose responses keep the old header in flexible versions
+pub(crate) const API_VERSIONS_KEY: i16 = 18;
+
+/// A request body, with what is needed to send it and decode its response
+///
+/// # Examples
+/// ```
+/// use kafka_serde::KafkaRequest;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize)]
+/// struct HeartbeatRequest {
+///     group_id: String,
+///     generation_id: i32,
+///     member_id: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct HeartbeatResponse {
+///     throttle_time_ms: i32,
+///     error_code: i16,
+/// }
+///
+/// impl KafkaRequest for HeartbeatRequest {
+///     const API_KEY: i16 = 12;
+///     const MIN_VERSION: i16 = 1;
+///     const MAX_VERSION: i16 = 2;
+///     type Response = HeartbeatResponse;
+/// }
+///
+/// assert!(HeartbeatRequest::check_version(2).is_ok());
+/// assert!(HeartbeatRequest::check_version(4).is_err());
+/// assert!(!HeartbeatRequest::is_flexible(2));
+/// ```
+pub trait KafkaRequest {
+    /// The API key of the request
+    const API_KEY: i16;
+    /// The lowest version the type can be sent in
+    const MIN_VERSION: i16;
+    /// The highest version the type can be sent in
+    const MAX_VERSION: i16;
+    /// The first flexible version of the API, if it has any
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = None;
+    /// The type the response decodes into
+    type Response;
+
+    /// Whether `version` is a flexible version of the API, whose headers end with tagged fields
+    fn is_flexible(version: i16) -> bool {
+        Self::FIRST_FLEXIBLE_VERSION.is_some_and(|first| version >= first)
+    }
+
+    /// Fails unless the type can be sent in `version`
+    fn check_version(version: i16) -> Result<()> {
+        if (Self::MIN_VERSION..=Self::MAX_VERSION).contains(&version) {
+            Ok(())
+        } else {
+            Err(ErrorKind::Custom(format!(
+                "unsupported version {} of API {}, expected {} to {}",
+                version,
+                Self::API_KEY,
+                Self::MIN_VERSION,
+                Self::MAX_VERSION
+            ))
+            .into())
+        }
+    }
+}
+
+type DecodeFn<T> = Box<dyn Fn(Decoder, i16, &[u8]) -> Result<T> + Send + Sync>;
+
+/// How the responses of an API are decoded
+struct Route<T> {
+    min_version: i16,
+    max_version: i16,
+    first_flexible_version: Option<i16>,
+    decode: DecodeFn<T>,
+}
+
+/// Decodes responses into `T`, picking the response type from the API key of the request
+///
+/// Each API is added with the [`KafkaRequest`] type of its requests, which tells the versions
+/// supported and the type of the response.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::api::ResponseDispatcher;
+/// use kafka_serde::KafkaRequest;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct HeartbeatResponse {
+///     throttle_time_ms: i32,
+///     error_code: i16,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct LeaveGroupResponse {
+///     error_code: i16,
+/// }
+///
+/// struct HeartbeatRequest;
+///
+/// impl KafkaRequest for HeartbeatRequest {
+///     const API_KEY: i16 = 12;
+///     const MIN_VERSION: i16 = 1;
+///     const MAX_VERSION: i16 = 3;
+///     type Response = HeartbeatResponse;
+/// }
+///
+/// struct LeaveGroupRequest;
+///
+/// impl KafkaRequest for LeaveGroupRequest {
+///     const API_KEY: i16 = 13;
+///     const MIN_VERSION: i16 = 0;
+///     const MAX_VERSION: i16 = 0;
+///     type Response = LeaveGroupResponse;
+/// }
+///
+/// #[derive(Debug)]
+/// enum Response {
+///     Heartbeat(HeartbeatResponse),
+///     LeaveGroup(LeaveGroupResponse),
+/// }
+///
+/// let dispatcher = ResponseDispatcher::new()
+///     .response::<HeartbeatRequest>(Response::Heartbeat)
+///     .response::<LeaveGroupRequest>(Response::LeaveGroup);
+///
+/// // the response to request 7, a LeaveGroup v0
+/// let (correlation_id, response) = dispatcher.decode_frame(13, 0, &[0, 0, 0, 7, 0, 25]).unwrap();
+/// assert_eq!(correlation_id, 7);
+/// assert!(matches!(response, Response::LeaveGroup(LeaveGroupResponse { error_code: 25 })));
+/// assert!(dispatcher.decode_frame(14, 0, &[0, 0, 0, 7]).is_err());
+/// ```
+pub struct ResponseDispatcher<T> {
+    routes: HashMap<i16, Route<T>>,
+    decoder: Decoder,
+}
+
+impl<T> Default for ResponseDispatcher<T> {
+    fn default() -> Self {
+        ResponseDispatcher {
+            routes: HashMap::new(),
+            decoder: Decoder::new(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ResponseDispatcher<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut api_keys: Vec<_> = self.routes.keys().collect();
+        api_keys.sort();
+        f.debug_struct("ResponseDispatcher")
+            .field("api_keys", &api_keys)
+            .field("decoder", &self.decoder)
+            .finish()
+    }
+}
+
+impl<T> ResponseDispatcher<T> {
+    /// Creates a dispatcher that doesn't support any API
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the decoder of the responses decoded with serde
+    pub fn decoder(mut self, decoder: Decoder) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    /// Supports the API of `Q`, decoding its responses with serde and wrapping them with `wrap`
+    pub fn response<Q>(self, wrap: impl Fn(Q::Response) -> T + Send + Sync + 'static) -> Self
+    where
+        Q: KafkaRequest,
+        Q::Response: DeserializeOwned,
+    {
+        self.route::<Q>(Box::new(move |decoder, _, body| {
+            decoder.decode(body).map(&wrap)
+        }))
+    }
+
+    /// Supports the API of `Q`, decoding its responses with `decode`, which is given the version
+    /// and the body. This fits the messages generated by [`codegen`](crate::codegen), which
+    /// decode themselves
+    pub fn response_with<Q>(
+        self,
+        decode: impl Fn(i16, &[u8]) -> Result<T> + Send + Sync + 'static,
+    ) -> Self
+    where
+        Q: KafkaRequest,
+    {
+        self.route::<Q>(Box::new(move |_, version, body| decode(version, body)))
+    }
+
+    fn route<Q: KafkaRequest>(mut self, decode: DecodeFn<T>) -> Self {
+        let route = Route {
+            min_version: Q::MIN_VERSION,
+            max_version: Q::MAX_VERSION,
+            first_flexible_version: Q::FIRST_FLEXIBLE_VERSION,
+            decode,
+        };
+        self.routes.insert(Q::API_KEY, route);
+        self
+    }
+
+    /// Whether responses to `api_version` of the API `api_key` can be decoded
+    pub fn supports(&self, api_key: i16, api_version: i16) -> bool {
+        self.routes
+            .get(&api_key)
+            .is_some_and(|route| (route.min_version..=route.max_version).contains(&api_version))
+    }
+
+    fn get(&self, api_key: i16, api_version: i16) -> Result<&Route<T>> {
+        match self.routes.get(&api_key) {
+            Some(route) if self.supports(api_key, api_version) => Ok(route),
+            Some(_) => Err(ErrorKind::Custom(format!(
+                "unsupported version {} of API {}",
+                api_version, api_key
+            ))
+            .into()),
+            None => Err(ErrorKind::Custom(format!("unsupported API {}", api_key)).into()),
+        }
+    }
+
+    /// Decodes the body of a response to `api_version` of the API `api_key`
+    pub fn decode_body(&self, api_key: i16, api_version: i16, body: &[u8]) -> Result<T> {
+        let route = self.get(api_key, api_version)?;
+        (route.decode)(self.decoder, api_version, body)
+    }
+
+    /// Decodes a response frame stripped of its size, returning its correlation id and body
+    pub fn decode_frame(&self, api_key: i16, api_version: i16, frame: &[u8]) -> Result<(i32, T)> {
+        let route = self.get(api_key, api_version)?;
+        let flexible = route
+            .first_flexible_version
+            .is_some_and(|first| api_version >= first);
+        let header_version = header::response_header_version(api_key, flexible);
+        let (header, body) = header::split_response(frame, header_version)?;
+        let body = (route.decode)(self.decoder, api_version, body)?;
+        Ok((header.correlation_id, body))
+    }
+}
+
+/// The versions of an API supported by a broker, an entry of the ApiVersions response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ApiVersionRange {
+    /// The API key
+    pub api_key: i16,
+    /// The lowest supported version
+    pub min_version: i16,
+    /// The highest supported version
+    pub max_version: i16,
+}
+
+/// The versions of the APIs supported by a broker
+///
+/// # Examples
+/// ```
+/// use kafka_serde::api::{ApiVersionRange, ApiVersions};
+/// use kafka_serde::KafkaRequest;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct ApiVersionsResponse {
+///     error_code: i16,
+///     api_keys: Vec<ApiVersionRange>,
+/// }
+///
+/// struct HeartbeatRequest;
+///
+/// impl KafkaRequest for HeartbeatRequest {
+///     const API_KEY: i16 = 12;
+///     const MIN_VERSION: i16 = 0;
+///     const MAX_VERSION: i16 = 4;
+///     type Response = ();
+/// }
+///
+/// // the broker supports Heartbeat v0 to v3, and Metadata v0 to v12
+/// let data = [0, 0, 0, 0, 0, 2, 0, 12, 0, 0, 0, 3, 0, 3, 0, 0, 0, 12];
+/// let response: ApiVersionsResponse = kafka_serde::from_bytes(&data).unwrap();
+/// let versions: ApiVersions = response.api_keys.into_iter().collect();
+/// assert_eq!(versions.version_of::<HeartbeatRequest>().unwrap(), 3);
+/// assert!(versions.negotiate(3, 13, 13).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiVersions {
+    ranges: BTreeMap<i16, ApiVersionRange>,
+}
+
+impl FromIterator<ApiVersionRange> for ApiVersions {
+    fn from_iter<I: IntoIterator<Item = ApiVersionRange>>(ranges: I) -> Self {
+        ApiVersions {
+            ranges: ranges
+                .into_iter()
+                .map(|range| (range.api_key, range))
+                .collect(),
+        }
+    }
+}
+
+impl ApiVersions {
+    /// The versions of the API `api_key` supported by the broker, if it supports it at all
+    pub fn get(&self, api_key: i16) -> Option<ApiVersionRange> {
+        self.ranges.get(&api_key).copied()
+    }
+
+    /// The ranges of all the APIs supported by the broker, by API key
+    pub fn iter(&self) -> impl Iterator<Item = ApiVersionRange> + '_ {
+        self.ranges.values().copied()
+    }
+
+    /// The highest version of the API `api_key` between `min_version` and `max_version` that
+    /// the broker supports
+    ///
+    /// Fails if the broker doesn't support the API, or none of the versions.
+    pub fn negotiate(&self, api_key: i16, min_version: i16, max_version: i16) -> Result<i16> {
+        let range = self.get(api_key).ok_or_else(|| {
+            ErrorKind::Custom(format!("API {} is not supported by the broker", api_key))
+        })?;
+        let version = max_version.min(range.max_version);
+        if version < min_version.max(range.min_version) {
+            return Err(ErrorKind::Custom(format!(
+                "no common version of API {}: the client supports {} to {}, the broker {} to {}",
+                api_key, min_version, max_version, range.min_version, range.max_version
+            ))
+            .into());
+        }
+        Ok(version)
+    }
+
+    /// The highest version `Q` can be sent in to the broker, see [`ApiVersions::negotiate`]
+    pub fn version_of<Q: KafkaRequest>(&self) -> Result<i16> {
+        self.negotiate(Q::API_KEY, Q::MIN_VERSION, Q::MAX_VERSION)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct ApiVersionsResponse {
+        error_code: i16,
+    }
+
+    struct ApiVersionsRequest;
+
+    impl KafkaRequest for ApiVersionsRequest {
+        const API_KEY: i16 = API_VERSIONS_KEY;
+        const MIN_VERSION: i16 = 0;
+        const MAX_VERSION: i16 = 3;
+        const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(3);
+        type Response = ApiVersionsResponse;
+    }
+
+    struct MetadataRequest;
+
+    impl KafkaRequest for MetadataRequest {
+        const API_KEY: i16 = 3;
+        const MIN_VERSION: i16 = 1;
+        const MAX_VERSION: i16 = 12;
+        const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(9);
+        type Response = i32;
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Response {
+        ApiVersions(ApiVersionsResponse),
+        Metadata(i32),
+    }
+
+    fn dispatcher() -> ResponseDispatcher<Response> {
+        ResponseDispatcher::new()
+            .response::<ApiVersionsRequest>(Response::ApiVersions)
+            .response_with::<MetadataRequest>(|version, body| {
+                assert_eq!(version, 9);
+                crate::from_bytes(body).map(Response::Metadata)
+            })
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let dispatcher = dispatcher();
+        // flexible versions strip the tagged fields of the header, except for ApiVersions
+        assert_eq!(
+            dispatcher
+                .decode_frame(3, 9, &[0, 0, 0, 1, 0, 0, 0, 0, 5])
+                .unwrap(),
+            (1, Response::Metadata(5))
+        );
+        assert_eq!(
+            dispatcher
+                .decode_frame(18, 3, &[0, 0, 0, 2, 0, 35])
+                .unwrap(),
+            (
+                2,
+                Response::ApiVersions(ApiVersionsResponse { error_code: 35 })
+            )
+        );
+        assert_eq!(
+            dispatcher.decode_body(18, 0, &[0, 0]).unwrap(),
+            Response::ApiVersions(ApiVersionsResponse { error_code: 0 })
+        );
+        assert!(dispatcher.supports(3, 12) && !dispatcher.supports(3, 0));
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let versions: ApiVersions = vec![
+            ApiVersionRange {
+                api_key: 3,
+                min_version: 0,
+                max_version: 8,
+            },
+            ApiVersionRange {
+                api_key: 18,
+                min_version: 0,
+                max_version: 4,
+            },
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(versions.version_of::<ApiVersionsRequest>().unwrap(), 3);
+        assert_eq!(versions.version_of::<MetadataRequest>().unwrap(), 8);
+        assert_eq!(versions.negotiate(3, 8, 8).unwrap(), 8);
+        for (api_key, min, max) in [(3, 9, 12), (4, 0, 5)] {
+            match *versions.negotiate(api_key, min, max).unwrap_err().kind() {
+                ErrorKind::Custom(_) => {}
+                ref e => panic!("unexpected error {}", e),
+            }
+        }
+        // brokers that don't support old versions anymore
+        let recent: ApiVersions = vec![ApiVersionRange {
+            api_key: 3,
+            min_version: 4,
+            max_version: 12,
+        }]
+        .into_iter()
+        .collect();
+        assert!(recent.negotiate(3, 0, 3).is_err());
+        assert_eq!(recent.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let dispatcher = dispatcher();
+        for (api_key, version) in [(3, 13), (4, 0)] {
+            match *dispatcher
+                .decode_body(api_key, version, &[])
+                .unwrap_err()
+                .kind()
+            {
+                ErrorKind::Custom(ref message) => assert!(message.starts_with("unsupported")),
+                ref e => panic!("unexpected error {}", e),
+            }
+        }
+        match *dispatcher.decode_frame(18, 3, &[0, 0]).unwrap_err().kind() {
+            ErrorKind::Decode { .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Decodes a captured request, response or message body, and prints it.
+//!
+//! The message is read from a file or stdin, as raw bytes, hex or base64. Its body is decoded
+//! with the message definition of Apache Kafka given with `--definition`, and printed as JSON.
+//! Without one, the body is printed in hex.
+use std::error::Error;
+use std::io::{self, Read};
+use std::process;
+
+use kafka_serde::capture::{self, CapturedRequest, FrameDump};
+use kafka_serde::schema::{self, Schema};
+
+const USAGE: &str = "\
+usage: kafka-serde-dump [OPTIONS] [FILE]
+
+Decodes a Kafka request, response or message body read from FILE, or stdin.
+
+options:
+    --format raw|hex|base64   how the input is encoded [default: raw]
+    --request                 the input is a request frame, whose header gives the version
+                              [default]
+    --response                the input is a response frame, in --api-version
+    --body                    the input is a message body alone, in --api-version
+    --api-version VERSION     the version of the message
+    --definition FILE         the JSON definition of the message, from
+                              clients/src/main/resources/common/message in Apache Kafka
+    -h, --help                print this help
+
+Frames may start with their size.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Raw,
+    Hex,
+    Base64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Request,
+    Response,
+    Body,
+}
+
+#[derive(Debug)]
+struct Options {
+    format: Format,
+    kind: Kind,
+    api_version: Option<i16>,
+    definition: Option<String>,
+    input: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        format: Format::Raw,
+        kind: Kind::Request,
+        api_version: None,
+        definition: None,
+        input: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--format" => {
+                options.format = match value("--format")?.as_str() {
+                    "raw" => Format::Raw,
+                    "hex" => Format::Hex,
+                    "base64" => Format::Base64,
+                    format => return Err(format!("unknown format {}", format)),
+                }
+            }
+            "--request" => options.kind = Kind::Request,
+            "--response" => options.kind = Kind::Response,
+            "--body" => options.kind = Kind::Body,
+            "--api-version" => {
+                let version = value("--api-version")?;
+                let version = version
+                    .parse()
+                    .map_err(|_| format!("invalid version {}", version))?;
+                options.api_version = Some(version);
+            }
+            "--definition" => options.definition = Some(value("--definition")?),
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option {}", arg))
+            }
+            _ if options.input.is_none() => options.input = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    if options.kind != Kind::Request && options.api_version.is_none() {
+        return Err("--response and --body need --api-version".to_owned());
+    }
+    Ok(options)
+}
+
+fn decode_base64(input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &b in input {
+        let sextet = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ if b.is_ascii_whitespace() => continue,
+            _ => return Err(format!("invalid base64 character {:?}", b as char)),
+        };
+        acc = acc << 6 | u32::from(sextet);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// Strips the size of a frame, if it starts with it
+fn strip_size(data: &[u8]) -> &[u8] {
+    match data {
+        [a, b, c, d, rest @ ..] if i32::from_be_bytes([*a, *b, *c, *d]) as usize == rest.len() => {
+            rest
+        }
+        _ => data,
+    }
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let mut input = Vec::new();
+    match options.input.as_deref() {
+        None | Some("-") => {
+            io::stdin().read_to_end(&mut input)?;
+        }
+        Some(path) => input = std::fs::read(path)?,
+    }
+    let data = match options.format {
+        Format::Raw => input,
+        Format::Hex => capture::decode_hex(&input)?,
+        Format::Base64 => decode_base64(&input)?,
+    };
+    let schema = match options.definition {
+        Some(ref path) => Some(Schema::from_definition(&std::fs::read_to_string(path)?)?),
+        None => None,
+    };
+
+    let (version, body) = match options.kind {
+        Kind::Request => {
+            let request = CapturedRequest::parse(strip_size(&data), schema.as_ref())?;
+            let header = &request.header;
+            println!("api key:        {}", header.api_key);
+            println!("api version:    {}", header.api_version);
+            println!("correlation id: {}", header.correlation_id);
+            println!("client id:      {:?}", header.client_id);
+            let version = options.api_version.unwrap_or(header.api_version);
+            (version, request.body)
+        }
+        Kind::Response => {
+            let version = options.api_version.unwrap_or_default();
+            // ApiVersions responses keep the header of non-flexible versions
+            let flexible = schema.as_ref().is_some_and(|schema| {
+                schema.is_flexible(version) && schema.name != "ApiVersionsResponse"
+            });
+            let (correlation_id, body) = capture::response_body(strip_size(&data), flexible)?;
+            println!("correlation id: {}", correlation_id);
+            (version, body.to_vec())
+        }
+        Kind::Body => (options.api_version.unwrap_or_default(), data),
+    };
+
+    match schema {
+        Some(schema) => {
+            let value = schema::to_json(&schema, version, &body)?;
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+        None => print!("{}", FrameDump::new(&body)),
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) if err.is_empty() => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(err) = run(options) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
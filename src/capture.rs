@@ -0,0 +1,682 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Dissecting captured traffic
+//!
+//! A [`Dissector`] follows a connection between a client and a broker from the TCP payloads
+//! captured on the wire, e.g. read from a pcap file. It reassembles the size-prefixed frames of
+//! both directions, whatever segments they were cut into, reads the request headers, and pairs
+//! every response with the request it answers, by correlation id.
+//!
+//! Bodies can then be decoded into the Rust types of the messages, with
+//! [`CapturedRequest::body`] and [`Exchange::body`], or into dynamic [`KafkaValue`]s, following
+//! the [`Schema`]s given to the dissector for the APIs it should understand.
+//!
+//! A [`FrameDump`] renders a single frame as a hex dump, after the fields of its header, and
+//! [`decode_hex`] reads back frames captured as hex.
+//!
+//! The capture must start with the connection, or at least on frame boundaries in both
+//! directions: the size of a frame can't be told apart from the bytes in the middle of one.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::capture::Dissector;
+//! use kafka_serde::schema::{Field, Schema, Type};
+//!
+//! let heartbeat = Schema::new("HeartbeatResponse", vec![Field::new("error_code", Type::Int16)]);
+//! let mut dissector = Dissector::new().api(12, Schema::new("HeartbeatRequest", vec![]), heartbeat);
+//!
+//! // a heartbeat request with correlation id 7, cut in two segments
+//! let requests = dissector.client_data(&[0, 0, 0, 10, 0, 12, 0, 0]).unwrap();
+//! assert!(requests.is_empty());
+//! let requests = dissector.client_data(&[0, 0, 0, 7, 0xff, 0xff]).unwrap();
+//! assert_eq!(requests[0].header.api_key, 12);
+//!
+//! let exchanges = dissector.server_data(&[0, 0, 0, 6, 0, 0, 0, 7, 0, 27]).unwrap();
+//! let request = exchanges[0].request.as_ref().unwrap();
+//! assert_eq!(request.header.correlation_id, 7);
+//! let response = dissector.decode_response(&exchanges[0]).unwrap().unwrap();
+//! assert_eq!(response.get("error_code").and_then(|e| e.as_i64()), Some(27));
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::client;
+use crate::error::{ErrorKind, Result};
+use crate::header::{self, split_request, split_response};
+use crate::schema::{KafkaValue, Schema};
+
+pub use crate::header::RequestHeader;
+
+/// The default limit on the size of frames, the default `socket.request.max.bytes` of brokers
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 100 * 1024 * 1024;
+
+/// Reassembles the size-prefixed frames of one direction of a connection
+///
+/// # Examples
+/// ```
+/// use kafka_serde::capture::FrameBuffer;
+///
+/// let mut frames = FrameBuffer::new();
+/// frames.push(&[0, 0, 0, 2, 1]);
+/// assert_eq!(frames.next_frame().unwrap(), None);
+/// frames.push(&[2, 0, 0]);
+/// assert_eq!(frames.next_frame().unwrap(), Some(vec![1, 2]));
+/// assert_eq!(frames.buffered(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameBuffer {
+    buf: Vec<u8>,
+    // where the next frame starts in buf, which is compacted once the frames before are large
+    start: usize,
+    max_frame_size: usize,
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        FrameBuffer {
+            buf: Vec::new(),
+            start: 0,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+}
+
+impl FrameBuffer {
+    /// Creates an empty buffer, accepting frames of up to [`DEFAULT_MAX_FRAME_SIZE`] bytes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the size over which frames are rejected: large sizes are usually bytes taken for a
+    /// frame size, after a segment went missing
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = size;
+        self
+    }
+
+    /// Appends captured bytes
+    pub fn push(&mut self, data: &[u8]) {
+        if self.start > 0 && self.start >= self.buf.len() / 2 {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Takes the next frame, without its size, if all of it was pushed
+    ///
+    /// Fails if the size of the frame is negative or over the limit. The stream can't be
+    /// followed after that, and the buffer should be [cleared](FrameBuffer::clear).
+    pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        let pending = &self.buf[self.start..];
+        if pending.len() < 4 {
+            return Ok(None);
+        }
+        let size = i32::from_be_bytes([pending[0], pending[1], pending[2], pending[3]]);
+        if size < 0 {
+            return Err(ErrorKind::InvalidLength(size).into());
+        }
+        let size = size as usize;
+        if size > self.max_frame_size {
+            return Err(ErrorKind::LimitExceeded {
+                what: "frame",
+                len: size,
+                max: self.max_frame_size,
+            }
+            .into());
+        }
+        if pending.len() < 4 + size {
+            return Ok(None);
+        }
+        let frame = pending[4..4 + size].to_vec();
+        self.start += 4 + size;
+        Ok(Some(frame))
+    }
+
+    /// How many bytes were pushed and not taken in frames yet
+    pub fn buffered(&self) -> usize {
+        self.buf.len() - self.start
+    }
+
+    /// Discards the buffered bytes
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.start = 0;
+    }
+}
+
+/// A request read from a capture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRequest {
+    /// The request header
+    pub header: RequestHeader,
+    /// The request body. For APIs without a schema, it starts with the tagged fields of the
+    /// header in flexible versions, as there is no telling which versions are
+    pub body: Vec<u8>,
+}
+
+impl CapturedRequest {
+    /// Reads a single request frame, stripped of its size. The tagged fields of the header are
+    /// stripped in the flexible versions of `schema`, if there is one
+    pub fn parse(frame: &[u8], schema: Option<&Schema>) -> Result<CapturedRequest> {
+        read_request(frame, |_, api_version| {
+            schema.is_some_and(|schema| schema.is_flexible(api_version))
+        })
+    }
+
+    /// Decodes the body into the type of the request
+    pub fn body<'a, T: Deserialize<'a>>(&'a self) -> Result<T> {
+        crate::from_bytes(&self.body)
+    }
+}
+
+/// A response read from a capture, with the request it answers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    /// The request, unless it wasn't captured
+    pub request: Option<CapturedRequest>,
+    /// The correlation id of the response
+    pub correlation_id: i32,
+    /// The response body. For APIs without a schema, and responses to requests that weren't
+    /// captured, it starts with the tagged fields of the header in flexible versions
+    pub response: Vec<u8>,
+}
+
+impl Exchange {
+    /// Decodes the response body into the type of the response
+    pub fn body<'a, T: Deserialize<'a>>(&'a self) -> Result<T> {
+        crate::from_bytes(&self.response)
+    }
+}
+
+/// The schemas of the requests and responses of an API
+#[derive(Debug, Clone)]
+struct Api {
+    request: Schema,
+    response: Schema,
+}
+
+/// Follows a connection from captured payloads, pairing requests and responses
+#[derive(Debug, Clone, Default)]
+pub struct Dissector {
+    requests: FrameBuffer,
+    responses: FrameBuffer,
+    in_flight: HashMap<i32, CapturedRequest>,
+    apis: HashMap<i16, Api>,
+}
+
+impl Dissector {
+    /// Creates a dissector that doesn't know any API
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the schemas of the requests and responses of the API `api_key`, to strip flexible
+    /// headers off their bodies and to decode them into [`KafkaValue`]s
+    pub fn api(mut self, api_key: i16, request: Schema, response: Schema) -> Self {
+        self.apis.insert(api_key, Api { request, response });
+        self
+    }
+
+    /// Sets the size over which frames are rejected, see [`FrameBuffer::max_frame_size`]
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.requests = self.requests.max_frame_size(size);
+        self.responses = self.responses.max_frame_size(size);
+        self
+    }
+
+    /// Takes bytes sent by the client, returning the requests they complete
+    ///
+    /// Produce requests with acks=0 are not kept for their response, which brokers don't send.
+    pub fn client_data(&mut self, data: &[u8]) -> Result<Vec<CapturedRequest>> {
+        self.requests.push(data);
+        let mut requests = Vec::new();
+        while let Some(frame) = self.requests.next_frame()? {
+            let request = self.read_request(&frame)?;
+            if self.expects_response(&request) {
+                self.in_flight
+                    .insert(request.header.correlation_id, request.clone());
+            }
+            requests.push(request);
+        }
+        Ok(requests)
+    }
+
+    /// Takes bytes sent by the broker, returning the responses they complete, paired with their
+    /// requests
+    pub fn server_data(&mut self, data: &[u8]) -> Result<Vec<Exchange>> {
+        self.responses.push(data);
+        let mut exchanges = Vec::new();
+        while let Some(frame) = self.responses.next_frame()? {
+            let correlation_id = split_response(&frame, 0)?.0.correlation_id;
+            let request = self.in_flight.remove(&correlation_id);
+            let header_version = request.as_ref().map_or(0, |request| {
+                let header = &request.header;
+                let flexible = self
+                    .apis
+                    .get(&header.api_key)
+                    .is_some_and(|api| api.response.is_flexible(header.api_version));
+                header::response_header_version(header.api_key, flexible)
+            });
+            let body = split_response(&frame, header_version)?.1;
+            exchanges.push(Exchange {
+                request,
+                correlation_id,
+                response: body.to_vec(),
+            });
+        }
+        Ok(exchanges)
+    }
+
+    fn read_request(&self, frame: &[u8]) -> Result<CapturedRequest> {
+        read_request(frame, |api_key, api_version| {
+            self.is_flexible(api_key, api_version)
+        })
+    }
+
+    /// Whether the schema of `api_key` tells that `api_version` is flexible
+    fn is_flexible(&self, api_key: i16, api_version: i16) -> bool {
+        self.apis
+            .get(&api_key)
+            .is_some_and(|api| api.request.is_flexible(api_version))
+    }
+
+    /// Whether the broker answers `request`, which it doesn't for Produce requests with acks=0
+    fn expects_response(&self, request: &CapturedRequest) -> bool {
+        let header = &request.header;
+        // the tagged fields of the header are left in the body without a schema
+        client::expects_response(
+            header.api_key,
+            header.api_version,
+            &request.body,
+            !self.is_flexible(header.api_key, header.api_version),
+        )
+    }
+
+    /// Decodes the body of `request` with the schema of its API, if the dissector has it
+    pub fn decode_request(&self, request: &CapturedRequest) -> Option<Result<KafkaValue>> {
+        let api = self.apis.get(&request.header.api_key)?;
+        Some(
+            api.request
+                .decode(request.header.api_version, &request.body),
+        )
+    }
+
+    /// Decodes the response of `exchange` with the schema of its API, if the dissector has it
+    /// and the request was captured
+    pub fn decode_response(&self, exchange: &Exchange) -> Option<Result<KafkaValue>> {
+        let header = &exchange.request.as_ref()?.header;
+        let api = self.apis.get(&header.api_key)?;
+        Some(api.response.decode(header.api_version, &exchange.response))
+    }
+
+    /// How many requests are waiting for their response
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+}
+
+/// What a [`FrameDump`] decodes the header of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    Request,
+    Response,
+    Raw,
+}
+
+/// Renders a frame as a hex dump with offsets, after the fields of its header
+///
+/// The [`Display`](fmt::Display) and [`Debug`](fmt::Debug) implementations print the same
+/// lines, so a problematic buffer can be logged with either. Frames are stripped of their size.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::capture::FrameDump;
+///
+/// let frame = [0, 12, 0, 0, 0, 0, 0, 7, 0xff, 0xff, 0, 1, b'g'];
+/// assert_eq!(
+///     FrameDump::request(&frame).to_string(),
+///     "request api_key=12 api_version=0 correlation_id=7 client_id=None\n\
+///      00000000  00 0c 00 00 00 00 00 07  ff ff 00 01 67           |............g|\n"
+/// );
+/// ```
+#[derive(Clone, Copy)]
+pub struct FrameDump<'a> {
+    frame: &'a [u8],
+    kind: FrameKind,
+    limit: usize,
+}
+
+impl<'a> FrameDump<'a> {
+    /// Dumps `frame` without decoding anything
+    pub fn new(frame: &'a [u8]) -> Self {
+        FrameDump {
+            frame,
+            kind: FrameKind::Raw,
+            limit: usize::MAX,
+        }
+    }
+
+    /// Dumps a request frame, after its header
+    pub fn request(frame: &'a [u8]) -> Self {
+        FrameDump {
+            kind: FrameKind::Request,
+            ..Self::new(frame)
+        }
+    }
+
+    /// Dumps a response frame, after its correlation id
+    pub fn response(frame: &'a [u8]) -> Self {
+        FrameDump {
+            kind: FrameKind::Response,
+            ..Self::new(frame)
+        }
+    }
+
+    /// Dumps only the first `bytes` bytes of large frames
+    pub fn limit(mut self, bytes: usize) -> Self {
+        self.limit = bytes;
+        self
+    }
+
+    fn write_header(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            FrameKind::Request => match read_request(self.frame, |_, _| false) {
+                Ok(request) => {
+                    let header = request.header;
+                    writeln!(
+                        f,
+                        "request api_key={} api_version={} correlation_id={} client_id={:?}",
+                        header.api_key, header.api_version, header.correlation_id, header.client_id
+                    )
+                }
+                Err(err) => writeln!(f, "request with an invalid header: {}", err),
+            },
+            FrameKind::Response => match response_body(self.frame, false) {
+                Ok((correlation_id, _)) => {
+                    writeln!(f, "response correlation_id={}", correlation_id)
+                }
+                Err(err) => writeln!(f, "response with an invalid header: {}", err),
+            },
+            FrameKind::Raw => writeln!(f, "frame of {} bytes", self.frame.len()),
+        }
+    }
+}
+
+impl fmt::Display for FrameDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_header(f)?;
+        let shown = &self.frame[..self.frame.len().min(self.limit)];
+        for (i, line) in shown.chunks(16).enumerate() {
+            write!(f, "{:08x} ", i * 16)?;
+            for j in 0..16 {
+                if j == 8 {
+                    f.write_str(" ")?;
+                }
+                match line.get(j) {
+                    Some(b) => write!(f, " {:02x}", b)?,
+                    None => f.write_str("   ")?,
+                }
+            }
+            f.write_str("  |")?;
+            for &b in line {
+                let c = if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", c)?;
+            }
+            f.write_str("|\n")?;
+        }
+        if shown.len() < self.frame.len() {
+            writeln!(f, "... {} more bytes", self.frame.len() - shown.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for FrameDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Splits a response frame, stripped of its size, into its correlation id and its body
+///
+/// `flexible_header` tells whether the header ends with tagged fields, as it does in flexible
+/// versions of all APIs but ApiVersions.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::capture::response_body;
+///
+/// assert_eq!(response_body(&[0, 0, 0, 7, 0, 27], false).unwrap(), (7, &[0, 27][..]));
+/// assert_eq!(response_body(&[0, 0, 0, 7, 0, 0, 27], true).unwrap(), (7, &[0, 27][..]));
+/// ```
+pub fn response_body(frame: &[u8], flexible_header: bool) -> Result<(i32, &[u8])> {
+    let (header, body) = split_response(frame, i16::from(flexible_header))?;
+    Ok((header.correlation_id, body))
+}
+
+/// Decodes bytes written as pairs of hex digits, ignoring whitespace, as frames copied from
+/// captures often are
+///
+/// # Examples
+/// ```
+/// use kafka_serde::capture::decode_hex;
+///
+/// assert_eq!(decode_hex(b"0000 001b\n").unwrap(), [0, 0, 0, 0x1b]);
+/// assert!(decode_hex(b"001").is_err());
+/// ```
+pub fn decode_hex(hex: &[u8]) -> Result<Vec<u8>> {
+    let digits: Vec<u8> = hex
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    if digits.len() % 2 != 0 {
+        return Err(ErrorKind::Custom("odd number of hex digits".to_owned()).into());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| {
+                    ErrorKind::Custom(format!("invalid hex {}", String::from_utf8_lossy(pair)))
+                        .into()
+                })
+        })
+        .collect()
+}
+
+/// Reads a request frame stripped of its size. `flexible` tells whether a version of an API is
+/// flexible, for the header to be stripped of its tagged fields
+pub(crate) fn read_request(
+    frame: &[u8],
+    flexible: impl Fn(i16, i16) -> bool,
+) -> Result<CapturedRequest> {
+    let (header, body) = split_request(frame, flexible)?;
+    Ok(CapturedRequest {
+        header,
+        body: body.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::produce::PRODUCE_KEY;
+    use crate::schema::{Field, Type};
+    use crate::ser;
+
+    fn metadata() -> Dissector {
+        let request = Schema::new(
+            "MetadataRequest",
+            vec![Field::new("topics", Type::Array(Box::new(Type::String)))],
+        )
+        .flexible_versions(9..);
+        let response = Schema::new(
+            "MetadataResponse",
+            vec![Field::new("throttle_time_ms", Type::Int32)],
+        )
+        .flexible_versions(9..);
+        Dissector::new().api(3, request, response)
+    }
+
+    fn request(correlation_id: i32, version: i16, body: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0, 0, 0, 0, 0, 3];
+        frame.extend_from_slice(&version.to_be_bytes());
+        frame.extend_from_slice(&correlation_id.to_be_bytes());
+        ser::write_nullable_string(&mut frame, Some("client")).unwrap();
+        if version >= 9 {
+            frame.push(0);
+        }
+        frame.extend_from_slice(body);
+        let size = (frame.len() - 4) as i32;
+        frame[..4].copy_from_slice(&size.to_be_bytes());
+        frame
+    }
+
+    fn response(correlation_id: i32, version: i16, body: &[u8]) -> Vec<u8> {
+        let mut frame = correlation_id.to_be_bytes().to_vec();
+        if version >= 9 {
+            frame.push(0);
+        }
+        frame.extend_from_slice(body);
+        let mut sized = (frame.len() as i32).to_be_bytes().to_vec();
+        sized.extend_from_slice(&frame);
+        sized
+    }
+
+    #[test]
+    fn test_pipelined() {
+        let mut dissector = metadata();
+        let mut client = request(1, 1, &[0, 0, 0, 1, 0, 1, b't']);
+        client.extend_from_slice(&request(2, 9, &[2, 2, b't', 0]));
+
+        // one byte at a time
+        let mut requests = Vec::new();
+        for byte in &client {
+            requests.extend(dissector.client_data(&[*byte]).unwrap());
+        }
+        assert_eq!(requests.len(), 2);
+        assert_eq!(dissector.in_flight(), 2);
+        assert_eq!(requests[1].header.client_id.as_deref(), Some("client"));
+        assert_eq!(requests[1].body, [2, 2, b't', 0]);
+        let topics = dissector.decode_request(&requests[1]).unwrap().unwrap();
+        assert_eq!(
+            topics.get("topics"),
+            Some(&KafkaValue::Array(vec![KafkaValue::String("t".into())]))
+        );
+
+        // answered out of order, in one segment
+        let mut server = response(2, 9, &[0, 0, 0, 5, 0]);
+        server.extend_from_slice(&response(1, 1, &[0, 0, 0, 6]));
+        let exchanges = dissector.server_data(&server).unwrap();
+        assert_eq!(dissector.in_flight(), 0);
+        assert_eq!(exchanges[0].request.as_ref(), Some(&requests[1]));
+        assert_eq!(exchanges[0].body::<i32>().unwrap(), 5);
+        let throttle = dissector.decode_response(&exchanges[1]).unwrap().unwrap();
+        assert_eq!(
+            throttle.get("throttle_time_ms"),
+            Some(&KafkaValue::Int32(6))
+        );
+    }
+
+    #[test]
+    fn test_unknown() {
+        let mut dissector = Dissector::new();
+        let requests = dissector.client_data(&request(1, 9, &[2])).unwrap();
+        // without a schema, the tagged fields of the header stay in the body
+        assert_eq!(requests[0].body, [0, 2]);
+        assert!(dissector.decode_request(&requests[0]).is_none());
+
+        let exchanges = dissector.server_data(&response(5, 0, &[1])).unwrap();
+        assert_eq!(exchanges[0].request, None);
+        assert_eq!(exchanges[0].correlation_id, 5);
+        assert_eq!(exchanges[0].response, [1]);
+    }
+
+    #[test]
+    fn test_acks_0() {
+        let produce = |correlation_id, version, body: &[u8]| {
+            let mut frame = request(correlation_id, version, body);
+            frame[5] = PRODUCE_KEY as u8;
+            frame
+        };
+        // acks=0 in v2, then acks=1 in v3 after a null transactional id, then acks=0 and acks=-1
+        // in v9, after the tagged fields of the header and a compact null transactional id
+        let mut client = produce(1, 2, &[0, 0, 0, 0, 0x75, 0x30]);
+        client.extend(produce(2, 3, &[0xff, 0xff, 0, 1]));
+        client.extend(produce(3, 9, &[0, 0, 0]));
+        client.extend(produce(4, 9, &[0, 0xff, 0xff]));
+
+        let mut dissector = Dissector::new();
+        assert_eq!(dissector.client_data(&client).unwrap().len(), 4);
+        assert_eq!(dissector.in_flight(), 2);
+        let exchanges = dissector.server_data(&response(2, 3, &[])).unwrap();
+        assert!(exchanges[0].request.is_some());
+
+        // the header is stripped with a schema
+        let schema = || Schema::new("Produce", vec![]).flexible_versions(9..);
+        let mut dissector = Dissector::new().api(PRODUCE_KEY, schema(), schema());
+        dissector.client_data(&client).unwrap();
+        assert_eq!(dissector.in_flight(), 2);
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+        let mut dissector = Dissector::new().max_frame_size(16);
+        match *dissector.client_data(&[0, 0, 1, 0]).unwrap_err().kind() {
+            ErrorKind::LimitExceeded { len: 256, .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        match *dissector.server_data(&[0xff, 0, 0, 0]).unwrap_err().kind() {
+            ErrorKind::InvalidLength(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        let mut truncated = Dissector::new();
+        assert!(truncated.client_data(&[0, 0, 0, 2, 0, 3]).is_err());
+    }
+
+    #[test]
+    fn test_frame_dump() {
+        let frame: Vec<u8> = (0..20).collect();
+        assert_eq!(
+            format!("{:?}", FrameDump::new(&frame).limit(17)),
+            "frame of 20 bytes\n\
+             00000000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f  |................|\n\
+             00000010  10                                                |.|\n\
+             ... 3 more bytes\n"
+        );
+        assert_eq!(
+            FrameDump::response(&[0, 0, 1, 0, b' ', b'~']).to_string(),
+            "response correlation_id=256\n\
+             00000000  00 00 01 00 20 7e                                 |.... ~|\n"
+        );
+        assert!(FrameDump::request(&[0, 1])
+            .to_string()
+            .starts_with("request with an invalid header: "));
+    }
+
+    #[test]
+    fn test_frame_buffer() {
+        let mut frames = FrameBuffer::new();
+        for i in 0..100u8 {
+            frames.push(&[0, 0, 0, 1, i]);
+            assert_eq!(frames.next_frame().unwrap(), Some(vec![i]));
+        }
+        assert!(frames.buf.len() < 10);
+        frames.push(&[0, 0]);
+        frames.clear();
+        assert_eq!(frames.buffered(), 0);
+    }
+}
@@ -0,0 +1,372 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::log::Logger;
+use super::throttle::Throttle;
+use super::{
+    decode_response, encode_request, no_response, read_frame, request_expects_response, Request,
+    RequestLog,
+};
+use crate::error::{ErrorKind, Result};
+use crate::{metrics, Decoder};
+
+/// A connection to a broker, sending requests over a blocking stream and decoding their
+/// responses
+///
+/// This is [`Connection`](super::Connection) without an async runtime, for command line tools
+/// and tests that only send a few requests. Each request blocks until its response is read.
+///
+/// A request that fails while in flight, e.g. because a read timeout of the stream expired,
+/// may leave its response in the stream. The connection fails every request after that: it
+/// must be replaced.
+///
+/// # Examples
+/// ```no_run
+/// use kafka_serde::client::{BlockingConnection, Request};
+/// use serde::{Deserialize, Serialize};
+/// use std::time::Duration;
+///
+/// #[derive(Serialize)]
+/// struct ApiVersionsRequest {}
+///
+/// #[derive(Deserialize)]
+/// struct ApiVersionsResponse {
+///     error_code: i16,
+///     api_keys: Vec<(i16, i16, i16)>,
+/// }
+///
+/// let mut connection = BlockingConnection::connect("localhost:9092")?.client_id("my-tool");
+/// connection.get_ref().set_read_timeout(Some(Duration::from_secs(10)))?;
+/// let response: ApiVersionsResponse =
+///     connection.send(&Request::new(18, 0, ApiVersionsRequest {}))?;
+/// # Ok::<(), kafka_serde::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct BlockingConnection<S = TcpStream> {
+    stream: S,
+    client_id: Option<String>,
+    decoder: Decoder,
+    correlation_id: i32,
+    buf: Vec<u8>,
+    log: Logger,
+    throttle: Throttle,
+    // a request failed while in flight
+    in_flight: bool,
+}
+
+impl BlockingConnection<TcpStream> {
+    /// Connects to the broker at `addr`
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        // requests are small and wait for their response: don't delay them
+        stream.set_nodelay(true)?;
+        Ok(BlockingConnection::new(stream))
+    }
+}
+
+impl<S> BlockingConnection<S> {
+    /// Creates a connection over `stream`, sending requests without a client id
+    pub fn new(stream: S) -> Self {
+        BlockingConnection {
+            stream,
+            client_id: None,
+            decoder: Decoder::new(),
+            correlation_id: 0,
+            buf: Vec::new(),
+            log: Logger::default(),
+            throttle: Throttle::default(),
+            in_flight: false,
+        }
+    }
+
+    /// Sets the client id sent in request headers, which brokers log and apply quotas to
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    /// Sets the decoder of response bodies, e.g. to limit what they can allocate
+    pub fn decoder(mut self, decoder: Decoder) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    /// Sets a log receiving every request sent, with its response and how long it took
+    pub fn log(mut self, log: impl RequestLog + 'static) -> Self {
+        self.log = Logger::new(log);
+        self
+    }
+
+    /// Sets whether requests wait for the throttle time of the previous response, which brokers
+    /// set when the client exceeds its quotas. Defaults to true
+    ///
+    /// Brokers stop reading from throttled connections for that long: requests sent meanwhile
+    /// time out as if the broker were down.
+    pub fn throttle(mut self, throttle: bool) -> Self {
+        self.throttle.set_enabled(throttle);
+        self
+    }
+
+    /// Returns the stream, e.g. to set its timeouts
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns the stream
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Read + Write> BlockingConnection<S> {
+    /// Sends `request`, then reads and decodes its response
+    ///
+    /// Brokers don't answer Produce requests with acks=0: they are only written, and their
+    /// response is `()`, or `None`.
+    pub fn send<B, R>(&mut self, request: &Request<B, R>) -> Result<R>
+    where
+        B: Serialize,
+        R: DeserializeOwned,
+    {
+        if self.in_flight {
+            return Err(ErrorKind::Custom(
+                "a request failed while in flight, the connection is out of sync".into(),
+            )
+            .into());
+        }
+        if let Some(remaining) = self.throttle.remaining() {
+            thread::sleep(remaining);
+        }
+        let correlation_id = self.correlation_id;
+        self.correlation_id = self.correlation_id.wrapping_add(1);
+
+        self.buf.clear();
+        encode_request(
+            &mut self.buf,
+            request,
+            correlation_id,
+            self.client_id.as_deref(),
+        )?;
+        let start = self.log.start();
+        let request_size = self.buf.len();
+        if !request_expects_response(&self.buf, request) {
+            let response = no_response()?;
+            let written = self.write(request.api_key);
+            if written.is_ok() {
+                self.in_flight = false;
+            }
+            self.log.report(
+                start,
+                request.api_key,
+                request.api_version,
+                request.response_header_version(),
+                correlation_id,
+                request_size,
+                None,
+                written.as_ref().err(),
+            );
+            return written.map(|()| response);
+        }
+        let read = self.round_trip(request.api_key);
+        let frame_read = read.is_ok();
+        if frame_read {
+            self.throttle.update(
+                request.api_key,
+                request.api_version,
+                request.response_header_version(),
+                &self.buf,
+            );
+        }
+        let response =
+            read.and_then(|()| decode_response(&self.buf, request, correlation_id, self.decoder));
+        self.log.report(
+            start,
+            request.api_key,
+            request.api_version,
+            request.response_header_version(),
+            correlation_id,
+            request_size,
+            if frame_read {
+                Some(&self.buf[..])
+            } else {
+                None
+            },
+            response.as_ref().err(),
+        );
+        response
+    }
+
+    /// Writes the request frame in the buffer, leaving the request in flight
+    fn write(&mut self, api_key: i16) -> Result<()> {
+        self.in_flight = true;
+        self.stream.write_all(&self.buf)?;
+        self.stream.flush()?;
+        metrics::report_request(api_key, self.buf.len());
+        Ok(())
+    }
+
+    /// Writes the request frame in the buffer, then reads the response frame into it, stripped
+    /// of its size
+    fn round_trip(&mut self, api_key: i16) -> Result<()> {
+        self.write(api_key)?;
+
+        let mut size = [0; 4];
+        self.stream.read_exact(&mut size)?;
+        let size = i32::from_be_bytes(size);
+        if size < 0 {
+            return Err(ErrorKind::InvalidLength(size).into());
+        }
+        read_frame(&mut self.stream, size as usize, &mut self.buf)?;
+        self.in_flight = false;
+        metrics::report_response(api_key, self.buf.len() + 4);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::{expects_response, LoggedRequest};
+    use crate::header;
+    use crate::produce::PRODUCE_KEY;
+    use serde::Deserialize;
+    use std::convert::TryInto;
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Echo {
+        n: i32,
+    }
+
+    /// A v3 Produce request with a null transactional id and no topics
+    #[derive(Clone, Copy, Serialize)]
+    struct Produce {
+        transactional_id: i16,
+        acks: i16,
+        timeout_ms: i32,
+        topics: i32,
+    }
+
+    /// Answers requests with their correlation id and the last 4 bytes of their body, plus one,
+    /// except Produce requests with acks=0
+    fn serve(mut stream: TcpStream) {
+        let mut size = [0; 4];
+        while stream.read_exact(&mut size).is_ok() {
+            let mut frame = vec![0; i32::from_be_bytes(size) as usize];
+            stream.read_exact(&mut frame).unwrap();
+            let (header, body) = header::split_request(&frame, |_, _| false).unwrap();
+            if !expects_response(header.api_key, header.api_version, body, false) {
+                continue;
+            }
+            let n = i32::from_be_bytes(frame[frame.len() - 4..].try_into().unwrap());
+            let mut response = vec![0, 0, 0, 8];
+            response.extend_from_slice(&frame[4..8]);
+            response.extend_from_slice(&(n + 1).to_be_bytes());
+            stream.write_all(&response).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_send() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || serve(listener.accept().unwrap().0));
+
+        let mut connection = BlockingConnection::connect(addr).unwrap().client_id("test");
+        for n in 0..3 {
+            let response: Echo = connection.send(&Request::new(1, 0, Echo { n })).unwrap();
+            assert_eq!(response, Echo { n: n + 1 });
+        }
+        drop(connection);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_acks_0() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || serve(listener.accept().unwrap().0));
+
+        let mut connection = BlockingConnection::connect(addr).unwrap();
+        let produce = Produce {
+            transactional_id: -1,
+            acks: 0,
+            timeout_ms: 1000,
+            topics: 0,
+        };
+        let request = Request::<_, Echo>::new(PRODUCE_KEY, 3, produce);
+        assert!(connection.send(&request).is_err());
+        let request = Request::<_, ()>::new(PRODUCE_KEY, 3, produce);
+        connection.send(&request).unwrap();
+
+        // the next request gets its own response
+        let response: Echo = connection.send(&Request::new(1, 0, Echo { n: 1 })).unwrap();
+        assert_eq!(response, Echo { n: 2 });
+        drop(connection);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_log() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || serve(listener.accept().unwrap().0));
+
+        type Entry = (i16, i32, usize, Option<usize>, Option<Vec<u8>>, bool);
+        let entries: Arc<Mutex<Vec<Entry>>> = Arc::default();
+        let log = entries.clone();
+        let mut connection =
+            BlockingConnection::connect(addr)
+                .unwrap()
+                .log(move |request: &LoggedRequest<'_>| {
+                    log.lock().unwrap().push((
+                        request.api_key,
+                        request.correlation_id,
+                        request.request_size,
+                        request.response_size,
+                        request.response.map(<[u8]>::to_vec),
+                        request.error.is_some(),
+                    ))
+                });
+        let _: Echo = connection.send(&Request::new(1, 0, Echo { n: 1 })).unwrap();
+        // the response announces 8 integers, but holds none
+        assert!(connection
+            .send::<_, Vec<i32>>(&Request::new(3, 0, Echo { n: 7 }))
+            .is_err());
+        drop(connection);
+        server.join().unwrap();
+
+        assert_eq!(
+            *entries.lock().unwrap(),
+            [
+                (1, 0, 18, Some(12), Some(vec![0, 0, 0, 2]), false),
+                (3, 1, 18, Some(12), Some(vec![0, 0, 0, 8]), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_request() {
+        // the broker hangs up before answering
+        let mut connection = BlockingConnection::new(std::io::Cursor::new(Vec::new()));
+        let request = Request::<_, Echo>::new(1, 0, Echo { n: 0 });
+        match *connection.send(&request).unwrap_err().kind() {
+            ErrorKind::Io(ref err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        match *connection.send(&request).unwrap_err().kind() {
+            ErrorKind::Custom(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::log::Logger;
+use super::throttle::Throttle;
+use super::{
+    decode_response, encode_request, no_response, request_expects_response, Request, RequestLog,
+    FRAME_CHUNK,
+};
+use crate::error::{ErrorKind, Result};
+use crate::{metrics, Decoder};
+
+/// A connection to a broker, sending requests over an async stream and decoding their
+/// responses
+///
+/// Requests are sent one at a time: each one waits for its response before the next one is
+/// written. The stream is usually a `TcpStream`, or a TLS stream wrapping one.
+///
+/// A request that is dropped while in flight, e.g. by a timeout, leaves its response in the
+/// stream. The connection can't tell it apart from the responses that follow, and fails every
+/// request after that: it must be replaced.
+///
+/// # Examples
+/// ```no_run
+/// use kafka_serde::client::{Connection, Request};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize)]
+/// struct ApiVersionsRequest {}
+///
+/// #[derive(Deserialize)]
+/// struct ApiVersionsResponse {
+///     error_code: i16,
+///     api_keys: Vec<(i16, i16, i16)>,
+/// }
+///
+/// async fn api_versions(broker: &str) -> kafka_serde::Result<ApiVersionsResponse> {
+///     let stream = tokio::net::TcpStream::connect(broker).await?;
+///     let mut connection = Connection::new(stream).client_id("my-client");
+///     connection.send(&Request::new(18, 0, ApiVersionsRequest {})).await
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Connection<S> {
+    stream: S,
+    client_id: Option<String>,
+    decoder: Decoder,
+    correlation_id: i32,
+    buf: Vec<u8>,
+    log: Logger,
+    throttle: Throttle,
+    // a request is in flight, or was dropped while it was
+    in_flight: bool,
+}
+
+impl<S> Connection<S> {
+    /// Creates a connection over `stream`, sending requests without a client id
+    pub fn new(stream: S) -> Self {
+        Connection {
+            stream,
+            client_id: None,
+            decoder: Decoder::new(),
+            correlation_id: 0,
+            buf: Vec::new(),
+            log: Logger::default(),
+            throttle: Throttle::default(),
+            in_flight: false,
+        }
+    }
+
+    /// Sets the client id sent in request headers, which brokers log and apply quotas to
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    /// Sets the decoder of response bodies, e.g. to limit what they can allocate
+    pub fn decoder(mut self, decoder: Decoder) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    /// Sets a log receiving every request sent, with its response and how long it took
+    pub fn log(mut self, log: impl RequestLog + 'static) -> Self {
+        self.log = Logger::new(log);
+        self
+    }
+
+    /// Sets whether requests wait for the throttle time of the previous response, which brokers
+    /// set when the client exceeds its quotas. Defaults to true
+    ///
+    /// Brokers stop reading from throttled connections for that long: requests sent meanwhile
+    /// time out as if the broker were down.
+    pub fn throttle(mut self, throttle: bool) -> Self {
+        self.throttle.set_enabled(throttle);
+        self
+    }
+
+    /// Returns the stream
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    /// Sends `request`, then reads and decodes its response
+    ///
+    /// Brokers don't answer Produce requests with acks=0: they are only written, and their
+    /// response is `()`, or `None`.
+    pub async fn send<B, R>(&mut self, request: &Request<B, R>) -> Result<R>
+    where
+        B: Serialize,
+        R: DeserializeOwned,
+    {
+        if self.in_flight {
+            return Err(ErrorKind::Custom(
+                "a request was dropped while in flight, the connection is out of sync".into(),
+            )
+            .into());
+        }
+        if let Some(remaining) = self.throttle.remaining() {
+            tokio::time::sleep(remaining).await;
+        }
+        let correlation_id = self.correlation_id;
+        self.correlation_id = self.correlation_id.wrapping_add(1);
+
+        self.buf.clear();
+        encode_request(
+            &mut self.buf,
+            request,
+            correlation_id,
+            self.client_id.as_deref(),
+        )?;
+        let start = self.log.start();
+        let request_size = self.buf.len();
+        if !request_expects_response(&self.buf, request) {
+            let response = no_response()?;
+            let written = self.write(request.api_key).await;
+            if written.is_ok() {
+                self.in_flight = false;
+            }
+            self.log.report(
+                start,
+                request.api_key,
+                request.api_version,
+                request.response_header_version(),
+                correlation_id,
+                request_size,
+                None,
+                written.as_ref().err(),
+            );
+            return written.map(|()| response);
+        }
+        let read = self.round_trip(request.api_key).await;
+        let frame_read = read.is_ok();
+        if frame_read {
+            self.throttle.update(
+                request.api_key,
+                request.api_version,
+                request.response_header_version(),
+                &self.buf,
+            );
+        }
+        let response =
+            read.and_then(|()| decode_response(&self.buf, request, correlation_id, self.decoder));
+        self.log.report(
+            start,
+            request.api_key,
+            request.api_version,
+            request.response_header_version(),
+            correlation_id,
+            request_size,
+            if frame_read {
+                Some(&self.buf[..])
+            } else {
+                None
+            },
+            response.as_ref().err(),
+        );
+        response
+    }
+
+    /// Writes the request frame in the buffer, leaving the request in flight
+    async fn write(&mut self, api_key: i16) -> Result<()> {
+        self.in_flight = true;
+        self.stream.write_all(&self.buf).await?;
+        self.stream.flush().await?;
+        metrics::report_request(api_key, self.buf.len());
+        Ok(())
+    }
+
+    /// Writes the request frame in the buffer, then reads the response frame into it, stripped
+    /// of its size
+    async fn round_trip(&mut self, api_key: i16) -> Result<()> {
+        self.write(api_key).await?;
+
+        let mut size = [0; 4];
+        self.stream.read_exact(&mut size).await?;
+        let size = i32::from_be_bytes(size);
+        if size < 0 {
+            return Err(ErrorKind::InvalidLength(size).into());
+        }
+        // the frame is allocated as its bytes arrive, see `read_frame`
+        let size = size as usize;
+        self.buf.clear();
+        self.buf.reserve(size.min(FRAME_CHUNK));
+        (&mut self.stream)
+            .take(size as u64)
+            .read_to_end(&mut self.buf)
+            .await?;
+        if self.buf.len() < size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        self.in_flight = false;
+        metrics::report_response(api_key, self.buf.len() + 4);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::client::expects_response;
+    use crate::header;
+    use crate::produce::PRODUCE_KEY;
+    use serde::Deserialize;
+    use std::convert::TryInto;
+    use tokio::io::DuplexStream;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Echo {
+        n: i32,
+    }
+
+    /// A v3 Produce request with a null transactional id and no topics
+    #[derive(Clone, Copy, Serialize)]
+    struct Produce {
+        transactional_id: i16,
+        acks: i16,
+        timeout_ms: i32,
+        topics: i32,
+    }
+
+    /// Answers requests with their correlation id and the last 4 bytes of their body, plus one,
+    /// except Produce requests with acks=0
+    pub(crate) async fn serve(mut stream: DuplexStream) {
+        let mut size = [0; 4];
+        while stream.read_exact(&mut size).await.is_ok() {
+            let mut frame = vec![0; i32::from_be_bytes(size) as usize];
+            stream.read_exact(&mut frame).await.unwrap();
+            let (header, body) = header::split_request(&frame, |_, _| false).unwrap();
+            if !expects_response(header.api_key, header.api_version, body, false) {
+                continue;
+            }
+            let n = i32::from_be_bytes(frame[frame.len() - 4..].try_into().unwrap());
+            let mut response = vec![0, 0, 0, 8];
+            response.extend_from_slice(&frame[4..8]);
+            response.extend_from_slice(&(n + 1).to_be_bytes());
+            stream.write_all(&response).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let (client, server) = tokio::io::duplex(64);
+        tokio::spawn(serve(server));
+        let mut connection = Connection::new(client).client_id("test");
+        for n in 0..3 {
+            let response: Echo = connection
+                .send(&Request::new(1, 0, Echo { n }))
+                .await
+                .unwrap();
+            assert_eq!(response, Echo { n: n + 1 });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_acks_0() {
+        let (client, server) = tokio::io::duplex(64);
+        tokio::spawn(serve(server));
+        let mut connection = Connection::new(client);
+        let produce = Produce {
+            transactional_id: -1,
+            acks: 0,
+            timeout_ms: 1000,
+            topics: 0,
+        };
+        let request = Request::<_, Echo>::new(PRODUCE_KEY, 3, produce);
+        assert!(connection.send(&request).await.is_err());
+        let request = Request::<_, ()>::new(PRODUCE_KEY, 3, produce);
+        connection.send(&request).await.unwrap();
+
+        // the next request gets its own response
+        let response: Echo = connection
+            .send(&Request::new(1, 0, Echo { n: 1 }))
+            .await
+            .unwrap();
+        assert_eq!(response, Echo { n: 2 });
+    }
+
+    #[tokio::test]
+    async fn test_dropped_request() {
+        let (client, _server) = tokio::io::duplex(64);
+        let mut connection = Connection::new(client);
+        let request = Request::<_, Echo>::new(1, 0, Echo { n: 0 });
+        // the server never answers
+        let send = connection.send(&request);
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(10), send)
+                .await
+                .is_err()
+        );
+        assert!(connection.send(&request).await.is_err());
+    }
+}
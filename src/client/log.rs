@@ -0,0 +1,176 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::header;
+use crate::schema::Schema;
+
+/// Receives every request sent by a connection, with its response, e.g. to audit the traffic
+/// of a client or to debug it
+///
+/// Requests are logged on the task or thread sending them, once their response is decoded or
+/// they failed: logging should be quick, like writing to a channel or a buffered file. Closures
+/// taking a [`LoggedRequest`] implement it.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::client::{BlockingConnection, LoggedRequest};
+///
+/// let connection = BlockingConnection::new(std::io::Cursor::new(Vec::<u8>::new())).log(
+///     |request: &LoggedRequest<'_>| {
+///         eprintln!(
+///             "api {} v{} #{}: {} bytes in {:?}, error {:?}",
+///             request.api_key,
+///             request.api_version,
+///             request.correlation_id,
+///             request.request_size,
+///             request.latency,
+///             request.error
+///         );
+///     },
+/// );
+/// ```
+pub trait RequestLog: Send + Sync {
+    /// Logs a request and its response
+    fn log(&self, request: &LoggedRequest<'_>);
+}
+
+impl<F: Fn(&LoggedRequest<'_>) + Send + Sync> RequestLog for F {
+    fn log(&self, request: &LoggedRequest<'_>) {
+        self(request)
+    }
+}
+
+/// A request sent by a connection, with its response, as given to a [`RequestLog`]
+#[derive(Debug, Clone, Copy)]
+pub struct LoggedRequest<'a> {
+    /// The API key
+    pub api_key: i16,
+    /// The API version
+    pub api_version: i16,
+    /// The correlation id of the request
+    pub correlation_id: i32,
+    /// How long it took to write the request, then to read and decode its response, or to
+    /// fail
+    pub latency: Duration,
+    /// The size of the request frame, size included
+    pub request_size: usize,
+    /// The size of the response frame, size included, unless no response was read
+    pub response_size: Option<usize>,
+    /// The response body, stripped of the response header, unless no response was read
+    pub response: Option<&'a [u8]>,
+    /// Why the request failed, if it did
+    pub error: Option<&'a Error>,
+}
+
+impl LoggedRequest<'_> {
+    /// Reads the top-level error code of the response, decoding it with `schema`
+    ///
+    /// Returns `None` if no response was read, if it doesn't decode, or if it has no top-level
+    /// `ErrorCode` field, like responses holding an error code per partition.
+    pub fn error_code(&self, schema: &Schema) -> Option<i16> {
+        let response = schema.decode(self.api_version, self.response?).ok()?;
+        let error_code = response
+            .get("ErrorCode")
+            .or_else(|| response.get("error_code"))?;
+        error_code.as_i64().map(|code| code as i16)
+    }
+}
+
+/// The log of a connection, if it has one
+#[derive(Clone, Default)]
+pub(crate) struct Logger(Option<Arc<dyn RequestLog>>);
+
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Some(RequestLog)"),
+            None => f.write_str("None"),
+        }
+    }
+}
+
+impl Logger {
+    pub(crate) fn new(log: impl RequestLog + 'static) -> Self {
+        Logger(Some(Arc::new(log)))
+    }
+
+    /// The time a request starts, if it will be logged. Clocks are only read when logging, as
+    /// some targets, like `wasm32-unknown-unknown`, have none
+    pub(crate) fn start(&self) -> Option<Instant> {
+        self.0.as_ref().map(|_| Instant::now())
+    }
+
+    /// Logs a request that started at `start`. `frame` is the response frame stripped of its
+    /// size, if one was read
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn report(
+        &self,
+        start: Option<Instant>,
+        api_key: i16,
+        api_version: i16,
+        response_header_version: i16,
+        correlation_id: i32,
+        request_size: usize,
+        frame: Option<&[u8]>,
+        error: Option<&Error>,
+    ) {
+        let (log, start) = match (&self.0, start) {
+            (Some(log), Some(start)) => (log, start),
+            _ => return,
+        };
+        let response = frame.and_then(|frame| {
+            header::split_response(frame, response_header_version)
+                .ok()
+                .map(|(_, body)| body)
+        });
+        log.log(&LoggedRequest {
+            api_key,
+            api_version,
+            correlation_id,
+            latency: start.elapsed(),
+            request_size,
+            response_size: frame.map(|frame| frame.len() + 4),
+            response,
+            error,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::{Field, Type};
+
+    #[test]
+    fn test_error_code() {
+        let schema = Schema::new(
+            "HeartbeatResponse",
+            vec![
+                Field::new("ThrottleTimeMs", Type::Int32),
+                Field::new("ErrorCode", Type::Int16),
+            ],
+        );
+        let mut request = LoggedRequest {
+            api_key: 12,
+            api_version: 1,
+            correlation_id: 0,
+            latency: Duration::from_millis(1),
+            request_size: 4,
+            response_size: Some(14),
+            response: Some(&[0, 0, 0, 0, 0, 27]),
+            error: None,
+        };
+        assert_eq!(request.error_code(&schema), Some(27));
+        request.response = Some(&[0]);
+        assert_eq!(request.error_code(&schema), None);
+        request.response = None;
+        assert_eq!(request.error_code(&schema), None);
+    }
+}
@@ -0,0 +1,336 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Sending requests to brokers
+//!
+//! A [`Request`] pairs a message body with the API it calls, and with the type its response
+//! decodes into. Requests are framed with the request header, and responses stripped of the
+//! response header, in the versions the API uses: flexible versions add tagged fields to both.
+//!
+//! With the `tokio` feature, a `Connection` sends requests over any tokio stream and decodes
+//! their responses. With the `tower` feature, a `KafkaService` exposes a connection as a
+//! `tower::Service`, to be wrapped in retry, timeout or load-balancing middleware. Without an
+//! async runtime, a [`BlockingConnection`] does the same over a `TcpStream`, or any blocking
+//! stream.
+//!
+//! Connections can report every request they send to a [`RequestLog`], with its response, its
+//! latency and its error if it failed, to audit or debug the traffic to brokers. They also wait
+//! for the throttle time of responses before sending the next request, as brokers enforcing
+//! quotas expect.
+use std::fmt;
+use std::io::{self, Read};
+use std::marker::PhantomData;
+use std::mem;
+
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::Serialize;
+
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::produce::PRODUCE_KEY;
+use crate::{header, ser, Decoder, FrameWriter, KafkaRequest};
+
+mod blocking;
+#[cfg(feature = "tokio")]
+mod connection;
+mod log;
+#[cfg(feature = "tower")]
+mod service;
+mod throttle;
+
+pub use self::blocking::BlockingConnection;
+#[cfg(feature = "tokio")]
+pub use self::connection::Connection;
+pub use self::log::{LoggedRequest, RequestLog};
+#[cfg(feature = "tower")]
+pub use self::service::KafkaService;
+
+/// How much room is made for a frame before its bytes arrive. The rest is allocated as they
+/// do, as frame sizes come from the peer, which could claim gigabytes it never sends
+pub(crate) const FRAME_CHUNK: usize = 64 * 1024;
+
+/// Reads the `size` bytes of a frame body into `buf`, failing if the reader ends before
+pub(crate) fn read_frame<R: Read>(reader: &mut R, size: usize, buf: &mut Vec<u8>) -> Result<()> {
+    buf.clear();
+    buf.reserve(size.min(FRAME_CHUNK));
+    reader.take(size as u64).read_to_end(buf)?;
+    if buf.len() < size {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(())
+}
+
+/// A request to a broker: the API it calls, and its body
+///
+/// `R` is the type the response body decodes into, usually inferred from where the response
+/// goes.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::client::Request;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize)]
+/// struct ApiVersionsRequest {}
+///
+/// #[derive(Deserialize)]
+/// struct ApiVersionsResponse {
+///     error_code: i16,
+///     api_keys: Vec<(i16, i16, i16)>,
+/// }
+///
+/// let request: Request<_, ApiVersionsResponse> = Request::new(18, 0, ApiVersionsRequest {});
+/// assert_eq!(request.api_key, 18);
+/// ```
+pub struct Request<B, R> {
+    /// The API key
+    pub api_key: i16,
+    /// The API version the body is encoded in
+    pub api_version: i16,
+    /// Whether `api_version` is a flexible version of the API, whose headers end with tagged
+    /// fields
+    pub flexible: bool,
+    /// The request body
+    pub body: B,
+    response: PhantomData<fn() -> R>,
+}
+
+impl<B, R> Request<B, R> {
+    /// Creates a request for a version of an API that is not flexible
+    pub fn new(api_key: i16, api_version: i16, body: B) -> Self {
+        Request {
+            api_key,
+            api_version,
+            flexible: false,
+            body,
+            response: PhantomData,
+        }
+    }
+
+    /// Sets whether the version of the API is flexible
+    pub fn flexible(mut self, flexible: bool) -> Self {
+        self.flexible = flexible;
+        self
+    }
+
+    /// The version of the response header. ApiVersions responses never end it with tagged
+    /// fields, so that clients can read them whatever version they asked for
+    fn response_header_version(&self) -> i16 {
+        header::response_header_version(self.api_key, self.flexible)
+    }
+}
+
+impl<B: KafkaRequest> Request<B, B::Response> {
+    /// Creates a request for `version` of the API of `body`, which decodes its response into
+    /// the response type of the body. Fails if the body can't be sent in `version`
+    ///
+    /// # Examples
+    /// ```
+    /// use kafka_serde::client::Request;
+    /// use kafka_serde::KafkaRequest;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Serialize)]
+    /// struct ApiVersionsRequest {}
+    ///
+    /// #[derive(Deserialize)]
+    /// struct ApiVersionsResponse {
+    ///     error_code: i16,
+    ///     api_keys: Vec<(i16, i16, i16)>,
+    /// }
+    ///
+    /// impl KafkaRequest for ApiVersionsRequest {
+    ///     const API_KEY: i16 = 18;
+    ///     const MIN_VERSION: i16 = 0;
+    ///     const MAX_VERSION: i16 = 0;
+    ///     type Response = ApiVersionsResponse;
+    /// }
+    ///
+    /// let request = Request::message(0, ApiVersionsRequest {}).unwrap();
+    /// assert_eq!(request.api_key, 18);
+    /// assert!(Request::message(1, ApiVersionsRequest {}).is_err());
+    /// ```
+    pub fn message(api_version: i16, body: B) -> Result<Self> {
+        B::check_version(api_version)?;
+        Ok(Request::new(B::API_KEY, api_version, body).flexible(B::is_flexible(api_version)))
+    }
+}
+
+impl<B: Clone, R> Clone for Request<B, R> {
+    fn clone(&self) -> Self {
+        Request {
+            api_key: self.api_key,
+            api_version: self.api_version,
+            flexible: self.flexible,
+            body: self.body.clone(),
+            response: PhantomData,
+        }
+    }
+}
+
+impl<B: fmt::Debug, R> fmt::Debug for Request<B, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Request")
+            .field("api_key", &self.api_key)
+            .field("api_version", &self.api_version)
+            .field("flexible", &self.flexible)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+/// Appends the frame of `request` to `buf`: its size, the request header and the body
+pub(crate) fn encode_request<B: Serialize, R>(
+    buf: &mut Vec<u8>,
+    request: &Request<B, R>,
+    correlation_id: i32,
+    client_id: Option<&str>,
+) -> Result<()> {
+    let mut frame = FrameWriter::with_buffer(mem::take(buf));
+    let result = frame.size_prefixed(|frame| {
+        header::write_request_header(
+            frame.buf_mut(),
+            request.api_key,
+            request.api_version,
+            request.flexible,
+            correlation_id,
+            client_id,
+        )?;
+        ser::append_to_vec(frame.buf_mut(), &request.body)
+    });
+    *buf = frame.into_inner();
+    result
+}
+
+/// Whether brokers answer a request to `api_version` of `api_key` whose body is `body`: they
+/// don't answer Produce requests with acks=0. `body` starts with the tagged fields of the
+/// request header if `header_tagged_fields`, as bodies split without the schema of the API do
+pub(crate) fn expects_response(
+    api_key: i16,
+    api_version: i16,
+    body: &[u8],
+    header_tagged_fields: bool,
+) -> bool {
+    if api_key != PRODUCE_KEY {
+        return true;
+    }
+    let acks = || {
+        let mut de = KafkaDeserializer::new(body);
+        if api_version >= 9 {
+            if header_tagged_fields {
+                de.skip_tagged_fields()?;
+            }
+            de.read_compact_nullable_string()?;
+        } else if api_version >= 3 {
+            de.read_nullable_string()?;
+        }
+        de.read_i16()
+    };
+    // bodies that can't be read may still be answered
+    acks().map_or(true, |acks| acks != 0)
+}
+
+/// Whether brokers answer `request`, whose frame is in `frame` with its size
+fn request_expects_response<B, R>(frame: &[u8], request: &Request<B, R>) -> bool {
+    header::split_request(&frame[4..], |_, _| request.flexible).map_or(true, |(_, body)| {
+        expects_response(request.api_key, request.api_version, body, false)
+    })
+}
+
+/// The response of requests that brokers don't answer, as told by [`expects_response`]: `()`,
+/// or `None`
+fn no_response<R: DeserializeOwned>() -> Result<R> {
+    let unit: serde::de::value::UnitDeserializer<serde::de::value::Error> = ().into_deserializer();
+    R::deserialize(unit).map_err(|_| {
+        ErrorKind::Custom(
+            "brokers don't answer Produce requests with acks=0: send them with a response of ()"
+                .into(),
+        )
+        .into()
+    })
+}
+
+/// Decodes the body of the response to `request`, from a frame stripped of its size
+pub(crate) fn decode_response<B, R: DeserializeOwned>(
+    frame: &[u8],
+    request: &Request<B, R>,
+    correlation_id: i32,
+    decoder: Decoder,
+) -> Result<R> {
+    let (header, body) = header::split_response(frame, request.response_header_version())?;
+    let response_id = header.correlation_id;
+    if response_id != correlation_id {
+        return Err(ErrorKind::Custom(format!(
+            "expected the response to request {}, got the one to {}",
+            correlation_id, response_id
+        ))
+        .into());
+    }
+    decoder.decode(body)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Body {
+        n: i32,
+    }
+
+    #[test]
+    fn test_read_frame() {
+        let mut buf = Vec::new();
+        read_frame(&mut &[1, 2, 3, 4][..], 3, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3]);
+
+        // the size comes from the peer, which sends far less
+        let err = read_frame(&mut &[1, 2, 3][..], i32::MAX as usize, &mut buf).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Io(ref err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert!(buf.capacity() < 1 << 20);
+    }
+
+    #[test]
+    fn test_encode_request() {
+        let mut buf = vec![0xff];
+        let request: Request<_, Body> = Request::new(3, 1, Body { n: 7 });
+        encode_request(&mut buf, &request, 5, Some("c")).unwrap();
+        assert_eq!(
+            buf,
+            [0xff, 0, 0, 0, 15, 0, 3, 0, 1, 0, 0, 0, 5, 0, 1, b'c', 0, 0, 0, 7]
+        );
+
+        buf.clear();
+        encode_request(&mut buf, &request.clone().flexible(true), 5, None).unwrap();
+        assert_eq!(
+            buf,
+            [0, 0, 0, 15, 0, 3, 0, 1, 0, 0, 0, 5, 0xff, 0xff, 0, 0, 0, 0, 7]
+        );
+    }
+
+    #[test]
+    fn test_decode_response() {
+        let request: Request<_, Body> = Request::new(3, 1, Body { n: 7 });
+        let frame = [0, 0, 0, 5, 0, 0, 0, 9];
+        let body = decode_response(&frame, &request, 5, Decoder::new()).unwrap();
+        assert_eq!(body, Body { n: 9 });
+        assert!(decode_response(&frame, &request, 6, Decoder::new()).is_err());
+
+        let flexible = request.flexible(true);
+        let frame = [0, 0, 0, 5, 0, 0, 0, 0, 9];
+        let body = decode_response(&frame, &flexible, 5, Decoder::new()).unwrap();
+        assert_eq!(body, Body { n: 9 });
+
+        // ApiVersions responses keep the v0 header
+        let api_versions: Request<_, Body> = Request::new(18, 3, Body { n: 0 }).flexible(true);
+        let frame = [0, 0, 0, 5, 0, 0, 0, 9];
+        let body = decode_response(&frame, &api_versions, 5, Decoder::new()).unwrap();
+        assert_eq!(body, Body { n: 9 });
+    }
+}
@@ -0,0 +1,120 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::Mutex;
+use tower_service::Service;
+
+use super::{Connection, Request};
+use crate::error::{Error, Result};
+
+/// A [`Connection`] as a `tower::Service`, taking [`Request`]s and returning their decoded
+/// responses
+///
+/// Clones share the connection, and their requests wait for their turn to be sent. Dropping a
+/// response future before it completes, as timeouts do, makes the connection fail every
+/// request after that, see [`Connection`].
+///
+/// # Examples
+/// ```no_run
+/// use kafka_serde::client::{Connection, KafkaService, Request};
+/// use serde::{Deserialize, Serialize};
+/// use tower_service::Service;
+///
+/// #[derive(Serialize)]
+/// struct ApiVersionsRequest {}
+///
+/// #[derive(Deserialize)]
+/// struct ApiVersionsResponse {
+///     error_code: i16,
+///     api_keys: Vec<(i16, i16, i16)>,
+/// }
+///
+/// type ApiVersions = Request<ApiVersionsRequest, ApiVersionsResponse>;
+///
+/// async fn api_versions(broker: &str) -> kafka_serde::Result<ApiVersionsResponse> {
+///     let stream = tokio::net::TcpStream::connect(broker).await?;
+///     let mut service = KafkaService::new(Connection::new(stream));
+///     std::future::poll_fn(|cx| Service::<ApiVersions>::poll_ready(&mut service, cx)).await?;
+///     service.call(Request::new(18, 0, ApiVersionsRequest {})).await
+/// }
+/// ```
+#[derive(Debug)]
+pub struct KafkaService<S> {
+    connection: Arc<Mutex<Connection<S>>>,
+}
+
+impl<S> KafkaService<S> {
+    /// Creates a service sending requests over `connection`
+    pub fn new(connection: Connection<S>) -> Self {
+        KafkaService {
+            connection: Arc::new(Mutex::new(connection)),
+        }
+    }
+}
+
+impl<S> Clone for KafkaService<S> {
+    fn clone(&self) -> Self {
+        KafkaService {
+            connection: self.connection.clone(),
+        }
+    }
+}
+
+impl<S, B, R> Service<Request<B, R>> for KafkaService<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    B: Serialize + Send + Sync + 'static,
+    R: DeserializeOwned + Send + 'static,
+{
+    type Response = R;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<R>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        // requests queue up on the connection instead
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B, R>) -> Self::Future {
+        let connection = self.connection.clone();
+        Box::pin(async move { connection.lock().await.send(&request).await })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::connection::test::serve;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Echo {
+        n: i32,
+    }
+
+    #[tokio::test]
+    async fn test_service() {
+        let (client, server) = tokio::io::duplex(64);
+        tokio::spawn(serve(server));
+        let mut service = KafkaService::new(Connection::new(client));
+        let mut clone = service.clone();
+
+        std::future::poll_fn(|cx| Service::<Request<Echo, Echo>>::poll_ready(&mut service, cx))
+            .await
+            .unwrap();
+        let first = service.call(Request::new(1, 0, Echo { n: 1 }));
+        let second = clone.call(Request::new(1, 0, Echo { n: 2 }));
+        let (first, second): (Echo, Echo) = tokio::try_join!(first, second).unwrap();
+        assert_eq!((first.n, second.n), (2, 3));
+    }
+}
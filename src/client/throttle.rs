@@ -0,0 +1,204 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use std::time::{Duration, Instant};
+
+use crate::header;
+use crate::produce::PRODUCE_KEY;
+
+/// Where the throttle time is in the response bodies of a version of an API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    /// At a fixed offset from the start
+    Start(usize),
+    /// In the last 4 bytes
+    End,
+}
+
+/// Where the throttle time is in the responses to `api_version` of `api_key`, if they have one
+/// that can be found without decoding them
+///
+/// Most responses start with it. Those of ApiVersions put it after their array of API keys,
+/// those of the delegation token APIs after fields of variable size, and those of flexible
+/// versions of Produce before tagged fields: their throttle time is not looked for.
+fn position(api_key: i16, api_version: i16) -> Option<Position> {
+    let since = match api_key {
+        PRODUCE_KEY if api_version <= 8 => return (api_version >= 1).then_some(Position::End),
+        // OffsetDelete: after the error code
+        47 => return Some(Position::Start(2)),
+        // Fetch, FindCoordinator, Heartbeat, LeaveGroup, SyncGroup, DescribeGroups, ListGroups,
+        // DeleteTopics
+        1 | 10 | 12..=16 | 20 => 1,
+        // ListOffsets, JoinGroup, CreateTopics, OffsetForLeaderEpoch
+        2 | 11 | 19 | 23 => 2,
+        // Metadata, OffsetCommit, OffsetFetch
+        3 | 8 | 9 => 3,
+        // from DeleteRecords to DescribeLogDirs, then CreatePartitions
+        21..=26 | 28..=35 | 37 => 0,
+        // from DeleteGroups to AlterUserScramCredentials
+        42..=46 | 48..=51 => 0,
+        // DescribeCluster, ConsumerGroupHeartbeat, ConsumerGroupDescribe
+        60 | 68 | 69 => 0,
+        _ => return None,
+    };
+    (api_version >= since).then_some(Position::Start(0))
+}
+
+/// Whether the client waits for the throttle time of responses to `api_version` of `api_key`,
+/// like `shouldClientThrottle` of the Java client
+///
+/// Since KIP-219, brokers answer throttled requests at once and leave it to the client to wait.
+/// Before, they delayed the response themselves: waiting again would throttle twice.
+fn client_throttles(api_key: i16, api_version: i16) -> bool {
+    let since = match api_key {
+        PRODUCE_KEY => 6,
+        // Fetch
+        1 => 8,
+        // ListOffsets, JoinGroup, CreateTopics, OffsetForLeaderEpoch
+        2 | 11 | 19 | 23 => 3,
+        // Metadata
+        3 => 6,
+        // OffsetCommit, OffsetFetch
+        8 | 9 => 4,
+        // FindCoordinator, Heartbeat, LeaveGroup, SyncGroup, DescribeGroups, ListGroups,
+        // DeleteTopics, DescribeConfigs
+        10 | 12..=16 | 20 | 32 => 2,
+        // from DeleteRecords to DescribeLogDirs, then CreatePartitions and DeleteGroups
+        21..=26 | 28..=35 | 37 | 42 => 1,
+        // APIs added after KIP-219
+        _ => 0,
+    };
+    api_version >= since
+}
+
+/// Reads the throttle time of a response body to `api_version` of `api_key`, if it has one
+/// that can be found
+fn throttle_time_ms(api_key: i16, api_version: i16, body: &[u8]) -> Option<i32> {
+    let offset = match position(api_key, api_version)? {
+        Position::Start(offset) => offset,
+        Position::End => body.len().checked_sub(4)?,
+    };
+    let bytes = body.get(offset..offset + 4)?;
+    Some(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Delays requests while a broker throttles the connection
+///
+/// Brokers answer the requests of clients exceeding their quotas with how long they must wait
+/// before sending the next one, then stop reading from the connection for that long. Older
+/// versions of APIs are throttled by the broker alone.
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    enabled: bool,
+    until: Option<Instant>,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle {
+            enabled: true,
+            until: None,
+        }
+    }
+}
+
+impl Throttle {
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.until = None;
+        }
+    }
+
+    /// How long to wait before sending the next request, if the connection is throttled
+    pub(crate) fn remaining(&mut self) -> Option<Duration> {
+        // clocks are only read when throttled, as some targets, like wasm32-unknown-unknown,
+        // have none
+        let until = self.until?;
+        let now = Instant::now();
+        if until <= now {
+            self.until = None;
+            return None;
+        }
+        Some(until - now)
+    }
+
+    /// Reads the throttle time of a response frame, stripped of its size
+    pub(crate) fn update(
+        &mut self,
+        api_key: i16,
+        api_version: i16,
+        header_version: i16,
+        frame: &[u8],
+    ) {
+        if !self.enabled || !client_throttles(api_key, api_version) {
+            return;
+        }
+        let body = match header::split_response(frame, header_version) {
+            Ok((_, body)) => body,
+            Err(_) => return,
+        };
+        match throttle_time_ms(api_key, api_version, body) {
+            Some(ms) if ms > 0 => {
+                self.until = Some(Instant::now() + Duration::from_millis(ms as u64));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_throttle_time_ms() {
+        // Heartbeat v0 has no throttle time
+        assert_eq!(throttle_time_ms(12, 0, &[0, 0]), None);
+        assert_eq!(throttle_time_ms(12, 1, &[0, 0, 0, 9, 0, 0]), Some(9));
+        assert_eq!(throttle_time_ms(3, 2, &[0, 0, 0, 9]), None);
+        assert_eq!(throttle_time_ms(3, 12, &[0, 0, 0, 9]), Some(9));
+        assert_eq!(throttle_time_ms(47, 0, &[0, 0, 0, 0, 0, 9]), Some(9));
+        assert_eq!(throttle_time_ms(0, 0, &[0, 0, 0, 0, 0, 0, 0, 0]), None);
+        assert_eq!(throttle_time_ms(0, 8, &[0, 0, 0, 0, 0, 0, 0, 9]), Some(9));
+        assert_eq!(throttle_time_ms(0, 9, &[0, 0, 0, 0, 0, 0, 0, 9]), None);
+        assert_eq!(throttle_time_ms(1, 4, &[0, 0]), None);
+        assert_eq!(throttle_time_ms(1000, 0, &[0, 0, 0, 9]), None);
+    }
+
+    #[test]
+    fn test_client_throttles() {
+        assert!(!client_throttles(PRODUCE_KEY, 5));
+        assert!(client_throttles(PRODUCE_KEY, 6));
+        assert!(!client_throttles(1, 7));
+        assert!(client_throttles(1, 8));
+        assert!(!client_throttles(3, 5));
+        assert!(client_throttles(3, 6));
+        assert!(!client_throttles(12, 1));
+        assert!(client_throttles(12, 2));
+        assert!(client_throttles(47, 0));
+    }
+
+    #[test]
+    fn test_throttle() {
+        let mut throttle = Throttle::default();
+        assert_eq!(throttle.remaining(), None);
+        // the broker already delayed a throttled Heartbeat v1 response
+        throttle.update(12, 1, 0, &[0, 0, 0, 1, 0, 0, 0xea, 0x60, 0, 0]);
+        assert_eq!(throttle.remaining(), None);
+        // a Heartbeat v2 response throttled for a minute
+        throttle.update(12, 2, 0, &[0, 0, 0, 1, 0, 0, 0xea, 0x60, 0, 0]);
+        assert!(throttle.remaining().unwrap() > Duration::from_secs(59));
+        throttle.set_enabled(false);
+        assert_eq!(throttle.remaining(), None);
+        throttle.update(12, 2, 0, &[0, 0, 0, 1, 0, 0, 0xea, 0x60, 0, 0]);
+        assert_eq!(throttle.remaining(), None);
+
+        throttle.set_enabled(true);
+        throttle.update(12, 2, 0, &[0, 0, 0, 1, 0, 0, 0, 1, 0, 0]);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(throttle.remaining(), None);
+    }
+}
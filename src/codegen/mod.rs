@@ -0,0 +1,1006 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Rust structs generated from the message definitions of Apache Kafka
+//!
+//! Apache Kafka describes every message of its protocol in a JSON file
+//! (`clients/src/main/resources/common/message/*.json` in its repository). [`generate`] turns
+//! one of them into Rust source: a struct for the message and one for every structure nested in
+//! it, with `decode` and `encode` methods following the message [`Schema`]. Messages also
+//! implement [`Message`](crate::schema::Message), for code generic over them. Keeping copies of
+//! the upstream files, and generating the code from a build script, keeps the messages in sync
+//! with the protocol without maintaining them by hand.
+//!
+//! Field names are turned into snake case. Nullable fields are `Option`s, and fields that are
+//! not in a version hold their default value, the one set in the definition if there is one,
+//! like `-1` for ids. Messages also get a builder, whose `build` method checks that the fields
+//! that were set are in the version the message is built for.
+//!
+//! Each generated file imports what it needs from this crate, and must be included in a module
+//! of its own. Requests implement [`KafkaRequest`](crate::KafkaRequest), naming their response
+//! from the parent module: the modules of a request and its response should be siblings, with
+//! the response re-exported next to them.
+//!
+//! # Examples
+//! In `build.rs`:
+//! ```no_run
+//! use std::path::Path;
+//!
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! for name in ["FindCoordinatorRequest", "FindCoordinatorResponse"] {
+//!     let definition = format!("messages/{}.json", name);
+//!     let generated = Path::new(&out_dir).join(format!("{}.rs", name));
+//!     kafka_serde::codegen::generate_file(&definition, generated).unwrap();
+//!     println!("cargo:rerun-if-changed={}", definition);
+//! }
+//! ```
+//! Then in the crate:
+//! ```ignore
+//! mod find_coordinator_response {
+//!     include!(concat!(env!("OUT_DIR"), "/FindCoordinatorResponse.rs"));
+//! }
+//!
+//! let response = find_coordinator_response::FindCoordinatorResponse::decode(4, &buf)?;
+//! ```
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::error::{ErrorKind, Result};
+use crate::schema::{Field, Schema, Type};
+
+/// A message definition, as read from its JSON file
+#[derive(Debug)]
+struct Definition {
+    name: String,
+    api_key: Option<i16>,
+    // whether the definition is of a request, whose response is defined next to it
+    request: bool,
+    valid_versions: RangeInclusive<i16>,
+    flexible_versions: RangeInclusive<i16>,
+    fields: Vec<FieldDef>,
+    // the inline and common structures the fields are made of
+    structs: Vec<(String, Vec<FieldDef>)>,
+}
+
+#[derive(Debug)]
+struct FieldDef {
+    name: String,
+    about: Option<String>,
+    ty: TypeDef,
+    versions: RangeInclusive<i16>,
+    nullable_versions: RangeInclusive<i16>,
+    tag: Option<u32>,
+    // the Rust expression of the default value, unless it is the default of the type
+    default: Option<String>,
+}
+
+#[derive(Debug)]
+enum TypeDef {
+    Primitive(Type),
+    Array(Box<TypeDef>),
+    Struct(String),
+}
+
+fn invalid(what: impl fmt::Display) -> crate::Error {
+    ErrorKind::Custom(format!("invalid message definition: {}", what)).into()
+}
+
+impl Definition {
+    fn parse(json: &str) -> Result<Definition> {
+        let value: Value = serde_json::from_str(&strip_comments(json)).map_err(invalid)?;
+        let object = value.as_object().ok_or_else(|| invalid("not an object"))?;
+        let name = string(object, "name")?.ok_or_else(|| invalid("missing name"))?;
+        let api_key = match object.get("apiKey") {
+            Some(key) => Some(
+                key.as_i64()
+                    .and_then(|key| i16::try_from(key).ok())
+                    .ok_or_else(|| invalid("invalid apiKey"))?,
+            ),
+            None => None,
+        };
+        let mut definition = Definition {
+            api_key,
+            request: string(object, "type")? == Some("request"),
+            valid_versions: versions(object, "validVersions")?
+                .ok_or_else(|| invalid("missing validVersions"))?,
+            flexible_versions: versions(object, "flexibleVersions")?.unwrap_or(none()),
+            fields: Vec::new(),
+            structs: Vec::new(),
+            name: name.to_owned(),
+        };
+        for common in array(object, "commonStructs")? {
+            let common = common.as_object().ok_or_else(|| invalid("commonStructs"))?;
+            let name = string(common, "name")?.ok_or_else(|| invalid("unnamed common struct"))?;
+            let fields = definition.parse_fields(common)?;
+            definition.structs.push((name.to_owned(), fields));
+        }
+        definition.fields = definition.parse_fields(object)?;
+        Ok(definition)
+    }
+
+    fn parse_fields(&mut self, object: &Map<String, Value>) -> Result<Vec<FieldDef>> {
+        let mut fields = Vec::new();
+        for field in array(object, "fields")? {
+            let field = field.as_object().ok_or_else(|| invalid("fields"))?;
+            let name = string(field, "name")?.ok_or_else(|| invalid("unnamed field"))?;
+            let ty = string(field, "type")?
+                .ok_or_else(|| invalid(format_args!("field {} has no type", name)))?;
+            let ty = self.parse_type(ty, field)?;
+            let tag = match field.get("tag") {
+                Some(tag) => Some(
+                    tag.as_u64()
+                        .and_then(|tag| u32::try_from(tag).ok())
+                        .ok_or_else(|| invalid(format_args!("invalid tag of {}", name)))?,
+                ),
+                None => None,
+            };
+            let nullable_versions = versions(field, "nullableVersions")?.unwrap_or(none());
+            fields.push(FieldDef {
+                name: name.to_owned(),
+                about: string(field, "about")?.map(str::to_owned),
+                default: default_value(field, name, &ty, !nullable_versions.is_empty())?,
+                ty,
+                versions: versions(field, "versions")?
+                    .ok_or_else(|| invalid(format_args!("field {} has no versions", name)))?,
+                nullable_versions,
+                tag,
+            });
+        }
+        Ok(fields)
+    }
+
+    fn parse_type(&mut self, ty: &str, field: &Map<String, Value>) -> Result<TypeDef> {
+        if let Some(element) = ty.strip_prefix("[]") {
+            return Ok(TypeDef::Array(Box::new(self.parse_type(element, field)?)));
+        }
+        let primitive = match ty {
+            "bool" => Type::Bool,
+            "int8" => Type::Int8,
+            "int16" => Type::Int16,
+            "uint16" => Type::Uint16,
+            "int32" => Type::Int32,
+            "uint32" => Type::Uint32,
+            "int64" => Type::Int64,
+            "float64" => Type::Float64,
+            "string" => Type::String,
+            "bytes" => Type::Bytes,
+            "records" => Type::Records,
+            "uuid" => Type::Uuid,
+            name if name.starts_with(|c: char| c.is_ascii_uppercase()) => {
+                // inline structures come with their fields, common ones are defined apart
+                if field.contains_key("fields") {
+                    let fields = self.parse_fields(field)?;
+                    self.structs.push((name.to_owned(), fields));
+                }
+                return Ok(TypeDef::Struct(name.to_owned()));
+            }
+            ty => return Err(invalid(format_args!("unknown type {}", ty))),
+        };
+        Ok(TypeDef::Primitive(primitive))
+    }
+
+    fn struct_fields(&self, name: &str) -> Result<&[FieldDef]> {
+        self.structs
+            .iter()
+            .find(|(s, _)| s == name)
+            .map(|(_, fields)| &fields[..])
+            .ok_or_else(|| invalid(format_args!("unknown struct {}", name)))
+    }
+
+    fn schema_fields(&self, fields: &[FieldDef]) -> Result<Vec<Field>> {
+        fields
+            .iter()
+            .map(|field| {
+                let mut schema_field = Field::new(&field.name, self.schema_type(&field.ty)?)
+                    .versions(field.versions.clone())
+                    .nullable_versions(field.nullable_versions.clone());
+                schema_field.tag = field.tag;
+                Ok(schema_field)
+            })
+            .collect()
+    }
+
+    fn schema_type(&self, ty: &TypeDef) -> Result<Type> {
+        Ok(match *ty {
+            TypeDef::Primitive(ref ty) => ty.clone(),
+            TypeDef::Array(ref element) => Type::Array(Box::new(self.schema_type(element)?)),
+            TypeDef::Struct(ref name) => {
+                Type::Struct(self.schema_fields(self.struct_fields(name)?)?)
+            }
+        })
+    }
+}
+
+/// Removes the `//` comments the definitions start with, and are sprinkled with
+fn strip_comments(json: &str) -> String {
+    let mut stripped = String::with_capacity(json.len());
+    for line in json.lines() {
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut end = line.len();
+        for (i, c) in line.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                '/' if !in_string && line[i + 1..].starts_with('/') => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        stripped.push_str(&line[..end]);
+        stripped.push('\n');
+    }
+    stripped
+}
+
+fn string<'a>(object: &'a Map<String, Value>, key: &str) -> Result<Option<&'a str>> {
+    match object.get(key) {
+        Some(value) => value
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| invalid(format_args!("{} is not a string", key))),
+        None => Ok(None),
+    }
+}
+
+fn array<'a>(object: &'a Map<String, Value>, key: &str) -> Result<&'a [Value]> {
+    match object.get(key) {
+        Some(value) => value
+            .as_array()
+            .map(|values| &values[..])
+            .ok_or_else(|| invalid(format_args!("{} is not an array", key))),
+        None => Ok(&[]),
+    }
+}
+
+#[allow(clippy::reversed_empty_ranges)]
+/// Reads the default value of a field into a Rust expression, unless it is the default of its
+/// type. Bytes, UUIDs, arrays and structures can only default to their empty value
+fn default_value(
+    field: &Map<String, Value>,
+    name: &str,
+    ty: &TypeDef,
+    nullable: bool,
+) -> Result<Option<String>> {
+    let default = match field.get("default") {
+        Some(Value::String(default)) => default.clone(),
+        Some(Value::Number(default)) => default.to_string(),
+        Some(Value::Bool(default)) => default.to_string(),
+        Some(_) => return Err(invalid(format_args!("invalid default of {}", name))),
+        None => return Ok(None),
+    };
+    let bad = || invalid(format_args!("invalid default of {}: {}", name, default));
+    if default == "null" {
+        return if nullable { Ok(None) } else { Err(bad()) };
+    }
+    let (expr, is_zero) = match *ty {
+        TypeDef::Primitive(Type::Bool) => match default.as_str() {
+            "true" => ("true".to_owned(), false),
+            "false" => ("false".to_owned(), true),
+            _ => return Err(bad()),
+        },
+        TypeDef::Primitive(
+            Type::Int8 | Type::Int16 | Type::Uint16 | Type::Int32 | Type::Uint32 | Type::Int64,
+        ) => {
+            let n = match default.strip_prefix("0x") {
+                Some(hex) => i64::from_str_radix(hex, 16),
+                None => default.parse(),
+            }
+            .map_err(|_| bad())?;
+            (n.to_string(), n == 0)
+        }
+        TypeDef::Primitive(Type::Float64) => {
+            let f: f64 = default.parse().map_err(|_| bad())?;
+            (format!("{:?}", f), f == 0.0)
+        }
+        TypeDef::Primitive(Type::String) => {
+            (format!("{:?}.to_owned()", default), default.is_empty())
+        }
+        _ => return Ok(None),
+    };
+    Ok(match (nullable, is_zero) {
+        (false, true) => None,
+        (false, false) => Some(expr),
+        (true, _) => Some(format!("Some({})", expr)),
+    })
+}
+
+#[allow(clippy::reversed_empty_ranges)]
+fn none() -> RangeInclusive<i16> {
+    1..=0
+}
+
+/// Reads versions written like `none`, `3`, `3+` or `0-4`
+fn versions(object: &Map<String, Value>, key: &str) -> Result<Option<RangeInclusive<i16>>> {
+    let versions = match string(object, key)? {
+        Some(versions) => versions,
+        None => return Ok(None),
+    };
+    let number = |n: &str| {
+        n.trim()
+            .parse::<i16>()
+            .map_err(|_| invalid(format_args!("invalid {}: {}", key, versions)))
+    };
+    let range = if versions == "none" {
+        none()
+    } else if let Some(start) = versions.strip_suffix('+') {
+        number(start)?..=i16::MAX
+    } else if let Some((start, end)) = versions.split_once('-') {
+        number(start)?..=number(end)?
+    } else {
+        let version = number(versions)?;
+        version..=version
+    };
+    Ok(Some(range))
+}
+
+impl Schema {
+    /// Reads the schema of a message from its JSON definition, like the ones of Apache Kafka
+    ///
+    /// This also decodes messages whose definition is only known at runtime.
+    ///
+    /// # Examples
+    /// ```
+    /// use kafka_serde::schema::Schema;
+    ///
+    /// let schema = Schema::from_definition(
+    ///     r#"{
+    ///         "name": "HeartbeatResponse",
+    ///         "validVersions": "0-4",
+    ///         "flexibleVersions": "4+",
+    ///         "fields": [
+    ///             { "name": "ThrottleTimeMs", "type": "int32", "versions": "1+" },
+    ///             { "name": "ErrorCode", "type": "int16", "versions": "0+" }
+    ///         ]
+    ///     }"#,
+    /// )
+    /// .unwrap();
+    /// let response = schema.decode(4, &[0, 0, 0, 10, 0, 27, 0]).unwrap();
+    /// assert_eq!(response.get("ErrorCode").and_then(|e| e.as_i64()), Some(27));
+    /// ```
+    pub fn from_definition(json: &str) -> Result<Schema> {
+        let definition = Definition::parse(json)?;
+        let fields = definition.schema_fields(&definition.fields)?;
+        Ok(Schema::new(definition.name, fields)
+            .valid_versions(definition.valid_versions)
+            .flexible_versions(definition.flexible_versions))
+    }
+}
+
+/// Generates the Rust source of the message defined by `json`, in the format of the definitions
+/// of Apache Kafka
+pub fn generate(json: &str) -> Result<String> {
+    let definition = Definition::parse(json)?;
+    let (defaults, plain): (Vec<&FieldDef>, Vec<&FieldDef>) = definition
+        .fields
+        .iter()
+        .chain(definition.structs.iter().flat_map(|(_, fields)| fields))
+        .partition(|field| field.default.is_some());
+    let mut out = format!(
+        "// Generated by kafka_serde::codegen from the definition of {}. Do not edit.\n\
+         use kafka_serde::schema::{{\n    \
+         {}{}Field, FromValue, KafkaValue, Schema, ToValue,{}\n}};\n",
+        definition.name,
+        if plain.is_empty() { "" } else { "take_field, " },
+        if defaults.is_empty() {
+            ""
+        } else {
+            "take_field_or, "
+        },
+        // messages without fields have no field types
+        if plain.is_empty() && defaults.is_empty() {
+            ""
+        } else {
+            " Type,"
+        }
+    );
+    write_source(&mut out, &definition).expect("formatting into a String can't fail");
+    Ok(out)
+}
+
+fn write_source(out: &mut String, definition: &Definition) -> fmt::Result {
+    let valid = &definition.valid_versions;
+    let doc = format!(
+        "The {} message, in versions {} to {}",
+        definition.name,
+        valid.start(),
+        valid.end()
+    );
+    write_struct(out, &doc, &definition.name, &definition.fields)?;
+    write_message_impl(out, definition)?;
+    for (name, fields) in &definition.structs {
+        write_struct(out, &format!("A {} of the message", name), name, fields)?;
+        write!(out, "\nimpl {} {{\n", name)?;
+        write_fields_fn(out, fields)?;
+        out.push_str("}\n");
+    }
+    Ok(())
+}
+
+/// Generates the Rust source of the message defined in the file `input`, into `output`, for
+/// build scripts
+pub fn generate_file(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<()> {
+    let json = std::fs::read_to_string(input)?;
+    std::fs::write(output, generate(&json)?)?;
+    Ok(())
+}
+
+fn write_struct(out: &mut String, doc: &str, name: &str, fields: &[FieldDef]) -> fmt::Result {
+    let defaults = fields.iter().any(|field| field.default.is_some());
+    write!(
+        out,
+        "\n/// {}\n#[derive(Debug, Clone, PartialEq{})]\npub struct {} {{\n",
+        doc,
+        if defaults { "" } else { ", Default" },
+        name
+    )?;
+    for field in fields {
+        if let Some(ref about) = field.about {
+            // brackets would be read as links
+            let about = about.replace('[', "\\[").replace(']', "\\]");
+            writeln!(out, "    /// {}", about)?;
+        }
+        writeln!(
+            out,
+            "    pub {}: {},",
+            field_name(&field.name),
+            rust_type(field)
+        )?;
+    }
+    out.push_str("}\n");
+
+    if defaults {
+        write!(
+            out,
+            "\nimpl Default for {} {{\n    fn default() -> Self {{\n        {} {{\n",
+            name, name
+        )?;
+        for field in fields {
+            writeln!(
+                out,
+                "            {}: {},",
+                field_name(&field.name),
+                default_expr(field)
+            )?;
+        }
+        out.push_str("        }\n    }\n}\n");
+    }
+
+    write!(
+        out,
+        "\nimpl FromValue for {} {{\n    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {{\n",
+        name
+    )?;
+    if fields.is_empty() {
+        write!(
+            out,
+            "        value.into_fields()?;\n        Ok({} {{}})\n",
+            name
+        )?;
+    } else {
+        write!(
+            out,
+            "        let mut fields = value.into_fields()?;\n        Ok({} {{\n",
+            name
+        )?;
+        for field in fields {
+            match field.default {
+                Some(ref default) => writeln!(
+                    out,
+                    "            {}: take_field_or(&mut fields, \"{}\", {})?,",
+                    field_name(&field.name),
+                    field.name,
+                    default
+                )?,
+                None => writeln!(
+                    out,
+                    "            {}: take_field(&mut fields, \"{}\")?,",
+                    field_name(&field.name),
+                    field.name
+                )?,
+            }
+        }
+        out.push_str("        })\n");
+    }
+    out.push_str("    }\n}\n");
+
+    write!(
+        out,
+        "\nimpl ToValue for {} {{\n    fn to_value(&self) -> KafkaValue {{\n",
+        name
+    )?;
+    let (tagged, untagged): (Vec<_>, Vec<_>) = fields.iter().partition(|f| f.tag.is_some());
+    let binding = if tagged.is_empty() { "let" } else { "let mut" };
+    writeln!(out, "        {} fields = vec![", binding)?;
+    for field in untagged {
+        writeln!(
+            out,
+            "            (\"{}\".to_owned(), self.{}.to_value()),",
+            field.name,
+            field_name(&field.name)
+        )?;
+    }
+    out.push_str("        ];\n");
+    // tagged fields are only written when they are set
+    for field in tagged {
+        let rust_name = field_name(&field.name);
+        write!(
+            out,
+            "        if self.{rust_name} != {default} {{\n            \
+             fields.push((\"{name}\".to_owned(), self.{rust_name}.to_value()));\n        }}\n",
+            rust_name = rust_name,
+            default = default_expr(field),
+            name = field.name
+        )?;
+    }
+    out.push_str("        KafkaValue::Struct(fields)\n    }\n}\n");
+    Ok(())
+}
+
+fn write_message_impl(out: &mut String, definition: &Definition) -> fmt::Result {
+    write!(out, "\nimpl {} {{\n", definition.name)?;
+    if let Some(api_key) = definition.api_key {
+        write!(
+            out,
+            "    /// The API key of the message\n    pub const API_KEY: i16 = {};\n\n",
+            api_key
+        )?;
+    }
+    write!(
+        out,
+        "    /// The schema of the message\n    \
+         pub fn schema() -> &'static Schema {{\n        \
+         static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();\n        \
+         SCHEMA.get_or_init(|| {{\n            \
+         Schema::new(\"{}\", Self::fields())\n                \
+         .valid_versions({})",
+        definition.name,
+        range(&definition.valid_versions)
+    )?;
+    if !definition.flexible_versions.is_empty() {
+        write!(
+            out,
+            "\n                .flexible_versions({})",
+            range(&definition.flexible_versions)
+        )?;
+    }
+    out.push_str(
+        "\n        })\n    }\n\n    \
+         /// Decodes the message in `version` from `buf`\n    \
+         pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {\n        \
+         Self::from_value(Self::schema().decode(version, buf)?)\n    }\n\n    \
+         /// Encodes the message in `version` at the end of `buf`\n    \
+         pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {\n        \
+         Self::schema().encode(version, &self.to_value(), buf)\n    }\n\n",
+    );
+    write_fields_fn(out, &definition.fields)?;
+    out.push_str("}\n");
+    write!(
+        out,
+        "\nimpl kafka_serde::schema::Message for {0} {{\n    \
+         fn schema() -> &'static Schema {{\n        \
+         {0}::schema()\n    }}\n}}\n",
+        definition.name
+    )?;
+    write_builder(out, definition)?;
+    write_request_impl(out, definition)
+}
+
+/// Writes the builder of the message, which checks that the fields it was given are in the
+/// version it builds
+fn write_builder(out: &mut String, definition: &Definition) -> fmt::Result {
+    let name = &definition.name;
+    write!(
+        out,
+        "\nimpl {name} {{\n    \
+         /// Starts building a message, whose fields hold their default value until they are set\n    \
+         pub fn builder() -> {name}Builder {{\n        \
+         {name}Builder::default()\n    }}\n}}\n\n\
+         /// Builds {name} messages\n\
+         #[derive(Debug, Clone, Default)]\n\
+         pub struct {name}Builder {{\n    \
+         message: {name},\n    \
+         // the fields that were set, which must be in the version built\n    \
+         set: Vec<&'static str>,\n}}\n\n\
+         impl {name}Builder {{\n",
+        name = name
+    )?;
+    for field in &definition.fields {
+        let rust_name = field_name(&field.name);
+        let (param, value) = match field.ty {
+            TypeDef::Array(ref element) if field.nullable_versions.is_empty() => (
+                format!(
+                    "impl IntoIterator<Item = impl Into<{}>>",
+                    type_name(element)
+                ),
+                "value.into_iter().map(Into::into).collect()",
+            ),
+            // numbers are taken as they are, for literals to get their type
+            TypeDef::Primitive(ref ty)
+                if field.nullable_versions.is_empty()
+                    && !matches!(*ty, Type::String | Type::Bytes | Type::Records) =>
+            {
+                (rust_type(field), "value")
+            }
+            _ => (format!("impl Into<{}>", rust_type(field)), "value.into()"),
+        };
+        write!(
+            out,
+            "    /// Sets `{rust_name}`\n    \
+             pub fn {rust_name}(mut self, value: {param}) -> Self {{\n        \
+             self.message.{rust_name} = {value};\n        \
+             self.set.push(\"{name}\");\n        \
+             self\n    }}\n\n",
+            rust_name = rust_name,
+            param = param,
+            value = value,
+            name = field.name
+        )?;
+    }
+    write!(
+        out,
+        "    /// Builds the message, failing unless the fields that were set are in `version`\n    \
+         pub fn build(self, version: i16) -> kafka_serde::Result<{}> {{\n        \
+         {}::schema().check_fields(version, &self.set)?;\n        \
+         Ok(self.message)\n    }}\n}}\n",
+        name, name
+    )
+}
+
+/// Implements `KafkaRequest` for requests, whose response is named after them
+fn write_request_impl(out: &mut String, definition: &Definition) -> fmt::Result {
+    let (api_key, prefix) = match (definition.api_key, definition.name.strip_suffix("Request")) {
+        (Some(api_key), Some(prefix)) if definition.request => (api_key, prefix),
+        _ => return Ok(()),
+    };
+    let first_flexible = if definition.flexible_versions.is_empty() {
+        "None".to_owned()
+    } else {
+        format!("Some({})", definition.flexible_versions.start())
+    };
+    write!(
+        out,
+        "\nimpl kafka_serde::KafkaRequest for {} {{\n    \
+         const API_KEY: i16 = {};\n    \
+         const MIN_VERSION: i16 = {};\n    \
+         const MAX_VERSION: i16 = {};\n    \
+         const FIRST_FLEXIBLE_VERSION: Option<i16> = {};\n    \
+         type Response = super::{}Response;\n}}\n",
+        definition.name,
+        api_key,
+        definition.valid_versions.start(),
+        definition.valid_versions.end(),
+        first_flexible,
+        prefix
+    )
+}
+
+fn write_fields_fn(out: &mut String, fields: &[FieldDef]) -> fmt::Result {
+    out.push_str("    fn fields() -> Vec<Field> {\n        vec![\n");
+    for field in fields {
+        write!(
+            out,
+            "            Field::new(\"{}\", {})",
+            field.name,
+            type_expr(&field.ty)
+        )?;
+        if field.versions != (0..=i16::MAX) {
+            write!(out, ".versions({})", range(&field.versions))?;
+        }
+        if !field.nullable_versions.is_empty() {
+            write!(
+                out,
+                ".nullable_versions({})",
+                range(&field.nullable_versions)
+            )?;
+        }
+        if let Some(tag) = field.tag {
+            write!(out, ".tag({})", tag)?;
+        }
+        out.push_str(",\n");
+    }
+    out.push_str("        ]\n    }\n");
+    Ok(())
+}
+
+fn range(versions: &RangeInclusive<i16>) -> String {
+    if *versions.end() == i16::MAX {
+        format!("{}..", versions.start())
+    } else {
+        format!("{}..={}", versions.start(), versions.end())
+    }
+}
+
+fn type_expr(ty: &TypeDef) -> String {
+    match *ty {
+        TypeDef::Primitive(ref ty) => format!("Type::{:?}", ty),
+        TypeDef::Array(ref element) => format!("Type::Array(Box::new({}))", type_expr(element)),
+        TypeDef::Struct(ref name) => format!("Type::Struct({}::fields())", name),
+    }
+}
+
+/// The expression of the default value of a field
+fn default_expr(field: &FieldDef) -> &str {
+    field.default.as_deref().unwrap_or("Default::default()")
+}
+
+fn type_name(ty: &TypeDef) -> String {
+    match *ty {
+        TypeDef::Primitive(ref ty) => match *ty {
+            Type::Bool => "bool",
+            Type::Int8 => "i8",
+            Type::Int16 => "i16",
+            Type::Uint16 => "u16",
+            Type::Int32 => "i32",
+            Type::Uint32 => "u32",
+            Type::Int64 => "i64",
+            Type::Float64 => "f64",
+            Type::String => "String",
+            Type::Bytes | Type::Records => "Vec<u8>",
+            Type::Uuid => "[u8; 16]",
+            Type::Array(_) | Type::Struct(_) => unreachable!("not a primitive"),
+        }
+        .to_owned(),
+        TypeDef::Array(ref element) => format!("Vec<{}>", type_name(element)),
+        TypeDef::Struct(ref name) => name.clone(),
+    }
+}
+
+fn rust_type(field: &FieldDef) -> String {
+    if field.nullable_versions.is_empty() {
+        type_name(&field.ty)
+    } else {
+        format!("Option<{}>", type_name(&field.ty))
+    }
+}
+
+/// Turns a field name like `ThrottleTimeMs` into `throttle_time_ms`, escaping keywords
+fn field_name(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            if previous.is_ascii_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_ascii_uppercase() && next_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    const KEYWORDS: &[&str] = &[
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do",
+        "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let",
+        "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+        "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+        "virtual", "where", "while", "yield",
+    ];
+    if KEYWORDS.contains(&&snake[..]) {
+        snake.insert_str(0, "r#");
+    }
+    snake
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::KafkaValue;
+
+    mod find_coordinator_response {
+        include!("testdata/FindCoordinatorResponse.rs");
+    }
+
+    use find_coordinator_response::{Coordinator, FindCoordinatorResponse, LeaderIdAndEpoch};
+
+    const DEFINITION: &str = include_str!("testdata/FindCoordinatorResponse.json");
+
+    #[test]
+    fn test_generate() {
+        // regenerate with `KAFKA_SERDE_BLESS=1 cargo test --features codegen`
+        let generated = generate(DEFINITION).unwrap();
+        if std::env::var_os("KAFKA_SERDE_BLESS").is_some() {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/codegen/testdata");
+            std::fs::write(format!("{}/FindCoordinatorResponse.rs", path), &generated).unwrap();
+        }
+        assert_eq!(
+            generated,
+            include_str!("testdata/FindCoordinatorResponse.rs")
+        );
+    }
+
+    #[test]
+    fn test_from_definition() {
+        let schema = Schema::from_definition(DEFINITION).unwrap();
+        assert_eq!(schema, *FindCoordinatorResponse::schema());
+        assert_eq!(FindCoordinatorResponse::API_KEY, 10);
+        assert_eq!(schema.valid_versions, 0..=4);
+        assert!(schema.is_flexible(3) && !schema.is_flexible(2));
+        assert_eq!(schema.fields[2].nullable_versions, 1..=3);
+        assert_eq!(schema.fields[7].tag, Some(0));
+        match schema.fields[6].ty {
+            Type::Array(ref element) => match **element {
+                Type::Struct(ref fields) => assert_eq!(fields[0].name, "Key"),
+                ref ty => panic!("unexpected type {:?}", ty),
+            },
+            ref ty => panic!("unexpected type {:?}", ty),
+        }
+
+        assert!(Schema::from_definition("{}").is_err());
+        assert!(Schema::from_definition(r#"{ "name": "A", "validVersions": "0-x" }"#).is_err());
+        assert!(Schema::from_definition(
+            r#"{ "name": "A", "validVersions": "0", "fields": [{ "name": "B", "type": "int" }] }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let v2 = FindCoordinatorResponse {
+            throttle_time_ms: 10,
+            error_message: Some("none".to_owned()),
+            node_id: 1,
+            host: "broker-1".to_owned(),
+            port: 9092,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        v2.encode(2, &mut buf).unwrap();
+        assert_eq!(FindCoordinatorResponse::decode(2, &buf).unwrap(), v2);
+
+        let v4 = FindCoordinatorResponse {
+            coordinators: vec![Coordinator {
+                key: "group".to_owned(),
+                node_id: 1,
+                host: "broker-1".to_owned(),
+                port: 9092,
+                ..Default::default()
+            }],
+            leader: LeaderIdAndEpoch {
+                leader_id: 1,
+                leader_epoch: 3,
+            },
+            ..Default::default()
+        };
+        buf.clear();
+        v4.encode(4, &mut buf).unwrap();
+        assert_eq!(FindCoordinatorResponse::decode(4, &buf).unwrap(), v4);
+        assert_eq!(crate::testing::assert_roundtrip(&v4, 4), buf);
+        let value = FindCoordinatorResponse::schema().decode(4, &buf).unwrap();
+        assert_eq!(
+            value.get("Leader").and_then(|l| l.get("LeaderEpoch")),
+            Some(&KafkaValue::Int32(3))
+        );
+
+        // unset tagged fields are left out
+        let mut untagged = Vec::new();
+        FindCoordinatorResponse::default()
+            .encode(4, &mut untagged)
+            .unwrap();
+        assert_eq!(untagged, [0, 0, 0, 0, 1, 0]);
+        assert!(FindCoordinatorResponse::default()
+            .encode(5, &mut untagged)
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder() {
+        let response = FindCoordinatorResponse::builder()
+            .throttle_time_ms(10)
+            .error_code(0)
+            .error_message("none".to_owned())
+            .node_id(1)
+            .host("broker-1")
+            .port(9092)
+            .build(2)
+            .unwrap();
+        assert_eq!(response.host, "broker-1");
+        assert_eq!(response.error_message.as_deref(), Some("none"));
+
+        let coordinators = FindCoordinatorResponse::builder().coordinators([Coordinator {
+            key: "group".to_owned(),
+            ..Default::default()
+        }]);
+        assert_eq!(coordinators.clone().build(4).unwrap().coordinators.len(), 1);
+        assert!(coordinators.clone().build(3).is_err());
+        let leader = coordinators.clone().leader(LeaderIdAndEpoch::default());
+        assert!(leader.build(4).is_ok());
+        assert!(coordinators.build(5).is_err());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let generated = generate(
+            r#"{
+                "name": "Defaults",
+                "validVersions": "0-1",
+                "flexibleVersions": "1+",
+                "fields": [
+                    { "name": "Id", "type": "int32", "versions": "0+", "default": "-1" },
+                    { "name": "Mask", "type": "int64", "versions": "0+", "default": "0x10" },
+                    { "name": "Enabled", "type": "bool", "versions": "0+", "default": "true" },
+                    { "name": "Zero", "type": "int16", "versions": "0+", "default": "0" },
+                    { "name": "Rack", "type": "string", "versions": "0+", "nullableVersions": "0+",
+                      "default": "\"" },
+                    { "name": "Epoch", "type": "int32", "versions": "1+", "taggedVersions": "1+",
+                      "tag": 0, "default": -1 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(generated.contains("use kafka_serde::schema::{\n    take_field, take_field_or, "));
+        assert!(generated.contains("#[derive(Debug, Clone, PartialEq)]\npub struct Defaults"));
+        assert!(generated.contains(
+            "            id: -1,\n            \
+             mask: 16,\n            \
+             enabled: true,\n            \
+             zero: Default::default(),\n            \
+             rack: Some(\"\\\"\".to_owned()),\n            \
+             epoch: -1,\n"
+        ));
+        assert!(generated.contains("zero: take_field(&mut fields, \"Zero\")?"));
+        assert!(generated.contains("epoch: take_field_or(&mut fields, \"Epoch\", -1)?"));
+        assert!(generated.contains("if self.epoch != -1 {"));
+
+        for default in [r#""x""#, r#""null""#, "[]"] {
+            let definition = format!(
+                r#"{{ "name": "A", "validVersions": "0", "fields": [
+                    {{ "name": "B", "type": "int32", "versions": "0+", "default": {} }}
+                ] }}"#,
+                default
+            );
+            assert!(generate(&definition).is_err());
+        }
+    }
+
+    #[test]
+    fn test_request() {
+        let generated = generate(
+            r#"{
+                "apiKey": 12,
+                "type": "request",
+                "name": "HeartbeatRequest",
+                "validVersions": "0-4",
+                "flexibleVersions": "4+",
+                "fields": [{ "name": "GroupId", "type": "string", "versions": "0+" }]
+            }"#,
+        )
+        .unwrap();
+        assert!(generated.contains(
+            "impl kafka_serde::KafkaRequest for HeartbeatRequest {\n    \
+             const API_KEY: i16 = 12;\n    \
+             const MIN_VERSION: i16 = 0;\n    \
+             const MAX_VERSION: i16 = 4;\n    \
+             const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(4);\n    \
+             type Response = super::HeartbeatResponse;\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_field_name() {
+        assert_eq!(field_name("ThrottleTimeMs"), "throttle_time_ms");
+        assert_eq!(field_name("TopicID"), "topic_id");
+        assert_eq!(field_name("HTTPServer"), "http_server");
+        assert_eq!(field_name("Type"), "r#type");
+        assert_eq!(field_name("V2Enabled"), "v2_enabled");
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        assert_eq!(
+            strip_comments("// header\n{ \"a\": \"http://b\" } // trailing\n"),
+            "\n{ \"a\": \"http://b\" } \n"
+        );
+    }
+}
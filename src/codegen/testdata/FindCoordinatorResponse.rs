@@ -0,0 +1,287 @@
+// Generated by kafka_serde::codegen from the definition of FindCoordinatorResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, take_field_or, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The FindCoordinatorResponse message, in versions 0 to 4
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FindCoordinatorResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// The error code, or 0 if there was no error.
+    pub error_code: i16,
+    /// The error message, or null if there was no error.
+    pub error_message: Option<String>,
+    /// The node id.
+    pub node_id: i32,
+    /// The host name.
+    pub host: String,
+    /// The port.
+    pub port: i32,
+    /// Each coordinator result in the response
+    pub coordinators: Vec<Coordinator>,
+    /// Not part of the upstream definition: a tagged field using a common struct, for tests.
+    pub leader: LeaderIdAndEpoch,
+}
+
+impl FromValue for FindCoordinatorResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(FindCoordinatorResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            error_message: take_field(&mut fields, "ErrorMessage")?,
+            node_id: take_field(&mut fields, "NodeId")?,
+            host: take_field(&mut fields, "Host")?,
+            port: take_field(&mut fields, "Port")?,
+            coordinators: take_field(&mut fields, "Coordinators")?,
+            leader: take_field(&mut fields, "Leader")?,
+        })
+    }
+}
+
+impl ToValue for FindCoordinatorResponse {
+    fn to_value(&self) -> KafkaValue {
+        let mut fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ErrorMessage".to_owned(), self.error_message.to_value()),
+            ("NodeId".to_owned(), self.node_id.to_value()),
+            ("Host".to_owned(), self.host.to_value()),
+            ("Port".to_owned(), self.port.to_value()),
+            ("Coordinators".to_owned(), self.coordinators.to_value()),
+        ];
+        if self.leader != Default::default() {
+            fields.push(("Leader".to_owned(), self.leader.to_value()));
+        }
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl FindCoordinatorResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 10;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("FindCoordinatorResponse", Self::fields())
+                .valid_versions(0..=4)
+                .flexible_versions(3..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32).versions(1..),
+            Field::new("ErrorCode", Type::Int16).versions(0..=3),
+            Field::new("ErrorMessage", Type::String).versions(1..=3).nullable_versions(1..=3),
+            Field::new("NodeId", Type::Int32).versions(0..=3),
+            Field::new("Host", Type::String).versions(0..=3),
+            Field::new("Port", Type::Int32).versions(0..=3),
+            Field::new("Coordinators", Type::Array(Box::new(Type::Struct(Coordinator::fields())))).versions(4..),
+            Field::new("Leader", Type::Struct(LeaderIdAndEpoch::fields())).versions(3..).tag(0),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for FindCoordinatorResponse {
+    fn schema() -> &'static Schema {
+        FindCoordinatorResponse::schema()
+    }
+}
+
+impl FindCoordinatorResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> FindCoordinatorResponseBuilder {
+        FindCoordinatorResponseBuilder::default()
+    }
+}
+
+/// Builds FindCoordinatorResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct FindCoordinatorResponseBuilder {
+    message: FindCoordinatorResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl FindCoordinatorResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `error_code`
+    pub fn error_code(mut self, value: i16) -> Self {
+        self.message.error_code = value;
+        self.set.push("ErrorCode");
+        self
+    }
+
+    /// Sets `error_message`
+    pub fn error_message(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.error_message = value.into();
+        self.set.push("ErrorMessage");
+        self
+    }
+
+    /// Sets `node_id`
+    pub fn node_id(mut self, value: i32) -> Self {
+        self.message.node_id = value;
+        self.set.push("NodeId");
+        self
+    }
+
+    /// Sets `host`
+    pub fn host(mut self, value: impl Into<String>) -> Self {
+        self.message.host = value.into();
+        self.set.push("Host");
+        self
+    }
+
+    /// Sets `port`
+    pub fn port(mut self, value: i32) -> Self {
+        self.message.port = value;
+        self.set.push("Port");
+        self
+    }
+
+    /// Sets `coordinators`
+    pub fn coordinators(mut self, value: impl IntoIterator<Item = impl Into<Coordinator>>) -> Self {
+        self.message.coordinators = value.into_iter().map(Into::into).collect();
+        self.set.push("Coordinators");
+        self
+    }
+
+    /// Sets `leader`
+    pub fn leader(mut self, value: impl Into<LeaderIdAndEpoch>) -> Self {
+        self.message.leader = value.into();
+        self.set.push("Leader");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<FindCoordinatorResponse> {
+        FindCoordinatorResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+/// A LeaderIdAndEpoch of the message
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderIdAndEpoch {
+    /// The ID of the current leader.
+    pub leader_id: i32,
+    /// The latest known leader epoch.
+    pub leader_epoch: i32,
+}
+
+impl Default for LeaderIdAndEpoch {
+    fn default() -> Self {
+        LeaderIdAndEpoch {
+            leader_id: -1,
+            leader_epoch: -1,
+        }
+    }
+}
+
+impl FromValue for LeaderIdAndEpoch {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(LeaderIdAndEpoch {
+            leader_id: take_field_or(&mut fields, "LeaderId", -1)?,
+            leader_epoch: take_field_or(&mut fields, "LeaderEpoch", -1)?,
+        })
+    }
+}
+
+impl ToValue for LeaderIdAndEpoch {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("LeaderId".to_owned(), self.leader_id.to_value()),
+            ("LeaderEpoch".to_owned(), self.leader_epoch.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl LeaderIdAndEpoch {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("LeaderId", Type::Int32),
+            Field::new("LeaderEpoch", Type::Int32),
+        ]
+    }
+}
+
+/// A Coordinator of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Coordinator {
+    /// The coordinator key.
+    pub key: String,
+    /// The node id.
+    pub node_id: i32,
+    /// The host name.
+    pub host: String,
+    /// The port.
+    pub port: i32,
+    /// The error code, or 0 if there was no error.
+    pub error_code: i16,
+    /// The error message, or null if there was no error.
+    pub error_message: Option<String>,
+}
+
+impl FromValue for Coordinator {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(Coordinator {
+            key: take_field(&mut fields, "Key")?,
+            node_id: take_field(&mut fields, "NodeId")?,
+            host: take_field(&mut fields, "Host")?,
+            port: take_field(&mut fields, "Port")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            error_message: take_field(&mut fields, "ErrorMessage")?,
+        })
+    }
+}
+
+impl ToValue for Coordinator {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("Key".to_owned(), self.key.to_value()),
+            ("NodeId".to_owned(), self.node_id.to_value()),
+            ("Host".to_owned(), self.host.to_value()),
+            ("Port".to_owned(), self.port.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ErrorMessage".to_owned(), self.error_message.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl Coordinator {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("Key", Type::String).versions(4..),
+            Field::new("NodeId", Type::Int32).versions(4..),
+            Field::new("Host", Type::String).versions(4..),
+            Field::new("Port", Type::Int32).versions(4..),
+            Field::new("ErrorCode", Type::Int16).versions(4..),
+            Field::new("ErrorMessage", Type::String).versions(4..).nullable_versions(4..),
+        ]
+    }
+}
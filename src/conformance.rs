@@ -0,0 +1,465 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Conformance checks against a real broker
+//!
+//! [`KafkaBroker`] starts a single-node Kafka broker in a container, through testcontainers,
+//! and [`Conformance`] exchanges requests with a broker using the crate, failing if the
+//! responses don't decode or don't say what they should:
+//!
+//! - ApiVersions v0, which must list Metadata, Produce and Fetch
+//! - Metadata v4, creating a topic and waiting for the leader of its first partition
+//! - Produce v8, writing records to that partition
+//! - Fetch v11, reading them back, which must return the same keys, values and headers
+//!
+//! The checks take any stream to a broker, for clients built on the crate to run them against
+//! their own brokers and proxies, or to run them next to checks of their own.
+//!
+//! Starting a container needs Docker. Tests using it are usually ignored by default, and run
+//! with `cargo test -- --ignored` where Docker is available.
+//!
+//! # Examples
+//! ```no_run
+//! use kafka_serde::conformance::{Conformance, KafkaBroker};
+//!
+//! let broker = KafkaBroker::start()?;
+//! let mut conformance = Conformance::new(broker.connect()?);
+//! conformance.run("conformance")?;
+//! # Ok::<(), kafka_serde::Error>(())
+//! ```
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use testcontainers::core::{IntoContainerPort, WaitFor};
+use testcontainers::runners::SyncRunner;
+use testcontainers::{Container, GenericImage};
+
+use crate::api::{ApiVersionRange, ApiVersions, API_VERSIONS_KEY};
+use crate::error::{ErrorKind, Result};
+use crate::fetch::{FetchResponse, FETCH_KEY};
+use crate::metadata::{MetadataResponse, METADATA_KEY};
+use crate::produce::{ProduceRequestBuilder, PRODUCE_KEY};
+use crate::records::{Header, Headers};
+use crate::schema::{take_field, Field, FromValue, KafkaValue, Schema, Type};
+use crate::{client, header, ser};
+
+/// The image of the broker started by [`KafkaBroker::start`]
+pub const KAFKA_IMAGE: &str = "apache/kafka";
+/// The tag of the image of the broker started by [`KafkaBroker::start`]
+pub const KAFKA_TAG: &str = "3.8.0";
+
+/// The port brokers listen on in their container
+const KAFKA_PORT: u16 = 9092;
+/// How many times Metadata is asked for until the topic has a leader
+const METADATA_ATTEMPTS: usize = 50;
+
+fn container_error(err: testcontainers::TestcontainersError) -> crate::Error {
+    ErrorKind::Custom(format!("failed to start the broker: {}", err)).into()
+}
+
+fn conformance_error(message: String) -> crate::Error {
+    ErrorKind::Custom(format!("conformance check failed: {}", message)).into()
+}
+
+/// A single-node Kafka broker running in a container, stopped when dropped
+#[derive(Debug)]
+pub struct KafkaBroker {
+    // kept to stop the container when the broker is dropped
+    _container: Container<GenericImage>,
+    host: String,
+    port: u16,
+}
+
+impl KafkaBroker {
+    /// Starts a broker from [`KAFKA_IMAGE`]:[`KAFKA_TAG`], pulling the image if needed, and
+    /// waits until it is ready
+    pub fn start() -> Result<KafkaBroker> {
+        Self::start_image(KAFKA_IMAGE, KAFKA_TAG)
+    }
+
+    /// Starts a broker from another image, configured like the `apache/kafka` one: a
+    /// single node in KRaft mode listening on port 9092
+    pub fn start_image(image: &str, tag: &str) -> Result<KafkaBroker> {
+        let container = GenericImage::new(image, tag)
+            .with_exposed_port(KAFKA_PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stdout("Kafka Server started"))
+            .start()
+            .map_err(container_error)?;
+        let host = container.get_host().map_err(container_error)?.to_string();
+        let port = container
+            .get_host_port_ipv4(KAFKA_PORT)
+            .map_err(container_error)?;
+        Ok(KafkaBroker {
+            _container: container,
+            host,
+            port,
+        })
+    }
+
+    /// The address of the broker, reachable from the host
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// Connects to the broker
+    ///
+    /// Brokers advertise the address they listen on in their container, which clients
+    /// following the Metadata responses can't reach: requests to the broker must go through
+    /// this connection, or another one to [`addr`](KafkaBroker::addr).
+    pub fn connect(&self) -> Result<TcpStream> {
+        let stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+        Ok(stream)
+    }
+}
+
+/// ApiVersions v0 requests have no fields
+#[derive(Serialize)]
+struct ApiVersionsRequest {}
+
+#[derive(Deserialize)]
+struct ApiVersionsResponse {
+    error_code: i16,
+    api_keys: Vec<ApiVersionRange>,
+}
+
+#[derive(Serialize)]
+struct MetadataRequest<'a> {
+    topics: Vec<&'a str>,
+    allow_auto_topic_creation: bool,
+}
+
+#[derive(Serialize)]
+struct FetchRequest<'a> {
+    replica_id: i32,
+    max_wait_ms: i32,
+    min_bytes: i32,
+    max_bytes: i32,
+    isolation_level: i8,
+    session_id: i32,
+    session_epoch: i32,
+    topics: Vec<FetchTopic<'a>>,
+    forgotten_topics_data: Vec<FetchTopic<'a>>,
+    rack_id: &'a str,
+}
+
+#[derive(Serialize)]
+struct FetchTopic<'a> {
+    topic: &'a str,
+    partitions: Vec<FetchPartition>,
+}
+
+#[derive(Serialize)]
+struct FetchPartition {
+    partition: i32,
+    current_leader_epoch: i32,
+    fetch_offset: i64,
+    log_start_offset: i64,
+    partition_max_bytes: i32,
+}
+
+/// The version of Produce sent, the last one before flexible versions
+const PRODUCE_VERSION: i16 = 8;
+
+/// The schema of ProduceResponse v8, to read the result of the partition produced to
+fn produce_response_schema() -> &'static Schema {
+    static SCHEMA: OnceLock<Schema> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        let record_error = Type::Struct(vec![
+            Field::new("BatchIndex", Type::Int32),
+            Field::new("BatchIndexErrorMessage", Type::String).nullable_versions(..),
+        ]);
+        let partition = Type::Struct(vec![
+            Field::new("Index", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("BaseOffset", Type::Int64),
+            Field::new("LogAppendTimeMs", Type::Int64),
+            Field::new("LogStartOffset", Type::Int64),
+            Field::new("RecordErrors", Type::Array(Box::new(record_error))),
+            Field::new("ErrorMessage", Type::String).nullable_versions(..),
+        ]);
+        let topic = Type::Struct(vec![
+            Field::new("Name", Type::String),
+            Field::new("PartitionResponses", Type::Array(Box::new(partition))),
+        ]);
+        Schema::new(
+            "ProduceResponse",
+            vec![
+                Field::new("Responses", Type::Array(Box::new(topic))),
+                Field::new("ThrottleTimeMs", Type::Int32),
+            ],
+        )
+        .valid_versions(PRODUCE_VERSION..=PRODUCE_VERSION)
+    })
+}
+
+/// The result of producing to a partition
+struct ProducedPartition {
+    error_code: i16,
+    base_offset: i64,
+}
+
+impl FromValue for ProducedPartition {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ProducedPartition {
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            base_offset: take_field(&mut fields, "BaseOffset")?,
+        })
+    }
+}
+
+/// Runs conformance checks against a broker, over a blocking stream to it
+///
+/// Each check sends a request, and fails if the response doesn't decode, holds an error, or
+/// doesn't say what it should.
+#[derive(Debug)]
+pub struct Conformance<S> {
+    stream: S,
+    correlation_id: i32,
+}
+
+impl<S: Read + Write> Conformance<S> {
+    /// Creates checks sending requests over `stream`
+    pub fn new(stream: S) -> Self {
+        Conformance {
+            stream,
+            correlation_id: 0,
+        }
+    }
+
+    /// Runs all checks, producing to and fetching from `topic`, which is created if it doesn't
+    /// exist
+    pub fn run(&mut self, topic: &str) -> Result<()> {
+        let versions = self.api_versions()?;
+        for api_key in [PRODUCE_KEY, FETCH_KEY, METADATA_KEY] {
+            if versions.get(api_key).is_none() {
+                return Err(conformance_error(format!(
+                    "API {} is not supported",
+                    api_key
+                )));
+            }
+        }
+        self.metadata(topic)?;
+        self.produce_fetch(topic)
+    }
+
+    /// Checks ApiVersions v0, returning the versions the broker supports
+    pub fn api_versions(&mut self) -> Result<ApiVersions> {
+        let body = self.exchange(API_VERSIONS_KEY, 0, &ApiVersionsRequest {})?;
+        let response: ApiVersionsResponse = crate::from_bytes(&body)?;
+        if response.error_code != 0 {
+            return Err(conformance_error(format!(
+                "ApiVersions failed with error {}",
+                response.error_code
+            )));
+        }
+        if response.api_keys.is_empty() {
+            return Err(conformance_error("ApiVersions listed no API".to_owned()));
+        }
+        Ok(response.api_keys.into_iter().collect())
+    }
+
+    /// Checks Metadata v4, creating `topic` if it doesn't exist, and returns the first response
+    /// in which its partition 0 has a leader
+    pub fn metadata(&mut self, topic: &str) -> Result<MetadataResponse> {
+        let request = MetadataRequest {
+            topics: vec![topic],
+            allow_auto_topic_creation: true,
+        };
+        for _ in 0..METADATA_ATTEMPTS {
+            let body = self.exchange(METADATA_KEY, 4, &request)?;
+            let response = MetadataResponse::decode(4, &body)?;
+            if response.brokers.is_empty() {
+                return Err(conformance_error("Metadata listed no broker".to_owned()));
+            }
+            let has_leader = response
+                .topics
+                .iter()
+                .filter(|metadata| metadata.error_code == 0)
+                .filter(|metadata| metadata.name.as_deref() == Some(topic))
+                .flat_map(|metadata| &metadata.partitions)
+                .any(|partition| partition.index == 0 && partition.leader_id >= 0);
+            if has_leader {
+                return Ok(response);
+            }
+            // the topic is being created
+            thread::sleep(Duration::from_millis(100));
+        }
+        Err(conformance_error(format!(
+            "partition 0 of {} has no leader",
+            topic
+        )))
+    }
+
+    /// Checks Produce v8 and Fetch v11, producing records to partition 0 of `topic` then
+    /// fetching them back
+    pub fn produce_fetch(&mut self, topic: &str) -> Result<()> {
+        let headers: Headers = vec![Header::new("check", Some(b"conformance".to_vec()))].into();
+        let records = vec![
+            (Some(b"key".to_vec()), Some(b"first".to_vec()), headers),
+            (None, Some(b"second".to_vec()), Headers::new()),
+            (Some(b"null".to_vec()), None, Headers::new()),
+        ];
+
+        self.correlation_id = self.correlation_id.wrapping_add(1);
+        let frame = ProduceRequestBuilder::new()
+            .api_version(PRODUCE_VERSION)
+            .client_id("kafka-serde-conformance")
+            .build(self.correlation_id, topic, 0, records.clone())?;
+        let body = self.round_trip(&frame, self.correlation_id)?;
+        let response = produce_response_schema().decode(PRODUCE_VERSION, &body)?;
+        let partition = response
+            .get("Responses")
+            .and_then(|topics| topics.as_array()?.first()?.get("PartitionResponses"))
+            .and_then(|partitions| partitions.as_array()?.first().cloned())
+            .ok_or_else(|| conformance_error("Produce returned no partition".to_owned()))?;
+        let produced = ProducedPartition::from_value(partition)?;
+        if produced.error_code != 0 {
+            return Err(conformance_error(format!(
+                "Produce failed with error {}",
+                produced.error_code
+            )));
+        }
+
+        let request = FetchRequest {
+            replica_id: -1,
+            max_wait_ms: 500,
+            min_bytes: 1,
+            max_bytes: 1 << 20,
+            isolation_level: 0,
+            session_id: 0,
+            session_epoch: -1,
+            topics: vec![FetchTopic {
+                topic,
+                partitions: vec![FetchPartition {
+                    partition: 0,
+                    current_leader_epoch: -1,
+                    fetch_offset: produced.base_offset,
+                    log_start_offset: -1,
+                    partition_max_bytes: 1 << 20,
+                }],
+            }],
+            forgotten_topics_data: Vec::new(),
+            rack_id: "",
+        };
+        let body = self.exchange(FETCH_KEY, 11, &request)?;
+        let response = FetchResponse::decode(11, &body)?;
+        let fetched = response
+            .records()
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .map(|record| (record.key, record.value, record.headers))
+            .take(records.len())
+            .collect::<Vec<_>>();
+        if fetched != records {
+            return Err(conformance_error(format!(
+                "Fetch returned {:?}, expected {:?}",
+                fetched, records
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sends a request that is not flexible, and returns the body of its response
+    fn exchange<B: Serialize>(
+        &mut self,
+        api_key: i16,
+        api_version: i16,
+        body: &B,
+    ) -> Result<Vec<u8>> {
+        self.correlation_id = self.correlation_id.wrapping_add(1);
+        let mut frame = vec![0; 4];
+        header::write_request_header(
+            &mut frame,
+            api_key,
+            api_version,
+            false,
+            self.correlation_id,
+            Some("kafka-serde-conformance"),
+        )?;
+        ser::append_to_vec(&mut frame, body)?;
+        let size = (frame.len() - 4) as i32;
+        frame[..4].copy_from_slice(&size.to_be_bytes());
+        self.round_trip(&frame, self.correlation_id)
+    }
+
+    /// Writes a request frame, and returns the body of the response, whose header must be a
+    /// v0 one
+    fn round_trip(&mut self, frame: &[u8], correlation_id: i32) -> Result<Vec<u8>> {
+        self.stream.write_all(frame)?;
+        self.stream.flush()?;
+        let mut size = [0; 4];
+        self.stream.read_exact(&mut size)?;
+        let size = i32::from_be_bytes(size);
+        if size < 0 {
+            return Err(ErrorKind::InvalidLength(size).into());
+        }
+        let mut response = Vec::new();
+        client::read_frame(&mut self.stream, size as usize, &mut response)?;
+        let (header, body) = header::split_response(&response, 0)?;
+        if header.correlation_id != correlation_id {
+            return Err(conformance_error(format!(
+                "expected the response to request {}, got the one to {}",
+                correlation_id, header.correlation_id
+            )));
+        }
+        Ok(body.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "mock-broker")]
+    #[test]
+    fn test_api_versions() {
+        use crate::mock::MockBroker;
+
+        #[derive(Serialize)]
+        struct Response {
+            error_code: i16,
+            api_keys: Vec<ApiVersionRange>,
+        }
+
+        let broker = MockBroker::new()
+            .respond_raw(API_VERSIONS_KEY, None, |_| {
+                crate::to_vec(&Response {
+                    error_code: 0,
+                    api_keys: vec![ApiVersionRange {
+                        api_key: FETCH_KEY,
+                        min_version: 4,
+                        max_version: 12,
+                    }],
+                })
+            })
+            .start()
+            .unwrap();
+        let mut conformance = Conformance::new(TcpStream::connect(broker.addr()).unwrap());
+        let versions = conformance.api_versions().unwrap();
+        assert_eq!(versions.get(FETCH_KEY).unwrap().max_version, 12);
+        // Produce and Metadata are missing
+        match *conformance.run("topic").unwrap_err().kind() {
+            ErrorKind::Custom(ref message) => assert!(message.contains("API 0")),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs Docker"]
+    fn test_broker() {
+        let broker = KafkaBroker::start().unwrap();
+        let mut conformance = Conformance::new(broker.connect().unwrap());
+        conformance.run("conformance").unwrap();
+        // the records are fetched from the offset they were produced at
+        conformance.produce_fetch("conformance").unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::{
+    check_version, read_topic_partitions, read_version, write_topic_partitions, TopicPartitions,
+};
+use crate::de::KafkaDeserializer;
+use crate::error::Result;
+use crate::ser;
+use std::io;
+
+/// The newest `ConsumerProtocolAssignment` version supported
+///
+/// Versions 1 to 3 only exist to match the subscription versions, and share the v0 fields.
+pub const ASSIGNMENT_VERSION: i16 = 3;
+
+/// The `ConsumerProtocolAssignment` schema, which the group leader computes for each member and
+/// distributes through SyncGroup
+///
+/// # Examples
+/// ```
+/// use kafka_serde::consumer::{Assignment, TopicPartitions, ASSIGNMENT_VERSION};
+///
+/// let assignment = Assignment {
+///     assigned_partitions: vec![TopicPartitions::new("events", vec![0, 1])],
+///     user_data: None,
+/// };
+/// let mut bytes = Vec::new();
+/// assignment.encode(ASSIGNMENT_VERSION, &mut bytes).unwrap();
+/// assert_eq!(Assignment::decode(&bytes).unwrap(), assignment);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Assignment {
+    /// The partitions assigned to the member
+    pub assigned_partitions: Vec<TopicPartitions>,
+    /// Data from the partition assignor
+    pub user_data: Option<Vec<u8>>,
+}
+
+impl Assignment {
+    /// Encodes the assignment into `writer`, with the given schema version
+    pub fn encode<W: io::Write>(&self, version: i16, writer: &mut W) -> Result<()> {
+        check_version(version, ASSIGNMENT_VERSION)?;
+        writer.write_all(&version.to_be_bytes())?;
+        write_topic_partitions(writer, &self.assigned_partitions)?;
+        ser::write_nullable_bytes(writer, self.user_data.as_deref())
+    }
+
+    /// Decodes an assignment from the SyncGroup assignment bytes in `buf`
+    ///
+    /// Members that were not assigned anything may get an empty buffer, which decodes into an
+    /// empty assignment.
+    pub fn decode(buf: &[u8]) -> Result<Assignment> {
+        if buf.is_empty() {
+            return Ok(Assignment::default());
+        }
+        let mut de = KafkaDeserializer::new(buf);
+        read_version(&mut de)?;
+        Ok(Assignment {
+            assigned_partitions: read_topic_partitions(&mut de)?,
+            user_data: de.read_nullable_bytes()?.map(|d| d.to_vec()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let assignment = Assignment {
+            assigned_partitions: vec![TopicPartitions::new("t", vec![3])],
+            user_data: None,
+        };
+        let mut buf = Vec::new();
+        assignment.encode(1, &mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [0, 1, 0, 0, 0, 1, 0, 1, b't', 0, 0, 0, 1, 0, 0, 0, 3, 0xff, 0xff, 0xff, 0xff]
+        );
+        assert_eq!(Assignment::decode(&buf).unwrap(), assignment);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(Assignment::decode(&[]).unwrap(), Assignment::default());
+    }
+
+    #[test]
+    fn test_newer_version() {
+        let assignment = Assignment {
+            assigned_partitions: vec![TopicPartitions::new("t", vec![0, 1])],
+            user_data: Some(vec![9]),
+        };
+        let mut buf = Vec::new();
+        assignment.encode(ASSIGNMENT_VERSION, &mut buf).unwrap();
+        buf[1] = 8;
+        buf.push(0);
+        assert_eq!(Assignment::decode(&buf).unwrap(), assignment);
+    }
+
+    #[test]
+    fn test_truncated() {
+        assert!(Assignment::decode(&[0, 0, 0, 0, 0, 1]).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Consumer group protocol
+//!
+//! The group membership APIs (JoinGroup, SyncGroup) carry the consumer's metadata as opaque
+//! bytes. Consumers fill them with the versioned schemas in this module, which start with their
+//! own `i16` version. Like the [`records`](crate::records) types, these come with `encode` and
+//! `decode` methods: their fields depend on the version, which serde can't express.
+//!
+//! Decoding follows the Java client: data written with a newer version than the ones known here
+//! is read as the newest known version, ignoring the fields that were added since.
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::ser;
+use std::io;
+
+mod assignment;
+mod sticky;
+mod subscription;
+
+pub use self::assignment::{Assignment, ASSIGNMENT_VERSION};
+pub use self::sticky::{CooperativeStickyUserData, StickyUserData};
+pub use self::subscription::{Subscription, SUBSCRIPTION_VERSION};
+
+/// A topic and some of its partitions
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct TopicPartitions {
+    /// The topic name
+    pub topic: String,
+    /// The partition indexes
+    pub partitions: Vec<i32>,
+}
+
+impl TopicPartitions {
+    /// Creates a new set of partitions of `topic`
+    pub fn new(topic: impl Into<String>, partitions: Vec<i32>) -> Self {
+        TopicPartitions {
+            topic: topic.into(),
+            partitions,
+        }
+    }
+
+    pub(crate) fn write<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        ser::write_string(writer, &self.topic)?;
+        ser::write_array_len(writer, self.partitions.len())?;
+        for partition in &self.partitions {
+            writer.write_all(&partition.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read(de: &mut KafkaDeserializer<'_>) -> Result<TopicPartitions> {
+        let topic = de.read_string()?.to_owned();
+        let len = de.read_array_len()?;
+        let mut partitions = Vec::new();
+        for _ in 0..len {
+            partitions.push(de.read_i32()?);
+        }
+        Ok(TopicPartitions { topic, partitions })
+    }
+}
+
+/// Writes an `ARRAY` of topic partitions
+pub(crate) fn write_topic_partitions<W: io::Write>(
+    writer: &mut W,
+    topics: &[TopicPartitions],
+) -> Result<()> {
+    ser::write_array_len(writer, topics.len())?;
+    for topic in topics {
+        topic.write(writer)?;
+    }
+    Ok(())
+}
+
+/// Reads an `ARRAY` of topic partitions
+pub(crate) fn read_topic_partitions(
+    de: &mut KafkaDeserializer<'_>,
+) -> Result<Vec<TopicPartitions>> {
+    let len = de.read_array_len()?;
+    let mut topics = Vec::new();
+    for _ in 0..len {
+        topics.push(TopicPartitions::read(de)?);
+    }
+    Ok(topics)
+}
+
+/// Reads the version that prefixes the embedded schemas, failing on negative ones
+pub(crate) fn read_version(de: &mut KafkaDeserializer<'_>) -> Result<i16> {
+    match de.read_i16()? {
+        version if version < 0 => {
+            Err(ErrorKind::Custom(format!("invalid schema version: {}", version)).into())
+        }
+        version => Ok(version),
+    }
+}
+
+/// Fails if `version` is newer than `max`, the newest version that can be encoded
+pub(crate) fn check_version(version: i16, max: i16) -> Result<()> {
+    if version < 0 || version > max {
+        return Err(ErrorKind::Custom(format!("unsupported schema version: {}", version)).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_topic_partitions() {
+        let topics = vec![
+            TopicPartitions::new("a", vec![0, 2]),
+            TopicPartitions::new("bc", vec![]),
+        ];
+        let mut buf = Vec::new();
+        write_topic_partitions(&mut buf, &topics).unwrap();
+        assert_eq!(
+            buf[..18],
+            [0, 0, 0, 2, 0, 1, b'a', 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]
+        );
+        let decoded = read_topic_partitions(&mut KafkaDeserializer::new(&buf)).unwrap();
+        assert_eq!(decoded, topics);
+    }
+}
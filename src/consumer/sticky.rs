@@ -0,0 +1,117 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! User data of the sticky assignors.
+//!
+//! Unlike the subscription and assignment schemas, these don't start with a version: the Java
+//! client tells versions apart by whether the optional trailing fields are present.
+use super::{read_topic_partitions, write_topic_partitions, TopicPartitions};
+use crate::de::KafkaDeserializer;
+use crate::error::Result;
+use std::io;
+
+/// The subscription user data of the `sticky` assignor
+///
+/// The v0 schema only carries the previous assignment. v1 adds the generation it was assigned
+/// in, which lets the leader resolve conflicting claims on the same partitions.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct StickyUserData {
+    /// The partitions the member owned before the rebalance
+    pub previous_assignment: Vec<TopicPartitions>,
+    /// The generation of the previous assignment. `None` for v0 user data
+    pub generation: Option<i32>,
+}
+
+impl StickyUserData {
+    /// Encodes the user data into `writer`, as v1 if there is a generation and as v0 otherwise
+    pub fn encode<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        write_topic_partitions(writer, &self.previous_assignment)?;
+        if let Some(generation) = self.generation {
+            writer.write_all(&generation.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Decodes v0 or v1 user data from `buf`
+    pub fn decode(buf: &[u8]) -> Result<StickyUserData> {
+        let mut de = KafkaDeserializer::new(buf);
+        let previous_assignment = read_topic_partitions(&mut de)?;
+        let generation = if de.remaining() >= std::mem::size_of::<i32>() {
+            Some(de.read_i32()?)
+        } else {
+            None
+        };
+        Ok(StickyUserData {
+            previous_assignment,
+            generation,
+        })
+    }
+}
+
+/// The subscription user data of the `cooperative-sticky` assignor
+///
+/// Owned partitions are already part of the v1+ subscription, so only the generation is left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CooperativeStickyUserData {
+    /// The generation the owned partitions were assigned in
+    pub generation: i32,
+}
+
+impl Default for CooperativeStickyUserData {
+    fn default() -> Self {
+        CooperativeStickyUserData { generation: -1 }
+    }
+}
+
+impl CooperativeStickyUserData {
+    /// Encodes the user data into `writer`
+    pub fn encode<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.generation.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Decodes the user data from `buf`
+    pub fn decode(buf: &[u8]) -> Result<CooperativeStickyUserData> {
+        let mut de = KafkaDeserializer::new(buf);
+        Ok(CooperativeStickyUserData {
+            generation: de.read_i32()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sticky_versions() {
+        let mut data = StickyUserData {
+            previous_assignment: vec![TopicPartitions::new("t", vec![0, 2])],
+            generation: None,
+        };
+        let mut v0 = Vec::new();
+        data.encode(&mut v0).unwrap();
+        assert_eq!(StickyUserData::decode(&v0).unwrap(), data);
+
+        data.generation = Some(4);
+        let mut v1 = Vec::new();
+        data.encode(&mut v1).unwrap();
+        assert_eq!(v1[..v0.len()], v0[..]);
+        assert_eq!(v1[v0.len()..], [0, 0, 0, 4]);
+        assert_eq!(StickyUserData::decode(&v1).unwrap(), data);
+    }
+
+    #[test]
+    fn test_cooperative_sticky() {
+        let data = CooperativeStickyUserData { generation: 12 };
+        let mut buf = Vec::new();
+        data.encode(&mut buf).unwrap();
+        assert_eq!(buf, [0, 0, 0, 12]);
+        assert_eq!(CooperativeStickyUserData::decode(&buf).unwrap(), data);
+        assert!(CooperativeStickyUserData::decode(&buf[..3]).is_err());
+    }
+}
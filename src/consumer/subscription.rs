@@ -0,0 +1,177 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::{
+    check_version, read_topic_partitions, read_version, write_topic_partitions, TopicPartitions,
+};
+use crate::de::KafkaDeserializer;
+use crate::error::Result;
+use crate::ser;
+use std::io;
+
+/// The newest `ConsumerProtocolSubscription` version supported
+pub const SUBSCRIPTION_VERSION: i16 = 3;
+
+/// The `ConsumerProtocolSubscription` schema, sent by each member in the JoinGroup protocol
+/// metadata
+///
+/// Fields that don't exist in the version being encoded are left out, and take their default
+/// value when decoding older versions.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::consumer::{Subscription, SUBSCRIPTION_VERSION};
+///
+/// let subscription = Subscription {
+///     topics: vec!["events".to_string()],
+///     ..Default::default()
+/// };
+/// let mut metadata = Vec::new();
+/// subscription.encode(SUBSCRIPTION_VERSION, &mut metadata).unwrap();
+/// assert_eq!(Subscription::decode(&metadata).unwrap(), subscription);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Subscription {
+    /// The topics the member is subscribed to
+    pub topics: Vec<String>,
+    /// Data for the partition assignor
+    pub user_data: Option<Vec<u8>>,
+    /// The partitions the member currently owns. Since v1
+    pub owned_partitions: Vec<TopicPartitions>,
+    /// The generation the owned partitions were assigned in, or -1. Since v2
+    pub generation_id: i32,
+    /// The rack of the member, for rack-aware assignment. Since v3
+    pub rack_id: Option<String>,
+}
+
+impl Default for Subscription {
+    fn default() -> Self {
+        Subscription {
+            topics: Vec::new(),
+            user_data: None,
+            owned_partitions: Vec::new(),
+            generation_id: -1,
+            rack_id: None,
+        }
+    }
+}
+
+impl Subscription {
+    /// Encodes the subscription into `writer`, with the given schema version
+    pub fn encode<W: io::Write>(&self, version: i16, writer: &mut W) -> Result<()> {
+        check_version(version, SUBSCRIPTION_VERSION)?;
+        writer.write_all(&version.to_be_bytes())?;
+        ser::write_array_len(writer, self.topics.len())?;
+        for topic in &self.topics {
+            ser::write_string(writer, topic)?;
+        }
+        ser::write_nullable_bytes(writer, self.user_data.as_deref())?;
+        if version >= 1 {
+            write_topic_partitions(writer, &self.owned_partitions)?;
+        }
+        if version >= 2 {
+            writer.write_all(&self.generation_id.to_be_bytes())?;
+        }
+        if version >= 3 {
+            ser::write_nullable_string(writer, self.rack_id.as_deref())?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a subscription from the protocol metadata in `buf`
+    pub fn decode(buf: &[u8]) -> Result<Subscription> {
+        let mut de = KafkaDeserializer::new(buf);
+        let version = read_version(&mut de)?;
+
+        let len = de.read_array_len()?;
+        let mut topics = Vec::new();
+        for _ in 0..len {
+            topics.push(de.read_string()?.to_owned());
+        }
+        let mut subscription = Subscription {
+            topics,
+            user_data: de.read_nullable_bytes()?.map(|d| d.to_vec()),
+            ..Default::default()
+        };
+        if version >= 1 {
+            subscription.owned_partitions = read_topic_partitions(&mut de)?;
+        }
+        if version >= 2 {
+            subscription.generation_id = de.read_i32()?;
+        }
+        if version >= 3 {
+            subscription.rack_id = de.read_nullable_string()?.map(|r| r.to_owned());
+        }
+        Ok(subscription)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Subscription {
+        Subscription {
+            topics: vec!["a".into(), "b".into()],
+            user_data: Some(vec![1, 2, 3]),
+            owned_partitions: vec![TopicPartitions::new("a", vec![0, 1])],
+            generation_id: 7,
+            rack_id: Some("rack-1".into()),
+        }
+    }
+
+    #[test]
+    fn test_encode_v0() {
+        let mut buf = Vec::new();
+        sample().encode(0, &mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [0, 0, 0, 0, 0, 2, 0, 1, b'a', 0, 1, b'b', 0, 0, 0, 3, 1, 2, 3]
+        );
+        let decoded = Subscription::decode(&buf).unwrap();
+        assert_eq!(decoded.topics, sample().topics);
+        assert_eq!(decoded.user_data, sample().user_data);
+        assert!(decoded.owned_partitions.is_empty());
+        assert_eq!(decoded.generation_id, -1);
+        assert_eq!(decoded.rack_id, None);
+    }
+
+    #[test]
+    fn test_roundtrip_all_versions() {
+        for version in 1..=SUBSCRIPTION_VERSION {
+            let mut buf = Vec::new();
+            sample().encode(version, &mut buf).unwrap();
+            let decoded = Subscription::decode(&buf).unwrap();
+            assert_eq!(decoded.owned_partitions, sample().owned_partitions);
+            assert_eq!(decoded.generation_id == 7, version >= 2);
+            assert_eq!(decoded.rack_id.is_some(), version >= 3);
+        }
+    }
+
+    #[test]
+    fn test_newer_version() {
+        let mut buf = Vec::new();
+        sample().encode(SUBSCRIPTION_VERSION, &mut buf).unwrap();
+        buf[1] = 9;
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(Subscription::decode(&buf).unwrap(), sample());
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        assert!(sample()
+            .encode(SUBSCRIPTION_VERSION + 1, &mut Vec::new())
+            .is_err());
+        assert!(Subscription::decode(&[0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut buf = Vec::new();
+        sample().encode(3, &mut buf).unwrap();
+        assert!(Subscription::decode(&buf[..buf.len() - 1]).is_err());
+    }
+}
@@ -3,100 +3,791 @@
 //
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
 // Datadog, Inc.
-use endianness::*;
+#[cfg(feature = "bytes")]
+use serde::de::DeserializeOwned;
 use serde::de::Visitor;
 use serde::Deserialize;
 use serde::Deserializer;
+use std::marker::PhantomData;
 
 use crate::error::{Error, ErrorKind, Result};
+use crate::intern::InternScope;
+#[cfg(feature = "bytes")]
+use crate::shared_bytes::SharedScope;
+use crate::{metrics, varint};
+
+pub(crate) struct KafkaDeserializer<'de, I = SliceInput<'de>> {
+    input: I,
+    // where the value being deserialized through serde starts, and what it is, for errors
+    mark: usize,
+    expected: &'static str,
+    // fields and indexes leading to the value that failed, innermost first. Only filled in as
+    // an error unwinds, so that successful deserialization doesn't pay for it
+    path: Vec<PathSegment>,
+    // how many sequences and structs are being deserialized
+    depth: usize,
+    options: Decoder,
+    marker: PhantomData<&'de [u8]>,
+}
+
+enum PathSegment {
+    Field(&'static str),
+    Index(usize),
+}
+
+/// How many bytes around the failing value are dumped in errors
+#[cfg(feature = "debug-errors")]
+const HEXDUMP_WINDOW: usize = 16;
+
+/// Dumps the bytes around `offset` in `buf`, 16 per line, with the byte at `offset` in brackets
+#[cfg(feature = "debug-errors")]
+fn hexdump(buf: &[u8], offset: usize) -> String {
+    use std::fmt::Write;
+
+    let start = offset.saturating_sub(HEXDUMP_WINDOW);
+    let end = buf.len().min(offset + HEXDUMP_WINDOW + 1);
+    let mut out = String::new();
+    for (line, chunk) in buf[start..end].chunks(16).enumerate() {
+        let line_start = start + line * 16;
+        if line > 0 {
+            out.push('\n');
+        }
+        let _ = write!(out, "{:08x}:", line_start);
+        for (i, byte) in chunk.iter().enumerate() {
+            if line_start + i == offset {
+                let _ = write!(out, " [{:02x}]", byte);
+            } else {
+                let _ = write!(out, " {:02x}", byte);
+            }
+        }
+    }
+    // the value may start right at the end of the buffer
+    if offset >= buf.len() {
+        let _ = write!(out, " []");
+    }
+    out
+}
+
+/// Where a [`KafkaDeserializer`] reads from
+pub(crate) trait Input<'de> {
+    /// How many bytes were read so far
+    fn position(&self) -> usize;
+
+    /// How many bytes are left
+    fn remaining(&self) -> usize;
 
-struct KafkaDeserializer<'de> {
+    /// Reads the next `len` bytes, which the caller checked are there
+    fn read(&mut self, len: usize) -> Reference<'de, '_>;
+
+    /// Dumps the bytes around `offset`, or nothing if they were already consumed
+    #[cfg(feature = "debug-errors")]
+    fn hexdump(&self, offset: usize) -> String;
+}
+
+/// Bytes read from an [`Input`]
+pub(crate) enum Reference<'de, 's> {
+    /// Borrowed from the input, for as long as it lives
+    Borrowed(&'de [u8]),
+    /// Only valid until the next read
+    #[cfg_attr(not(feature = "bytes"), allow(dead_code))]
+    Transient(&'s [u8]),
+}
+
+impl Reference<'_, '_> {
+    fn as_slice(&self) -> &[u8] {
+        match *self {
+            Reference::Borrowed(bytes) => bytes,
+            Reference::Transient(bytes) => bytes,
+        }
+    }
+}
+
+/// A contiguous buffer, which values can borrow from
+pub(crate) struct SliceInput<'de> {
     buf: &'de [u8],
     pos: usize,
 }
 
+impl<'de> SliceInput<'de> {
+    fn take(&mut self, len: usize) -> &'de [u8] {
+        let begin = self.pos;
+        self.pos += len;
+        &self.buf[begin..self.pos]
+    }
+}
+
+impl<'de> Input<'de> for SliceInput<'de> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    fn read(&mut self, len: usize) -> Reference<'de, '_> {
+        Reference::Borrowed(self.take(len))
+    }
+
+    #[cfg(feature = "debug-errors")]
+    fn hexdump(&self, offset: usize) -> String {
+        hexdump(self.buf, offset)
+    }
+}
+
+/// A buffer made of chunks that may not be contiguous, consumed as it is read
+///
+/// Reads are served from the current chunk when it holds them, and only copied to `scratch`
+/// when they straddle chunks.
+#[cfg(feature = "bytes")]
+pub(crate) struct BufInput<'b, B: bytes::Buf> {
+    buf: &'b mut B,
+    pos: usize,
+    // bytes handed out from the current chunk, consumed before the next read
+    pending: usize,
+    scratch: Vec<u8>,
+}
+
+#[cfg(feature = "bytes")]
+impl<'b, B: bytes::Buf> BufInput<'b, B> {
+    fn new(buf: &'b mut B) -> Self {
+        BufInput {
+            buf,
+            pos: 0,
+            pending: 0,
+            scratch: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<'de, B: bytes::Buf> Input<'de> for BufInput<'_, B> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn remaining(&self) -> usize {
+        self.buf.remaining() - self.pending
+    }
+
+    fn read(&mut self, len: usize) -> Reference<'de, '_> {
+        self.buf.advance(std::mem::take(&mut self.pending));
+        self.pos += len;
+        if self.buf.chunk().len() >= len {
+            self.pending = len;
+            Reference::Transient(&self.buf.chunk()[..len])
+        } else {
+            self.scratch.clear();
+            self.scratch.resize(len, 0);
+            self.buf.copy_to_slice(&mut self.scratch);
+            Reference::Transient(&self.scratch)
+        }
+    }
+
+    #[cfg(feature = "debug-errors")]
+    fn hexdump(&self, _offset: usize) -> String {
+        String::new()
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<B: bytes::Buf> Drop for BufInput<'_, B> {
+    fn drop(&mut self) {
+        self.buf.advance(self.pending);
+    }
+}
+
 impl<'de> KafkaDeserializer<'de> {
+    pub(crate) fn new(buf: &'de [u8]) -> Self {
+        Self::with_options(buf, Decoder::default())
+    }
+
+    fn with_options(buf: &'de [u8], options: Decoder) -> Self {
+        Self::from_input(SliceInput { buf, pos: 0 }, options)
+    }
+
+    /// The bytes that are yet to be deserialized, without consuming them
+    pub(crate) fn peek_remaining(&self) -> &'de [u8] {
+        &self.input.buf[self.input.pos..]
+    }
+
+    pub(crate) fn slice(&mut self, len: usize) -> Result<&'de [u8]> {
+        self.check_room(len)?;
+        Ok(self.input.take(len))
+    }
+
+    fn read_unsigned_varlong(&mut self, max_len: usize) -> Result<u64> {
+        let mut value = 0u64;
+        for i in 0..max_len {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ErrorKind::InvalidVarint.into())
+    }
+
+    pub(crate) fn read_varint(&mut self) -> Result<i32> {
+        let value = self.read_unsigned_varlong(varint::MAX_VARINT_LEN)?;
+        if value > u32::MAX as u64 {
+            return Err(ErrorKind::InvalidVarint.into());
+        }
+        Ok(varint::unzigzag(value) as i32)
+    }
+
+    pub(crate) fn read_varlong(&mut self) -> Result<i64> {
+        let value = self.read_unsigned_varlong(varint::MAX_VARLONG_LEN)?;
+        Ok(varint::unzigzag(value))
+    }
+
+    /// Reads an `UNSIGNED_VARINT`
+    pub(crate) fn read_unsigned_varint(&mut self) -> Result<u32> {
+        let value = self.read_unsigned_varlong(varint::MAX_VARINT_LEN)?;
+        if value > u32::MAX as u64 {
+            return Err(ErrorKind::InvalidVarint.into());
+        }
+        Ok(value as u32)
+    }
+
+    /// Reads a `COMPACT_NULLABLE_STRING`, prefixed by its length plus one, where 0 means null
+    pub(crate) fn read_compact_nullable_string(&mut self) -> Result<Option<&'de str>> {
+        match self.read_unsigned_varint()? {
+            0 => Ok(None),
+            len => {
+                let trusted = self.options.trusted_utf8;
+                Ok(Some(to_str(self.slice(len as usize - 1)?, trusted)?))
+            }
+        }
+    }
+
+    /// Reads a `COMPACT_STRING`, prefixed by its length plus one
+    pub(crate) fn read_compact_string(&mut self) -> Result<&'de str> {
+        self.read_compact_nullable_string()?
+            .ok_or_else(|| ErrorKind::Custom("unexpected null string".into()).into())
+    }
+
+    /// Reads `COMPACT_NULLABLE_BYTES`, prefixed by their length plus one, where 0 means null
+    pub(crate) fn read_compact_nullable_bytes(&mut self) -> Result<Option<&'de [u8]>> {
+        match self.read_unsigned_varint()? {
+            0 => Ok(None),
+            len => Ok(Some(self.slice(len as usize - 1)?)),
+        }
+    }
+
+    /// Reads `COMPACT_BYTES`, prefixed by their length plus one
+    pub(crate) fn read_compact_bytes(&mut self) -> Result<&'de [u8]> {
+        self.read_compact_nullable_bytes()?
+            .ok_or_else(|| ErrorKind::Custom("unexpected null bytes".into()).into())
+    }
+
+    /// Reads the element count of a `COMPACT_ARRAY`, stored plus one, where 0 means null
+    pub(crate) fn read_compact_array_len(&mut self) -> Result<Option<usize>> {
+        match self.read_unsigned_varint()? {
+            0 => Ok(None),
+            len => Ok(Some(len as usize - 1)),
+        }
+    }
+
+    /// Skips over the tagged fields at the end of a flexible structure
+    pub(crate) fn skip_tagged_fields(&mut self) -> Result<()> {
+        let count = self.read_unsigned_varint()?;
+        for _ in 0..count {
+            let _tag = self.read_unsigned_varint()?;
+            let size = self.read_unsigned_varint()?;
+            self.slice(size as usize)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a `NULLABLE_STRING`, prefixed by its `i16` length, where -1 means null
+    pub(crate) fn read_nullable_string(&mut self) -> Result<Option<&'de str>> {
+        match self.read_i16()? {
+            -1 => Ok(None),
+            len if len < 0 => Err(ErrorKind::InvalidLength(len.into()).into()),
+            len => {
+                let trusted = self.options.trusted_utf8;
+                Ok(Some(to_str(self.slice(len as usize)?, trusted)?))
+            }
+        }
+    }
+
+    /// Reads a `STRING`, prefixed by its `i16` length
+    pub(crate) fn read_string(&mut self) -> Result<&'de str> {
+        self.read_nullable_string()?
+            .ok_or_else(|| ErrorKind::Custom("unexpected null string".into()).into())
+    }
+
+    /// Reads `NULLABLE_BYTES`, prefixed by their `i32` length, where -1 means null
+    pub(crate) fn read_nullable_bytes(&mut self) -> Result<Option<&'de [u8]>> {
+        match self.read_i32()? {
+            -1 => Ok(None),
+            len if len < 0 => Err(ErrorKind::InvalidLength(len).into()),
+            len => Ok(Some(self.slice(len as usize)?)),
+        }
+    }
+
+    /// Reads the `i32` element count of an `ARRAY`. Null arrays are read as empty ones
+    pub(crate) fn read_array_len(&mut self) -> Result<usize> {
+        match self.read_i32()? {
+            -1 => Ok(0),
+            len if len < 0 => Err(ErrorKind::InvalidLength(len).into()),
+            len => Ok(len as usize),
+        }
+    }
+}
+
+impl<'de, I: Input<'de>> KafkaDeserializer<'de, I> {
+    fn from_input(input: I, options: Decoder) -> Self {
+        KafkaDeserializer {
+            input,
+            mark: 0,
+            expected: "",
+            path: Vec::new(),
+            depth: 0,
+            options,
+            marker: PhantomData,
+        }
+    }
+
+    fn check_limit(&self, len: usize, max: usize) -> Result<()> {
+        if len > max {
+            return Err(ErrorKind::LimitExceeded {
+                what: self.expected,
+                len,
+                max,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Records that a value of type `expected` starts at the current position
+    pub(crate) fn expect(&mut self, expected: &'static str) {
+        self.mark = self.input.position();
+        self.expected = expected;
+    }
+
+    /// Adds the position of the value being deserialized to `err`, and the path leading to it
+    pub(crate) fn locate(&self, err: Error) -> Error {
+        let mut path = String::new();
+        for segment in self.path.iter().rev() {
+            match segment {
+                PathSegment::Field(name) if path.is_empty() => path.push_str(name),
+                PathSegment::Field(name) => {
+                    path.push('.');
+                    path.push_str(name);
+                }
+                PathSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+            }
+        }
+        self.locate_at(err, path)
+    }
+
+    /// Like [`locate`](KafkaDeserializer::locate), with the path leading to the value tracked
+    /// by the caller
+    pub(crate) fn locate_at(&self, err: Error, path: String) -> Error {
+        ErrorKind::Decode {
+            offset: self.mark,
+            expected: self.expected,
+            path,
+            error: err,
+            #[cfg(feature = "debug-errors")]
+            hexdump: self.input.hexdump(self.mark),
+        }
+        .into()
+    }
+
+    /// How many bytes were read so far
+    pub(crate) fn position(&self) -> usize {
+        self.input.position()
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.input.remaining()
+    }
+
     fn check_room(&self, room: usize) -> Result<()> {
-        if self.pos + room > self.buf.len() {
-            Err(Box::new(ErrorKind::NotEnoughBytes))
+        if room > self.remaining() {
+            Err(ErrorKind::NeedMoreBytes(room - self.remaining()).into())
         } else {
             Ok(())
         }
     }
 
-    fn check_room_for<T: Sized>(&self) -> Result<()> {
-        self.check_room(std::mem::size_of::<T>())
+    /// Reads the next `len` bytes, borrowed from the input if it allows it
+    fn read_exact(&mut self, len: usize) -> Result<Reference<'de, '_>> {
+        self.check_room(len)?;
+        Ok(self.input.read(len))
     }
 
-    fn slice(&mut self, len: usize) -> Result<&'de [u8]> {
-        self.check_room(len)?;
-        let begin = self.pos;
-        self.pos += len;
-        Ok(&self.buf[begin..self.pos])
+    /// Reads the next `N` bytes, for the big-endian integers
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_exact(N)?.as_slice());
+        Ok(array)
     }
 
-    fn copy_slice(&mut self, len: usize) -> Result<Vec<u8>> {
-        self.check_room(len)?;
-        let begin = self.pos;
-        self.pos += len;
-        let mut bytes: Vec<u8> = Vec::with_capacity(len);
-        bytes.extend_from_slice(&self.buf[begin..self.pos]);
-        Ok(bytes)
+    pub(crate) fn read_i8(&mut self) -> Result<i8> {
+        Ok(i8::from_be_bytes(self.read_array()?))
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8> {
+        Ok(u8::from_be_bytes(self.read_array()?))
+    }
+
+    pub(crate) fn read_i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.read_array()?))
+    }
+
+    pub(crate) fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.read_array()?))
+    }
+
+    pub(crate) fn read_i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.read_array()?))
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.read_array()?))
+    }
+
+    pub(crate) fn read_i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.read_array()?))
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.read_array()?))
+    }
+
+    /// Deserializes `len` consecutive values, named after `fields` if they are struct fields
+    fn deserialize_elements<V>(
+        &mut self,
+        len: usize,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        struct Access<'a, 'de, I> {
+            deserializer: &'a mut KafkaDeserializer<'de, I>,
+            len: usize,
+            fields: &'static [&'static str],
+            index: usize,
+            // the last field the input ended before, with the error decoding it
+            ended: Option<(usize, Error)>,
+        }
+
+        impl<'de, 'a, I: Input<'de>> serde::de::SeqAccess<'de> for Access<'a, 'de, I> {
+            type Error = Error;
+
+            fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+            where
+                T: serde::de::DeserializeSeed<'de>,
+            {
+                if self.len > 0 {
+                    self.len -= 1;
+                    let index = self.index;
+                    self.index += 1;
+                    let at_end = !self.fields.is_empty() && self.deserializer.remaining() == 0;
+                    let depth = self.deserializer.path.len();
+                    match serde::de::DeserializeSeed::deserialize(seed, &mut *self.deserializer) {
+                        Ok(value) => Ok(Some(value)),
+                        // older versions of messages omit their trailing fields: when the input
+                        // ends before a field that needs bytes, it takes its `#[serde(default)]`,
+                        // if it has one
+                        Err(err) if at_end && err.is_incomplete() => {
+                            self.deserializer.path.truncate(depth);
+                            self.ended = Some((index, err));
+                            Ok(None)
+                        }
+                        Err(err) => {
+                            let segment = match self.fields.get(index) {
+                                Some(field) => PathSegment::Field(field),
+                                None => PathSegment::Index(index),
+                            };
+                            self.deserializer.path.push(segment);
+                            Err(err)
+                        }
+                    }
+                } else {
+                    Ok(None)
+                }
+            }
+
+            fn size_hint(&self) -> Option<usize> {
+                // visitors may allocate from the hint, so it can't go over what the input
+                // holds: elements take a byte or more, but for empty structs
+                Some(self.len.min(self.deserializer.remaining()))
+            }
+        }
+
+        // recursive types like trees nest as deep as the input says, so bail out before
+        // overflowing the stack
+        if self.depth >= self.options.max_depth {
+            return Err(ErrorKind::LimitExceeded {
+                what: "nesting depth",
+                len: self.depth + 1,
+                max: self.options.max_depth,
+            }
+            .into());
+        }
+        self.depth += 1;
+        let mut access = Access {
+            deserializer: &mut *self,
+            len,
+            fields,
+            index: 0,
+            ended: None,
+        };
+        let result = visitor.visit_seq(&mut access);
+        let ended = access.ended;
+        self.depth -= 1;
+        match (result, ended) {
+            // the visitor found no default for the last field the input ended before, which is
+            // the input ending early rather than the struct being invalid
+            (Err(err), Some((index, ended))) if matches!(*err.kind(), ErrorKind::Custom(_)) => {
+                self.path.push(PathSegment::Field(fields[index]));
+                Err(ended)
+            }
+            (result, _) => result,
+        }
+    }
+}
+
+/// Reads `bytes` as a string, checking that they are UTF-8 unless they are `trusted` to be, see
+/// [`Decoder::trusted_utf8`]
+fn to_str(bytes: &[u8], trusted: bool) -> std::result::Result<&str, std::str::Utf8Error> {
+    if trusted {
+        // SAFETY: the strings of the input were vouched for when `trusted_utf8` was enabled
+        Ok(unsafe { std::str::from_utf8_unchecked(bytes) })
+    } else {
+        std::str::from_utf8(bytes)
+    }
+}
+
+/// Deserializes kafka payloads, with options to relax or tighten the default checks
+///
+/// [`from_bytes`] uses the default options, which are strict: anything that doesn't follow the
+/// protocol is an error.
+///
+/// # Examples
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Metadata {
+///     host: String,
+/// }
+///
+/// let data = [0x00, 0x03, b'a', 0xff, b'b'];
+/// assert!(kafka_serde::from_bytes::<Metadata>(&data).is_err());
+///
+/// let decoder = kafka_serde::Decoder::new().lossy_utf8(true);
+/// let metadata: Metadata = decoder.decode(&data).unwrap();
+/// assert_eq!(metadata.host, "a\u{fffd}b");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Decoder {
+    lossy_utf8: bool,
+    trusted_utf8: bool,
+    lenient_bool: bool,
+    intern_strings: bool,
+    max_string_len: usize,
+    max_bytes_len: usize,
+    max_array_len: usize,
+    max_depth: usize,
+}
+
+/// Default limit for bytes and arrays, the default `socket.request.max.bytes` of brokers
+const DEFAULT_MAX_LEN: usize = 100 * 1024 * 1024;
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder {
+            lossy_utf8: false,
+            trusted_utf8: false,
+            lenient_bool: false,
+            intern_strings: false,
+            max_string_len: i16::MAX as usize,
+            max_bytes_len: DEFAULT_MAX_LEN,
+            max_array_len: DEFAULT_MAX_LEN,
+            max_depth: 128,
+        }
+    }
+}
+
+impl Decoder {
+    /// Creates a decoder with the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to replace invalid UTF-8 in strings with `U+FFFD`, rather than failing with
+    /// [`ErrorKind::InvalidStringEncoding`]. Defaults to `false`
+    ///
+    /// This only works for strings deserialized into owned types like `String`, or into a
+    /// `Cow<str>` which then owns the repaired string: invalid strings can't be borrowed from
+    /// the buffer as `&str`.
+    pub fn lossy_utf8(mut self, lossy: bool) -> Self {
+        self.lossy_utf8 = lossy;
+        self
+    }
+
+    /// Whether to take strings as they are, without checking that they are valid UTF-8.
+    /// Defaults to `false`
+    ///
+    /// Checking strings takes a noticeable part of the time spent decoding messages made of
+    /// them, like Metadata responses: `cargo bench --bench utf8` decodes one 15 to 25% faster
+    /// without. Pipelines decoding traffic they know to be valid, e.g. from their own brokers,
+    /// can skip it.
+    ///
+    /// # Safety
+    ///
+    /// All strings of the payloads decoded must be valid UTF-8: Rust strings that are not are
+    /// undefined behavior.
+    ///
+    /// # Examples
+    /// ```
+    /// let data = [0x00, 0x04, b'h', b'o', b's', b't'];
+    /// // SAFETY: the string of the payload is valid UTF-8
+    /// let decoder = unsafe { kafka_serde::Decoder::new().trusted_utf8(true) };
+    /// let host: &str = decoder.decode(&data).unwrap();
+    /// assert_eq!(host, "host");
+    /// ```
+    pub unsafe fn trusted_utf8(mut self, trusted: bool) -> Self {
+        self.trusted_utf8 = trusted;
+        self
+    }
+
+    /// Whether to read any nonzero byte as `true`, like some non-Java clients write them, rather
+    /// than failing with [`ErrorKind::InvalidBoolEncoding`]. Defaults to `false`
+    pub fn lenient_bool(mut self, lenient: bool) -> Self {
+        self.lenient_bool = lenient;
+        self
     }
 
-    fn read_i8(&mut self) -> Result<i8> {
-        self.check_room_for::<i8>()?;
-        let value = self.buf[self.pos];
-        self.pos += std::mem::size_of::<i8>();
-        Ok(value as i8)
+    /// Whether [`InternedStr`](crate::InternedStr) values that are equal share one allocation,
+    /// among those of the same payload. Defaults to `false`
+    pub fn intern_strings(mut self, intern: bool) -> Self {
+        self.intern_strings = intern;
+        self
     }
 
-    fn read_u8(&mut self) -> Result<u8> {
-        self.check_room_for::<u8>()?;
-        let value = self.buf[self.pos];
-        self.pos += std::mem::size_of::<u8>();
-        Ok(value)
+    /// The longest string accepted, in bytes. Defaults to `i16::MAX`, the longest a string can be
+    pub fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = len;
+        self
     }
 
-    fn read_i16(&mut self) -> Result<i16> {
-        let value = read_i16(&self.buf[self.pos..], ByteOrder::BigEndian)?;
-        self.pos += std::mem::size_of::<i16>();
-        Ok(value)
+    /// The longest byte array accepted. Defaults to 100 MiB
+    pub fn max_bytes_len(mut self, len: usize) -> Self {
+        self.max_bytes_len = len;
+        self
     }
 
-    fn read_u16(&mut self) -> Result<u16> {
-        let value = read_u16(&self.buf[self.pos..], ByteOrder::BigEndian)?;
-        self.pos += std::mem::size_of::<u16>();
-        Ok(value)
+    /// The most elements accepted in an array, which includes byte arrays deserialized into a
+    /// `Vec<u8>`. Defaults to 100 Mi
+    pub fn max_array_len(mut self, len: usize) -> Self {
+        self.max_array_len = len;
+        self
     }
 
-    fn read_i32(&mut self) -> Result<i32> {
-        let value = read_i32(&self.buf[self.pos..], ByteOrder::BigEndian)?;
-        self.pos += std::mem::size_of::<i32>();
-        Ok(value)
+    /// How deep sequences, tuples and structs may be nested in each other. Defaults to 128
+    ///
+    /// This only matters for recursive types, whose nesting depends on the input.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
     }
 
-    fn read_u32(&mut self) -> Result<u32> {
-        let value = read_u32(&self.buf[self.pos..], ByteOrder::BigEndian)?;
-        self.pos += std::mem::size_of::<u32>();
-        Ok(value)
+    /// Deserializes a payload contained in a byte slice
+    ///
+    /// Lengths read from `buf` are checked against the limits before anything is read or
+    /// allocated, failing with [`ErrorKind::LimitExceeded`] if they are over.
+    ///
+    /// Errors are wrapped in [`ErrorKind::Decode`], which tells where in `buf` the value that
+    /// could not be deserialized starts.
+    pub fn decode<'de, T>(&self, buf: &'de [u8]) -> Result<T>
+    where
+        T: Deserialize<'de>,
+    {
+        let _scope = if self.intern_strings {
+            Some(InternScope::enter())
+        } else {
+            None
+        };
+        let mut k_der = KafkaDeserializer::with_options(buf, *self);
+        let result = T::deserialize(&mut k_der).map_err(|err| k_der.locate(err));
+        metrics::report_decode(&result, k_der.position());
+        result
     }
 
-    fn read_i64(&mut self) -> Result<i64> {
-        let value = read_i64(&self.buf[self.pos..], ByteOrder::BigEndian)?;
-        self.pos += std::mem::size_of::<i64>();
-        Ok(value)
+    /// Deserializes a payload contained in a [`Bytes`](bytes::Bytes) buffer, whose byte fields
+    /// can share it
+    ///
+    /// Fields using [`shared_bytes`](crate::shared_bytes) become slices of `buf`, instead of
+    /// copies. Everything else is deserialized like [`Decoder::decode`] does.
+    #[cfg(feature = "bytes")]
+    pub fn decode_shared<'de, T>(&self, buf: &'de bytes::Bytes) -> Result<T>
+    where
+        T: Deserialize<'de>,
+    {
+        let _scope = SharedScope::enter(buf);
+        self.decode(buf)
     }
 
-    fn read_u64(&mut self) -> Result<u64> {
-        let value = read_u64(&self.buf[self.pos..], ByteOrder::BigEndian)?;
-        self.pos += std::mem::size_of::<u64>();
-        Ok(value)
+    /// Deserializes a payload from a buffer whose bytes may not be contiguous, like a chain of
+    /// network buffers, consuming exactly the bytes of the payload
+    ///
+    /// Strings and byte arrays are handed over from the chunk that holds them, and only copied
+    /// when they straddle two chunks. Nothing can be borrowed from `buf`, hence the owned `T`.
+    ///
+    /// Offsets in errors count from where `buf` was when the call started. On errors, `buf` is
+    /// left after the last value that could be read.
+    ///
+    /// # Examples
+    /// ```
+    /// use bytes::Buf;
+    ///
+    /// let first: &[u8] = &[0x00, 0x05, b'h', b'e'];
+    /// let second: &[u8] = &[b'l', b'l', b'o', 0xff];
+    /// let mut buf = first.chain(second);
+    /// let host: String = kafka_serde::Decoder::new().decode_buf(&mut buf).unwrap();
+    /// assert_eq!(host, "hello");
+    /// assert_eq!(buf.remaining(), 1);
+    /// ```
+    #[cfg(feature = "bytes")]
+    pub fn decode_buf<T, B>(&self, buf: &mut B) -> Result<T>
+    where
+        T: DeserializeOwned,
+        B: bytes::Buf,
+    {
+        let _scope = if self.intern_strings {
+            Some(InternScope::enter())
+        } else {
+            None
+        };
+        let mut k_der = KafkaDeserializer::from_input(BufInput::new(buf), *self);
+        let result = T::deserialize(&mut k_der).map_err(|err| k_der.locate(err));
+        metrics::report_decode(&result, k_der.position());
+        result
     }
 }
 
 /// Deserialize a kafka payload contained in a byte slice
 ///
+/// Errors are wrapped in [`ErrorKind::Decode`], which tells where in `buf` the value that could
+/// not be deserialized starts. See [`Decoder`] to change how strict deserialization is.
+///
+/// Strings and byte arrays can be borrowed from `buf`, as `&str` and `&[u8]`. `Cow<str>` and
+/// `Cow<[u8]>` fields marked `#[serde(borrow)]` borrow them too, but own them when they can't,
+/// e.g. when they are decoded from a `bytes::Buf` or repaired by [`Decoder::lossy_utf8`].
+///
+/// Struct fields marked `#[serde(default)]` take their default value when `buf` ends before
+/// them, like the trailing fields that older versions of messages omit.
+///
 /// # Examples
 /// ```
 /// use serde::Deserialize;
@@ -116,11 +807,23 @@ pub fn from_bytes<'de, T>(buf: &'de [u8]) -> Result<T>
 where
     T: Deserialize<'de>,
 {
-    let mut k_der = KafkaDeserializer { buf, pos: 0 };
-    T::deserialize(&mut k_der)
+    Decoder::new().decode(buf)
+}
+
+/// Deserialize a kafka payload from a [`bytes::Buf`], whose bytes may not be contiguous
+///
+/// This uses the default options, see [`Decoder::decode_buf`].
+#[cfg(feature = "bytes")]
+#[inline]
+pub fn from_buf<T, B>(buf: &mut B) -> Result<T>
+where
+    T: DeserializeOwned,
+    B: bytes::Buf,
+{
+    Decoder::new().decode_buf(buf)
 }
 
-impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
+impl<'de, I: Input<'de>> Deserializer<'de> for &mut KafkaDeserializer<'de, I> {
     type Error = Error;
 
     fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
@@ -134,10 +837,12 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: serde::de::Visitor<'de>,
     {
+        self.expect("bool");
         let value = self.read_u8()?;
         match value {
             0 => visitor.visit_bool(false),
             1 => visitor.visit_bool(true),
+            _ if self.options.lenient_bool => visitor.visit_bool(true),
             _ => Err(ErrorKind::InvalidBoolEncoding(value).into()),
         }
     }
@@ -146,6 +851,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        self.expect("i8");
         visitor.visit_i8(self.read_i8()?)
     }
 
@@ -153,6 +859,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: serde::de::Visitor<'de>,
     {
+        self.expect("u8");
         visitor.visit_u8(self.read_u8()?)
     }
 
@@ -160,6 +867,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        self.expect("u16");
         visitor.visit_u16(self.read_u16()?)
     }
 
@@ -167,6 +875,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        self.expect("i16");
         visitor.visit_i16(self.read_i16()?)
     }
 
@@ -174,6 +883,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        self.expect("u32");
         visitor.visit_u32(self.read_u32()?)
     }
 
@@ -181,6 +891,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        self.expect("i32");
         visitor.visit_i32(self.read_i32()?)
     }
 
@@ -188,6 +899,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        self.expect("u64");
         visitor.visit_u64(self.read_u64()?)
     }
 
@@ -195,6 +907,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        self.expect("i64");
         visitor.visit_i64(self.read_i64()?)
     }
 
@@ -209,9 +922,8 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let value = read_f64(&self.buf[self.pos..], ByteOrder::BigEndian)?;
-        self.pos += std::mem::size_of::<f64>();
-        visitor.visit_f64(value)
+        self.expect("f64");
+        visitor.visit_f64(f64::from_be_bytes(self.read_array()?))
     }
 
     fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value>
@@ -232,46 +944,88 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        self.expect("string");
         let len = self.read_i16()?;
         if len == 0 || len == -1 {
             return visitor.visit_borrowed_str("");
         }
+        if len < 0 {
+            return Err(ErrorKind::InvalidLength(len.into()).into());
+        }
         let len = len as usize;
-        let out_str = std::str::from_utf8(self.slice(len)?)?;
-        visitor.visit_borrowed_str(out_str)
+        self.check_limit(len, self.options.max_string_len)?;
+        let lossy = self.options.lossy_utf8;
+        let trusted = self.options.trusted_utf8;
+        match self.read_exact(len)? {
+            Reference::Borrowed(bytes) => match to_str(bytes, trusted) {
+                Ok(out_str) => visitor.visit_borrowed_str(out_str),
+                Err(_) if lossy => {
+                    visitor.visit_string(String::from_utf8_lossy(bytes).into_owned())
+                }
+                Err(err) => Err(err.into()),
+            },
+            Reference::Transient(bytes) => match to_str(bytes, trusted) {
+                Ok(out_str) => visitor.visit_str(out_str),
+                Err(_) if lossy => {
+                    visitor.visit_string(String::from_utf8_lossy(bytes).into_owned())
+                }
+                Err(err) => Err(err.into()),
+            },
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
+        self.expect("string");
         let len = self.read_i16()?;
         if len == 0 || len == -1 {
             return visitor.visit_string("".into());
         }
+        if len < 0 {
+            return Err(ErrorKind::InvalidLength(len.into()).into());
+        }
         let len = len as usize;
-        let bytes = self.copy_slice(len)?;
-        let out_string = String::from_utf8(bytes)?;
-        visitor.visit_string(out_string)
+        self.check_limit(len, self.options.max_string_len)?;
+        // validate in place, so that the string is allocated and copied exactly once, by the
+        // visitor
+        let lossy = self.options.lossy_utf8;
+        let trusted = self.options.trusted_utf8;
+        let bytes = self.read_exact(len)?;
+        let bytes = bytes.as_slice();
+        match to_str(bytes, trusted) {
+            Ok(out_str) => visitor.visit_str(out_str),
+            Err(_) if lossy => visitor.visit_string(String::from_utf8_lossy(bytes).into_owned()),
+            Err(err) => Err(err.into()),
+        }
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
+        self.expect("bytes");
         let len = self.read_i32()?;
         if len == 0 || len == -1 {
             return visitor.visit_borrowed_bytes(&[]);
         }
+        if len < 0 {
+            return Err(ErrorKind::InvalidLength(len).into());
+        }
         let len = len as usize;
-        visitor.visit_borrowed_bytes(self.slice(len)?)
+        self.check_limit(len, self.options.max_bytes_len)?;
+        match self.read_exact(len)? {
+            Reference::Borrowed(bytes) => visitor.visit_borrowed_bytes(bytes),
+            Reference::Transient(bytes) => visitor.visit_bytes(bytes),
+        }
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        type_not_supported!("de-byte-buf")
+        self.deserialize_bytes(visitor)
     }
 
     fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value>
@@ -299,48 +1053,21 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let mut len = self.read_i32()?;
-        if len == -1 {
-            len = 0;
-        }
-        self.deserialize_tuple(len as usize, visitor)
+        self.expect("array length");
+        let len = match self.read_i32()? {
+            -1 => 0,
+            len if len < 0 => return Err(ErrorKind::InvalidLength(len).into()),
+            len => len as usize,
+        };
+        self.check_limit(len, self.options.max_array_len)?;
+        self.deserialize_tuple(len, visitor)
     }
 
     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        struct Access<'a, 'de> {
-            deserializer: &'a mut KafkaDeserializer<'de>,
-            len: usize,
-        }
-
-        impl<'de, 'a> serde::de::SeqAccess<'de> for Access<'a, 'de> {
-            type Error = Error;
-
-            fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
-            where
-                T: serde::de::DeserializeSeed<'de>,
-            {
-                if self.len > 0 {
-                    self.len -= 1;
-                    let value =
-                        (serde::de::DeserializeSeed::deserialize(seed, &mut *self.deserializer))?;
-                    Ok(Some(value))
-                } else {
-                    Ok(None)
-                }
-            }
-
-            fn size_hint(&self) -> Option<usize> {
-                Some(self.len)
-            }
-        }
-
-        visitor.visit_seq(Access {
-            deserializer: self,
-            len,
-        })
+        self.deserialize_elements(len, &[], visitor)
     }
 
     fn deserialize_tuple_struct<V>(
@@ -355,11 +1082,15 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
         type_not_supported!("de-tuple-struct")
     }
 
+    // structs with `#[serde(flatten)]` fields deserialize as maps too, buffering the fields
+    // they don't know as self-describing values, which kafka payloads are not: they can't be
+    // supported, only serialized. They are encoded like nested structs, or a `header::Frame`,
+    // which decode the same bytes
     fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        type_not_supported!("de-map")
+        type_not_supported!("de-map, or struct with flattened fields: nest them or use a Frame")
     }
 
     fn deserialize_struct<V>(
@@ -371,7 +1102,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_tuple(fields.len(), visitor)
+        self.deserialize_elements(fields.len(), fields, visitor)
     }
 
     fn deserialize_enum<V>(
@@ -405,6 +1136,7 @@ impl<'de, 'a> Deserializer<'de> for &'a mut KafkaDeserializer<'de> {
 mod test {
     use super::*;
     use serde::Deserialize;
+    use std::borrow::Cow;
 
     #[derive(Deserialize, Debug, Default)]
     struct Dummy1 {
@@ -417,7 +1149,7 @@ mod test {
         let data = [0x05, 0x01];
         let dummy: Dummy1 = from_bytes(&data).unwrap();
         assert_eq!(dummy.value, 5);
-        assert_eq!(dummy.off, true);
+        assert!(dummy.off);
     }
 
     #[derive(Deserialize, Debug, Default)]
@@ -461,6 +1193,7 @@ mod test {
     }
 
     #[derive(Deserialize, Debug, Default)]
+    #[allow(dead_code)]
     struct Foo<'a> {
         string: &'a str,
         bytes: &'a [u8],
@@ -555,4 +1288,484 @@ mod test {
         let dummy: DummySequence = from_bytes(&data).unwrap();
         assert_eq!(dummy.value.len(), 0);
     }
+
+    #[test]
+    fn test_read_varint() {
+        let data = [0x00, 0x01, 0x80, 0x01, 0xff, 0xff, 0xff, 0xff, 0x0f];
+        let mut de = KafkaDeserializer::new(&data);
+        assert_eq!(de.read_varint().unwrap(), 0);
+        assert_eq!(de.read_varint().unwrap(), -1);
+        assert_eq!(de.read_varint().unwrap(), 64);
+        assert_eq!(de.read_varint().unwrap(), i32::MIN);
+        assert!(de.read_varint().is_err());
+    }
+
+    #[test]
+    fn test_read_nullable() {
+        let data = [
+            0xff, 0xff, 0x00, 0x02, b'h', b'i', 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+            0x07, 0xff, 0xfe,
+        ];
+        let mut de = KafkaDeserializer::new(&data);
+        assert_eq!(de.read_nullable_string().unwrap(), None);
+        assert_eq!(de.read_string().unwrap(), "hi");
+        assert_eq!(de.read_nullable_bytes().unwrap(), None);
+        assert_eq!(de.read_nullable_bytes().unwrap(), Some(&[7u8][..]));
+        assert!(de.read_nullable_string().is_err());
+    }
+
+    #[test]
+    fn test_read_compact() {
+        let data = [
+            0x00, 0x03, b'h', b'i', 0x02, 0x00, 0x01, 0xaa, 0x01, 0x00, 0x01, 0x02, 0xcc,
+        ];
+        let mut de = KafkaDeserializer::new(&data);
+        assert_eq!(de.read_compact_nullable_string().unwrap(), None);
+        assert_eq!(de.read_compact_string().unwrap(), "hi");
+        // two tagged fields: tag 0 with one byte, tag 1 with no bytes
+        de.skip_tagged_fields().unwrap();
+        assert_eq!(de.read_compact_array_len().unwrap(), Some(0));
+        assert_eq!(de.read_compact_bytes().unwrap(), [0xcc]);
+        assert_eq!(de.remaining(), 0);
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Dummy4 {
+        a: i32,
+        b: bool,
+        c: String,
+    }
+
+    #[test]
+    fn test_error_offset() {
+        let data = [0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x05, b'a'];
+        match *from_bytes::<Dummy4>(&data).unwrap_err().kind() {
+            ErrorKind::Decode {
+                offset: 5,
+                expected: "string",
+                ref path,
+                ref error,
+                ..
+            } if path == "c" => match *error.kind() {
+                ErrorKind::NeedMoreBytes(4) => {}
+                ref e => panic!("unexpected error {}", e),
+            },
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        let data = [0x00, 0x00, 0x00, 0x01, 0x02];
+        let err = from_bytes::<Dummy4>(&data).unwrap_err();
+        // the hexdump of the debug-errors feature comes on the next lines
+        assert_eq!(
+            err.to_string().lines().next().unwrap(),
+            "b: at byte 4, decoding bool: invalid bool encoding, expected 0 or 1, found 2"
+        );
+        match *err.root_cause() {
+            ErrorKind::InvalidBoolEncoding(2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert!(!err.is_incomplete());
+    }
+
+    #[test]
+    fn test_lenient_bool() {
+        let data = [0x00, 0x00, 0x00, 0x01, 0xff, 0x00, 0x00];
+        let decoder = Decoder::new().lenient_bool(true);
+        let dummy: Dummy4 = decoder.decode(&data).unwrap();
+        assert!(dummy.b);
+        let dummy: Dummy4 = decoder.decode(&[0, 0, 0, 1, 0, 0, 0]).unwrap();
+        assert!(!dummy.b);
+    }
+
+    #[cfg(feature = "debug-errors")]
+    #[test]
+    fn test_hexdump() {
+        let data: Vec<u8> = (0..40).collect();
+        assert_eq!(
+            hexdump(&data, 20),
+            "00000004: 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f 10 11 12 13\n\
+             00000014: [14] 15 16 17 18 19 1a 1b 1c 1d 1e 1f 20 21 22 23\n\
+             00000024: 24"
+        );
+        assert_eq!(hexdump(&data[..2], 1), "00000000: 00 [01]");
+        assert_eq!(hexdump(&data[..2], 2), "00000000: 00 01 []");
+
+        let data = [0x00, 0x00, 0x00, 0x01, 0x02];
+        let err = from_bytes::<Dummy4>(&data).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("found 2\n00000000: 00 00 00 01 [02]"));
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Partition {
+        index: i32,
+        error_code: i16,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Topic {
+        name: String,
+        partitions: Vec<Partition>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Response {
+        topics: Vec<Topic>,
+    }
+
+    #[test]
+    fn test_error_path() {
+        let data = [
+            0x00, 0x00, 0x00, 0x02, // two topics
+            0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x00, // "a", no partitions
+            0x00, 0x01, b'b', 0x00, 0x00, 0x00, 0x01, // "b", one partition
+            0x00, 0x00, 0x00, 0x03, 0x00, // partition 3, truncated error code
+        ];
+        let err = from_bytes::<Response>(&data).unwrap_err();
+        assert!(err.is_incomplete());
+        assert_eq!(
+            err.to_string().lines().next().unwrap(),
+            "topics[1].partitions[0].error_code: at byte 22, decoding i16: not enough bytes, 1 more needed"
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Header {
+            api_key: i16,
+            api_version: i16,
+        }
+        #[derive(Debug, Deserialize)]
+        struct Flattened {
+            #[serde(flatten)]
+            _header: Header,
+            _n: i32,
+        }
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Nested {
+            header: Header,
+            n: i32,
+        }
+
+        let data = [0, 18, 0, 3, 0, 0, 0, 7];
+        let err = from_bytes::<Flattened>(&data).unwrap_err();
+        match *err.root_cause() {
+            ErrorKind::TypeNotSupported(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        let nested = from_bytes::<Nested>(&data).unwrap();
+        assert_eq!(nested.header.api_key, 18);
+        assert_eq!(nested.n, 7);
+    }
+
+    #[test]
+    fn test_default_trailing_fields() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Partition {
+            index: i32,
+            error_code: i16,
+            #[serde(default)]
+            leader_epoch: i32,
+            #[serde(default)]
+            replicas: Vec<i32>,
+        }
+
+        // v0 ends before the leader epoch
+        let v0 = [0, 0, 0, 3, 0, 0];
+        let partition = from_bytes::<Partition>(&v0).unwrap();
+        assert_eq!(
+            partition,
+            Partition {
+                index: 3,
+                error_code: 0,
+                leader_epoch: 0,
+                replicas: vec![],
+            }
+        );
+        let v1 = [0, 0, 0, 3, 0, 0, 0, 0, 0, 7];
+        assert_eq!(from_bytes::<Partition>(&v1).unwrap().leader_epoch, 7);
+
+        // fields without defaults are still needed
+        let err = from_bytes::<Partition>(&v0[..4]).unwrap_err();
+        assert!(err.is_incomplete());
+        assert_eq!(
+            err.to_string().lines().next().unwrap(),
+            "error_code: at byte 4, decoding i16: not enough bytes, 2 more needed"
+        );
+        // and the input can't end in the middle of a field
+        let err = from_bytes::<Partition>(&v1[..8]).unwrap_err();
+        match *err.root_cause() {
+            ErrorKind::NeedMoreBytes(2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        // while fields of no size don't need any
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Empty {}
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Request {
+            version: i16,
+            body: Empty,
+        }
+        let request = from_bytes::<Request>(&[0, 2]).unwrap();
+        assert_eq!(request.body, Empty {});
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error as _;
+
+        let data = [0x00, 0x02, 0xc3, 0x28];
+        let err = from_bytes::<DummyString>(&data).unwrap_err();
+        let cause = err.source().unwrap();
+        assert_eq!(cause.to_string(), "string not utf-8 encoded");
+        let utf8 = cause.source().unwrap();
+        let utf8 = utf8.downcast_ref::<std::str::Utf8Error>().unwrap();
+        assert_eq!(utf8.valid_up_to(), 0);
+    }
+
+    #[test]
+    fn test_trusted_utf8() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Hosts<'a> {
+            borrowed: &'a str,
+            owned: String,
+        }
+
+        let data = [0x00, 0x02, 0xc3, 0xa9, 0x00, 0x01, b'a'];
+        // SAFETY: the strings are valid UTF-8
+        let decoder = unsafe { Decoder::new().trusted_utf8(true) };
+        let hosts: Hosts<'_> = decoder.decode(&data).unwrap();
+        assert_eq!(hosts, from_bytes(&data).unwrap());
+        assert_eq!(hosts.borrowed, "é");
+
+        let mut de = KafkaDeserializer::with_options(&data, decoder);
+        assert_eq!(de.read_nullable_string().unwrap(), Some("é"));
+    }
+
+    #[test]
+    fn test_lossy_utf8() {
+        let data = [0x00, 0x02, 0xc3, 0x28];
+        let decoder = Decoder::new().lossy_utf8(true);
+        let dummy: DummyString = decoder.decode(&data).unwrap();
+        assert_eq!(dummy.value, "\u{fffd}(");
+        // borrowed strings can't hold the replacement characters
+        assert!(decoder.decode::<DummyStringReference<'_>>(&data).is_err());
+        assert!(from_bytes::<DummyString>(&data).is_err());
+    }
+
+    #[test]
+    fn test_read_integers() {
+        let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let mut de = KafkaDeserializer::new(&data);
+        assert_eq!(de.read_i64().unwrap(), 0x0102030405060708);
+        let mut de = KafkaDeserializer::new(&data[..6]);
+        assert_eq!(de.read_i32().unwrap(), 0x01020304);
+        assert_eq!(de.read_u16().unwrap(), 0x0506);
+        for len in 0..8 {
+            let mut de = KafkaDeserializer::new(&data[..len]);
+            match *de.read_i64().unwrap_err().kind() {
+                ErrorKind::NeedMoreBytes(n) if n == 8 - len => {}
+                ref e => panic!("unexpected error {}", e),
+            }
+            // nothing is consumed
+            assert_eq!(de.remaining(), len);
+        }
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        let data = [0xff, 0xfb, 0x63];
+        let err = from_bytes::<DummyString>(&data).unwrap_err();
+        match *err.root_cause() {
+            ErrorKind::InvalidLength(-5) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert!(!err.is_incomplete());
+
+        let data = [0xff, 0xff, 0xff, 0xfe, 0x00];
+        match *from_bytes::<DummySequence>(&data).unwrap_err().root_cause() {
+            ErrorKind::InvalidLength(-2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        match *from_bytes::<DummyByteReference<'_>>(&data)
+            .unwrap_err()
+            .root_cause()
+        {
+            ErrorKind::InvalidLength(-2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        let mut de = KafkaDeserializer::new(&[0xff, 0xfe]);
+        match *de.read_nullable_string().unwrap_err().kind() {
+            ErrorKind::InvalidLength(-2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let data = [0x00, 0x00, 0x00, 0x3, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03];
+        let decoder = Decoder::new().max_array_len(2);
+        match *decoder
+            .decode::<DummySequence>(&data)
+            .unwrap_err()
+            .root_cause()
+        {
+            ErrorKind::LimitExceeded {
+                what: "array length",
+                len: 3,
+                max: 2,
+            } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert!(Decoder::new()
+            .max_array_len(3)
+            .decode::<DummySequence>(&data)
+            .is_ok());
+
+        let data = [0x00, 0x0a, 0x63, 0x6f, 0x6e, 0x73];
+        let err = Decoder::new()
+            .max_string_len(4)
+            .decode::<DummyString>(&data)
+            .unwrap_err();
+        // the limit is checked before running out of bytes
+        match *err.root_cause() {
+            ErrorKind::LimitExceeded { len: 10, .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        let data = [0xff, 0xff, 0xff, 0xf0];
+        assert!(from_bytes::<DummyByteReference<'_>>(&data).is_err());
+        let data = [0x00, 0x00, 0x00, 0x02, 0x01, 0x02];
+        let decoder = Decoder::new().max_bytes_len(1);
+        assert!(decoder.decode::<DummyByteReference<'_>>(&data).is_err());
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Tree {
+        children: Vec<Tree>,
+    }
+
+    #[test]
+    fn test_max_depth() {
+        // every tree has one child, down to an empty one
+        let mut data = Vec::new();
+        for _ in 0..10 {
+            data.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+        }
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+
+        // a tree and its children array are two levels each
+        let tree: Tree = Decoder::new().max_depth(22).decode(&data).unwrap();
+        assert_eq!(tree.children.len(), 1);
+        let err = Decoder::new()
+            .max_depth(21)
+            .decode::<Tree>(&data)
+            .unwrap_err();
+        match *err.root_cause() {
+            ErrorKind::LimitExceeded {
+                what: "nesting depth",
+                len: 22,
+                max: 21,
+            } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        let data = [0x00, 0x00, 0x00, 0x01].repeat(100_000);
+        assert!(from_bytes::<Tree>(&data).is_err());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Chunked {
+        topic: String,
+        partition: i32,
+        hosts: Vec<String>,
+        data: Vec<u8>,
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_decode_buf() {
+        use bytes::Buf;
+
+        let data = [
+            0x00, 0x05, b't', b'o', b'p', b'i', b'c', // topic
+            0x00, 0x00, 0x00, 0x07, // partition
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x01, b'a', 0x00, 0x02, b'b', b'c', // hosts
+            0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03, // data
+            0xff, // the next payload
+        ];
+        let expected: Chunked = from_bytes(&data).unwrap();
+        // split everywhere, so that every value straddles chunks at some point
+        for i in 0..=data.len() {
+            let mut buf = data[..i].chain(&data[i..]);
+            assert_eq!(from_buf::<Chunked, _>(&mut buf).unwrap(), expected);
+            assert_eq!(buf.chunk(), [0xff]);
+        }
+
+        let mut buf = data[..10].chain(&data[10..20]);
+        let err = from_buf::<Chunked, _>(&mut buf).unwrap_err();
+        assert!(err.is_incomplete());
+        assert_eq!(
+            err.to_string().lines().next().unwrap(),
+            "hosts[1]: at byte 18, decoding string: not enough bytes, 2 more needed"
+        );
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Cows<'a> {
+        #[serde(borrow)]
+        name: Cow<'a, str>,
+        #[serde(borrow)]
+        data: Cow<'a, [u8]>,
+    }
+
+    #[test]
+    fn test_cow() {
+        let data = [0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x02, 0x01, 0x02];
+        let cows: Cows<'_> = from_bytes(&data).unwrap();
+        assert!(matches!(cows.name, Cow::Borrowed("a")));
+        assert!(matches!(cows.data, Cow::Borrowed([1, 2])));
+
+        let data = [0x00, 0x02, 0xc3, 0x28, 0x00, 0x00, 0x00, 0x00];
+        let cows: Cows<'_> = Decoder::new().lossy_utf8(true).decode(&data).unwrap();
+        assert!(matches!(cows.name, Cow::Owned(ref name) if name == "\u{fffd}("));
+        assert!(cows.data.is_empty());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_cow_from_buf() {
+        use bytes::Buf;
+
+        #[derive(Deserialize)]
+        struct OwnedCows {
+            name: Cow<'static, str>,
+            data: Cow<'static, [u8]>,
+        }
+
+        let data = [0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x02, 0x01, 0x02];
+        let cows: OwnedCows = from_buf(&mut data[..3].chain(&data[3..])).unwrap();
+        assert!(matches!(cows.name, Cow::Owned(ref name) if name == "a"));
+        assert!(matches!(cows.data, Cow::Owned(ref data) if data == &[1, 2]));
+    }
+
+    #[test]
+    fn test_read_varint_too_long() {
+        let data = [0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        let mut de = KafkaDeserializer::new(&data);
+        match *de.read_varint().unwrap_err().kind() {
+            ErrorKind::InvalidVarint => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
 }
@@ -3,7 +3,6 @@
 //
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
 // Datadog, Inc.
-use endianness::EndiannessError;
 use serde::de;
 use serde::ser;
 use std::fmt;
@@ -12,7 +11,26 @@ use std::fmt::Formatter;
 use std::io;
 
 /// Error returned by Kafka serde
-pub type Error = Box<ErrorKind>;
+///
+/// It is as small as a pointer, so that results stay cheap to pass around. Match on
+/// [`Error::kind`] for details, or branch on [`Error::category`] for what to do about it.
+///
+/// The [`ErrorKind`] is boxed: unboxed, its largest variants, the ones with a path or a message,
+/// would make every `Result` of the crate several times larger, on paths that rarely fail.
+/// [`Error::into_kind`] takes it out of the box.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::ErrorCategory;
+///
+/// fn should_retry(err: &kafka_serde::Error) -> bool {
+///     match err.category() {
+///         ErrorCategory::Io => true,
+///         _ => err.is_incomplete(),
+///     }
+/// }
+/// ```
+pub struct Error(Box<ErrorKind>);
 
 /// Result alias where the Error component is a kafka_serde::Error
 pub type Result<T> = core::result::Result<T, Error>;
@@ -20,75 +38,302 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[derive(Debug)]
 /// Errors that may happen when parsing a kafka payload (reader or writer)
 pub enum ErrorKind {
-    /// Wraps an I/O Error. Will only be seen if the write cursors return an I/O error
+    /// Wraps an I/O error of the reader or writer
     Io(io::Error),
     /// Trying to serialize to or from a type that is not yet supported
     TypeNotSupported(&'static str),
     /// A boolean was expected, but a value different than 0 or 1 was found
     InvalidBoolEncoding(u8),
     /// A UTF-8 string was expected, but could not decode it.
-    InvalidStringEncoding,
+    InvalidStringEncoding(std::str::Utf8Error),
     /// The buffer ran out of bytes but we still had more data to deserialize
+    ///
+    /// Streams that end early fail with an [`ErrorKind::Io`] error of kind `UnexpectedEof`
+    /// instead, which keeps the error of the reader. Both are
+    /// [incomplete](ErrorKind::is_incomplete).
     NotEnoughBytes,
+    /// The buffer ended in the middle of a value, which needs at least this many more bytes
+    ///
+    /// The data may still be valid: it is just incomplete. See [`ErrorKind::is_incomplete`].
+    NeedMoreBytes(usize),
+    /// A length or element count was negative, and not the -1 that stands for null where null
+    /// is allowed
+    InvalidLength(i32),
+    /// A variable-length integer was longer than its type allows
+    InvalidVarint,
+    /// The record data uses a message format version (magic byte) that is not supported here
+    UnsupportedMagic(i8),
+    /// The record data is compressed with an unknown codec, or one whose feature is disabled
+    UnsupportedCompression(i8),
+    /// The checksum stored in a record batch or legacy message doesn't match its contents
+    CrcMismatch {
+        /// The checksum found in the batch header or message
+        expected: u32,
+        /// The checksum computed over the batch or message contents
+        computed: u32,
+    },
+    /// A length read from the input, or the nesting depth, is over the limit set in the
+    /// [`Decoder`](crate::Decoder)
+    LimitExceeded {
+        /// What the length is about, e.g. `string`, `array length` or `nesting depth`
+        what: &'static str,
+        /// The length that was read
+        len: usize,
+        /// The limit
+        max: usize,
+    },
+    /// A value is too long for the length prefix the protocol gives it, e.g. a string of more
+    /// than `i16::MAX` bytes. Nothing is written for it
+    LengthOverflow {
+        /// What the length is about, e.g. `string`
+        what: &'static str,
+        /// The length of the value
+        len: usize,
+        /// The longest the prefix allows
+        max: usize,
+    },
+    /// Deserialization failed on the value starting at `offset`
+    ///
+    /// Returned by [`from_bytes`](crate::from_bytes), around the error that caused the failure.
+    Decode {
+        /// Offset of the value in the deserialized buffer
+        offset: usize,
+        /// The Kafka type of the value, e.g. `i32` or `string`
+        expected: &'static str,
+        /// The fields and array indexes leading to the value, e.g.
+        /// `topics[2].partitions[0].error_code`. Empty if the value is not nested
+        path: String,
+        /// What went wrong
+        error: Error,
+        /// The bytes around the value, with its first byte in brackets. Only with the
+        /// `debug-errors` feature, and empty when decoding from a `bytes::Buf`
+        #[cfg(feature = "debug-errors")]
+        hexdump: String,
+    },
     /// Custom errors
     Custom(String),
 }
 
+/// The broad class of an error, telling what can be done about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// The reader or writer failed
+    Io,
+    /// The data doesn't follow the protocol, or ended early
+    Protocol,
+    /// The data is over one of the limits of the [`Decoder`](crate::Decoder), or of the
+    /// protocol
+    Limit,
+    /// The data or the types use something that is not implemented here
+    Unsupported,
+    /// A custom error, from a `Serialize` or `Deserialize` implementation or from a schema
+    Custom,
+}
+
+impl Error {
+    /// The kind of error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0
+    }
+
+    /// Consumes the error, returning its kind
+    pub fn into_kind(self) -> ErrorKind {
+        *self.0
+    }
+
+    /// The class of the error that caused this one
+    pub fn category(&self) -> ErrorCategory {
+        self.0.category()
+    }
+
+    /// Whether decoding failed because the data ended early. See [`ErrorKind::is_incomplete`]
+    pub fn is_incomplete(&self) -> bool {
+        self.0.is_incomplete()
+    }
+
+    /// The error that caused this one. See [`ErrorKind::root_cause`]
+    pub fn root_cause(&self) -> &ErrorKind {
+        self.0.root_cause()
+    }
+}
+
+impl ErrorKind {
+    /// The class of the error that caused this one
+    pub fn category(&self) -> ErrorCategory {
+        match self.root_cause() {
+            ErrorKind::Io(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                ErrorCategory::Protocol
+            }
+            ErrorKind::Io(_) => ErrorCategory::Io,
+            ErrorKind::InvalidBoolEncoding(_)
+            | ErrorKind::InvalidStringEncoding(_)
+            | ErrorKind::NotEnoughBytes
+            | ErrorKind::NeedMoreBytes(_)
+            | ErrorKind::InvalidLength(_)
+            | ErrorKind::InvalidVarint
+            | ErrorKind::CrcMismatch { .. } => ErrorCategory::Protocol,
+            ErrorKind::LimitExceeded { .. } | ErrorKind::LengthOverflow { .. } => {
+                ErrorCategory::Limit
+            }
+            ErrorKind::TypeNotSupported(_)
+            | ErrorKind::UnsupportedMagic(_)
+            | ErrorKind::UnsupportedCompression(_) => ErrorCategory::Unsupported,
+            ErrorKind::Custom(_) => ErrorCategory::Custom,
+            ErrorKind::Decode { .. } => unreachable!("root causes are never Decode"),
+        }
+    }
+
+    /// Whether decoding failed because the data ended early, rather than because it is
+    /// malformed
+    ///
+    /// Streaming consumers can buffer more data and try again when this is true, reading at
+    /// least as many bytes as [`ErrorKind::NeedMoreBytes`] asks for. Streams that end early
+    /// are incomplete too.
+    pub fn is_incomplete(&self) -> bool {
+        match self.root_cause() {
+            ErrorKind::NotEnoughBytes | ErrorKind::NeedMoreBytes(_) => true,
+            ErrorKind::Io(err) => err.kind() == io::ErrorKind::UnexpectedEof,
+            _ => false,
+        }
+    }
+
+    /// The error that caused this one, skipping over the context added around it
+    ///
+    /// Use it to match on the kind of error regardless of where it happened.
+    pub fn root_cause(&self) -> &ErrorKind {
+        match self {
+            ErrorKind::Decode { error, .. } => error.root_cause(),
+            kind => kind,
+        }
+    }
+}
+
 impl Display for ErrorKind {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
         match *self {
             ErrorKind::Io(ref ioerr) => write!(fmt, "io error: {}", ioerr),
             ErrorKind::InvalidBoolEncoding(b) => {
-                write!(fmt, "{}, expected 0 or 1, found {}", self, b)
+                write!(fmt, "invalid bool encoding, expected 0 or 1, found {}", b)
             }
-            ErrorKind::InvalidStringEncoding => {
+            ErrorKind::InvalidStringEncoding(_) => {
                 write!(fmt, "string not utf-8 encoded")
             }
             ErrorKind::NotEnoughBytes => {
-                write!(fmt, "not enought bytes")
+                write!(fmt, "not enough bytes")
+            }
+            ErrorKind::NeedMoreBytes(n) => {
+                write!(fmt, "not enough bytes, {} more needed", n)
+            }
+            ErrorKind::InvalidLength(len) => write!(fmt, "invalid length: {}", len),
+            ErrorKind::InvalidVarint => {
+                write!(fmt, "malformed variable-length integer")
+            }
+            ErrorKind::UnsupportedMagic(m) => {
+                write!(fmt, "unsupported message format version: {}", m)
+            }
+            ErrorKind::UnsupportedCompression(c) => {
+                write!(fmt, "unsupported compression codec: {}", c)
             }
+            ErrorKind::CrcMismatch { expected, computed } => write!(
+                fmt,
+                "crc mismatch: expected {:#010x}, computed {:#010x}",
+                expected, computed
+            ),
             ErrorKind::TypeNotSupported(s) => {
                 write!(fmt, "not supported: {}", s)
             }
+            ErrorKind::LimitExceeded { what, len, max } => {
+                write!(
+                    fmt,
+                    "{} of length {} is over the limit of {}",
+                    what, len, max
+                )
+            }
+            ErrorKind::LengthOverflow { what, len, max } => {
+                write!(
+                    fmt,
+                    "{} of length {} is longer than the {} its length prefix allows",
+                    what, len, max
+                )
+            }
+            ErrorKind::Decode {
+                offset,
+                expected,
+                ref path,
+                ref error,
+                ..
+            } => {
+                if !path.is_empty() {
+                    write!(fmt, "{}: ", path)?;
+                }
+                write!(fmt, "at byte {}, decoding {}: {}", offset, expected, error)?;
+                #[cfg(feature = "debug-errors")]
+                if let ErrorKind::Decode { ref hexdump, .. } = *self {
+                    // the bytes are gone when they were consumed from a `bytes::Buf`
+                    if !hexdump.is_empty() {
+                        write!(fmt, "\n{}", hexdump)?;
+                    }
+                }
+                Ok(())
+            }
             ErrorKind::Custom(ref s) => s.fmt(fmt),
         }
     }
 }
 
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error(Box::new(kind))
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, fmt)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, fmt)
+    }
+}
+
 impl From<io::Error> for Error {
     #[cold]
     fn from(err: io::Error) -> Error {
+        // streaming decoders run into the end of their input as an `UnexpectedEof`, which
+        // `is_incomplete` reports like the end of slices
         ErrorKind::Io(err).into()
     }
 }
 
-impl From<EndiannessError> for Error {
-    fn from(_err: EndiannessError) -> Error {
-        ErrorKind::NotEnoughBytes.into()
-    }
-}
-
 impl From<std::str::Utf8Error> for Error {
-    fn from(_err: std::str::Utf8Error) -> Error {
-        ErrorKind::InvalidStringEncoding.into()
+    fn from(err: std::str::Utf8Error) -> Error {
+        ErrorKind::InvalidStringEncoding(err).into()
     }
 }
 
 impl From<std::string::FromUtf8Error> for Error {
-    fn from(_err: std::string::FromUtf8Error) -> Error {
-        ErrorKind::InvalidStringEncoding.into()
+    fn from(err: std::string::FromUtf8Error) -> Error {
+        ErrorKind::InvalidStringEncoding(err.utf8_error()).into()
     }
 }
 
 impl From<Error> for io::Error {
     fn from(err: Error) -> io::Error {
-        io::Error::new(io::ErrorKind::Other, err)
+        io::Error::other(err)
     }
 }
 
 impl serde::de::StdError for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        match *self.0 {
+            ErrorKind::Io(ref err) => Some(err),
+            ErrorKind::InvalidStringEncoding(ref err) => Some(err),
+            ErrorKind::Decode { ref error, .. } => Some(error),
+            _ => None,
+        }
     }
 }
 
@@ -105,3 +350,56 @@ impl ser::Error for Error {
         ErrorKind::Custom(msg.to_string()).into()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(std::mem::size_of::<Error>(), std::mem::size_of::<usize>());
+        assert_eq!(
+            std::mem::size_of::<Result<()>>(),
+            std::mem::size_of::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_category() {
+        let err: Error = ErrorKind::LimitExceeded {
+            what: "string",
+            len: 10,
+            max: 5,
+        }
+        .into();
+        assert_eq!(err.category(), ErrorCategory::Limit);
+
+        let err: Error = ErrorKind::Decode {
+            offset: 4,
+            expected: "bool",
+            path: String::new(),
+            error: ErrorKind::InvalidBoolEncoding(2).into(),
+            #[cfg(feature = "debug-errors")]
+            hexdump: String::new(),
+        }
+        .into();
+        assert_eq!(err.category(), ErrorCategory::Protocol);
+        match err.into_kind() {
+            ErrorKind::Decode { offset: 4, .. } => {}
+            e => panic!("unexpected error {}", e),
+        }
+
+        let err: Error = io::Error::from(io::ErrorKind::BrokenPipe).into();
+        assert_eq!(err.category(), ErrorCategory::Io);
+        assert!(!err.is_incomplete());
+        let err: Error = io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended").into();
+        assert_eq!(err.category(), ErrorCategory::Protocol);
+        assert!(err.is_incomplete());
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "stream ended");
+        let err: Error = ErrorKind::UnsupportedMagic(3).into();
+        assert_eq!(err.category(), ErrorCategory::Unsupported);
+        let err = <Error as de::Error>::custom("unknown variant");
+        assert_eq!(err.category(), ErrorCategory::Custom);
+    }
+}
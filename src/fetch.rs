@@ -0,0 +1,410 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Fetch responses
+//!
+//! A [`FetchResponse`] decodes the body of a FetchResponse, and [`FetchResponse::records`]
+//! iterates over the records of all its partitions, with their topic, partition, offset and
+//! timestamp, decompressing their batches along the way. Consumers get the records they fetched
+//! without walking the nested topics and partitions, or decoding record sets themselves.
+//!
+//! Versions 4 to 12 are supported, which return v2 record batches and name topics. Fields that
+//! are not decoded, like the aborted transactions, are skipped.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::fetch::FetchResponse;
+//!
+//! fn consume(body: &[u8]) -> kafka_serde::Result<()> {
+//!     let response = FetchResponse::decode(11, body)?;
+//!     for record in response.records() {
+//!         let record = record?;
+//!         println!("{}-{}@{}: {:?}", record.topic, record.partition, record.offset, record.value);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use std::mem;
+use std::sync::OnceLock;
+
+use crate::error::Result;
+use crate::records::{BatchDecoder, Headers, RecordDeserializer};
+use crate::schema::{take_field, take_field_or, Field, FromValue, KafkaValue, Schema, Type};
+
+/// The API key of Fetch
+pub const FETCH_KEY: i16 = 1;
+
+/// The first version of Fetch returning record batches
+const MIN_VERSION: i16 = 4;
+/// The last version of Fetch naming topics, which later versions identify by their id
+const MAX_VERSION: i16 = 12;
+
+/// The schema of FetchResponse, in the versions supported
+fn schema() -> &'static Schema {
+    static SCHEMA: OnceLock<Schema> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        let aborted = Type::Struct(vec![
+            Field::new("ProducerId", Type::Int64),
+            Field::new("FirstOffset", Type::Int64),
+        ]);
+        let partition = Type::Struct(vec![
+            Field::new("PartitionIndex", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("HighWatermark", Type::Int64),
+            Field::new("LastStableOffset", Type::Int64),
+            Field::new("LogStartOffset", Type::Int64).versions(5..),
+            Field::new("AbortedTransactions", Type::Array(Box::new(aborted))).nullable_versions(..),
+            Field::new("PreferredReadReplica", Type::Int32).versions(11..),
+            Field::new("Records", Type::Records).nullable_versions(..),
+        ]);
+        let topic = Type::Struct(vec![
+            Field::new("Topic", Type::String),
+            Field::new("Partitions", Type::Array(Box::new(partition))),
+        ]);
+        Schema::new(
+            "FetchResponse",
+            vec![
+                Field::new("ThrottleTimeMs", Type::Int32),
+                Field::new("ErrorCode", Type::Int16).versions(7..),
+                Field::new("SessionId", Type::Int32).versions(7..),
+                Field::new("Responses", Type::Array(Box::new(topic))),
+            ],
+        )
+        .valid_versions(MIN_VERSION..=MAX_VERSION)
+        .flexible_versions(12..)
+    })
+}
+
+/// A decoded FetchResponse
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FetchResponse {
+    /// How long the response was throttled for, in milliseconds
+    pub throttle_time_ms: i32,
+    /// The error of the whole request, 0 if there was none
+    pub error_code: i16,
+    /// The id of the fetch session
+    pub session_id: i32,
+    /// The topics fetched
+    pub topics: Vec<FetchedTopic>,
+}
+
+/// A topic of a [`FetchResponse`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FetchedTopic {
+    /// The topic name
+    pub name: String,
+    /// The partitions fetched
+    pub partitions: Vec<FetchedPartition>,
+}
+
+/// A partition of a [`FetchResponse`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FetchedPartition {
+    /// The partition index
+    pub index: i32,
+    /// The error of the partition, 0 if there was none
+    pub error_code: i16,
+    /// The offset following the last record replicated to all in-sync replicas
+    pub high_watermark: i64,
+    /// The offset following the last record of committed transactions
+    pub last_stable_offset: i64,
+    /// The first offset of the log, -1 in versions without it
+    pub log_start_offset: i64,
+    /// The record batches fetched, as encoded, null if there are none
+    pub records: Option<Vec<u8>>,
+}
+
+impl FetchResponse {
+    /// Decodes the body of a FetchResponse in `version`, from 4 to 12
+    pub fn decode(version: i16, body: &[u8]) -> Result<FetchResponse> {
+        Self::from_value(schema().decode(version, body)?)
+    }
+
+    /// Iterates over the records of all partitions, decoding the batches of each partition
+    /// when the iteration reaches it
+    ///
+    /// Partitions whose batches fail to decode yield an error, then the iteration carries on
+    /// with the next one.
+    pub fn records(&self) -> FetchedRecords<'_> {
+        FetchedRecords {
+            decoder: BatchDecoder::new(),
+            partitions: Box::new(self.topics.iter().flat_map(|topic| {
+                topic
+                    .partitions
+                    .iter()
+                    .map(move |partition| (topic.name.as_str(), partition))
+            })),
+            current: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl FromValue for FetchResponse {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(FetchResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            session_id: take_field(&mut fields, "SessionId")?,
+            topics: take_field(&mut fields, "Responses")?,
+        })
+    }
+}
+
+impl FromValue for FetchedTopic {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(FetchedTopic {
+            name: take_field(&mut fields, "Topic")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+        })
+    }
+}
+
+impl FromValue for FetchedPartition {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(FetchedPartition {
+            index: take_field(&mut fields, "PartitionIndex")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            high_watermark: take_field(&mut fields, "HighWatermark")?,
+            last_stable_offset: take_field(&mut fields, "LastStableOffset")?,
+            log_start_offset: take_field_or(&mut fields, "LogStartOffset", -1)?,
+            records: take_field(&mut fields, "Records")?,
+        })
+    }
+}
+
+/// A record of a [`FetchResponse`], with where it was read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedRecord<'a> {
+    /// The topic of the record
+    pub topic: &'a str,
+    /// The partition of the record
+    pub partition: i32,
+    /// The offset of the record
+    pub offset: i64,
+    /// The timestamp of the record, in milliseconds since the epoch
+    pub timestamp: i64,
+    /// The record key
+    pub key: Option<Vec<u8>>,
+    /// The record value
+    pub value: Option<Vec<u8>>,
+    /// The record headers
+    pub headers: Headers,
+}
+
+impl FetchedRecord<'_> {
+    /// Decodes the key with `deserializer`, unless it is null
+    pub fn key_with<T, D: RecordDeserializer<T>>(&self, deserializer: &D) -> Option<Result<T>> {
+        self.key.as_deref().map(|key| deserializer.deserialize(key))
+    }
+
+    /// Decodes the value with `deserializer`, unless it is null
+    pub fn value_with<T, D: RecordDeserializer<T>>(&self, deserializer: &D) -> Option<Result<T>> {
+        self.value
+            .as_deref()
+            .map(|value| deserializer.deserialize(value))
+    }
+}
+
+/// The records of a [`FetchResponse`], see [`FetchResponse::records`]
+pub struct FetchedRecords<'a> {
+    decoder: BatchDecoder,
+    partitions: Box<dyn Iterator<Item = (&'a str, &'a FetchedPartition)> + 'a>,
+    // the records of the partition being iterated over
+    current: std::vec::IntoIter<FetchedRecord<'a>>,
+}
+
+impl std::fmt::Debug for FetchedRecords<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FetchedRecords")
+            .field("decoder", &self.decoder)
+            .finish()
+    }
+}
+
+impl<'a> Iterator for FetchedRecords<'a> {
+    type Item = Result<FetchedRecord<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.current.next() {
+                return Some(Ok(record));
+            }
+            let (topic, partition) = self.partitions.next()?;
+            let records = partition.records.as_deref().unwrap_or_default();
+            match self.decoder.decode_all(records) {
+                Ok(batches) => {
+                    self.current = batches
+                        .into_iter()
+                        .flat_map(|mut batch| {
+                            let records = mem::take(&mut batch.records);
+                            records.into_iter().map(move |record| FetchedRecord {
+                                topic,
+                                partition: partition.index,
+                                offset: batch.offset_of(&record),
+                                timestamp: batch.timestamp_of(&record),
+                                key: record.key,
+                                value: record.value,
+                                headers: record.headers,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                        .into_iter();
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::records::{Compression, RecordBatchBuilder, Utf8};
+    use crate::ErrorKind;
+
+    fn batches(base_offset: i64, values: &[&str]) -> Vec<u8> {
+        let mut builder = RecordBatchBuilder::new()
+            .base_offset(base_offset)
+            .max_batch_size(90)
+            .compression(if cfg!(feature = "gzip") {
+                Compression::Gzip
+            } else {
+                Compression::None
+            });
+        for (i, value) in values.iter().enumerate() {
+            builder.append(
+                1000 + i as i64,
+                None,
+                Some(value.as_bytes().to_vec()),
+                Headers::new(),
+            );
+        }
+        let mut buf = Vec::new();
+        for batch in builder.build() {
+            batch.encode(&mut buf).unwrap();
+        }
+        buf
+    }
+
+    fn partition(index: i32, records: Option<Vec<u8>>) -> KafkaValue {
+        KafkaValue::Struct(vec![
+            ("PartitionIndex".to_owned(), KafkaValue::Int32(index)),
+            ("ErrorCode".to_owned(), KafkaValue::Int16(0)),
+            ("HighWatermark".to_owned(), KafkaValue::Int64(10)),
+            ("LastStableOffset".to_owned(), KafkaValue::Int64(10)),
+            ("LogStartOffset".to_owned(), KafkaValue::Int64(0)),
+            ("AbortedTransactions".to_owned(), KafkaValue::Null),
+            ("PreferredReadReplica".to_owned(), KafkaValue::Int32(-1)),
+            (
+                "Records".to_owned(),
+                records.map_or(KafkaValue::Null, KafkaValue::Records),
+            ),
+        ])
+    }
+
+    fn response(version: i16, partitions: Vec<KafkaValue>) -> Vec<u8> {
+        let topic = KafkaValue::Struct(vec![
+            ("Topic".to_owned(), KafkaValue::String("topic".to_owned())),
+            ("Partitions".to_owned(), KafkaValue::Array(partitions)),
+        ]);
+        let response = KafkaValue::Struct(vec![
+            ("ThrottleTimeMs".to_owned(), KafkaValue::Int32(0)),
+            ("ErrorCode".to_owned(), KafkaValue::Int16(0)),
+            ("SessionId".to_owned(), KafkaValue::Int32(3)),
+            ("Responses".to_owned(), KafkaValue::Array(vec![topic])),
+        ]);
+        let mut buf = Vec::new();
+        schema().encode(version, &response, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_records() {
+        for version in [4, 7, 11, 12] {
+            let body = response(
+                version,
+                vec![
+                    partition(0, Some(batches(5, &["a", "b", "c", "d", "e"]))),
+                    partition(1, None),
+                    partition(2, Some(batches(0, &["f"]))),
+                ],
+            );
+            let response = FetchResponse::decode(version, &body).unwrap();
+            assert_eq!(response.session_id, if version >= 7 { 3 } else { 0 });
+            assert_eq!(
+                response.topics[0].partitions[0].log_start_offset,
+                if version >= 5 { 0 } else { -1 }
+            );
+
+            let records: Vec<_> = response.records().map(Result::unwrap).collect();
+            let values: Vec<_> = records
+                .iter()
+                .map(|record| (record.partition, record.offset, record.value.as_deref()))
+                .collect();
+            assert_eq!(
+                values,
+                [
+                    (0, 5, Some(&b"a"[..])),
+                    (0, 6, Some(&b"b"[..])),
+                    (0, 7, Some(&b"c"[..])),
+                    (0, 8, Some(&b"d"[..])),
+                    (0, 9, Some(&b"e"[..])),
+                    (2, 0, Some(&b"f"[..])),
+                ]
+            );
+            assert_eq!(records[1].timestamp, 1001);
+            assert!(records.iter().all(|record| record.topic == "topic"));
+            assert_eq!(records[5].value_with(&Utf8).unwrap().unwrap(), "f");
+        }
+    }
+
+    #[test]
+    fn test_invalid_records() {
+        // a batch whose length is negative
+        let mut invalid = vec![0; 8];
+        invalid.extend_from_slice(&(-2i32).to_be_bytes());
+        let body = response(
+            11,
+            vec![
+                partition(0, Some(invalid)),
+                partition(1, Some(batches(0, &["a"]))),
+            ],
+        );
+        let response = FetchResponse::decode(11, &body).unwrap();
+        let mut records = response.records();
+        match *records.next().unwrap().unwrap_err().kind() {
+            ErrorKind::InvalidLength(-2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert_eq!(records.next().unwrap().unwrap().partition, 1);
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn test_truncated_records() {
+        // the response ran out of room in the middle of the second batch of partition 0, and
+        // before the length of the batch of partition 1
+        let mut truncated = batches(0, &["a"]);
+        let second = batches(1, &["b"]);
+        truncated.extend_from_slice(&second[..second.len() / 2]);
+        let body = response(
+            11,
+            vec![
+                partition(0, Some(truncated)),
+                partition(1, Some(vec![0; 11])),
+            ],
+        );
+        let response = FetchResponse::decode(11, &body).unwrap();
+        let records: Vec<_> = response.records().collect::<Result<_>>().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].offset, 0);
+    }
+}
@@ -0,0 +1,428 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Request and response headers
+//!
+//! Frames start with a header, whose layout depends on the API and version of the request:
+//!
+//! - request header v0, only used by ControlledShutdown v0: api key, api version and
+//!   correlation id
+//! - request header v1: the same, then the client id
+//! - request header v2, in flexible versions: the same, then tagged fields
+//! - response header v0: the correlation id
+//! - response header v1, in flexible versions but those of ApiVersions: the correlation id, then
+//!   tagged fields
+//!
+//! [`split_request`] and [`split_response`] read the header of a frame and return it with the
+//! body that follows, for routing layers to forward bodies without knowing these layouts. They
+//! only need to be told which versions of which APIs are flexible. [`RawFrame`] borrows both from
+//! the frame, with the API and version its body is in, for proxies to route frames and only
+//! decode the bodies they need to.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::header::{self, split_request, split_response};
+//!
+//! // a Metadata v9 request, flexible, with correlation id 7 and client id "c"
+//! let frame = [0, 3, 0, 9, 0, 0, 0, 7, 0, 1, b'c', 0, 1, 0];
+//! let (header, body) = split_request(&frame, |api_key, version| api_key == 3 && version >= 9)
+//!     .unwrap();
+//! assert_eq!((header.api_key, header.api_version, header.correlation_id), (3, 9, 7));
+//! assert_eq!(header.client_id.as_deref(), Some("c"));
+//! assert_eq!(body, [1, 0]);
+//!
+//! let version = header::response_header_version(3, true);
+//! let (header, body) = split_response(&[0, 0, 0, 7, 0, 0, 0, 0, 0], version).unwrap();
+//! assert_eq!(header.correlation_id, 7);
+//! assert_eq!(body, [0, 0, 0, 0]);
+//! ```
+use crate::api::API_VERSIONS_KEY;
+use serde::{Deserialize, Serialize};
+
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::{ser, Decoder};
+
+/// The API key of ControlledShutdown, whose version 0 has no client id in its header
+const CONTROLLED_SHUTDOWN_KEY: i16 = 7;
+
+/// The header of a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct RequestHeader {
+    /// The API key
+    pub api_key: i16,
+    /// The API version
+    pub api_version: i16,
+    /// The id the response will carry
+    pub correlation_id: i32,
+    /// The client id, null if the client didn't set one or the header has none
+    pub client_id: Option<String>,
+}
+
+/// The header of a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ResponseHeader {
+    /// The correlation id of the request the response answers
+    pub correlation_id: i32,
+}
+
+/// A header followed by a body, in place of a struct with `#[serde(flatten)]` fields: those
+/// serialize, but can't deserialize, while a frame does both
+///
+/// # Examples
+/// ```
+/// use kafka_serde::header::Frame;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Header {
+///     api_key: i16,
+///     api_version: i16,
+///     correlation_id: i32,
+///     client_id: String,
+/// }
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct ApiVersionsRequest {}
+///
+/// let frame = Frame {
+///     header: Header {
+///         api_key: 18,
+///         api_version: 0,
+///         correlation_id: 1,
+///         client_id: "c".to_owned(),
+///     },
+///     body: ApiVersionsRequest {},
+/// };
+/// let data = kafka_serde::to_vec(&frame).unwrap();
+/// assert_eq!(data, [0, 18, 0, 0, 0, 0, 0, 1, 0, 1, b'c']);
+/// let decoded: Frame<Header, ApiVersionsRequest> = kafka_serde::from_bytes(&data).unwrap();
+/// assert_eq!(decoded, frame);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame<H, B> {
+    /// The header
+    pub header: H,
+    /// The body
+    pub body: B,
+}
+
+/// The version of the header of requests to `api_version` of the API `api_key`, `flexible`
+/// telling whether it is a flexible version
+pub fn request_header_version(api_key: i16, api_version: i16, flexible: bool) -> i16 {
+    if flexible {
+        2
+    } else if api_key == CONTROLLED_SHUTDOWN_KEY && api_version == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+/// The version of the header of responses of the API `api_key`, in a version that is flexible
+/// or not
+pub fn response_header_version(api_key: i16, flexible: bool) -> i16 {
+    if flexible && api_key != API_VERSIONS_KEY {
+        1
+    } else {
+        0
+    }
+}
+
+/// Writes the header of a request at the end of `buf`, in the version for `api_version` of the
+/// API `api_key`
+pub(crate) fn write_request_header(
+    buf: &mut Vec<u8>,
+    api_key: i16,
+    api_version: i16,
+    flexible: bool,
+    correlation_id: i32,
+    client_id: Option<&str>,
+) -> Result<()> {
+    let version = request_header_version(api_key, api_version, flexible);
+    buf.extend_from_slice(&api_key.to_be_bytes());
+    buf.extend_from_slice(&api_version.to_be_bytes());
+    buf.extend_from_slice(&correlation_id.to_be_bytes());
+    // the client id stays a NULLABLE_STRING in flexible versions, for brokers to parse it
+    // before knowing the version
+    if version >= 1 {
+        ser::write_nullable_string(buf, client_id)?;
+    }
+    if version >= 2 {
+        // no tagged fields
+        buf.push(0);
+    }
+    Ok(())
+}
+
+/// Splits a request frame, stripped of its size, into its header and its body
+///
+/// `flexible` tells whether a version of an API is flexible. Flexible versions of the APIs it
+/// doesn't know keep the tagged fields of their header at the start of their body.
+pub fn split_request(
+    frame: &[u8],
+    flexible: impl Fn(i16, i16) -> bool,
+) -> Result<(RequestHeader, &[u8])> {
+    let raw = RawFrame::request(frame, flexible)?;
+    let header = RequestHeader {
+        api_key: raw.api_key,
+        api_version: raw.api_version,
+        correlation_id: raw.correlation_id,
+        client_id: raw.client_id.map(str::to_owned),
+    };
+    Ok((header, raw.body))
+}
+
+/// Splits a response frame, stripped of its size, into its header and its body, given the
+/// version of the header, see [`response_header_version`]
+pub fn split_response(frame: &[u8], header_version: i16) -> Result<(ResponseHeader, &[u8])> {
+    if !(0..=1).contains(&header_version) {
+        return Err(ErrorKind::Custom(format!(
+            "unsupported response header version {}",
+            header_version
+        ))
+        .into());
+    }
+    let mut de = KafkaDeserializer::new(frame);
+    let mut read_header = || {
+        de.expect("correlation id");
+        let correlation_id = de.read_i32()?;
+        if header_version >= 1 {
+            de.expect("tagged fields");
+            de.skip_tagged_fields()?;
+        }
+        Ok(ResponseHeader { correlation_id })
+    };
+    let header = read_header().map_err(|err| de.locate(err))?;
+    Ok((header, de.peek_remaining()))
+}
+
+/// A frame whose header is decoded, and whose body is left as it is, borrowed from the frame
+///
+/// Request frames say which API and version their body is in. Response frames don't: the
+/// caller tells, from the request they answer.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::header::RawFrame;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct HeartbeatRequest<'a> {
+///     group_id: &'a str,
+///     generation_id: i32,
+///     member_id: &'a str,
+/// }
+///
+/// // a Heartbeat v1 request, with correlation id 7 and client id "c"
+/// let frame = [0, 12, 0, 1, 0, 0, 0, 7, 0, 1, b'c', 0, 1, b'g', 0, 0, 0, 2, 0, 1, b'm'];
+/// let raw = RawFrame::request(&frame, |_, _| false).unwrap();
+/// assert_eq!((raw.api_key, raw.api_version, raw.correlation_id), (12, 1, 7));
+/// assert_eq!(raw.client_id, Some("c"));
+///
+/// // only the bodies of the APIs handled here are decoded, the others are forwarded as they are
+/// if raw.api_key == 12 {
+///     let request: HeartbeatRequest<'_> = raw.decode().unwrap();
+///     assert_eq!(request.group_id, "g");
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawFrame<'a> {
+    /// The API key
+    pub api_key: i16,
+    /// The API version
+    pub api_version: i16,
+    /// The id of the request, carried by its response
+    pub correlation_id: i32,
+    /// The client id of request headers that have one and set it
+    pub client_id: Option<&'a str>,
+    /// The body, which follows the header
+    pub body: &'a [u8],
+}
+
+impl<'a> RawFrame<'a> {
+    /// Reads the header of a request frame, stripped of its size, see [`split_request`]
+    pub fn request(frame: &'a [u8], flexible: impl Fn(i16, i16) -> bool) -> Result<Self> {
+        let mut de = KafkaDeserializer::new(frame);
+        let mut read_header = || {
+            de.expect("request header");
+            let api_key = de.read_i16()?;
+            let api_version = de.read_i16()?;
+            let correlation_id = de.read_i32()?;
+            let flexible = flexible(api_key, api_version);
+            let version = request_header_version(api_key, api_version, flexible);
+            let client_id = if version >= 1 {
+                de.read_nullable_string()?
+            } else {
+                None
+            };
+            if version >= 2 {
+                de.expect("tagged fields");
+                de.skip_tagged_fields()?;
+            }
+            Ok((api_key, api_version, correlation_id, client_id))
+        };
+        let (api_key, api_version, correlation_id, client_id) =
+            read_header().map_err(|err| de.locate(err))?;
+        Ok(RawFrame {
+            api_key,
+            api_version,
+            correlation_id,
+            client_id,
+            body: de.peek_remaining(),
+        })
+    }
+
+    /// Reads the header of a response frame, stripped of its size, to a request to
+    /// `api_version` of the API `api_key`, `flexible` telling whether it is a flexible version
+    pub fn response(
+        frame: &'a [u8],
+        api_key: i16,
+        api_version: i16,
+        flexible: bool,
+    ) -> Result<Self> {
+        let header_version = response_header_version(api_key, flexible);
+        let (header, body) = split_response(frame, header_version)?;
+        Ok(RawFrame {
+            api_key,
+            api_version,
+            correlation_id: header.correlation_id,
+            client_id: None,
+            body,
+        })
+    }
+
+    /// Decodes the body
+    pub fn decode<T: Deserialize<'a>>(&self) -> Result<T> {
+        self.decode_with(Decoder::new())
+    }
+
+    /// Decodes the body with the options of `decoder`
+    pub fn decode_with<T: Deserialize<'a>>(&self, decoder: Decoder) -> Result<T> {
+        decoder.decode(self.body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_header_versions() {
+        assert_eq!(request_header_version(7, 0, false), 0);
+        assert_eq!(request_header_version(7, 1, false), 1);
+        assert_eq!(request_header_version(3, 9, true), 2);
+        assert_eq!(response_header_version(3, true), 1);
+        assert_eq!(response_header_version(3, false), 0);
+        assert_eq!(response_header_version(API_VERSIONS_KEY, true), 0);
+    }
+
+    #[test]
+    fn test_frame() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Header {
+            correlation_id: i32,
+        }
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Body {
+            n: i32,
+        }
+        #[derive(Serialize)]
+        struct Flattened {
+            correlation_id: i32,
+            #[serde(flatten)]
+            body: Body,
+        }
+
+        let frame = Frame {
+            header: Header { correlation_id: 7 },
+            body: Body { n: 8 },
+        };
+        let data = crate::to_vec(&frame).unwrap();
+        let flattened = Flattened {
+            correlation_id: 7,
+            body: Body { n: 8 },
+        };
+        assert_eq!(data, crate::to_vec(&flattened).unwrap());
+        let decoded: Frame<Header, Body> = crate::from_bytes(&data).unwrap();
+        assert_eq!(decoded, frame);
+    }
+
+    #[test]
+    fn test_split_request() {
+        // ControlledShutdown v0 has no client id
+        let frame = [0, 7, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2];
+        let (header, body) = split_request(&frame, |_, _| false).unwrap();
+        assert_eq!(header.client_id, None);
+        assert_eq!(body, [0, 0, 0, 2]);
+
+        // the tagged fields of flexible headers are only stripped in the versions known to be
+        let frame = [0, 18, 0, 3, 0, 0, 0, 1, 0xff, 0xff, 0, 4];
+        let (header, body) = split_request(&frame, |_, _| false).unwrap();
+        assert_eq!(header.client_id, None);
+        assert_eq!(body, [0, 4]);
+        let (_, body) = split_request(&frame, |api_key, _| api_key == 18).unwrap();
+        assert_eq!(body, [4]);
+
+        match *split_request(&frame[..9], |_, _| false).unwrap_err().kind() {
+            ErrorKind::Decode { .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_raw_frame() {
+        // a Metadata v9 request, flexible, whose body is a null array of topics and a flag
+        let frame = [0, 3, 0, 9, 0, 0, 0, 7, 0, 1, b'c', 0, 0, 1];
+        let raw =
+            RawFrame::request(&frame, |api_key, version| api_key == 3 && version >= 9).unwrap();
+        assert_eq!(raw.client_id, Some("c"));
+        assert_eq!(raw.body, [0, 1]);
+        #[derive(Deserialize)]
+        struct Body {
+            // the length of a compact array, null
+            topics: u8,
+            allow_auto_topic_creation: bool,
+        }
+        let body: Body = raw.decode().unwrap();
+        assert_eq!(body.topics, 0);
+        assert!(body.allow_auto_topic_creation);
+
+        // the api of a response comes from its request
+        let raw = RawFrame::response(&[0, 0, 0, 7, 0, 0, 0, 0, 5], 3, 9, true).unwrap();
+        assert_eq!(
+            (raw.api_key, raw.api_version, raw.correlation_id),
+            (3, 9, 7)
+        );
+        assert_eq!(raw.body, [0, 0, 0, 5]);
+        assert_eq!(raw.decode::<i32>().unwrap(), 5);
+        let raw = RawFrame::response(&[0, 0, 0, 7, 0, 0, 0, 5], 18, 3, true).unwrap();
+        assert_eq!(raw.decode::<u32>().unwrap(), 5);
+
+        match *RawFrame::request(&frame[..5], |_, _| false)
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::Decode { .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_split_response() {
+        let frame = [0, 0, 0, 7, 1, 0, 2, 1, 2, 27];
+        let (header, body) = split_response(&frame, 0).unwrap();
+        assert_eq!(header.correlation_id, 7);
+        assert_eq!(body, &frame[4..]);
+        // a tagged field 0 of 2 bytes
+        assert_eq!(split_response(&frame, 1).unwrap().1, [27]);
+        match *split_response(&frame, 2).unwrap_err().kind() {
+            ErrorKind::Custom(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Strings sharing one allocation per distinct value within a decoded payload.
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+thread_local! {
+    // the strings seen by the decode running on this thread, if it interns them
+    static INTERNER: RefCell<Option<HashSet<Arc<str>>>> = const { RefCell::new(None) };
+}
+
+/// Interns strings for as long as it lives, restoring what the thread did before on drop
+pub(crate) struct InternScope {
+    previous: Option<HashSet<Arc<str>>>,
+}
+
+impl InternScope {
+    pub(crate) fn enter() -> Self {
+        let previous = INTERNER.with(|interner| interner.replace(Some(HashSet::new())));
+        InternScope { previous }
+    }
+}
+
+impl Drop for InternScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        INTERNER.with(|interner| *interner.borrow_mut() = previous);
+    }
+}
+
+fn intern(s: &str) -> Arc<str> {
+    INTERNER.with(|interner| match *interner.borrow_mut() {
+        Some(ref mut strings) => match strings.get(s) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = Arc::from(s);
+                strings.insert(interned.clone());
+                interned
+            }
+        },
+        None => Arc::from(s),
+    })
+}
+
+/// A string deserialized as an `Arc<str>`, shared with the equal strings of the same payload
+///
+/// Responses like Metadata and Fetch repeat the same topic names many times over. When
+/// [`Decoder::intern_strings`](crate::Decoder::intern_strings) is on, every `InternedStr` of a
+/// payload with the same value points to the same allocation. Otherwise, each one gets its own.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::{Decoder, InternedStr};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Partition {
+///     topic: InternedStr,
+///     partition: i32,
+/// }
+///
+/// let data = [0, 0, 0, 2, 0, 1, b't', 0, 0, 0, 0, 0, 1, b't', 0, 0, 0, 1];
+/// let partitions: Vec<Partition> = Decoder::new().intern_strings(true).decode(&data).unwrap();
+/// assert_eq!(&*partitions[0].topic, "t");
+/// assert!(InternedStr::ptr_eq(&partitions[0].topic, &partitions[1].topic));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct InternedStr(Arc<str>);
+
+impl InternedStr {
+    /// Whether `a` and `b` share the same allocation
+    pub fn ptr_eq(a: &InternedStr, b: &InternedStr) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+
+    /// Consumes the string, returning the shared one
+    pub fn into_inner(self) -> Arc<str> {
+        self.0
+    }
+}
+
+impl Deref for InternedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for InternedStr {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for InternedStr {
+    fn from(s: &str) -> Self {
+        InternedStr(Arc::from(s))
+    }
+}
+
+impl fmt::Debug for InternedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for InternedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for InternedStr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for InternedStr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct InternedVisitor;
+
+        impl<'de> Visitor<'de> for InternedVisitor {
+            type Value = InternedStr;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> std::result::Result<InternedStr, E> {
+                Ok(InternedStr(intern(s)))
+            }
+        }
+
+        deserializer.deserialize_str(InternedVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scope() {
+        let a = intern("a");
+        assert!(!Arc::ptr_eq(&a, &intern("a")));
+        {
+            let _scope = InternScope::enter();
+            let b = intern("a");
+            assert!(Arc::ptr_eq(&b, &intern("a")));
+            {
+                let _nested = InternScope::enter();
+                assert!(!Arc::ptr_eq(&b, &intern("a")));
+            }
+            assert!(Arc::ptr_eq(&b, &intern("a")));
+        }
+        assert!(!Arc::ptr_eq(&a, &intern("a")));
+    }
+}
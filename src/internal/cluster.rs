@@ -0,0 +1,615 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use std::convert::TryInto;
+
+/// The version of the frame around the records of `__cluster_metadata`
+const FRAME_VERSION: u32 = 1;
+
+/// A record of `__cluster_metadata`, the log of the KRaft controllers
+///
+/// The controllers store the metadata of the cluster as a log of changes, replicated with Raft,
+/// and in snapshots of it, the `.checkpoint` files of the log directory. Their records have no
+/// key, and their value is a frame holding the type and version of the record that follows.
+/// Records are flexible, and may carry tagged fields on top of these. They are skipped.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::internal::MetadataRecord;
+///
+/// fn print_topic(value: &[u8]) -> kafka_serde::Result<()> {
+///     if let MetadataRecord::Topic(topic) = MetadataRecord::decode(value)? {
+///         println!("{}: {:02x?}", topic.name, topic.topic_id);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum MetadataRecord {
+    /// A broker registered with the controllers
+    RegisterBroker(RegisterBrokerRecord),
+    /// A broker was removed from the cluster
+    UnregisterBroker(UnregisterBrokerRecord),
+    /// A topic was created
+    Topic(TopicRecord),
+    /// A partition was created, or its state replaced
+    Partition(PartitionRecord),
+    /// A configuration was set, or removed
+    Config(ConfigRecord),
+    /// A broker was fenced, and can no longer lead partitions
+    FenceBroker(BrokerRecord),
+    /// A broker was unfenced
+    UnfenceBroker(BrokerRecord),
+    /// A topic was deleted
+    RemoveTopic(RemoveTopicRecord),
+    /// The level of a feature was set
+    FeatureLevel(FeatureLevelRecord),
+    /// A record the controllers append to check that the log is writable
+    NoOp,
+    /// A record type that is not decoded here, with its version
+    Unknown {
+        /// The type of the record, the API key of its message
+        record_type: u32,
+        /// The version of the record
+        version: i16,
+    },
+}
+
+impl MetadataRecord {
+    /// Decodes a record value from `buf`
+    pub fn decode(buf: &[u8]) -> Result<MetadataRecord> {
+        let mut de = KafkaDeserializer::new(buf);
+        let frame_version = de.read_unsigned_varint()?;
+        if frame_version != FRAME_VERSION {
+            return Err(ErrorKind::Custom(format!(
+                "unsupported metadata record frame version: {}",
+                frame_version
+            ))
+            .into());
+        }
+        let record_type = de.read_unsigned_varint()?;
+        let version = de.read_unsigned_varint()?;
+        let version = version.try_into().map_err(|_| {
+            ErrorKind::Custom(format!("invalid metadata record version: {}", version))
+        })?;
+        let record = match record_type {
+            0 => MetadataRecord::RegisterBroker(RegisterBrokerRecord::read(&mut de, version)?),
+            1 => {
+                check_version(version, "UnregisterBrokerRecord", 0)?;
+                MetadataRecord::UnregisterBroker(UnregisterBrokerRecord {
+                    broker_id: de.read_i32()?,
+                    broker_epoch: de.read_i64()?,
+                })
+            }
+            2 => {
+                check_version(version, "TopicRecord", 0)?;
+                MetadataRecord::Topic(TopicRecord {
+                    name: de.read_compact_string()?.to_owned(),
+                    topic_id: read_uuid(&mut de)?,
+                })
+            }
+            3 => MetadataRecord::Partition(PartitionRecord::read(&mut de, version)?),
+            4 => {
+                check_version(version, "ConfigRecord", 0)?;
+                MetadataRecord::Config(ConfigRecord {
+                    resource_type: de.read_i8()?,
+                    resource_name: de.read_compact_string()?.to_owned(),
+                    name: de.read_compact_string()?.to_owned(),
+                    value: de.read_compact_nullable_string()?.map(str::to_owned),
+                })
+            }
+            7 => {
+                check_version(version, "FenceBrokerRecord", 0)?;
+                MetadataRecord::FenceBroker(BrokerRecord::read(&mut de)?)
+            }
+            8 => {
+                check_version(version, "UnfenceBrokerRecord", 0)?;
+                MetadataRecord::UnfenceBroker(BrokerRecord::read(&mut de)?)
+            }
+            9 => {
+                check_version(version, "RemoveTopicRecord", 0)?;
+                MetadataRecord::RemoveTopic(RemoveTopicRecord {
+                    topic_id: read_uuid(&mut de)?,
+                })
+            }
+            12 => {
+                check_version(version, "FeatureLevelRecord", 0)?;
+                MetadataRecord::FeatureLevel(FeatureLevelRecord {
+                    name: de.read_compact_string()?.to_owned(),
+                    feature_level: de.read_i16()?,
+                })
+            }
+            20 => {
+                check_version(version, "NoOpRecord", 0)?;
+                MetadataRecord::NoOp
+            }
+            record_type => {
+                return Ok(MetadataRecord::Unknown {
+                    record_type,
+                    version,
+                })
+            }
+        };
+        // partition records read theirs, as they have one
+        if !matches!(record, MetadataRecord::Partition(_)) {
+            de.skip_tagged_fields()?;
+        }
+        Ok(record)
+    }
+}
+
+/// The registration of a broker, `RegisterBrokerRecord`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct RegisterBrokerRecord {
+    /// The broker id
+    pub broker_id: i32,
+    /// Whether the broker is a ZooKeeper broker migrating to KRaft (v2+)
+    pub is_migrating_zk_broker: bool,
+    /// The id of this run of the broker, changing when it restarts
+    pub incarnation_id: [u8; 16],
+    /// The epoch the controllers gave to the registration
+    pub broker_epoch: i64,
+    /// The listeners of the broker
+    pub endpoints: Vec<BrokerEndpoint>,
+    /// The features the broker supports
+    pub features: Vec<BrokerFeature>,
+    /// The rack of the broker
+    pub rack: Option<String>,
+    /// Whether the broker registers fenced (v1+, `true` before)
+    pub fenced: bool,
+    /// Whether the broker is shutting down (v2+)
+    pub in_controlled_shutdown: bool,
+    /// The ids of the log directories of the broker (v3+)
+    pub log_dirs: Vec<[u8; 16]>,
+}
+
+impl RegisterBrokerRecord {
+    fn read(de: &mut KafkaDeserializer<'_>, version: i16) -> Result<RegisterBrokerRecord> {
+        check_version(version, "RegisterBrokerRecord", 3)?;
+        let broker_id = de.read_i32()?;
+        let is_migrating_zk_broker = version >= 2 && read_bool(de)?;
+        let incarnation_id = read_uuid(de)?;
+        let broker_epoch = de.read_i64()?;
+        let mut endpoints = Vec::new();
+        for _ in 0..compact_array_len(de)? {
+            endpoints.push(BrokerEndpoint {
+                name: de.read_compact_string()?.to_owned(),
+                host: de.read_compact_string()?.to_owned(),
+                port: de.read_u16()?,
+                security_protocol: de.read_i16()?,
+            });
+            de.skip_tagged_fields()?;
+        }
+        let mut features = Vec::new();
+        for _ in 0..compact_array_len(de)? {
+            features.push(BrokerFeature {
+                name: de.read_compact_string()?.to_owned(),
+                min_supported_version: de.read_i16()?,
+                max_supported_version: de.read_i16()?,
+            });
+            de.skip_tagged_fields()?;
+        }
+        let rack = de.read_compact_nullable_string()?.map(str::to_owned);
+        let fenced = version < 1 || read_bool(de)?;
+        let in_controlled_shutdown = version >= 2 && read_bool(de)?;
+        let mut log_dirs = Vec::new();
+        if version >= 3 {
+            for _ in 0..compact_array_len(de)? {
+                log_dirs.push(read_uuid(de)?);
+            }
+        }
+        Ok(RegisterBrokerRecord {
+            broker_id,
+            is_migrating_zk_broker,
+            incarnation_id,
+            broker_epoch,
+            endpoints,
+            features,
+            rack,
+            fenced,
+            in_controlled_shutdown,
+            log_dirs,
+        })
+    }
+}
+
+/// A listener of a [`RegisterBrokerRecord`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct BrokerEndpoint {
+    /// The name of the listener
+    pub name: String,
+    /// The host clients connect to
+    pub host: String,
+    /// The port clients connect to
+    pub port: u16,
+    /// The id of the security protocol of the listener
+    pub security_protocol: i16,
+}
+
+/// A feature supported by the broker of a [`RegisterBrokerRecord`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct BrokerFeature {
+    /// The name of the feature
+    pub name: String,
+    /// The lowest level of the feature the broker supports
+    pub min_supported_version: i16,
+    /// The highest level of the feature the broker supports
+    pub max_supported_version: i16,
+}
+
+/// The removal of a broker, `UnregisterBrokerRecord`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct UnregisterBrokerRecord {
+    /// The broker id
+    pub broker_id: i32,
+    /// The epoch of the registration that was removed
+    pub broker_epoch: i64,
+}
+
+/// A broker and the epoch of its registration, for `FenceBrokerRecord` and
+/// `UnfenceBrokerRecord`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct BrokerRecord {
+    /// The broker id
+    pub id: i32,
+    /// The epoch of the registration of the broker
+    pub epoch: i64,
+}
+
+impl BrokerRecord {
+    fn read(de: &mut KafkaDeserializer<'_>) -> Result<BrokerRecord> {
+        Ok(BrokerRecord {
+            id: de.read_i32()?,
+            epoch: de.read_i64()?,
+        })
+    }
+}
+
+/// The creation of a topic, `TopicRecord`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct TopicRecord {
+    /// The name of the topic
+    pub name: String,
+    /// The id of the topic
+    pub topic_id: [u8; 16],
+}
+
+/// The state of a partition, `PartitionRecord`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct PartitionRecord {
+    /// The partition index
+    pub partition_id: i32,
+    /// The id of the topic of the partition
+    pub topic_id: [u8; 16],
+    /// The replicas of the partition
+    pub replicas: Vec<i32>,
+    /// The in-sync replicas
+    pub isr: Vec<i32>,
+    /// The replicas being removed from the partition
+    pub removing_replicas: Vec<i32>,
+    /// The replicas being added to the partition
+    pub adding_replicas: Vec<i32>,
+    /// The leader of the partition, or -1
+    pub leader: i32,
+    /// Whether the leader is recovering from an unclean election (1) or not (0), a tagged field
+    pub leader_recovery_state: i8,
+    /// The epoch of the leader
+    pub leader_epoch: i32,
+    /// The epoch of the partition, bumped on every change
+    pub partition_epoch: i32,
+    /// The ids of the log directories of the replicas, in the same order (v1+)
+    pub directories: Vec<[u8; 16]>,
+}
+
+impl PartitionRecord {
+    fn read(de: &mut KafkaDeserializer<'_>, version: i16) -> Result<PartitionRecord> {
+        check_version(version, "PartitionRecord", 2)?;
+        let mut record = PartitionRecord {
+            partition_id: de.read_i32()?,
+            topic_id: read_uuid(de)?,
+            replicas: read_replicas(de)?,
+            isr: read_replicas(de)?,
+            removing_replicas: read_replicas(de)?,
+            adding_replicas: read_replicas(de)?,
+            leader: de.read_i32()?,
+            leader_recovery_state: 0,
+            leader_epoch: de.read_i32()?,
+            partition_epoch: de.read_i32()?,
+            directories: Vec::new(),
+        };
+        if version >= 1 {
+            for _ in 0..compact_array_len(de)? {
+                record.directories.push(read_uuid(de)?);
+            }
+        }
+        let count = de.read_unsigned_varint()?;
+        for _ in 0..count {
+            let tag = de.read_unsigned_varint()?;
+            let size = de.read_unsigned_varint()?;
+            let mut field = KafkaDeserializer::new(de.slice(size as usize)?);
+            if tag == 0 {
+                record.leader_recovery_state = field.read_i8()?;
+            }
+        }
+        Ok(record)
+    }
+}
+
+/// A configuration set, or removed when its value is null, `ConfigRecord`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ConfigRecord {
+    /// The type of the resource: 2 for topics, 4 for brokers
+    pub resource_type: i8,
+    /// The name of the resource, empty for the default configuration of brokers
+    pub resource_name: String,
+    /// The name of the configuration
+    pub name: String,
+    /// The value of the configuration
+    pub value: Option<String>,
+}
+
+/// The deletion of a topic, `RemoveTopicRecord`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct RemoveTopicRecord {
+    /// The id of the topic
+    pub topic_id: [u8; 16],
+}
+
+/// The level of a feature, like `metadata.version`, `FeatureLevelRecord`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FeatureLevelRecord {
+    /// The name of the feature
+    pub name: String,
+    /// The level of the feature, 0 when it is disabled
+    pub feature_level: i16,
+}
+
+fn check_version(version: i16, record: &str, max: i16) -> Result<()> {
+    if version > max {
+        return Err(
+            ErrorKind::Custom(format!("unsupported {} version: {}", record, version)).into(),
+        );
+    }
+    Ok(())
+}
+
+fn read_bool(de: &mut KafkaDeserializer<'_>) -> Result<bool> {
+    Ok(de.read_i8()? != 0)
+}
+
+fn compact_array_len(de: &mut KafkaDeserializer<'_>) -> Result<usize> {
+    Ok(de.read_compact_array_len()?.unwrap_or(0))
+}
+
+fn read_uuid(de: &mut KafkaDeserializer<'_>) -> Result<[u8; 16]> {
+    let mut uuid = [0; 16];
+    uuid.copy_from_slice(de.slice(16)?);
+    Ok(uuid)
+}
+
+fn read_replicas(de: &mut KafkaDeserializer<'_>) -> Result<Vec<i32>> {
+    let len = compact_array_len(de)?;
+    let mut replicas = Vec::with_capacity(len.min(de.remaining() / 4));
+    for _ in 0..len {
+        replicas.push(de.read_i32()?);
+    }
+    Ok(replicas)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frame(record_type: u8, version: u8) -> Vec<u8> {
+        vec![1, record_type, version]
+    }
+
+    #[test]
+    fn test_topic() {
+        let mut buf = frame(2, 0);
+        buf.extend_from_slice(&[4, b'f', b'o', b'o']);
+        buf.extend_from_slice(&[7; 16]);
+        buf.push(0);
+        assert_eq!(
+            MetadataRecord::decode(&buf).unwrap(),
+            MetadataRecord::Topic(TopicRecord {
+                name: "foo".into(),
+                topic_id: [7; 16],
+            })
+        );
+
+        let mut buf = frame(9, 0);
+        buf.extend_from_slice(&[7; 16]);
+        buf.push(0);
+        assert_eq!(
+            MetadataRecord::decode(&buf).unwrap(),
+            MetadataRecord::RemoveTopic(RemoveTopicRecord { topic_id: [7; 16] })
+        );
+    }
+
+    #[test]
+    fn test_partition() {
+        for version in 0..=2 {
+            let mut buf = frame(3, version);
+            buf.extend_from_slice(&2i32.to_be_bytes());
+            buf.extend_from_slice(&[7; 16]);
+            // replicas 1 and 2, both in sync, none being moved
+            buf.extend_from_slice(&[3, 0, 0, 0, 1, 0, 0, 0, 2]);
+            buf.extend_from_slice(&[3, 0, 0, 0, 1, 0, 0, 0, 2]);
+            buf.extend_from_slice(&[1, 1]);
+            buf.extend_from_slice(&1i32.to_be_bytes());
+            buf.extend_from_slice(&5i32.to_be_bytes());
+            buf.extend_from_slice(&9i32.to_be_bytes());
+            if version >= 1 {
+                buf.push(3);
+                buf.extend_from_slice(&[3; 16]);
+                buf.extend_from_slice(&[4; 16]);
+            }
+            // the leader recovery state, then an unknown tagged field
+            buf.extend_from_slice(&[2, 0, 1, 1, 5, 2, 0, 0]);
+            let record = match MetadataRecord::decode(&buf).unwrap() {
+                MetadataRecord::Partition(record) => record,
+                record => panic!("unexpected record {:?}", record),
+            };
+            assert_eq!(
+                record,
+                PartitionRecord {
+                    partition_id: 2,
+                    topic_id: [7; 16],
+                    replicas: vec![1, 2],
+                    isr: vec![1, 2],
+                    removing_replicas: vec![],
+                    adding_replicas: vec![],
+                    leader: 1,
+                    leader_recovery_state: 1,
+                    leader_epoch: 5,
+                    partition_epoch: 9,
+                    directories: if version >= 1 {
+                        vec![[3; 16], [4; 16]]
+                    } else {
+                        vec![]
+                    },
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_register_broker() {
+        for version in 0..=3 {
+            let mut buf = frame(0, version);
+            buf.extend_from_slice(&1i32.to_be_bytes());
+            if version >= 2 {
+                buf.push(0);
+            }
+            buf.extend_from_slice(&[9; 16]);
+            buf.extend_from_slice(&12i64.to_be_bytes());
+            // a PLAINTEXT listener on b1:9092
+            buf.extend_from_slice(&[2, 10]);
+            buf.extend_from_slice(b"PLAINTEXT");
+            buf.extend_from_slice(&[3, b'b', b'1', 0x23, 0x84, 0, 0, 0]);
+            // metadata.version, from 1 to 19
+            buf.extend_from_slice(&[2, 17]);
+            buf.extend_from_slice(b"metadata.version");
+            buf.extend_from_slice(&[0, 1, 0, 19, 0]);
+            // no rack
+            buf.push(0);
+            if version >= 1 {
+                buf.push(0);
+            }
+            if version >= 2 {
+                buf.push(1);
+            }
+            if version >= 3 {
+                buf.push(2);
+                buf.extend_from_slice(&[5; 16]);
+            }
+            buf.push(0);
+            let record = match MetadataRecord::decode(&buf).unwrap() {
+                MetadataRecord::RegisterBroker(record) => record,
+                record => panic!("unexpected record {:?}", record),
+            };
+            assert_eq!(record.broker_id, 1);
+            assert_eq!(record.incarnation_id, [9; 16]);
+            assert_eq!(record.broker_epoch, 12);
+            assert_eq!(
+                record.endpoints,
+                [BrokerEndpoint {
+                    name: "PLAINTEXT".into(),
+                    host: "b1".into(),
+                    port: 9092,
+                    security_protocol: 0,
+                }]
+            );
+            assert_eq!(record.features[0].name, "metadata.version");
+            assert_eq!(record.features[0].max_supported_version, 19);
+            assert_eq!(record.rack, None);
+            assert_eq!(record.fenced, version == 0);
+            assert_eq!(record.in_controlled_shutdown, version >= 2);
+            assert_eq!(record.log_dirs.len(), (version >= 3) as usize);
+        }
+    }
+
+    #[test]
+    fn test_other_records() {
+        let mut buf = frame(4, 0);
+        buf.push(2);
+        buf.extend_from_slice(&[4, b'f', b'o', b'o']);
+        buf.push(13);
+        buf.extend_from_slice(b"retention.ms");
+        buf.extend_from_slice(&[3, b'1', b'0', 0]);
+        assert_eq!(
+            MetadataRecord::decode(&buf).unwrap(),
+            MetadataRecord::Config(ConfigRecord {
+                resource_type: 2,
+                resource_name: "foo".into(),
+                name: "retention.ms".into(),
+                value: Some("10".into()),
+            })
+        );
+
+        let mut buf = frame(7, 0);
+        buf.extend_from_slice(&3i32.to_be_bytes());
+        buf.extend_from_slice(&4i64.to_be_bytes());
+        buf.push(0);
+        assert_eq!(
+            MetadataRecord::decode(&buf).unwrap(),
+            MetadataRecord::FenceBroker(BrokerRecord { id: 3, epoch: 4 })
+        );
+
+        let mut buf = frame(12, 0);
+        buf.push(17);
+        buf.extend_from_slice(b"metadata.version");
+        buf.extend_from_slice(&[0, 19, 0]);
+        assert_eq!(
+            MetadataRecord::decode(&buf).unwrap(),
+            MetadataRecord::FeatureLevel(FeatureLevelRecord {
+                name: "metadata.version".into(),
+                feature_level: 19,
+            })
+        );
+
+        assert_eq!(
+            MetadataRecord::decode(&[1, 20, 0, 0]).unwrap(),
+            MetadataRecord::NoOp
+        );
+        assert_eq!(
+            MetadataRecord::decode(&[1, 21, 0, 0]).unwrap(),
+            MetadataRecord::Unknown {
+                record_type: 21,
+                version: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let err = MetadataRecord::decode(&[0, 20, 0, 0]).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Custom(ref msg) => {
+                assert_eq!(msg, "unsupported metadata record frame version: 0")
+            }
+            ref e => panic!("unexpected error {}", e),
+        }
+        let err = MetadataRecord::decode(&[1, 2, 1, 0]).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Custom(ref msg) => assert_eq!(msg, "unsupported TopicRecord version: 1"),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
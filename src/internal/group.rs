@@ -0,0 +1,266 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::{read_array_len, read_bytes, read_nullable_string, read_string, read_version};
+use crate::consumer::{Assignment, Subscription};
+use crate::de::KafkaDeserializer;
+use crate::error::Result;
+
+const GROUP_METADATA_VALUE_VERSION: i16 = 4;
+
+/// The state of a group, the value of a [`GroupMetadataKey`](super::GroupMetadataKey)
+///
+/// The coordinator writes it after every rebalance. Members of consumer groups (those with the
+/// `consumer` protocol type) can have their subscriptions and assignments decoded with
+/// [`MemberMetadata::consumer_subscription`] and [`MemberMetadata::consumer_assignment`].
+///
+/// # Examples
+/// ```
+/// use kafka_serde::internal::GroupMetadataValue;
+///
+/// fn print_members(value: &[u8]) -> kafka_serde::Result<()> {
+///     let group = GroupMetadataValue::decode(value)?;
+///     for member in &group.members {
+///         let assignment = member.consumer_assignment()?;
+///         println!("{}: {:?}", member.member_id, assignment.assigned_partitions);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GroupMetadataValue {
+    /// The protocol type, `consumer` for consumer groups
+    pub protocol_type: String,
+    /// The generation of the group
+    pub generation: i32,
+    /// The protocol chosen for the current generation, e.g. the assignor of consumer groups
+    pub protocol: Option<String>,
+    /// The member id of the group leader
+    pub leader: Option<String>,
+    /// When the group last changed state, in milliseconds since the epoch, or -1. Since v2
+    pub current_state_timestamp: i64,
+    /// The members of the group
+    pub members: Vec<MemberMetadata>,
+}
+
+/// A member of a group, as stored in a [`GroupMetadataValue`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct MemberMetadata {
+    /// The member id assigned by the coordinator
+    pub member_id: String,
+    /// The static membership id. Since v3
+    pub group_instance_id: Option<String>,
+    /// The client id of the member
+    pub client_id: String,
+    /// The host the member connected from
+    pub client_host: String,
+    /// How long the member may take to rejoin during a rebalance. Before v1, this is the
+    /// session timeout
+    pub rebalance_timeout: i32,
+    /// How long the member may go without heartbeats before being removed
+    pub session_timeout: i32,
+    /// The protocol metadata sent by the member in JoinGroup
+    pub subscription: Vec<u8>,
+    /// The assignment computed by the leader for this member
+    pub assignment: Vec<u8>,
+}
+
+impl MemberMetadata {
+    /// Decodes the subscription of a member of a consumer group
+    pub fn consumer_subscription(&self) -> Result<Subscription> {
+        Subscription::decode(&self.subscription)
+    }
+
+    /// Decodes the assignment of a member of a consumer group
+    pub fn consumer_assignment(&self) -> Result<Assignment> {
+        Assignment::decode(&self.assignment)
+    }
+
+    fn read(de: &mut KafkaDeserializer<'_>, version: i16) -> Result<MemberMetadata> {
+        let flexible = version >= 4;
+        let member_id = read_string(de, flexible)?.to_owned();
+        let group_instance_id = if version >= 3 {
+            read_nullable_string(de, flexible)?.map(|id| id.to_owned())
+        } else {
+            None
+        };
+        let client_id = read_string(de, flexible)?.to_owned();
+        let client_host = read_string(de, flexible)?.to_owned();
+        let rebalance_timeout = de.read_i32()?;
+        let session_timeout = if version >= 1 {
+            de.read_i32()?
+        } else {
+            rebalance_timeout
+        };
+        let member = MemberMetadata {
+            member_id,
+            group_instance_id,
+            client_id,
+            client_host,
+            rebalance_timeout,
+            session_timeout,
+            subscription: read_bytes(de, flexible)?.to_vec(),
+            assignment: read_bytes(de, flexible)?.to_vec(),
+        };
+        if flexible {
+            de.skip_tagged_fields()?;
+        }
+        Ok(member)
+    }
+}
+
+impl GroupMetadataValue {
+    /// Decodes the group state from `buf`, in any version from 0 to 4
+    pub fn decode(buf: &[u8]) -> Result<GroupMetadataValue> {
+        let mut de = KafkaDeserializer::new(buf);
+        let version = read_version(
+            &mut de,
+            "group metadata value",
+            GROUP_METADATA_VALUE_VERSION,
+        )?;
+        let flexible = version >= 4;
+        let protocol_type = read_string(&mut de, flexible)?.to_owned();
+        let generation = de.read_i32()?;
+        let protocol = read_nullable_string(&mut de, flexible)?.map(|p| p.to_owned());
+        let leader = read_nullable_string(&mut de, flexible)?.map(|l| l.to_owned());
+        let current_state_timestamp = if version >= 2 { de.read_i64()? } else { -1 };
+
+        let len = read_array_len(&mut de, flexible)?;
+        let mut members = Vec::new();
+        for _ in 0..len {
+            members.push(MemberMetadata::read(&mut de, version)?);
+        }
+        if flexible {
+            de.skip_tagged_fields()?;
+        }
+        Ok(GroupMetadataValue {
+            protocol_type,
+            generation,
+            protocol,
+            leader,
+            current_state_timestamp,
+            members,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::consumer::{TopicPartitions, ASSIGNMENT_VERSION, SUBSCRIPTION_VERSION};
+    use crate::ser;
+
+    fn string(buf: &mut Vec<u8>, s: &str, flexible: bool) {
+        if flexible {
+            buf.push(s.len() as u8 + 1);
+            buf.extend_from_slice(s.as_bytes());
+        } else {
+            ser::write_string(buf, s).unwrap();
+        }
+    }
+
+    fn bytes(buf: &mut Vec<u8>, b: &[u8], flexible: bool) {
+        if flexible {
+            buf.push(b.len() as u8 + 1);
+            buf.extend_from_slice(b);
+        } else {
+            ser::write_nullable_bytes(buf, Some(b)).unwrap();
+        }
+    }
+
+    fn value(version: i16, subscription: &[u8], assignment: &[u8]) -> Vec<u8> {
+        let flexible = version >= 4;
+        let mut buf = version.to_be_bytes().to_vec();
+        string(&mut buf, "consumer", flexible);
+        buf.extend_from_slice(&5i32.to_be_bytes());
+        string(&mut buf, "range", flexible);
+        string(&mut buf, "m-1", flexible);
+        if version >= 2 {
+            buf.extend_from_slice(&1000i64.to_be_bytes());
+        }
+        if flexible {
+            buf.push(2);
+        } else {
+            buf.extend_from_slice(&1i32.to_be_bytes());
+        }
+        string(&mut buf, "m-1", flexible);
+        if version >= 3 {
+            // null group instance id
+            if flexible {
+                buf.push(0);
+            } else {
+                buf.extend_from_slice(&[0xff, 0xff]);
+            }
+        }
+        string(&mut buf, "client", flexible);
+        string(&mut buf, "/10.0.0.1", flexible);
+        buf.extend_from_slice(&300_000i32.to_be_bytes());
+        if version >= 1 {
+            buf.extend_from_slice(&10_000i32.to_be_bytes());
+        }
+        bytes(&mut buf, subscription, flexible);
+        bytes(&mut buf, assignment, flexible);
+        if flexible {
+            // no tagged fields, for the member then for the group
+            buf.extend_from_slice(&[0, 0]);
+        }
+        buf
+    }
+
+    #[test]
+    fn test_all_versions() {
+        for version in 0..=GROUP_METADATA_VALUE_VERSION {
+            let group = GroupMetadataValue::decode(&value(version, &[1], &[2, 3])).unwrap();
+            assert_eq!(group.protocol_type, "consumer");
+            assert_eq!(group.generation, 5);
+            assert_eq!(group.protocol.as_deref(), Some("range"));
+            assert_eq!(group.leader.as_deref(), Some("m-1"));
+            assert_eq!(
+                group.current_state_timestamp,
+                if version >= 2 { 1000 } else { -1 }
+            );
+
+            let member = &group.members[0];
+            assert_eq!(member.member_id, "m-1");
+            assert_eq!(member.group_instance_id, None);
+            assert_eq!(member.client_id, "client");
+            assert_eq!(member.client_host, "/10.0.0.1");
+            assert_eq!(member.rebalance_timeout, 300_000);
+            let session_timeout = if version >= 1 { 10_000 } else { 300_000 };
+            assert_eq!(member.session_timeout, session_timeout);
+            assert_eq!(member.subscription, [1]);
+            assert_eq!(member.assignment, [2, 3]);
+        }
+    }
+
+    #[test]
+    fn test_consumer_member() {
+        let subscription = Subscription {
+            topics: vec!["t".into()],
+            ..Default::default()
+        };
+        let assignment = Assignment {
+            assigned_partitions: vec![TopicPartitions::new("t", vec![0])],
+            user_data: None,
+        };
+        let mut sub = Vec::new();
+        subscription.encode(SUBSCRIPTION_VERSION, &mut sub).unwrap();
+        let mut assign = Vec::new();
+        assignment.encode(ASSIGNMENT_VERSION, &mut assign).unwrap();
+
+        let group = GroupMetadataValue::decode(&value(3, &sub, &assign)).unwrap();
+        let member = &group.members[0];
+        assert_eq!(member.consumer_subscription().unwrap(), subscription);
+        assert_eq!(member.consumer_assignment().unwrap(), assignment);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let buf = value(3, &[1], &[2]);
+        assert!(GroupMetadataValue::decode(&buf[..buf.len() - 1]).is_err());
+    }
+}
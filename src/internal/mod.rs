@@ -0,0 +1,89 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Internal topics
+//!
+//! Brokers keep the state of consumer groups and transactions in internal topics
+//! (`__consumer_offsets`, `__transaction_state`). The keys and values of their records follow
+//! versioned schemas, whose `i16` version comes first. This module decodes them, so that tools
+//! can inspect that state by reading the topics directly, or from dumps of their segments.
+//!
+//! KRaft clusters keep their metadata in `__cluster_metadata`, whose records are decoded by
+//! [`MetadataRecord`].
+//!
+//! A null record value is a tombstone: the key it belongs to was deleted.
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+
+mod cluster;
+mod group;
+mod offsets;
+mod transaction;
+
+pub use self::cluster::{
+    BrokerEndpoint, BrokerFeature, BrokerRecord, ConfigRecord, FeatureLevelRecord, MetadataRecord,
+    PartitionRecord, RegisterBrokerRecord, RemoveTopicRecord, TopicRecord, UnregisterBrokerRecord,
+};
+pub use self::group::{GroupMetadataValue, MemberMetadata};
+pub use self::offsets::{GroupMetadataKey, OffsetCommitKey, OffsetCommitValue, OffsetsKey};
+pub use self::transaction::{TransactionLogKey, TransactionLogValue, TransactionState};
+
+/// Reads the schema version that starts every key and value, failing if it is not one of the
+/// versions in `0..=max`
+pub(crate) fn read_version(de: &mut KafkaDeserializer<'_>, schema: &str, max: i16) -> Result<i16> {
+    match de.read_i16()? {
+        version if version < 0 || version > max => {
+            Err(ErrorKind::Custom(format!("unsupported {} version: {}", schema, version)).into())
+        }
+        version => Ok(version),
+    }
+}
+
+/// Reads a `STRING`, or a `COMPACT_STRING` in flexible versions
+pub(crate) fn read_string<'de>(
+    de: &mut KafkaDeserializer<'de>,
+    flexible: bool,
+) -> Result<&'de str> {
+    if flexible {
+        de.read_compact_string()
+    } else {
+        de.read_string()
+    }
+}
+
+/// Reads a `NULLABLE_STRING`, or a `COMPACT_NULLABLE_STRING` in flexible versions
+pub(crate) fn read_nullable_string<'de>(
+    de: &mut KafkaDeserializer<'de>,
+    flexible: bool,
+) -> Result<Option<&'de str>> {
+    if flexible {
+        de.read_compact_nullable_string()
+    } else {
+        de.read_nullable_string()
+    }
+}
+
+/// Reads `BYTES`, or `COMPACT_BYTES` in flexible versions
+pub(crate) fn read_bytes<'de>(
+    de: &mut KafkaDeserializer<'de>,
+    flexible: bool,
+) -> Result<&'de [u8]> {
+    if flexible {
+        de.read_compact_bytes()
+    } else {
+        de.read_nullable_bytes()?
+            .ok_or_else(|| ErrorKind::Custom("unexpected null bytes".into()).into())
+    }
+}
+
+/// Reads the element count of an `ARRAY`, or a `COMPACT_ARRAY` in flexible versions. Null
+/// arrays are read as empty ones
+pub(crate) fn read_array_len(de: &mut KafkaDeserializer<'_>, flexible: bool) -> Result<usize> {
+    if flexible {
+        Ok(de.read_compact_array_len()?.unwrap_or(0))
+    } else {
+        de.read_array_len()
+    }
+}
@@ -0,0 +1,210 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::{read_string, read_version};
+use crate::de::KafkaDeserializer;
+use crate::error::Result;
+
+/// The key of a record in `__consumer_offsets`
+///
+/// The key version tells what the record holds: committed offsets (v0 and v1) or group
+/// metadata (v2). Newer versions are used by the consumer group protocol from KIP-848, and are
+/// reported as [`OffsetsKey::Unknown`].
+///
+/// # Examples
+/// ```
+/// use kafka_serde::internal::{OffsetCommitValue, OffsetsKey};
+///
+/// fn print_offset(key: &[u8], value: Option<&[u8]>) -> kafka_serde::Result<()> {
+///     if let OffsetsKey::OffsetCommit(key) = OffsetsKey::decode(key)? {
+///         match value {
+///             Some(value) => {
+///                 let value = OffsetCommitValue::decode(value)?;
+///                 println!("{}/{}/{}: {}", key.group, key.topic, key.partition, value.offset);
+///             }
+///             None => println!("{}/{}/{}: deleted", key.group, key.topic, key.partition),
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum OffsetsKey {
+    /// The offset committed by a group for a partition
+    OffsetCommit(OffsetCommitKey),
+    /// The metadata of a group
+    GroupMetadata(GroupMetadataKey),
+    /// A record type that is not decoded here, with its key version
+    Unknown(i16),
+}
+
+impl OffsetsKey {
+    /// Decodes a record key from `buf`
+    pub fn decode(buf: &[u8]) -> Result<OffsetsKey> {
+        let mut de = KafkaDeserializer::new(buf);
+        let key = match de.read_i16()? {
+            0 | 1 => OffsetsKey::OffsetCommit(OffsetCommitKey {
+                group: de.read_string()?.to_owned(),
+                topic: de.read_string()?.to_owned(),
+                partition: de.read_i32()?,
+            }),
+            2 => OffsetsKey::GroupMetadata(GroupMetadataKey {
+                group: de.read_string()?.to_owned(),
+            }),
+            version => OffsetsKey::Unknown(version),
+        };
+        Ok(key)
+    }
+}
+
+/// The key of a committed offset
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct OffsetCommitKey {
+    /// The group that committed the offset
+    pub group: String,
+    /// The topic of the partition
+    pub topic: String,
+    /// The partition index
+    pub partition: i32,
+}
+
+/// The key of the metadata of a group, whose value is a
+/// [`GroupMetadataValue`](super::GroupMetadataValue)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GroupMetadataKey {
+    /// The group id
+    pub group: String,
+}
+
+const OFFSET_COMMIT_VALUE_VERSION: i16 = 4;
+
+/// A committed offset, the value of an [`OffsetCommitKey`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct OffsetCommitValue {
+    /// The committed offset
+    pub offset: i64,
+    /// The leader epoch of the last consumed record, or -1. Since v3
+    pub leader_epoch: i32,
+    /// Metadata attached to the commit by the consumer
+    pub metadata: String,
+    /// When the offset was committed, in milliseconds since the epoch
+    pub commit_timestamp: i64,
+    /// When the offset expires. Only present in v1
+    pub expire_timestamp: Option<i64>,
+}
+
+impl Default for OffsetCommitValue {
+    fn default() -> Self {
+        OffsetCommitValue {
+            offset: 0,
+            leader_epoch: -1,
+            metadata: String::new(),
+            commit_timestamp: 0,
+            expire_timestamp: None,
+        }
+    }
+}
+
+impl OffsetCommitValue {
+    /// Decodes a committed offset from `buf`, in any version from 0 to 4
+    pub fn decode(buf: &[u8]) -> Result<OffsetCommitValue> {
+        let mut de = KafkaDeserializer::new(buf);
+        let version = read_version(&mut de, "offset commit value", OFFSET_COMMIT_VALUE_VERSION)?;
+        let mut value = OffsetCommitValue {
+            offset: de.read_i64()?,
+            ..Default::default()
+        };
+        if version >= 3 {
+            value.leader_epoch = de.read_i32()?;
+        }
+        value.metadata = read_string(&mut de, version >= 4)?.to_owned();
+        value.commit_timestamp = de.read_i64()?;
+        if version == 1 {
+            value.expire_timestamp = Some(de.read_i64()?);
+        }
+        if version >= 4 {
+            de.skip_tagged_fields()?;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ser;
+
+    #[test]
+    fn test_offset_commit_key() {
+        let mut buf = vec![0, 1];
+        ser::write_string(&mut buf, "group").unwrap();
+        ser::write_string(&mut buf, "topic").unwrap();
+        buf.extend_from_slice(&3i32.to_be_bytes());
+        assert_eq!(
+            OffsetsKey::decode(&buf).unwrap(),
+            OffsetsKey::OffsetCommit(OffsetCommitKey {
+                group: "group".into(),
+                topic: "topic".into(),
+                partition: 3,
+            })
+        );
+        assert!(OffsetsKey::decode(&buf[..buf.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_other_keys() {
+        let mut buf = vec![0, 2];
+        ser::write_string(&mut buf, "group").unwrap();
+        assert_eq!(
+            OffsetsKey::decode(&buf).unwrap(),
+            OffsetsKey::GroupMetadata(GroupMetadataKey {
+                group: "group".into()
+            })
+        );
+        assert_eq!(OffsetsKey::decode(&[0, 5]).unwrap(), OffsetsKey::Unknown(5));
+    }
+
+    fn value(version: i16) -> Vec<u8> {
+        let mut buf = version.to_be_bytes().to_vec();
+        buf.extend_from_slice(&42i64.to_be_bytes());
+        if version >= 3 {
+            buf.extend_from_slice(&5i32.to_be_bytes());
+        }
+        if version >= 4 {
+            buf.extend_from_slice(&[3, b'm', b'd']);
+        } else {
+            ser::write_string(&mut buf, "md").unwrap();
+        }
+        buf.extend_from_slice(&1000i64.to_be_bytes());
+        if version == 1 {
+            buf.extend_from_slice(&2000i64.to_be_bytes());
+        }
+        if version >= 4 {
+            buf.push(0);
+        }
+        buf
+    }
+
+    #[test]
+    fn test_offset_commit_value() {
+        for version in 0..=OFFSET_COMMIT_VALUE_VERSION {
+            let decoded = OffsetCommitValue::decode(&value(version)).unwrap();
+            assert_eq!(decoded.offset, 42);
+            assert_eq!(decoded.metadata, "md");
+            assert_eq!(decoded.commit_timestamp, 1000);
+            assert_eq!(decoded.leader_epoch, if version >= 3 { 5 } else { -1 });
+            assert_eq!(decoded.expire_timestamp.is_some(), version == 1);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_value_version() {
+        assert!(OffsetCommitValue::decode(&value(5)).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::{read_array_len, read_string, read_version};
+use crate::consumer::TopicPartitions;
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+
+/// The key of a record in `__transaction_state`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct TransactionLogKey {
+    /// The transactional id of the producer
+    pub transactional_id: String,
+}
+
+impl TransactionLogKey {
+    /// Decodes a record key from `buf`
+    pub fn decode(buf: &[u8]) -> Result<TransactionLogKey> {
+        let mut de = KafkaDeserializer::new(buf);
+        read_version(&mut de, "transaction log key", 0)?;
+        Ok(TransactionLogKey {
+            transactional_id: de.read_string()?.to_owned(),
+        })
+    }
+}
+
+/// The state of a transaction, as tracked by the transaction coordinator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum TransactionState {
+    /// No transaction in progress
+    Empty,
+    /// Partitions were added to the transaction
+    Ongoing,
+    /// The producer asked to commit, markers are being written
+    PrepareCommit,
+    /// The producer asked to abort, markers are being written
+    PrepareAbort,
+    /// The transaction was committed
+    CompleteCommit,
+    /// The transaction was aborted
+    CompleteAbort,
+    /// The transactional id expired and is about to be removed
+    Dead,
+    /// The transaction is being aborted to fence off a producer with an older epoch
+    PrepareEpochFence,
+}
+
+impl TransactionState {
+    /// Converts the state id stored in the transaction log
+    pub fn from_id(id: i8) -> Result<TransactionState> {
+        match id {
+            0 => Ok(TransactionState::Empty),
+            1 => Ok(TransactionState::Ongoing),
+            2 => Ok(TransactionState::PrepareCommit),
+            3 => Ok(TransactionState::PrepareAbort),
+            4 => Ok(TransactionState::CompleteCommit),
+            5 => Ok(TransactionState::CompleteAbort),
+            6 => Ok(TransactionState::Dead),
+            7 => Ok(TransactionState::PrepareEpochFence),
+            id => Err(ErrorKind::Custom(format!("unknown transaction state: {}", id)).into()),
+        }
+    }
+
+    /// The state id, as stored in the transaction log
+    pub fn id(self) -> i8 {
+        match self {
+            TransactionState::Empty => 0,
+            TransactionState::Ongoing => 1,
+            TransactionState::PrepareCommit => 2,
+            TransactionState::PrepareAbort => 3,
+            TransactionState::CompleteCommit => 4,
+            TransactionState::CompleteAbort => 5,
+            TransactionState::Dead => 6,
+            TransactionState::PrepareEpochFence => 7,
+        }
+    }
+}
+
+const TRANSACTION_LOG_VALUE_VERSION: i16 = 1;
+
+/// The state of a transactional producer, the value of a [`TransactionLogKey`]
+///
+/// Version 1 is flexible, and may carry tagged fields on top of these. They are skipped.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::internal::{TransactionLogKey, TransactionLogValue, TransactionState};
+///
+/// fn is_ongoing(key: &[u8], value: &[u8]) -> kafka_serde::Result<bool> {
+///     let key = TransactionLogKey::decode(key)?;
+///     let value = TransactionLogValue::decode(value)?;
+///     println!("{}: {:?}", key.transactional_id, value.state);
+///     Ok(value.state == TransactionState::Ongoing)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct TransactionLogValue {
+    /// The producer id
+    pub producer_id: i64,
+    /// The producer epoch
+    pub producer_epoch: i16,
+    /// How long the transaction may stay open before the coordinator aborts it
+    pub transaction_timeout_ms: i32,
+    /// The state of the transaction
+    pub state: TransactionState,
+    /// The partitions written to in the current transaction
+    pub partitions: Vec<TopicPartitions>,
+    /// When the state was last updated, in milliseconds since the epoch
+    pub last_update_timestamp_ms: i64,
+    /// When the current transaction started, in milliseconds since the epoch, or -1
+    pub start_timestamp_ms: i64,
+}
+
+impl TransactionLogValue {
+    /// Decodes the transaction state from `buf`
+    pub fn decode(buf: &[u8]) -> Result<TransactionLogValue> {
+        let mut de = KafkaDeserializer::new(buf);
+        let version = read_version(
+            &mut de,
+            "transaction log value",
+            TRANSACTION_LOG_VALUE_VERSION,
+        )?;
+        let flexible = version >= 1;
+        let producer_id = de.read_i64()?;
+        let producer_epoch = de.read_i16()?;
+        let transaction_timeout_ms = de.read_i32()?;
+        let state = TransactionState::from_id(de.read_i8()?)?;
+        let partitions = read_partitions(&mut de, flexible)?;
+        let value = TransactionLogValue {
+            producer_id,
+            producer_epoch,
+            transaction_timeout_ms,
+            state,
+            partitions,
+            last_update_timestamp_ms: de.read_i64()?,
+            start_timestamp_ms: de.read_i64()?,
+        };
+        if flexible {
+            de.skip_tagged_fields()?;
+        }
+        Ok(value)
+    }
+}
+
+fn read_partitions(de: &mut KafkaDeserializer<'_>, flexible: bool) -> Result<Vec<TopicPartitions>> {
+    let len = read_array_len(de, flexible)?;
+    let mut topics = Vec::new();
+    for _ in 0..len {
+        let topic = read_string(de, flexible)?;
+        let count = read_array_len(de, flexible)?;
+        let mut partitions = Vec::new();
+        for _ in 0..count {
+            partitions.push(de.read_i32()?);
+        }
+        if flexible {
+            de.skip_tagged_fields()?;
+        }
+        topics.push(TopicPartitions::new(topic, partitions));
+    }
+    Ok(topics)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ser;
+
+    #[test]
+    fn test_key() {
+        let mut buf = vec![0, 0];
+        ser::write_string(&mut buf, "txn-1").unwrap();
+        let key = TransactionLogKey::decode(&buf).unwrap();
+        assert_eq!(key.transactional_id, "txn-1");
+        buf[1] = 1;
+        assert!(TransactionLogKey::decode(&buf).is_err());
+    }
+
+    fn value(version: i16) -> Vec<u8> {
+        let mut buf = version.to_be_bytes().to_vec();
+        buf.extend_from_slice(&1234i64.to_be_bytes());
+        buf.extend_from_slice(&3i16.to_be_bytes());
+        buf.extend_from_slice(&60_000i32.to_be_bytes());
+        buf.push(1);
+        if version >= 1 {
+            // one topic, "t", with partitions 0 and 4, no tagged fields
+            buf.extend_from_slice(&[2, 2, b't', 3, 0, 0, 0, 0, 0, 0, 0, 4, 0]);
+        } else {
+            buf.extend_from_slice(&1i32.to_be_bytes());
+            ser::write_string(&mut buf, "t").unwrap();
+            buf.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 4]);
+        }
+        buf.extend_from_slice(&2000i64.to_be_bytes());
+        buf.extend_from_slice(&1000i64.to_be_bytes());
+        if version >= 1 {
+            // a tagged field, tag 0 with an i64
+            buf.extend_from_slice(&[1, 0, 8]);
+            buf.extend_from_slice(&99i64.to_be_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_value() {
+        for version in 0..=TRANSACTION_LOG_VALUE_VERSION {
+            let decoded = TransactionLogValue::decode(&value(version)).unwrap();
+            assert_eq!(
+                decoded,
+                TransactionLogValue {
+                    producer_id: 1234,
+                    producer_epoch: 3,
+                    transaction_timeout_ms: 60_000,
+                    state: TransactionState::Ongoing,
+                    partitions: vec![TopicPartitions::new("t", vec![0, 4])],
+                    last_update_timestamp_ms: 2000,
+                    start_timestamp_ms: 1000,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_state() {
+        let mut buf = value(0);
+        buf[16] = 9;
+        assert!(TransactionLogValue::decode(&buf).is_err());
+        for id in 0..8 {
+            assert_eq!(TransactionState::from_id(id).unwrap().id(), id);
+        }
+    }
+}
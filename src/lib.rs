@@ -7,10 +7,20 @@
 //!
 //! Details: Options are allowed during serialization, but not deserialization
 //!
-//! variable sizes like varint, compact bytes, etc, are not supported yet.
+//! variable sizes like varint, compact bytes, etc, are not supported yet in the serde
+//! implementation. The v2 record format, which relies on them heavily, is available in the
+//! [`records`] module, and the formats embedded in the consumer group APIs in the [`consumer`]
+//! module. The schemas of the broker's internal topics can be decoded with the [`internal`]
+//! module.
 //! nullable_string and nullable_bytes are supported during deserialization (they will
 //! deserialize into standard string, str and byte-slices) but not yet during serialization.
 //!
+//! Structs with `#[serde(flatten)]` fields, like a header and a body, serialize to their fields
+//! in order, while flattened maps fail, as their keys would be lost. These structs don't
+//! deserialize: serde decodes flattened fields before knowing their types, which needs a
+//! self-describing format. A [`header::Frame`] of the header and the body, or nested structs,
+//! encode the same bytes and decode them.
+//!
 #![warn(missing_docs, missing_debug_implementations, rust_2018_idioms)]
 use std::any::type_name;
 
@@ -20,19 +30,57 @@ pub(crate) fn type_of<T>(_: T) -> &'static str {
 
 macro_rules! type_not_supported {
     ($x:ident) => {
-        Err(Box::new(crate::ErrorKind::TypeNotSupported(
-            crate::type_of($x),
-        )))
+        Err(crate::ErrorKind::TypeNotSupported(crate::type_of($x)).into())
     };
     ($x:expr) => {
-        Err(Box::new(crate::ErrorKind::TypeNotSupported($x)))
+        Err(crate::ErrorKind::TypeNotSupported($x).into())
     };
 }
 
+// generated messages name the crate
+extern crate self as kafka_serde;
+
+pub mod api;
+pub mod capture;
+pub mod client;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod consumer;
 mod de;
 mod error;
+pub mod fetch;
+pub mod header;
+mod intern;
+pub mod internal;
+pub mod messages;
+pub mod metadata;
+pub mod metrics;
+#[cfg(feature = "mock-broker")]
+pub mod mock;
+pub mod partitioner;
+pub mod produce;
+pub mod records;
+pub mod schema;
 mod ser;
+#[cfg(feature = "bytes")]
+pub mod shared_bytes;
+pub mod storage;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+pub mod tagged;
+pub mod testing;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub mod timestamp;
+mod varint;
 
-pub use self::de::from_bytes;
-pub use self::error::{Error, ErrorKind, Result};
-pub use self::ser::to_writer;
+pub use self::api::KafkaRequest;
+#[cfg(feature = "bytes")]
+pub use self::de::from_buf;
+pub use self::de::{from_bytes, Decoder};
+pub use self::error::{Error, ErrorCategory, ErrorKind, Result};
+pub use self::intern::InternedStr;
+#[cfg(feature = "bytes")]
+pub use self::ser::to_buf;
+pub use self::ser::{to_vec, to_writer, FixedSize, FrameWriter};
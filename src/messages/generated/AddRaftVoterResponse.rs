@@ -0,0 +1,120 @@
+// Generated by kafka_serde::codegen from the definition of AddRaftVoterResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The AddRaftVoterResponse message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AddRaftVoterResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// The error code, or 0 if there was no error.
+    pub error_code: i16,
+    /// The error message, or null if there was no error.
+    pub error_message: Option<String>,
+}
+
+impl FromValue for AddRaftVoterResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(AddRaftVoterResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            error_message: take_field(&mut fields, "ErrorMessage")?,
+        })
+    }
+}
+
+impl ToValue for AddRaftVoterResponse {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ErrorMessage".to_owned(), self.error_message.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl AddRaftVoterResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 80;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("AddRaftVoterResponse", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("ErrorMessage", Type::String).nullable_versions(0..),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for AddRaftVoterResponse {
+    fn schema() -> &'static Schema {
+        AddRaftVoterResponse::schema()
+    }
+}
+
+impl AddRaftVoterResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> AddRaftVoterResponseBuilder {
+        AddRaftVoterResponseBuilder::default()
+    }
+}
+
+/// Builds AddRaftVoterResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct AddRaftVoterResponseBuilder {
+    message: AddRaftVoterResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl AddRaftVoterResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `error_code`
+    pub fn error_code(mut self, value: i16) -> Self {
+        self.message.error_code = value;
+        self.set.push("ErrorCode");
+        self
+    }
+
+    /// Sets `error_message`
+    pub fn error_message(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.error_message = value.into();
+        self.set.push("ErrorMessage");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<AddRaftVoterResponse> {
+        AddRaftVoterResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
@@ -0,0 +1,209 @@
+// Generated by kafka_serde::codegen from the definition of DescribeTopicPartitionsRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, take_field_or, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The DescribeTopicPartitionsRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct DescribeTopicPartitionsRequest {
+    /// The topics to fetch details for.
+    pub topics: Vec<TopicRequest>,
+    /// The maximum number of partitions included in the response.
+    pub response_partition_limit: i32,
+    /// The first topic and partition index to fetch details for.
+    pub cursor: Option<Cursor>,
+}
+
+impl Default for DescribeTopicPartitionsRequest {
+    fn default() -> Self {
+        DescribeTopicPartitionsRequest {
+            topics: Default::default(),
+            response_partition_limit: 2000,
+            cursor: Default::default(),
+        }
+    }
+}
+
+impl FromValue for DescribeTopicPartitionsRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(DescribeTopicPartitionsRequest {
+            topics: take_field(&mut fields, "Topics")?,
+            response_partition_limit: take_field_or(&mut fields, "ResponsePartitionLimit", 2000)?,
+            cursor: take_field(&mut fields, "Cursor")?,
+        })
+    }
+}
+
+impl ToValue for DescribeTopicPartitionsRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("Topics".to_owned(), self.topics.to_value()),
+            ("ResponsePartitionLimit".to_owned(), self.response_partition_limit.to_value()),
+            ("Cursor".to_owned(), self.cursor.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl DescribeTopicPartitionsRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 75;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("DescribeTopicPartitionsRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("Topics", Type::Array(Box::new(Type::Struct(TopicRequest::fields())))),
+            Field::new("ResponsePartitionLimit", Type::Int32),
+            Field::new("Cursor", Type::Struct(Cursor::fields())).nullable_versions(0..),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for DescribeTopicPartitionsRequest {
+    fn schema() -> &'static Schema {
+        DescribeTopicPartitionsRequest::schema()
+    }
+}
+
+impl DescribeTopicPartitionsRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> DescribeTopicPartitionsRequestBuilder {
+        DescribeTopicPartitionsRequestBuilder::default()
+    }
+}
+
+/// Builds DescribeTopicPartitionsRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct DescribeTopicPartitionsRequestBuilder {
+    message: DescribeTopicPartitionsRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl DescribeTopicPartitionsRequestBuilder {
+    /// Sets `topics`
+    pub fn topics(mut self, value: impl IntoIterator<Item = impl Into<TopicRequest>>) -> Self {
+        self.message.topics = value.into_iter().map(Into::into).collect();
+        self.set.push("Topics");
+        self
+    }
+
+    /// Sets `response_partition_limit`
+    pub fn response_partition_limit(mut self, value: i32) -> Self {
+        self.message.response_partition_limit = value;
+        self.set.push("ResponsePartitionLimit");
+        self
+    }
+
+    /// Sets `cursor`
+    pub fn cursor(mut self, value: impl Into<Option<Cursor>>) -> Self {
+        self.message.cursor = value.into();
+        self.set.push("Cursor");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<DescribeTopicPartitionsRequest> {
+        DescribeTopicPartitionsRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for DescribeTopicPartitionsRequest {
+    const API_KEY: i16 = 75;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::DescribeTopicPartitionsResponse;
+}
+
+/// A TopicRequest of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TopicRequest {
+    /// The topic name.
+    pub name: String,
+}
+
+impl FromValue for TopicRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(TopicRequest {
+            name: take_field(&mut fields, "Name")?,
+        })
+    }
+}
+
+impl ToValue for TopicRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("Name".to_owned(), self.name.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl TopicRequest {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("Name", Type::String),
+        ]
+    }
+}
+
+/// A Cursor of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Cursor {
+    /// The name for the first topic to process.
+    pub topic_name: String,
+    /// The partition index to start with.
+    pub partition_index: i32,
+}
+
+impl FromValue for Cursor {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(Cursor {
+            topic_name: take_field(&mut fields, "TopicName")?,
+            partition_index: take_field(&mut fields, "PartitionIndex")?,
+        })
+    }
+}
+
+impl ToValue for Cursor {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicName".to_owned(), self.topic_name.to_value()),
+            ("PartitionIndex".to_owned(), self.partition_index.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl Cursor {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicName", Type::String),
+            Field::new("PartitionIndex", Type::Int32),
+        ]
+    }
+}
@@ -0,0 +1,318 @@
+// Generated by kafka_serde::codegen from the definition of DescribeTopicPartitionsResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, take_field_or, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The DescribeTopicPartitionsResponse message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DescribeTopicPartitionsResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// Each topic in the response.
+    pub topics: Vec<DescribeTopicPartitionsResponseTopic>,
+    /// The next topic and partition index to fetch details for.
+    pub next_cursor: Option<Cursor>,
+}
+
+impl FromValue for DescribeTopicPartitionsResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(DescribeTopicPartitionsResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            topics: take_field(&mut fields, "Topics")?,
+            next_cursor: take_field(&mut fields, "NextCursor")?,
+        })
+    }
+}
+
+impl ToValue for DescribeTopicPartitionsResponse {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("Topics".to_owned(), self.topics.to_value()),
+            ("NextCursor".to_owned(), self.next_cursor.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl DescribeTopicPartitionsResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 75;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("DescribeTopicPartitionsResponse", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32),
+            Field::new("Topics", Type::Array(Box::new(Type::Struct(DescribeTopicPartitionsResponseTopic::fields())))),
+            Field::new("NextCursor", Type::Struct(Cursor::fields())).nullable_versions(0..),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for DescribeTopicPartitionsResponse {
+    fn schema() -> &'static Schema {
+        DescribeTopicPartitionsResponse::schema()
+    }
+}
+
+impl DescribeTopicPartitionsResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> DescribeTopicPartitionsResponseBuilder {
+        DescribeTopicPartitionsResponseBuilder::default()
+    }
+}
+
+/// Builds DescribeTopicPartitionsResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct DescribeTopicPartitionsResponseBuilder {
+    message: DescribeTopicPartitionsResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl DescribeTopicPartitionsResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `topics`
+    pub fn topics(mut self, value: impl IntoIterator<Item = impl Into<DescribeTopicPartitionsResponseTopic>>) -> Self {
+        self.message.topics = value.into_iter().map(Into::into).collect();
+        self.set.push("Topics");
+        self
+    }
+
+    /// Sets `next_cursor`
+    pub fn next_cursor(mut self, value: impl Into<Option<Cursor>>) -> Self {
+        self.message.next_cursor = value.into();
+        self.set.push("NextCursor");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<DescribeTopicPartitionsResponse> {
+        DescribeTopicPartitionsResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+/// A DescribeTopicPartitionsResponsePartition of the message
+#[derive(Debug, Clone, PartialEq)]
+pub struct DescribeTopicPartitionsResponsePartition {
+    /// The partition error, or 0 if there was no error.
+    pub error_code: i16,
+    /// The partition index.
+    pub partition_index: i32,
+    /// The ID of the leader broker.
+    pub leader_id: i32,
+    /// The leader epoch of this partition.
+    pub leader_epoch: i32,
+    /// The set of all nodes that host this partition.
+    pub replica_nodes: Vec<i32>,
+    /// The set of nodes that are in sync with the leader for this partition.
+    pub isr_nodes: Vec<i32>,
+    /// The new eligible leader replicas otherwise.
+    pub eligible_leader_replicas: Option<Vec<i32>>,
+    /// The last known ELR.
+    pub last_known_elr: Option<Vec<i32>>,
+    /// The set of offline replicas of this partition.
+    pub offline_replicas: Vec<i32>,
+}
+
+impl Default for DescribeTopicPartitionsResponsePartition {
+    fn default() -> Self {
+        DescribeTopicPartitionsResponsePartition {
+            error_code: Default::default(),
+            partition_index: Default::default(),
+            leader_id: Default::default(),
+            leader_epoch: -1,
+            replica_nodes: Default::default(),
+            isr_nodes: Default::default(),
+            eligible_leader_replicas: Default::default(),
+            last_known_elr: Default::default(),
+            offline_replicas: Default::default(),
+        }
+    }
+}
+
+impl FromValue for DescribeTopicPartitionsResponsePartition {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(DescribeTopicPartitionsResponsePartition {
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            partition_index: take_field(&mut fields, "PartitionIndex")?,
+            leader_id: take_field(&mut fields, "LeaderId")?,
+            leader_epoch: take_field_or(&mut fields, "LeaderEpoch", -1)?,
+            replica_nodes: take_field(&mut fields, "ReplicaNodes")?,
+            isr_nodes: take_field(&mut fields, "IsrNodes")?,
+            eligible_leader_replicas: take_field(&mut fields, "EligibleLeaderReplicas")?,
+            last_known_elr: take_field(&mut fields, "LastKnownElr")?,
+            offline_replicas: take_field(&mut fields, "OfflineReplicas")?,
+        })
+    }
+}
+
+impl ToValue for DescribeTopicPartitionsResponsePartition {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("PartitionIndex".to_owned(), self.partition_index.to_value()),
+            ("LeaderId".to_owned(), self.leader_id.to_value()),
+            ("LeaderEpoch".to_owned(), self.leader_epoch.to_value()),
+            ("ReplicaNodes".to_owned(), self.replica_nodes.to_value()),
+            ("IsrNodes".to_owned(), self.isr_nodes.to_value()),
+            ("EligibleLeaderReplicas".to_owned(), self.eligible_leader_replicas.to_value()),
+            ("LastKnownElr".to_owned(), self.last_known_elr.to_value()),
+            ("OfflineReplicas".to_owned(), self.offline_replicas.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl DescribeTopicPartitionsResponsePartition {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("PartitionIndex", Type::Int32),
+            Field::new("LeaderId", Type::Int32),
+            Field::new("LeaderEpoch", Type::Int32),
+            Field::new("ReplicaNodes", Type::Array(Box::new(Type::Int32))),
+            Field::new("IsrNodes", Type::Array(Box::new(Type::Int32))),
+            Field::new("EligibleLeaderReplicas", Type::Array(Box::new(Type::Int32))).nullable_versions(0..),
+            Field::new("LastKnownElr", Type::Array(Box::new(Type::Int32))).nullable_versions(0..),
+            Field::new("OfflineReplicas", Type::Array(Box::new(Type::Int32))),
+        ]
+    }
+}
+
+/// A DescribeTopicPartitionsResponseTopic of the message
+#[derive(Debug, Clone, PartialEq)]
+pub struct DescribeTopicPartitionsResponseTopic {
+    /// The topic error, or 0 if there was no error.
+    pub error_code: i16,
+    /// The topic name.
+    pub name: Option<String>,
+    /// The topic id.
+    pub topic_id: [u8; 16],
+    /// True if the topic is internal.
+    pub is_internal: bool,
+    /// Each partition in the topic.
+    pub partitions: Vec<DescribeTopicPartitionsResponsePartition>,
+    /// 32-bit bitfield to represent authorized operations for this topic.
+    pub topic_authorized_operations: i32,
+}
+
+impl Default for DescribeTopicPartitionsResponseTopic {
+    fn default() -> Self {
+        DescribeTopicPartitionsResponseTopic {
+            error_code: Default::default(),
+            name: Default::default(),
+            topic_id: Default::default(),
+            is_internal: Default::default(),
+            partitions: Default::default(),
+            topic_authorized_operations: -2147483648,
+        }
+    }
+}
+
+impl FromValue for DescribeTopicPartitionsResponseTopic {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(DescribeTopicPartitionsResponseTopic {
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            name: take_field(&mut fields, "Name")?,
+            topic_id: take_field(&mut fields, "TopicId")?,
+            is_internal: take_field(&mut fields, "IsInternal")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+            topic_authorized_operations: take_field_or(&mut fields, "TopicAuthorizedOperations", -2147483648)?,
+        })
+    }
+}
+
+impl ToValue for DescribeTopicPartitionsResponseTopic {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("Name".to_owned(), self.name.to_value()),
+            ("TopicId".to_owned(), self.topic_id.to_value()),
+            ("IsInternal".to_owned(), self.is_internal.to_value()),
+            ("Partitions".to_owned(), self.partitions.to_value()),
+            ("TopicAuthorizedOperations".to_owned(), self.topic_authorized_operations.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl DescribeTopicPartitionsResponseTopic {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("Name", Type::String).nullable_versions(0..),
+            Field::new("TopicId", Type::Uuid),
+            Field::new("IsInternal", Type::Bool),
+            Field::new("Partitions", Type::Array(Box::new(Type::Struct(DescribeTopicPartitionsResponsePartition::fields())))),
+            Field::new("TopicAuthorizedOperations", Type::Int32),
+        ]
+    }
+}
+
+/// A Cursor of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Cursor {
+    /// The name for the first topic to process.
+    pub topic_name: String,
+    /// The partition index to start with.
+    pub partition_index: i32,
+}
+
+impl FromValue for Cursor {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(Cursor {
+            topic_name: take_field(&mut fields, "TopicName")?,
+            partition_index: take_field(&mut fields, "PartitionIndex")?,
+        })
+    }
+}
+
+impl ToValue for Cursor {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicName".to_owned(), self.topic_name.to_value()),
+            ("PartitionIndex".to_owned(), self.partition_index.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl Cursor {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicName", Type::String),
+            Field::new("PartitionIndex", Type::Int32),
+        ]
+    }
+}
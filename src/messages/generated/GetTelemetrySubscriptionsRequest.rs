@@ -0,0 +1,104 @@
+// Generated by kafka_serde::codegen from the definition of GetTelemetrySubscriptionsRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The GetTelemetrySubscriptionsRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GetTelemetrySubscriptionsRequest {
+    /// Unique id for this client instance, must be set to 0 on the first request.
+    pub client_instance_id: [u8; 16],
+}
+
+impl FromValue for GetTelemetrySubscriptionsRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(GetTelemetrySubscriptionsRequest {
+            client_instance_id: take_field(&mut fields, "ClientInstanceId")?,
+        })
+    }
+}
+
+impl ToValue for GetTelemetrySubscriptionsRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ClientInstanceId".to_owned(), self.client_instance_id.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl GetTelemetrySubscriptionsRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 71;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("GetTelemetrySubscriptionsRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ClientInstanceId", Type::Uuid),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for GetTelemetrySubscriptionsRequest {
+    fn schema() -> &'static Schema {
+        GetTelemetrySubscriptionsRequest::schema()
+    }
+}
+
+impl GetTelemetrySubscriptionsRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> GetTelemetrySubscriptionsRequestBuilder {
+        GetTelemetrySubscriptionsRequestBuilder::default()
+    }
+}
+
+/// Builds GetTelemetrySubscriptionsRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct GetTelemetrySubscriptionsRequestBuilder {
+    message: GetTelemetrySubscriptionsRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl GetTelemetrySubscriptionsRequestBuilder {
+    /// Sets `client_instance_id`
+    pub fn client_instance_id(mut self, value: [u8; 16]) -> Self {
+        self.message.client_instance_id = value;
+        self.set.push("ClientInstanceId");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<GetTelemetrySubscriptionsRequest> {
+        GetTelemetrySubscriptionsRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for GetTelemetrySubscriptionsRequest {
+    const API_KEY: i16 = 71;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::GetTelemetrySubscriptionsResponse;
+}
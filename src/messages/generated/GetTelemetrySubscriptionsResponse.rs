@@ -0,0 +1,192 @@
+// Generated by kafka_serde::codegen from the definition of GetTelemetrySubscriptionsResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The GetTelemetrySubscriptionsResponse message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GetTelemetrySubscriptionsResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// The error code, or 0 if there was no error.
+    pub error_code: i16,
+    /// Assigned client instance id if ClientInstanceId was 0 in the request, else 0.
+    pub client_instance_id: [u8; 16],
+    /// Unique identifier for the current subscription set for this client instance.
+    pub subscription_id: i32,
+    /// Compression types that broker accepts for the PushTelemetryRequest.
+    pub accepted_compression_types: Vec<i8>,
+    /// Configured push interval, which is the lowest configured interval in the current subscription set.
+    pub push_interval_ms: i32,
+    /// The maximum bytes of binary data the broker accepts in PushTelemetryRequest.
+    pub telemetry_max_bytes: i32,
+    /// Flag to indicate monotonic/counter metrics are to be emitted as deltas or cumulative values.
+    pub delta_temporality: bool,
+    /// Requested metrics prefix string match. Empty array: No metrics subscribed, Array\[0\] empty string: All metrics subscribed.
+    pub requested_metrics: Vec<String>,
+}
+
+impl FromValue for GetTelemetrySubscriptionsResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(GetTelemetrySubscriptionsResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            client_instance_id: take_field(&mut fields, "ClientInstanceId")?,
+            subscription_id: take_field(&mut fields, "SubscriptionId")?,
+            accepted_compression_types: take_field(&mut fields, "AcceptedCompressionTypes")?,
+            push_interval_ms: take_field(&mut fields, "PushIntervalMs")?,
+            telemetry_max_bytes: take_field(&mut fields, "TelemetryMaxBytes")?,
+            delta_temporality: take_field(&mut fields, "DeltaTemporality")?,
+            requested_metrics: take_field(&mut fields, "RequestedMetrics")?,
+        })
+    }
+}
+
+impl ToValue for GetTelemetrySubscriptionsResponse {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ClientInstanceId".to_owned(), self.client_instance_id.to_value()),
+            ("SubscriptionId".to_owned(), self.subscription_id.to_value()),
+            ("AcceptedCompressionTypes".to_owned(), self.accepted_compression_types.to_value()),
+            ("PushIntervalMs".to_owned(), self.push_interval_ms.to_value()),
+            ("TelemetryMaxBytes".to_owned(), self.telemetry_max_bytes.to_value()),
+            ("DeltaTemporality".to_owned(), self.delta_temporality.to_value()),
+            ("RequestedMetrics".to_owned(), self.requested_metrics.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl GetTelemetrySubscriptionsResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 71;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("GetTelemetrySubscriptionsResponse", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("ClientInstanceId", Type::Uuid),
+            Field::new("SubscriptionId", Type::Int32),
+            Field::new("AcceptedCompressionTypes", Type::Array(Box::new(Type::Int8))),
+            Field::new("PushIntervalMs", Type::Int32),
+            Field::new("TelemetryMaxBytes", Type::Int32),
+            Field::new("DeltaTemporality", Type::Bool),
+            Field::new("RequestedMetrics", Type::Array(Box::new(Type::String))),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for GetTelemetrySubscriptionsResponse {
+    fn schema() -> &'static Schema {
+        GetTelemetrySubscriptionsResponse::schema()
+    }
+}
+
+impl GetTelemetrySubscriptionsResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> GetTelemetrySubscriptionsResponseBuilder {
+        GetTelemetrySubscriptionsResponseBuilder::default()
+    }
+}
+
+/// Builds GetTelemetrySubscriptionsResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct GetTelemetrySubscriptionsResponseBuilder {
+    message: GetTelemetrySubscriptionsResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl GetTelemetrySubscriptionsResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `error_code`
+    pub fn error_code(mut self, value: i16) -> Self {
+        self.message.error_code = value;
+        self.set.push("ErrorCode");
+        self
+    }
+
+    /// Sets `client_instance_id`
+    pub fn client_instance_id(mut self, value: [u8; 16]) -> Self {
+        self.message.client_instance_id = value;
+        self.set.push("ClientInstanceId");
+        self
+    }
+
+    /// Sets `subscription_id`
+    pub fn subscription_id(mut self, value: i32) -> Self {
+        self.message.subscription_id = value;
+        self.set.push("SubscriptionId");
+        self
+    }
+
+    /// Sets `accepted_compression_types`
+    pub fn accepted_compression_types(mut self, value: impl IntoIterator<Item = impl Into<i8>>) -> Self {
+        self.message.accepted_compression_types = value.into_iter().map(Into::into).collect();
+        self.set.push("AcceptedCompressionTypes");
+        self
+    }
+
+    /// Sets `push_interval_ms`
+    pub fn push_interval_ms(mut self, value: i32) -> Self {
+        self.message.push_interval_ms = value;
+        self.set.push("PushIntervalMs");
+        self
+    }
+
+    /// Sets `telemetry_max_bytes`
+    pub fn telemetry_max_bytes(mut self, value: i32) -> Self {
+        self.message.telemetry_max_bytes = value;
+        self.set.push("TelemetryMaxBytes");
+        self
+    }
+
+    /// Sets `delta_temporality`
+    pub fn delta_temporality(mut self, value: bool) -> Self {
+        self.message.delta_temporality = value;
+        self.set.push("DeltaTemporality");
+        self
+    }
+
+    /// Sets `requested_metrics`
+    pub fn requested_metrics(mut self, value: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.message.requested_metrics = value.into_iter().map(Into::into).collect();
+        self.set.push("RequestedMetrics");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<GetTelemetrySubscriptionsResponse> {
+        GetTelemetrySubscriptionsResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
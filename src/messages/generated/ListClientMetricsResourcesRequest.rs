@@ -0,0 +1,91 @@
+// Generated by kafka_serde::codegen from the definition of ListClientMetricsResourcesRequest. Do not edit.
+use kafka_serde::schema::{
+    Field, FromValue, KafkaValue, Schema, ToValue,
+};
+
+/// The ListClientMetricsResourcesRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ListClientMetricsResourcesRequest {
+}
+
+impl FromValue for ListClientMetricsResourcesRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        value.into_fields()?;
+        Ok(ListClientMetricsResourcesRequest {})
+    }
+}
+
+impl ToValue for ListClientMetricsResourcesRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ListClientMetricsResourcesRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 74;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ListClientMetricsResourcesRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ListClientMetricsResourcesRequest {
+    fn schema() -> &'static Schema {
+        ListClientMetricsResourcesRequest::schema()
+    }
+}
+
+impl ListClientMetricsResourcesRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ListClientMetricsResourcesRequestBuilder {
+        ListClientMetricsResourcesRequestBuilder::default()
+    }
+}
+
+/// Builds ListClientMetricsResourcesRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct ListClientMetricsResourcesRequestBuilder {
+    message: ListClientMetricsResourcesRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ListClientMetricsResourcesRequestBuilder {
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ListClientMetricsResourcesRequest> {
+        ListClientMetricsResourcesRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for ListClientMetricsResourcesRequest {
+    const API_KEY: i16 = 74;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::ListClientMetricsResourcesResponse;
+}
@@ -0,0 +1,153 @@
+// Generated by kafka_serde::codegen from the definition of ListClientMetricsResourcesResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The ListClientMetricsResourcesResponse message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ListClientMetricsResourcesResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// The error code, or 0 if there was no error.
+    pub error_code: i16,
+    /// Each client metrics resource in the response.
+    pub client_metrics_resources: Vec<ClientMetricsResource>,
+}
+
+impl FromValue for ListClientMetricsResourcesResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ListClientMetricsResourcesResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            client_metrics_resources: take_field(&mut fields, "ClientMetricsResources")?,
+        })
+    }
+}
+
+impl ToValue for ListClientMetricsResourcesResponse {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ClientMetricsResources".to_owned(), self.client_metrics_resources.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ListClientMetricsResourcesResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 74;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ListClientMetricsResourcesResponse", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("ClientMetricsResources", Type::Array(Box::new(Type::Struct(ClientMetricsResource::fields())))),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ListClientMetricsResourcesResponse {
+    fn schema() -> &'static Schema {
+        ListClientMetricsResourcesResponse::schema()
+    }
+}
+
+impl ListClientMetricsResourcesResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ListClientMetricsResourcesResponseBuilder {
+        ListClientMetricsResourcesResponseBuilder::default()
+    }
+}
+
+/// Builds ListClientMetricsResourcesResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct ListClientMetricsResourcesResponseBuilder {
+    message: ListClientMetricsResourcesResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ListClientMetricsResourcesResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `error_code`
+    pub fn error_code(mut self, value: i16) -> Self {
+        self.message.error_code = value;
+        self.set.push("ErrorCode");
+        self
+    }
+
+    /// Sets `client_metrics_resources`
+    pub fn client_metrics_resources(mut self, value: impl IntoIterator<Item = impl Into<ClientMetricsResource>>) -> Self {
+        self.message.client_metrics_resources = value.into_iter().map(Into::into).collect();
+        self.set.push("ClientMetricsResources");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ListClientMetricsResourcesResponse> {
+        ListClientMetricsResourcesResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+/// A ClientMetricsResource of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ClientMetricsResource {
+    /// The resource name.
+    pub name: String,
+}
+
+impl FromValue for ClientMetricsResource {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ClientMetricsResource {
+            name: take_field(&mut fields, "Name")?,
+        })
+    }
+}
+
+impl ToValue for ClientMetricsResource {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("Name".to_owned(), self.name.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ClientMetricsResource {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("Name", Type::String),
+        ]
+    }
+}
@@ -0,0 +1,152 @@
+// Generated by kafka_serde::codegen from the definition of PushTelemetryRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The PushTelemetryRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PushTelemetryRequest {
+    /// Unique id for this client instance.
+    pub client_instance_id: [u8; 16],
+    /// Unique identifier for the current subscription.
+    pub subscription_id: i32,
+    /// Client is terminating the connection.
+    pub terminating: bool,
+    /// Compression codec used to compress the metrics.
+    pub compression_type: i8,
+    /// Metrics encoded in OpenTelemetry MetricsData v1 protobuf format.
+    pub metrics: Vec<u8>,
+}
+
+impl FromValue for PushTelemetryRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(PushTelemetryRequest {
+            client_instance_id: take_field(&mut fields, "ClientInstanceId")?,
+            subscription_id: take_field(&mut fields, "SubscriptionId")?,
+            terminating: take_field(&mut fields, "Terminating")?,
+            compression_type: take_field(&mut fields, "CompressionType")?,
+            metrics: take_field(&mut fields, "Metrics")?,
+        })
+    }
+}
+
+impl ToValue for PushTelemetryRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ClientInstanceId".to_owned(), self.client_instance_id.to_value()),
+            ("SubscriptionId".to_owned(), self.subscription_id.to_value()),
+            ("Terminating".to_owned(), self.terminating.to_value()),
+            ("CompressionType".to_owned(), self.compression_type.to_value()),
+            ("Metrics".to_owned(), self.metrics.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl PushTelemetryRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 72;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("PushTelemetryRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ClientInstanceId", Type::Uuid),
+            Field::new("SubscriptionId", Type::Int32),
+            Field::new("Terminating", Type::Bool),
+            Field::new("CompressionType", Type::Int8),
+            Field::new("Metrics", Type::Bytes),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for PushTelemetryRequest {
+    fn schema() -> &'static Schema {
+        PushTelemetryRequest::schema()
+    }
+}
+
+impl PushTelemetryRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> PushTelemetryRequestBuilder {
+        PushTelemetryRequestBuilder::default()
+    }
+}
+
+/// Builds PushTelemetryRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct PushTelemetryRequestBuilder {
+    message: PushTelemetryRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl PushTelemetryRequestBuilder {
+    /// Sets `client_instance_id`
+    pub fn client_instance_id(mut self, value: [u8; 16]) -> Self {
+        self.message.client_instance_id = value;
+        self.set.push("ClientInstanceId");
+        self
+    }
+
+    /// Sets `subscription_id`
+    pub fn subscription_id(mut self, value: i32) -> Self {
+        self.message.subscription_id = value;
+        self.set.push("SubscriptionId");
+        self
+    }
+
+    /// Sets `terminating`
+    pub fn terminating(mut self, value: bool) -> Self {
+        self.message.terminating = value;
+        self.set.push("Terminating");
+        self
+    }
+
+    /// Sets `compression_type`
+    pub fn compression_type(mut self, value: i8) -> Self {
+        self.message.compression_type = value;
+        self.set.push("CompressionType");
+        self
+    }
+
+    /// Sets `metrics`
+    pub fn metrics(mut self, value: impl Into<Vec<u8>>) -> Self {
+        self.message.metrics = value.into();
+        self.set.push("Metrics");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<PushTelemetryRequest> {
+        PushTelemetryRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for PushTelemetryRequest {
+    const API_KEY: i16 = 72;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::PushTelemetryResponse;
+}
@@ -0,0 +1,128 @@
+// Generated by kafka_serde::codegen from the definition of RemoveRaftVoterRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The RemoveRaftVoterRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RemoveRaftVoterRequest {
+    /// The cluster id of the request.
+    pub cluster_id: Option<String>,
+    /// The replica id of the voter getting removed from the topic partition.
+    pub voter_id: i32,
+    /// The directory id of the voter getting removed from the topic partition.
+    pub voter_directory_id: [u8; 16],
+}
+
+impl FromValue for RemoveRaftVoterRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(RemoveRaftVoterRequest {
+            cluster_id: take_field(&mut fields, "ClusterId")?,
+            voter_id: take_field(&mut fields, "VoterId")?,
+            voter_directory_id: take_field(&mut fields, "VoterDirectoryId")?,
+        })
+    }
+}
+
+impl ToValue for RemoveRaftVoterRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ClusterId".to_owned(), self.cluster_id.to_value()),
+            ("VoterId".to_owned(), self.voter_id.to_value()),
+            ("VoterDirectoryId".to_owned(), self.voter_directory_id.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl RemoveRaftVoterRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 81;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("RemoveRaftVoterRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ClusterId", Type::String).nullable_versions(0..),
+            Field::new("VoterId", Type::Int32),
+            Field::new("VoterDirectoryId", Type::Uuid),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for RemoveRaftVoterRequest {
+    fn schema() -> &'static Schema {
+        RemoveRaftVoterRequest::schema()
+    }
+}
+
+impl RemoveRaftVoterRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> RemoveRaftVoterRequestBuilder {
+        RemoveRaftVoterRequestBuilder::default()
+    }
+}
+
+/// Builds RemoveRaftVoterRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct RemoveRaftVoterRequestBuilder {
+    message: RemoveRaftVoterRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl RemoveRaftVoterRequestBuilder {
+    /// Sets `cluster_id`
+    pub fn cluster_id(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.cluster_id = value.into();
+        self.set.push("ClusterId");
+        self
+    }
+
+    /// Sets `voter_id`
+    pub fn voter_id(mut self, value: i32) -> Self {
+        self.message.voter_id = value;
+        self.set.push("VoterId");
+        self
+    }
+
+    /// Sets `voter_directory_id`
+    pub fn voter_directory_id(mut self, value: [u8; 16]) -> Self {
+        self.message.voter_directory_id = value;
+        self.set.push("VoterDirectoryId");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<RemoveRaftVoterRequest> {
+        RemoveRaftVoterRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for RemoveRaftVoterRequest {
+    const API_KEY: i16 = 81;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::RemoveRaftVoterResponse;
+}
@@ -0,0 +1,259 @@
+// Generated by kafka_serde::codegen from the definition of ShareAcknowledgeRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The ShareAcknowledgeRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShareAcknowledgeRequest {
+    /// The group identifier.
+    pub group_id: Option<String>,
+    /// The member ID.
+    pub member_id: Option<String>,
+    /// The current share session epoch: 0 to open a share session; -1 to close it; otherwise increments for consecutive requests.
+    pub share_session_epoch: i32,
+    /// The topics containing records to acknowledge.
+    pub topics: Vec<AcknowledgeTopic>,
+}
+
+impl FromValue for ShareAcknowledgeRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ShareAcknowledgeRequest {
+            group_id: take_field(&mut fields, "GroupId")?,
+            member_id: take_field(&mut fields, "MemberId")?,
+            share_session_epoch: take_field(&mut fields, "ShareSessionEpoch")?,
+            topics: take_field(&mut fields, "Topics")?,
+        })
+    }
+}
+
+impl ToValue for ShareAcknowledgeRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("GroupId".to_owned(), self.group_id.to_value()),
+            ("MemberId".to_owned(), self.member_id.to_value()),
+            ("ShareSessionEpoch".to_owned(), self.share_session_epoch.to_value()),
+            ("Topics".to_owned(), self.topics.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ShareAcknowledgeRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 79;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ShareAcknowledgeRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("GroupId", Type::String).nullable_versions(0..),
+            Field::new("MemberId", Type::String).nullable_versions(0..),
+            Field::new("ShareSessionEpoch", Type::Int32),
+            Field::new("Topics", Type::Array(Box::new(Type::Struct(AcknowledgeTopic::fields())))),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ShareAcknowledgeRequest {
+    fn schema() -> &'static Schema {
+        ShareAcknowledgeRequest::schema()
+    }
+}
+
+impl ShareAcknowledgeRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ShareAcknowledgeRequestBuilder {
+        ShareAcknowledgeRequestBuilder::default()
+    }
+}
+
+/// Builds ShareAcknowledgeRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct ShareAcknowledgeRequestBuilder {
+    message: ShareAcknowledgeRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ShareAcknowledgeRequestBuilder {
+    /// Sets `group_id`
+    pub fn group_id(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.group_id = value.into();
+        self.set.push("GroupId");
+        self
+    }
+
+    /// Sets `member_id`
+    pub fn member_id(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.member_id = value.into();
+        self.set.push("MemberId");
+        self
+    }
+
+    /// Sets `share_session_epoch`
+    pub fn share_session_epoch(mut self, value: i32) -> Self {
+        self.message.share_session_epoch = value;
+        self.set.push("ShareSessionEpoch");
+        self
+    }
+
+    /// Sets `topics`
+    pub fn topics(mut self, value: impl IntoIterator<Item = impl Into<AcknowledgeTopic>>) -> Self {
+        self.message.topics = value.into_iter().map(Into::into).collect();
+        self.set.push("Topics");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ShareAcknowledgeRequest> {
+        ShareAcknowledgeRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for ShareAcknowledgeRequest {
+    const API_KEY: i16 = 79;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::ShareAcknowledgeResponse;
+}
+
+/// A AcknowledgementBatch of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AcknowledgementBatch {
+    /// First offset of batch of records to acknowledge.
+    pub first_offset: i64,
+    /// Last offset (inclusive) of batch of records to acknowledge.
+    pub last_offset: i64,
+    /// Array of acknowledge types - 0:Gap,1:Accept,2:Release,3:Reject.
+    pub acknowledge_types: Vec<i8>,
+}
+
+impl FromValue for AcknowledgementBatch {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(AcknowledgementBatch {
+            first_offset: take_field(&mut fields, "FirstOffset")?,
+            last_offset: take_field(&mut fields, "LastOffset")?,
+            acknowledge_types: take_field(&mut fields, "AcknowledgeTypes")?,
+        })
+    }
+}
+
+impl ToValue for AcknowledgementBatch {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("FirstOffset".to_owned(), self.first_offset.to_value()),
+            ("LastOffset".to_owned(), self.last_offset.to_value()),
+            ("AcknowledgeTypes".to_owned(), self.acknowledge_types.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl AcknowledgementBatch {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("FirstOffset", Type::Int64),
+            Field::new("LastOffset", Type::Int64),
+            Field::new("AcknowledgeTypes", Type::Array(Box::new(Type::Int8))),
+        ]
+    }
+}
+
+/// A AcknowledgePartition of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AcknowledgePartition {
+    /// The partition index.
+    pub partition_index: i32,
+    /// Record batches to acknowledge.
+    pub acknowledgement_batches: Vec<AcknowledgementBatch>,
+}
+
+impl FromValue for AcknowledgePartition {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(AcknowledgePartition {
+            partition_index: take_field(&mut fields, "PartitionIndex")?,
+            acknowledgement_batches: take_field(&mut fields, "AcknowledgementBatches")?,
+        })
+    }
+}
+
+impl ToValue for AcknowledgePartition {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("PartitionIndex".to_owned(), self.partition_index.to_value()),
+            ("AcknowledgementBatches".to_owned(), self.acknowledgement_batches.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl AcknowledgePartition {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("PartitionIndex", Type::Int32),
+            Field::new("AcknowledgementBatches", Type::Array(Box::new(Type::Struct(AcknowledgementBatch::fields())))),
+        ]
+    }
+}
+
+/// A AcknowledgeTopic of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AcknowledgeTopic {
+    /// The unique topic ID.
+    pub topic_id: [u8; 16],
+    /// The partitions containing records to acknowledge.
+    pub partitions: Vec<AcknowledgePartition>,
+}
+
+impl FromValue for AcknowledgeTopic {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(AcknowledgeTopic {
+            topic_id: take_field(&mut fields, "TopicId")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+        })
+    }
+}
+
+impl ToValue for AcknowledgeTopic {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicId".to_owned(), self.topic_id.to_value()),
+            ("Partitions".to_owned(), self.partitions.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl AcknowledgeTopic {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicId", Type::Uuid),
+            Field::new("Partitions", Type::Array(Box::new(Type::Struct(AcknowledgePartition::fields())))),
+        ]
+    }
+}
@@ -0,0 +1,316 @@
+// Generated by kafka_serde::codegen from the definition of ShareAcknowledgeResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The ShareAcknowledgeResponse message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShareAcknowledgeResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// The top level response error code.
+    pub error_code: i16,
+    /// The top-level error message, or null if there was no error.
+    pub error_message: Option<String>,
+    /// The response topics.
+    pub responses: Vec<ShareAcknowledgeTopicResponse>,
+    /// Endpoints for all current leaders enumerated in PartitionData with error NOT_LEADER_OR_FOLLOWER.
+    pub node_endpoints: Vec<NodeEndpoint>,
+}
+
+impl FromValue for ShareAcknowledgeResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ShareAcknowledgeResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            error_message: take_field(&mut fields, "ErrorMessage")?,
+            responses: take_field(&mut fields, "Responses")?,
+            node_endpoints: take_field(&mut fields, "NodeEndpoints")?,
+        })
+    }
+}
+
+impl ToValue for ShareAcknowledgeResponse {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ErrorMessage".to_owned(), self.error_message.to_value()),
+            ("Responses".to_owned(), self.responses.to_value()),
+            ("NodeEndpoints".to_owned(), self.node_endpoints.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ShareAcknowledgeResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 79;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ShareAcknowledgeResponse", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("ErrorMessage", Type::String).nullable_versions(0..),
+            Field::new("Responses", Type::Array(Box::new(Type::Struct(ShareAcknowledgeTopicResponse::fields())))),
+            Field::new("NodeEndpoints", Type::Array(Box::new(Type::Struct(NodeEndpoint::fields())))),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ShareAcknowledgeResponse {
+    fn schema() -> &'static Schema {
+        ShareAcknowledgeResponse::schema()
+    }
+}
+
+impl ShareAcknowledgeResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ShareAcknowledgeResponseBuilder {
+        ShareAcknowledgeResponseBuilder::default()
+    }
+}
+
+/// Builds ShareAcknowledgeResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct ShareAcknowledgeResponseBuilder {
+    message: ShareAcknowledgeResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ShareAcknowledgeResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `error_code`
+    pub fn error_code(mut self, value: i16) -> Self {
+        self.message.error_code = value;
+        self.set.push("ErrorCode");
+        self
+    }
+
+    /// Sets `error_message`
+    pub fn error_message(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.error_message = value.into();
+        self.set.push("ErrorMessage");
+        self
+    }
+
+    /// Sets `responses`
+    pub fn responses(mut self, value: impl IntoIterator<Item = impl Into<ShareAcknowledgeTopicResponse>>) -> Self {
+        self.message.responses = value.into_iter().map(Into::into).collect();
+        self.set.push("Responses");
+        self
+    }
+
+    /// Sets `node_endpoints`
+    pub fn node_endpoints(mut self, value: impl IntoIterator<Item = impl Into<NodeEndpoint>>) -> Self {
+        self.message.node_endpoints = value.into_iter().map(Into::into).collect();
+        self.set.push("NodeEndpoints");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ShareAcknowledgeResponse> {
+        ShareAcknowledgeResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+/// A LeaderIdAndEpoch of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LeaderIdAndEpoch {
+    /// The ID of the current leader or -1 if the leader is unknown.
+    pub leader_id: i32,
+    /// The latest known leader epoch.
+    pub leader_epoch: i32,
+}
+
+impl FromValue for LeaderIdAndEpoch {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(LeaderIdAndEpoch {
+            leader_id: take_field(&mut fields, "LeaderId")?,
+            leader_epoch: take_field(&mut fields, "LeaderEpoch")?,
+        })
+    }
+}
+
+impl ToValue for LeaderIdAndEpoch {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("LeaderId".to_owned(), self.leader_id.to_value()),
+            ("LeaderEpoch".to_owned(), self.leader_epoch.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl LeaderIdAndEpoch {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("LeaderId", Type::Int32),
+            Field::new("LeaderEpoch", Type::Int32),
+        ]
+    }
+}
+
+/// A PartitionData of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PartitionData {
+    /// The partition index.
+    pub partition_index: i32,
+    /// The error code, or 0 if there was no error.
+    pub error_code: i16,
+    /// The error message, or null if there was no error.
+    pub error_message: Option<String>,
+    /// The current leader of the partition.
+    pub current_leader: LeaderIdAndEpoch,
+}
+
+impl FromValue for PartitionData {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(PartitionData {
+            partition_index: take_field(&mut fields, "PartitionIndex")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            error_message: take_field(&mut fields, "ErrorMessage")?,
+            current_leader: take_field(&mut fields, "CurrentLeader")?,
+        })
+    }
+}
+
+impl ToValue for PartitionData {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("PartitionIndex".to_owned(), self.partition_index.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ErrorMessage".to_owned(), self.error_message.to_value()),
+            ("CurrentLeader".to_owned(), self.current_leader.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl PartitionData {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("PartitionIndex", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("ErrorMessage", Type::String).nullable_versions(0..),
+            Field::new("CurrentLeader", Type::Struct(LeaderIdAndEpoch::fields())),
+        ]
+    }
+}
+
+/// A ShareAcknowledgeTopicResponse of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShareAcknowledgeTopicResponse {
+    /// The unique topic ID.
+    pub topic_id: [u8; 16],
+    /// The topic partitions.
+    pub partitions: Vec<PartitionData>,
+}
+
+impl FromValue for ShareAcknowledgeTopicResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ShareAcknowledgeTopicResponse {
+            topic_id: take_field(&mut fields, "TopicId")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+        })
+    }
+}
+
+impl ToValue for ShareAcknowledgeTopicResponse {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicId".to_owned(), self.topic_id.to_value()),
+            ("Partitions".to_owned(), self.partitions.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ShareAcknowledgeTopicResponse {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicId", Type::Uuid),
+            Field::new("Partitions", Type::Array(Box::new(Type::Struct(PartitionData::fields())))),
+        ]
+    }
+}
+
+/// A NodeEndpoint of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NodeEndpoint {
+    /// The ID of the associated node.
+    pub node_id: i32,
+    /// The node's hostname.
+    pub host: String,
+    /// The node's port.
+    pub port: i32,
+    /// The rack of the node, or null if it has not been assigned to a rack.
+    pub rack: Option<String>,
+}
+
+impl FromValue for NodeEndpoint {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(NodeEndpoint {
+            node_id: take_field(&mut fields, "NodeId")?,
+            host: take_field(&mut fields, "Host")?,
+            port: take_field(&mut fields, "Port")?,
+            rack: take_field(&mut fields, "Rack")?,
+        })
+    }
+}
+
+impl ToValue for NodeEndpoint {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("NodeId".to_owned(), self.node_id.to_value()),
+            ("Host".to_owned(), self.host.to_value()),
+            ("Port".to_owned(), self.port.to_value()),
+            ("Rack".to_owned(), self.rack.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl NodeEndpoint {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("NodeId", Type::Int32),
+            Field::new("Host", Type::String),
+            Field::new("Port", Type::Int32),
+            Field::new("Rack", Type::String).nullable_versions(0..),
+        ]
+    }
+}
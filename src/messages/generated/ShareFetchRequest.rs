@@ -0,0 +1,365 @@
+// Generated by kafka_serde::codegen from the definition of ShareFetchRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, take_field_or, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The ShareFetchRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShareFetchRequest {
+    /// The group identifier.
+    pub group_id: Option<String>,
+    /// The member ID.
+    pub member_id: Option<String>,
+    /// The current share session epoch: 0 to open a share session; -1 to close it; otherwise increments for consecutive requests.
+    pub share_session_epoch: i32,
+    /// The maximum time in milliseconds to wait for the response.
+    pub max_wait_ms: i32,
+    /// The minimum bytes to accumulate in the response.
+    pub min_bytes: i32,
+    /// The maximum bytes to fetch. See KIP-74 for cases where this limit may not be honored.
+    pub max_bytes: i32,
+    /// The topics to fetch.
+    pub topics: Vec<FetchTopic>,
+    /// The partitions to remove from this share session.
+    pub forgotten_topics_data: Vec<ForgottenTopic>,
+}
+
+impl Default for ShareFetchRequest {
+    fn default() -> Self {
+        ShareFetchRequest {
+            group_id: Default::default(),
+            member_id: Default::default(),
+            share_session_epoch: Default::default(),
+            max_wait_ms: Default::default(),
+            min_bytes: Default::default(),
+            max_bytes: 2147483647,
+            topics: Default::default(),
+            forgotten_topics_data: Default::default(),
+        }
+    }
+}
+
+impl FromValue for ShareFetchRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ShareFetchRequest {
+            group_id: take_field(&mut fields, "GroupId")?,
+            member_id: take_field(&mut fields, "MemberId")?,
+            share_session_epoch: take_field(&mut fields, "ShareSessionEpoch")?,
+            max_wait_ms: take_field(&mut fields, "MaxWaitMs")?,
+            min_bytes: take_field(&mut fields, "MinBytes")?,
+            max_bytes: take_field_or(&mut fields, "MaxBytes", 2147483647)?,
+            topics: take_field(&mut fields, "Topics")?,
+            forgotten_topics_data: take_field(&mut fields, "ForgottenTopicsData")?,
+        })
+    }
+}
+
+impl ToValue for ShareFetchRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("GroupId".to_owned(), self.group_id.to_value()),
+            ("MemberId".to_owned(), self.member_id.to_value()),
+            ("ShareSessionEpoch".to_owned(), self.share_session_epoch.to_value()),
+            ("MaxWaitMs".to_owned(), self.max_wait_ms.to_value()),
+            ("MinBytes".to_owned(), self.min_bytes.to_value()),
+            ("MaxBytes".to_owned(), self.max_bytes.to_value()),
+            ("Topics".to_owned(), self.topics.to_value()),
+            ("ForgottenTopicsData".to_owned(), self.forgotten_topics_data.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ShareFetchRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 78;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ShareFetchRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("GroupId", Type::String).nullable_versions(0..),
+            Field::new("MemberId", Type::String).nullable_versions(0..),
+            Field::new("ShareSessionEpoch", Type::Int32),
+            Field::new("MaxWaitMs", Type::Int32),
+            Field::new("MinBytes", Type::Int32),
+            Field::new("MaxBytes", Type::Int32),
+            Field::new("Topics", Type::Array(Box::new(Type::Struct(FetchTopic::fields())))),
+            Field::new("ForgottenTopicsData", Type::Array(Box::new(Type::Struct(ForgottenTopic::fields())))),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ShareFetchRequest {
+    fn schema() -> &'static Schema {
+        ShareFetchRequest::schema()
+    }
+}
+
+impl ShareFetchRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ShareFetchRequestBuilder {
+        ShareFetchRequestBuilder::default()
+    }
+}
+
+/// Builds ShareFetchRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct ShareFetchRequestBuilder {
+    message: ShareFetchRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ShareFetchRequestBuilder {
+    /// Sets `group_id`
+    pub fn group_id(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.group_id = value.into();
+        self.set.push("GroupId");
+        self
+    }
+
+    /// Sets `member_id`
+    pub fn member_id(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.member_id = value.into();
+        self.set.push("MemberId");
+        self
+    }
+
+    /// Sets `share_session_epoch`
+    pub fn share_session_epoch(mut self, value: i32) -> Self {
+        self.message.share_session_epoch = value;
+        self.set.push("ShareSessionEpoch");
+        self
+    }
+
+    /// Sets `max_wait_ms`
+    pub fn max_wait_ms(mut self, value: i32) -> Self {
+        self.message.max_wait_ms = value;
+        self.set.push("MaxWaitMs");
+        self
+    }
+
+    /// Sets `min_bytes`
+    pub fn min_bytes(mut self, value: i32) -> Self {
+        self.message.min_bytes = value;
+        self.set.push("MinBytes");
+        self
+    }
+
+    /// Sets `max_bytes`
+    pub fn max_bytes(mut self, value: i32) -> Self {
+        self.message.max_bytes = value;
+        self.set.push("MaxBytes");
+        self
+    }
+
+    /// Sets `topics`
+    pub fn topics(mut self, value: impl IntoIterator<Item = impl Into<FetchTopic>>) -> Self {
+        self.message.topics = value.into_iter().map(Into::into).collect();
+        self.set.push("Topics");
+        self
+    }
+
+    /// Sets `forgotten_topics_data`
+    pub fn forgotten_topics_data(mut self, value: impl IntoIterator<Item = impl Into<ForgottenTopic>>) -> Self {
+        self.message.forgotten_topics_data = value.into_iter().map(Into::into).collect();
+        self.set.push("ForgottenTopicsData");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ShareFetchRequest> {
+        ShareFetchRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for ShareFetchRequest {
+    const API_KEY: i16 = 78;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::ShareFetchResponse;
+}
+
+/// A AcknowledgementBatch of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AcknowledgementBatch {
+    /// First offset of batch of records to acknowledge.
+    pub first_offset: i64,
+    /// Last offset (inclusive) of batch of records to acknowledge.
+    pub last_offset: i64,
+    /// Array of acknowledge types - 0:Gap,1:Accept,2:Release,3:Reject.
+    pub acknowledge_types: Vec<i8>,
+}
+
+impl FromValue for AcknowledgementBatch {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(AcknowledgementBatch {
+            first_offset: take_field(&mut fields, "FirstOffset")?,
+            last_offset: take_field(&mut fields, "LastOffset")?,
+            acknowledge_types: take_field(&mut fields, "AcknowledgeTypes")?,
+        })
+    }
+}
+
+impl ToValue for AcknowledgementBatch {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("FirstOffset".to_owned(), self.first_offset.to_value()),
+            ("LastOffset".to_owned(), self.last_offset.to_value()),
+            ("AcknowledgeTypes".to_owned(), self.acknowledge_types.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl AcknowledgementBatch {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("FirstOffset", Type::Int64),
+            Field::new("LastOffset", Type::Int64),
+            Field::new("AcknowledgeTypes", Type::Array(Box::new(Type::Int8))),
+        ]
+    }
+}
+
+/// A FetchPartition of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FetchPartition {
+    /// The partition index.
+    pub partition_index: i32,
+    /// The maximum bytes to fetch from this partition. 0 when only acknowledgement with no fetching is required. See KIP-74 for cases where this limit may not be honored.
+    pub partition_max_bytes: i32,
+    /// Record batches to acknowledge.
+    pub acknowledgement_batches: Vec<AcknowledgementBatch>,
+}
+
+impl FromValue for FetchPartition {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(FetchPartition {
+            partition_index: take_field(&mut fields, "PartitionIndex")?,
+            partition_max_bytes: take_field(&mut fields, "PartitionMaxBytes")?,
+            acknowledgement_batches: take_field(&mut fields, "AcknowledgementBatches")?,
+        })
+    }
+}
+
+impl ToValue for FetchPartition {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("PartitionIndex".to_owned(), self.partition_index.to_value()),
+            ("PartitionMaxBytes".to_owned(), self.partition_max_bytes.to_value()),
+            ("AcknowledgementBatches".to_owned(), self.acknowledgement_batches.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl FetchPartition {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("PartitionIndex", Type::Int32),
+            Field::new("PartitionMaxBytes", Type::Int32),
+            Field::new("AcknowledgementBatches", Type::Array(Box::new(Type::Struct(AcknowledgementBatch::fields())))),
+        ]
+    }
+}
+
+/// A FetchTopic of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FetchTopic {
+    /// The unique topic ID.
+    pub topic_id: [u8; 16],
+    /// The partitions to fetch.
+    pub partitions: Vec<FetchPartition>,
+}
+
+impl FromValue for FetchTopic {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(FetchTopic {
+            topic_id: take_field(&mut fields, "TopicId")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+        })
+    }
+}
+
+impl ToValue for FetchTopic {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicId".to_owned(), self.topic_id.to_value()),
+            ("Partitions".to_owned(), self.partitions.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl FetchTopic {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicId", Type::Uuid),
+            Field::new("Partitions", Type::Array(Box::new(Type::Struct(FetchPartition::fields())))),
+        ]
+    }
+}
+
+/// A ForgottenTopic of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ForgottenTopic {
+    /// The unique topic ID.
+    pub topic_id: [u8; 16],
+    /// The partitions indexes to forget.
+    pub partitions: Vec<i32>,
+}
+
+impl FromValue for ForgottenTopic {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ForgottenTopic {
+            topic_id: take_field(&mut fields, "TopicId")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+        })
+    }
+}
+
+impl ToValue for ForgottenTopic {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicId".to_owned(), self.topic_id.to_value()),
+            ("Partitions".to_owned(), self.partitions.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ForgottenTopic {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicId", Type::Uuid),
+            Field::new("Partitions", Type::Array(Box::new(Type::Int32))),
+        ]
+    }
+}
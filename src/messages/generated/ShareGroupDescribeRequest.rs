@@ -0,0 +1,116 @@
+// Generated by kafka_serde::codegen from the definition of ShareGroupDescribeRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The ShareGroupDescribeRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShareGroupDescribeRequest {
+    /// The ids of the groups to describe.
+    pub group_ids: Vec<String>,
+    /// Whether to include authorized operations.
+    pub include_authorized_operations: bool,
+}
+
+impl FromValue for ShareGroupDescribeRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ShareGroupDescribeRequest {
+            group_ids: take_field(&mut fields, "GroupIds")?,
+            include_authorized_operations: take_field(&mut fields, "IncludeAuthorizedOperations")?,
+        })
+    }
+}
+
+impl ToValue for ShareGroupDescribeRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("GroupIds".to_owned(), self.group_ids.to_value()),
+            ("IncludeAuthorizedOperations".to_owned(), self.include_authorized_operations.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ShareGroupDescribeRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 77;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ShareGroupDescribeRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("GroupIds", Type::Array(Box::new(Type::String))),
+            Field::new("IncludeAuthorizedOperations", Type::Bool),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ShareGroupDescribeRequest {
+    fn schema() -> &'static Schema {
+        ShareGroupDescribeRequest::schema()
+    }
+}
+
+impl ShareGroupDescribeRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ShareGroupDescribeRequestBuilder {
+        ShareGroupDescribeRequestBuilder::default()
+    }
+}
+
+/// Builds ShareGroupDescribeRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct ShareGroupDescribeRequestBuilder {
+    message: ShareGroupDescribeRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ShareGroupDescribeRequestBuilder {
+    /// Sets `group_ids`
+    pub fn group_ids(mut self, value: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.message.group_ids = value.into_iter().map(Into::into).collect();
+        self.set.push("GroupIds");
+        self
+    }
+
+    /// Sets `include_authorized_operations`
+    pub fn include_authorized_operations(mut self, value: bool) -> Self {
+        self.message.include_authorized_operations = value;
+        self.set.push("IncludeAuthorizedOperations");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ShareGroupDescribeRequest> {
+        ShareGroupDescribeRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for ShareGroupDescribeRequest {
+    const API_KEY: i16 = 77;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::ShareGroupDescribeResponse;
+}
@@ -0,0 +1,336 @@
+// Generated by kafka_serde::codegen from the definition of ShareGroupDescribeResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, take_field_or, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The ShareGroupDescribeResponse message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShareGroupDescribeResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// Each described group.
+    pub groups: Vec<DescribedGroup>,
+}
+
+impl FromValue for ShareGroupDescribeResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ShareGroupDescribeResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            groups: take_field(&mut fields, "Groups")?,
+        })
+    }
+}
+
+impl ToValue for ShareGroupDescribeResponse {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("Groups".to_owned(), self.groups.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ShareGroupDescribeResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 77;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ShareGroupDescribeResponse", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32),
+            Field::new("Groups", Type::Array(Box::new(Type::Struct(DescribedGroup::fields())))),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ShareGroupDescribeResponse {
+    fn schema() -> &'static Schema {
+        ShareGroupDescribeResponse::schema()
+    }
+}
+
+impl ShareGroupDescribeResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ShareGroupDescribeResponseBuilder {
+        ShareGroupDescribeResponseBuilder::default()
+    }
+}
+
+/// Builds ShareGroupDescribeResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct ShareGroupDescribeResponseBuilder {
+    message: ShareGroupDescribeResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ShareGroupDescribeResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `groups`
+    pub fn groups(mut self, value: impl IntoIterator<Item = impl Into<DescribedGroup>>) -> Self {
+        self.message.groups = value.into_iter().map(Into::into).collect();
+        self.set.push("Groups");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ShareGroupDescribeResponse> {
+        ShareGroupDescribeResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+/// A TopicPartitions of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TopicPartitions {
+    /// The topic ID.
+    pub topic_id: [u8; 16],
+    /// The topic name.
+    pub topic_name: String,
+    /// The partitions.
+    pub partitions: Vec<i32>,
+}
+
+impl FromValue for TopicPartitions {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(TopicPartitions {
+            topic_id: take_field(&mut fields, "TopicId")?,
+            topic_name: take_field(&mut fields, "TopicName")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+        })
+    }
+}
+
+impl ToValue for TopicPartitions {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicId".to_owned(), self.topic_id.to_value()),
+            ("TopicName".to_owned(), self.topic_name.to_value()),
+            ("Partitions".to_owned(), self.partitions.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl TopicPartitions {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicId", Type::Uuid),
+            Field::new("TopicName", Type::String),
+            Field::new("Partitions", Type::Array(Box::new(Type::Int32))),
+        ]
+    }
+}
+
+/// A Assignment of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Assignment {
+    /// The assigned topic-partitions to the member.
+    pub topic_partitions: Vec<TopicPartitions>,
+}
+
+impl FromValue for Assignment {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(Assignment {
+            topic_partitions: take_field(&mut fields, "TopicPartitions")?,
+        })
+    }
+}
+
+impl ToValue for Assignment {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicPartitions".to_owned(), self.topic_partitions.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl Assignment {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicPartitions", Type::Array(Box::new(Type::Struct(TopicPartitions::fields())))),
+        ]
+    }
+}
+
+/// A Member of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Member {
+    /// The member ID.
+    pub member_id: String,
+    /// The member rack ID.
+    pub rack_id: Option<String>,
+    /// The current member epoch.
+    pub member_epoch: i32,
+    /// The client ID.
+    pub client_id: String,
+    /// The client host.
+    pub client_host: String,
+    /// The subscribed topic names.
+    pub subscribed_topic_names: Vec<String>,
+    /// The current assignment.
+    pub assignment: Assignment,
+}
+
+impl FromValue for Member {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(Member {
+            member_id: take_field(&mut fields, "MemberId")?,
+            rack_id: take_field(&mut fields, "RackId")?,
+            member_epoch: take_field(&mut fields, "MemberEpoch")?,
+            client_id: take_field(&mut fields, "ClientId")?,
+            client_host: take_field(&mut fields, "ClientHost")?,
+            subscribed_topic_names: take_field(&mut fields, "SubscribedTopicNames")?,
+            assignment: take_field(&mut fields, "Assignment")?,
+        })
+    }
+}
+
+impl ToValue for Member {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("MemberId".to_owned(), self.member_id.to_value()),
+            ("RackId".to_owned(), self.rack_id.to_value()),
+            ("MemberEpoch".to_owned(), self.member_epoch.to_value()),
+            ("ClientId".to_owned(), self.client_id.to_value()),
+            ("ClientHost".to_owned(), self.client_host.to_value()),
+            ("SubscribedTopicNames".to_owned(), self.subscribed_topic_names.to_value()),
+            ("Assignment".to_owned(), self.assignment.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl Member {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("MemberId", Type::String),
+            Field::new("RackId", Type::String).nullable_versions(0..),
+            Field::new("MemberEpoch", Type::Int32),
+            Field::new("ClientId", Type::String),
+            Field::new("ClientHost", Type::String),
+            Field::new("SubscribedTopicNames", Type::Array(Box::new(Type::String))),
+            Field::new("Assignment", Type::Struct(Assignment::fields())),
+        ]
+    }
+}
+
+/// A DescribedGroup of the message
+#[derive(Debug, Clone, PartialEq)]
+pub struct DescribedGroup {
+    /// The describe error, or 0 if there was no error.
+    pub error_code: i16,
+    /// The top-level error message, or null if there was no error.
+    pub error_message: Option<String>,
+    /// The group ID string.
+    pub group_id: String,
+    /// The group state string, or the empty string.
+    pub group_state: String,
+    /// The group epoch.
+    pub group_epoch: i32,
+    /// The assignment epoch.
+    pub assignment_epoch: i32,
+    /// The selected assignor.
+    pub assignor_name: String,
+    /// The members.
+    pub members: Vec<Member>,
+    /// 32-bit bitfield to represent authorized operations for this group.
+    pub authorized_operations: i32,
+}
+
+impl Default for DescribedGroup {
+    fn default() -> Self {
+        DescribedGroup {
+            error_code: Default::default(),
+            error_message: Default::default(),
+            group_id: Default::default(),
+            group_state: Default::default(),
+            group_epoch: Default::default(),
+            assignment_epoch: Default::default(),
+            assignor_name: Default::default(),
+            members: Default::default(),
+            authorized_operations: -2147483648,
+        }
+    }
+}
+
+impl FromValue for DescribedGroup {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(DescribedGroup {
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            error_message: take_field(&mut fields, "ErrorMessage")?,
+            group_id: take_field(&mut fields, "GroupId")?,
+            group_state: take_field(&mut fields, "GroupState")?,
+            group_epoch: take_field(&mut fields, "GroupEpoch")?,
+            assignment_epoch: take_field(&mut fields, "AssignmentEpoch")?,
+            assignor_name: take_field(&mut fields, "AssignorName")?,
+            members: take_field(&mut fields, "Members")?,
+            authorized_operations: take_field_or(&mut fields, "AuthorizedOperations", -2147483648)?,
+        })
+    }
+}
+
+impl ToValue for DescribedGroup {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ErrorMessage".to_owned(), self.error_message.to_value()),
+            ("GroupId".to_owned(), self.group_id.to_value()),
+            ("GroupState".to_owned(), self.group_state.to_value()),
+            ("GroupEpoch".to_owned(), self.group_epoch.to_value()),
+            ("AssignmentEpoch".to_owned(), self.assignment_epoch.to_value()),
+            ("AssignorName".to_owned(), self.assignor_name.to_value()),
+            ("Members".to_owned(), self.members.to_value()),
+            ("AuthorizedOperations".to_owned(), self.authorized_operations.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl DescribedGroup {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("ErrorMessage", Type::String).nullable_versions(0..),
+            Field::new("GroupId", Type::String),
+            Field::new("GroupState", Type::String),
+            Field::new("GroupEpoch", Type::Int32),
+            Field::new("AssignmentEpoch", Type::Int32),
+            Field::new("AssignorName", Type::String),
+            Field::new("Members", Type::Array(Box::new(Type::Struct(Member::fields())))),
+            Field::new("AuthorizedOperations", Type::Int32),
+        ]
+    }
+}
@@ -0,0 +1,152 @@
+// Generated by kafka_serde::codegen from the definition of ShareGroupHeartbeatRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The ShareGroupHeartbeatRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShareGroupHeartbeatRequest {
+    /// The group identifier.
+    pub group_id: String,
+    /// The member id generated by the consumer. The member id must be kept during the entire lifetime of the consumer process.
+    pub member_id: String,
+    /// The current member epoch; 0 to join the group; -1 to leave the group.
+    pub member_epoch: i32,
+    /// null if not provided or if it didn't change since the last heartbeat; the rack ID of consumer otherwise.
+    pub rack_id: Option<String>,
+    /// null if it didn't change since the last heartbeat; the subscribed topic names otherwise.
+    pub subscribed_topic_names: Option<Vec<String>>,
+}
+
+impl FromValue for ShareGroupHeartbeatRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ShareGroupHeartbeatRequest {
+            group_id: take_field(&mut fields, "GroupId")?,
+            member_id: take_field(&mut fields, "MemberId")?,
+            member_epoch: take_field(&mut fields, "MemberEpoch")?,
+            rack_id: take_field(&mut fields, "RackId")?,
+            subscribed_topic_names: take_field(&mut fields, "SubscribedTopicNames")?,
+        })
+    }
+}
+
+impl ToValue for ShareGroupHeartbeatRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("GroupId".to_owned(), self.group_id.to_value()),
+            ("MemberId".to_owned(), self.member_id.to_value()),
+            ("MemberEpoch".to_owned(), self.member_epoch.to_value()),
+            ("RackId".to_owned(), self.rack_id.to_value()),
+            ("SubscribedTopicNames".to_owned(), self.subscribed_topic_names.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ShareGroupHeartbeatRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 76;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ShareGroupHeartbeatRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("GroupId", Type::String),
+            Field::new("MemberId", Type::String),
+            Field::new("MemberEpoch", Type::Int32),
+            Field::new("RackId", Type::String).nullable_versions(0..),
+            Field::new("SubscribedTopicNames", Type::Array(Box::new(Type::String))).nullable_versions(0..),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ShareGroupHeartbeatRequest {
+    fn schema() -> &'static Schema {
+        ShareGroupHeartbeatRequest::schema()
+    }
+}
+
+impl ShareGroupHeartbeatRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ShareGroupHeartbeatRequestBuilder {
+        ShareGroupHeartbeatRequestBuilder::default()
+    }
+}
+
+/// Builds ShareGroupHeartbeatRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct ShareGroupHeartbeatRequestBuilder {
+    message: ShareGroupHeartbeatRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ShareGroupHeartbeatRequestBuilder {
+    /// Sets `group_id`
+    pub fn group_id(mut self, value: impl Into<String>) -> Self {
+        self.message.group_id = value.into();
+        self.set.push("GroupId");
+        self
+    }
+
+    /// Sets `member_id`
+    pub fn member_id(mut self, value: impl Into<String>) -> Self {
+        self.message.member_id = value.into();
+        self.set.push("MemberId");
+        self
+    }
+
+    /// Sets `member_epoch`
+    pub fn member_epoch(mut self, value: i32) -> Self {
+        self.message.member_epoch = value;
+        self.set.push("MemberEpoch");
+        self
+    }
+
+    /// Sets `rack_id`
+    pub fn rack_id(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.rack_id = value.into();
+        self.set.push("RackId");
+        self
+    }
+
+    /// Sets `subscribed_topic_names`
+    pub fn subscribed_topic_names(mut self, value: impl Into<Option<Vec<String>>>) -> Self {
+        self.message.subscribed_topic_names = value.into();
+        self.set.push("SubscribedTopicNames");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ShareGroupHeartbeatRequest> {
+        ShareGroupHeartbeatRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for ShareGroupHeartbeatRequest {
+    const API_KEY: i16 = 76;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::ShareGroupHeartbeatResponse;
+}
@@ -0,0 +1,239 @@
+// Generated by kafka_serde::codegen from the definition of ShareGroupHeartbeatResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The ShareGroupHeartbeatResponse message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShareGroupHeartbeatResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// The top-level error code, or 0 if there was no error.
+    pub error_code: i16,
+    /// The top-level error message, or null if there was no error.
+    pub error_message: Option<String>,
+    /// The member ID generated by the coordinator. Only provided when the member joins with MemberEpoch == 0.
+    pub member_id: Option<String>,
+    /// The member epoch.
+    pub member_epoch: i32,
+    /// The heartbeat interval in milliseconds.
+    pub heartbeat_interval_ms: i32,
+    /// null if not provided; the assignment otherwise.
+    pub assignment: Option<Assignment>,
+}
+
+impl FromValue for ShareGroupHeartbeatResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(ShareGroupHeartbeatResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            error_message: take_field(&mut fields, "ErrorMessage")?,
+            member_id: take_field(&mut fields, "MemberId")?,
+            member_epoch: take_field(&mut fields, "MemberEpoch")?,
+            heartbeat_interval_ms: take_field(&mut fields, "HeartbeatIntervalMs")?,
+            assignment: take_field(&mut fields, "Assignment")?,
+        })
+    }
+}
+
+impl ToValue for ShareGroupHeartbeatResponse {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+            ("ErrorMessage".to_owned(), self.error_message.to_value()),
+            ("MemberId".to_owned(), self.member_id.to_value()),
+            ("MemberEpoch".to_owned(), self.member_epoch.to_value()),
+            ("HeartbeatIntervalMs".to_owned(), self.heartbeat_interval_ms.to_value()),
+            ("Assignment".to_owned(), self.assignment.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl ShareGroupHeartbeatResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 76;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("ShareGroupHeartbeatResponse", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("ErrorMessage", Type::String).nullable_versions(0..),
+            Field::new("MemberId", Type::String).nullable_versions(0..),
+            Field::new("MemberEpoch", Type::Int32),
+            Field::new("HeartbeatIntervalMs", Type::Int32),
+            Field::new("Assignment", Type::Struct(Assignment::fields())).nullable_versions(0..),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for ShareGroupHeartbeatResponse {
+    fn schema() -> &'static Schema {
+        ShareGroupHeartbeatResponse::schema()
+    }
+}
+
+impl ShareGroupHeartbeatResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> ShareGroupHeartbeatResponseBuilder {
+        ShareGroupHeartbeatResponseBuilder::default()
+    }
+}
+
+/// Builds ShareGroupHeartbeatResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct ShareGroupHeartbeatResponseBuilder {
+    message: ShareGroupHeartbeatResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl ShareGroupHeartbeatResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `error_code`
+    pub fn error_code(mut self, value: i16) -> Self {
+        self.message.error_code = value;
+        self.set.push("ErrorCode");
+        self
+    }
+
+    /// Sets `error_message`
+    pub fn error_message(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.error_message = value.into();
+        self.set.push("ErrorMessage");
+        self
+    }
+
+    /// Sets `member_id`
+    pub fn member_id(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.member_id = value.into();
+        self.set.push("MemberId");
+        self
+    }
+
+    /// Sets `member_epoch`
+    pub fn member_epoch(mut self, value: i32) -> Self {
+        self.message.member_epoch = value;
+        self.set.push("MemberEpoch");
+        self
+    }
+
+    /// Sets `heartbeat_interval_ms`
+    pub fn heartbeat_interval_ms(mut self, value: i32) -> Self {
+        self.message.heartbeat_interval_ms = value;
+        self.set.push("HeartbeatIntervalMs");
+        self
+    }
+
+    /// Sets `assignment`
+    pub fn assignment(mut self, value: impl Into<Option<Assignment>>) -> Self {
+        self.message.assignment = value.into();
+        self.set.push("Assignment");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<ShareGroupHeartbeatResponse> {
+        ShareGroupHeartbeatResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+/// A TopicPartitions of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TopicPartitions {
+    /// The topic ID.
+    pub topic_id: [u8; 16],
+    /// The partitions.
+    pub partitions: Vec<i32>,
+}
+
+impl FromValue for TopicPartitions {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(TopicPartitions {
+            topic_id: take_field(&mut fields, "TopicId")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+        })
+    }
+}
+
+impl ToValue for TopicPartitions {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicId".to_owned(), self.topic_id.to_value()),
+            ("Partitions".to_owned(), self.partitions.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl TopicPartitions {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicId", Type::Uuid),
+            Field::new("Partitions", Type::Array(Box::new(Type::Int32))),
+        ]
+    }
+}
+
+/// A Assignment of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Assignment {
+    /// The partitions assigned to the member.
+    pub topic_partitions: Vec<TopicPartitions>,
+}
+
+impl FromValue for Assignment {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(Assignment {
+            topic_partitions: take_field(&mut fields, "TopicPartitions")?,
+        })
+    }
+}
+
+impl ToValue for Assignment {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("TopicPartitions".to_owned(), self.topic_partitions.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl Assignment {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("TopicPartitions", Type::Array(Box::new(Type::Struct(TopicPartitions::fields())))),
+        ]
+    }
+}
@@ -0,0 +1,245 @@
+// Generated by kafka_serde::codegen from the definition of UpdateRaftVoterRequest. Do not edit.
+use kafka_serde::schema::{
+    take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The UpdateRaftVoterRequest message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UpdateRaftVoterRequest {
+    /// The cluster id.
+    pub cluster_id: Option<String>,
+    /// The current leader epoch of the partition, -1 for unknown leader epoch.
+    pub current_leader_epoch: i32,
+    /// The replica id of the voter getting updated in the topic partition.
+    pub voter_id: i32,
+    /// The directory id of the voter getting updated in the topic partition.
+    pub voter_directory_id: [u8; 16],
+    /// The endpoint that can be used to communicate with the leader.
+    pub listeners: Vec<Listener>,
+    /// The range of versions of the protocol that the replica supports.
+    pub k_raft_version_feature: KRaftVersionFeature,
+}
+
+impl FromValue for UpdateRaftVoterRequest {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(UpdateRaftVoterRequest {
+            cluster_id: take_field(&mut fields, "ClusterId")?,
+            current_leader_epoch: take_field(&mut fields, "CurrentLeaderEpoch")?,
+            voter_id: take_field(&mut fields, "VoterId")?,
+            voter_directory_id: take_field(&mut fields, "VoterDirectoryId")?,
+            listeners: take_field(&mut fields, "Listeners")?,
+            k_raft_version_feature: take_field(&mut fields, "KRaftVersionFeature")?,
+        })
+    }
+}
+
+impl ToValue for UpdateRaftVoterRequest {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("ClusterId".to_owned(), self.cluster_id.to_value()),
+            ("CurrentLeaderEpoch".to_owned(), self.current_leader_epoch.to_value()),
+            ("VoterId".to_owned(), self.voter_id.to_value()),
+            ("VoterDirectoryId".to_owned(), self.voter_directory_id.to_value()),
+            ("Listeners".to_owned(), self.listeners.to_value()),
+            ("KRaftVersionFeature".to_owned(), self.k_raft_version_feature.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl UpdateRaftVoterRequest {
+    /// The API key of the message
+    pub const API_KEY: i16 = 82;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("UpdateRaftVoterRequest", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ClusterId", Type::String).nullable_versions(0..),
+            Field::new("CurrentLeaderEpoch", Type::Int32),
+            Field::new("VoterId", Type::Int32),
+            Field::new("VoterDirectoryId", Type::Uuid),
+            Field::new("Listeners", Type::Array(Box::new(Type::Struct(Listener::fields())))),
+            Field::new("KRaftVersionFeature", Type::Struct(KRaftVersionFeature::fields())),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for UpdateRaftVoterRequest {
+    fn schema() -> &'static Schema {
+        UpdateRaftVoterRequest::schema()
+    }
+}
+
+impl UpdateRaftVoterRequest {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> UpdateRaftVoterRequestBuilder {
+        UpdateRaftVoterRequestBuilder::default()
+    }
+}
+
+/// Builds UpdateRaftVoterRequest messages
+#[derive(Debug, Clone, Default)]
+pub struct UpdateRaftVoterRequestBuilder {
+    message: UpdateRaftVoterRequest,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl UpdateRaftVoterRequestBuilder {
+    /// Sets `cluster_id`
+    pub fn cluster_id(mut self, value: impl Into<Option<String>>) -> Self {
+        self.message.cluster_id = value.into();
+        self.set.push("ClusterId");
+        self
+    }
+
+    /// Sets `current_leader_epoch`
+    pub fn current_leader_epoch(mut self, value: i32) -> Self {
+        self.message.current_leader_epoch = value;
+        self.set.push("CurrentLeaderEpoch");
+        self
+    }
+
+    /// Sets `voter_id`
+    pub fn voter_id(mut self, value: i32) -> Self {
+        self.message.voter_id = value;
+        self.set.push("VoterId");
+        self
+    }
+
+    /// Sets `voter_directory_id`
+    pub fn voter_directory_id(mut self, value: [u8; 16]) -> Self {
+        self.message.voter_directory_id = value;
+        self.set.push("VoterDirectoryId");
+        self
+    }
+
+    /// Sets `listeners`
+    pub fn listeners(mut self, value: impl IntoIterator<Item = impl Into<Listener>>) -> Self {
+        self.message.listeners = value.into_iter().map(Into::into).collect();
+        self.set.push("Listeners");
+        self
+    }
+
+    /// Sets `k_raft_version_feature`
+    pub fn k_raft_version_feature(mut self, value: impl Into<KRaftVersionFeature>) -> Self {
+        self.message.k_raft_version_feature = value.into();
+        self.set.push("KRaftVersionFeature");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<UpdateRaftVoterRequest> {
+        UpdateRaftVoterRequest::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+impl kafka_serde::KafkaRequest for UpdateRaftVoterRequest {
+    const API_KEY: i16 = 82;
+    const MIN_VERSION: i16 = 0;
+    const MAX_VERSION: i16 = 0;
+    const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(0);
+    type Response = super::UpdateRaftVoterResponse;
+}
+
+/// A Listener of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Listener {
+    /// The name of the endpoint.
+    pub name: String,
+    /// The hostname.
+    pub host: String,
+    /// The port.
+    pub port: u16,
+}
+
+impl FromValue for Listener {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(Listener {
+            name: take_field(&mut fields, "Name")?,
+            host: take_field(&mut fields, "Host")?,
+            port: take_field(&mut fields, "Port")?,
+        })
+    }
+}
+
+impl ToValue for Listener {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("Name".to_owned(), self.name.to_value()),
+            ("Host".to_owned(), self.host.to_value()),
+            ("Port".to_owned(), self.port.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl Listener {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("Name", Type::String),
+            Field::new("Host", Type::String),
+            Field::new("Port", Type::Uint16),
+        ]
+    }
+}
+
+/// A KRaftVersionFeature of the message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct KRaftVersionFeature {
+    /// The minimum supported KRaft protocol version.
+    pub min_supported_version: i16,
+    /// The maximum supported KRaft protocol version.
+    pub max_supported_version: i16,
+}
+
+impl FromValue for KRaftVersionFeature {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(KRaftVersionFeature {
+            min_supported_version: take_field(&mut fields, "MinSupportedVersion")?,
+            max_supported_version: take_field(&mut fields, "MaxSupportedVersion")?,
+        })
+    }
+}
+
+impl ToValue for KRaftVersionFeature {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("MinSupportedVersion".to_owned(), self.min_supported_version.to_value()),
+            ("MaxSupportedVersion".to_owned(), self.max_supported_version.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl KRaftVersionFeature {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("MinSupportedVersion", Type::Int16),
+            Field::new("MaxSupportedVersion", Type::Int16),
+        ]
+    }
+}
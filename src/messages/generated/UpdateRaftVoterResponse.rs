@@ -0,0 +1,181 @@
+// Generated by kafka_serde::codegen from the definition of UpdateRaftVoterResponse. Do not edit.
+use kafka_serde::schema::{
+    take_field, take_field_or, Field, FromValue, KafkaValue, Schema, ToValue, Type,
+};
+
+/// The UpdateRaftVoterResponse message, in versions 0 to 0
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UpdateRaftVoterResponse {
+    /// The duration in milliseconds for which the request was throttled due to a quota violation, or zero if the request did not violate any quota.
+    pub throttle_time_ms: i32,
+    /// The error code, or 0 if there was no error.
+    pub error_code: i16,
+    /// Details of the current Raft cluster leader.
+    pub current_leader: CurrentLeader,
+}
+
+impl FromValue for UpdateRaftVoterResponse {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(UpdateRaftVoterResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            current_leader: take_field(&mut fields, "CurrentLeader")?,
+        })
+    }
+}
+
+impl ToValue for UpdateRaftVoterResponse {
+    fn to_value(&self) -> KafkaValue {
+        let mut fields = vec![
+            ("ThrottleTimeMs".to_owned(), self.throttle_time_ms.to_value()),
+            ("ErrorCode".to_owned(), self.error_code.to_value()),
+        ];
+        if self.current_leader != Default::default() {
+            fields.push(("CurrentLeader".to_owned(), self.current_leader.to_value()));
+        }
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl UpdateRaftVoterResponse {
+    /// The API key of the message
+    pub const API_KEY: i16 = 82;
+
+    /// The schema of the message
+    pub fn schema() -> &'static Schema {
+        static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| {
+            Schema::new("UpdateRaftVoterResponse", Self::fields())
+                .valid_versions(0..=0)
+                .flexible_versions(0..)
+        })
+    }
+
+    /// Decodes the message in `version` from `buf`
+    pub fn decode(version: i16, buf: &[u8]) -> kafka_serde::Result<Self> {
+        Self::from_value(Self::schema().decode(version, buf)?)
+    }
+
+    /// Encodes the message in `version` at the end of `buf`
+    pub fn encode(&self, version: i16, buf: &mut Vec<u8>) -> kafka_serde::Result<()> {
+        Self::schema().encode(version, &self.to_value(), buf)
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("ThrottleTimeMs", Type::Int32),
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("CurrentLeader", Type::Struct(CurrentLeader::fields())).tag(0),
+        ]
+    }
+}
+
+impl kafka_serde::schema::Message for UpdateRaftVoterResponse {
+    fn schema() -> &'static Schema {
+        UpdateRaftVoterResponse::schema()
+    }
+}
+
+impl UpdateRaftVoterResponse {
+    /// Starts building a message, whose fields hold their default value until they are set
+    pub fn builder() -> UpdateRaftVoterResponseBuilder {
+        UpdateRaftVoterResponseBuilder::default()
+    }
+}
+
+/// Builds UpdateRaftVoterResponse messages
+#[derive(Debug, Clone, Default)]
+pub struct UpdateRaftVoterResponseBuilder {
+    message: UpdateRaftVoterResponse,
+    // the fields that were set, which must be in the version built
+    set: Vec<&'static str>,
+}
+
+impl UpdateRaftVoterResponseBuilder {
+    /// Sets `throttle_time_ms`
+    pub fn throttle_time_ms(mut self, value: i32) -> Self {
+        self.message.throttle_time_ms = value;
+        self.set.push("ThrottleTimeMs");
+        self
+    }
+
+    /// Sets `error_code`
+    pub fn error_code(mut self, value: i16) -> Self {
+        self.message.error_code = value;
+        self.set.push("ErrorCode");
+        self
+    }
+
+    /// Sets `current_leader`
+    pub fn current_leader(mut self, value: impl Into<CurrentLeader>) -> Self {
+        self.message.current_leader = value.into();
+        self.set.push("CurrentLeader");
+        self
+    }
+
+    /// Builds the message, failing unless the fields that were set are in `version`
+    pub fn build(self, version: i16) -> kafka_serde::Result<UpdateRaftVoterResponse> {
+        UpdateRaftVoterResponse::schema().check_fields(version, &self.set)?;
+        Ok(self.message)
+    }
+}
+
+/// A CurrentLeader of the message
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentLeader {
+    /// The replica id of the current leader or -1 if the leader is unknown.
+    pub leader_id: i32,
+    /// The latest known leader epoch.
+    pub leader_epoch: i32,
+    /// The node's hostname.
+    pub host: String,
+    /// The node's port.
+    pub port: i32,
+}
+
+impl Default for CurrentLeader {
+    fn default() -> Self {
+        CurrentLeader {
+            leader_id: -1,
+            leader_epoch: -1,
+            host: Default::default(),
+            port: Default::default(),
+        }
+    }
+}
+
+impl FromValue for CurrentLeader {
+    fn from_value(value: KafkaValue) -> kafka_serde::Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(CurrentLeader {
+            leader_id: take_field_or(&mut fields, "LeaderId", -1)?,
+            leader_epoch: take_field_or(&mut fields, "LeaderEpoch", -1)?,
+            host: take_field(&mut fields, "Host")?,
+            port: take_field(&mut fields, "Port")?,
+        })
+    }
+}
+
+impl ToValue for CurrentLeader {
+    fn to_value(&self) -> KafkaValue {
+        let fields = vec![
+            ("LeaderId".to_owned(), self.leader_id.to_value()),
+            ("LeaderEpoch".to_owned(), self.leader_epoch.to_value()),
+            ("Host".to_owned(), self.host.to_value()),
+            ("Port".to_owned(), self.port.to_value()),
+        ];
+        KafkaValue::Struct(fields)
+    }
+}
+
+impl CurrentLeader {
+    fn fields() -> Vec<Field> {
+        vec![
+            Field::new("LeaderId", Type::Int32),
+            Field::new("LeaderEpoch", Type::Int32),
+            Field::new("Host", Type::String),
+            Field::new("Port", Type::Int32),
+        ]
+    }
+}
@@ -0,0 +1,484 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Messages of recent APIs
+//!
+//! The messages of APIs added to Apache Kafka lately, which clients can't get from older
+//! copies of its message definitions. They are [generated](crate::codegen) from the definitions
+//! in `src/messages/definitions`, copied from Apache Kafka, into `src/messages/generated`, and
+//! have the same `decode` and `encode` methods and builders as the messages generated by users.
+//!
+//! Each message is in a module of its own, along with the structures nested in it. Messages
+//! are re-exported here, and requests implement [`KafkaRequest`](crate::KafkaRequest).
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::messages::DescribeTopicPartitionsRequest;
+//!
+//! let request = DescribeTopicPartitionsRequest::builder()
+//!     .response_partition_limit(100)
+//!     .build(0)
+//!     .unwrap();
+//! let mut body = Vec::new();
+//! request.encode(0, &mut body).unwrap();
+//! assert_eq!(DescribeTopicPartitionsRequest::decode(0, &body).unwrap(), request);
+//! ```
+
+/// DescribeTopicPartitionsRequest (KIP-966), describing the partitions of topics a page at a
+/// time, without the cost of Metadata requests
+pub mod describe_topic_partitions_request {
+    include!("generated/DescribeTopicPartitionsRequest.rs");
+}
+/// DescribeTopicPartitionsResponse (KIP-966)
+pub mod describe_topic_partitions_response {
+    include!("generated/DescribeTopicPartitionsResponse.rs");
+}
+
+/// GetTelemetrySubscriptionsRequest (KIP-714), asking which metrics the broker wants the client
+/// to push
+pub mod get_telemetry_subscriptions_request {
+    include!("generated/GetTelemetrySubscriptionsRequest.rs");
+}
+/// GetTelemetrySubscriptionsResponse (KIP-714)
+pub mod get_telemetry_subscriptions_response {
+    include!("generated/GetTelemetrySubscriptionsResponse.rs");
+}
+/// PushTelemetryRequest (KIP-714), pushing the metrics of a client, encoded as OpenTelemetry
+/// `MetricsData`
+pub mod push_telemetry_request {
+    include!("generated/PushTelemetryRequest.rs");
+}
+/// PushTelemetryResponse (KIP-714)
+pub mod push_telemetry_response {
+    include!("generated/PushTelemetryResponse.rs");
+}
+/// ListClientMetricsResourcesRequest (KIP-714), listing the client metrics subscriptions of the
+/// cluster
+pub mod list_client_metrics_resources_request {
+    include!("generated/ListClientMetricsResourcesRequest.rs");
+}
+/// ListClientMetricsResourcesResponse (KIP-714)
+pub mod list_client_metrics_resources_response {
+    include!("generated/ListClientMetricsResourcesResponse.rs");
+}
+/// ShareGroupHeartbeatRequest (KIP-932), joining a share group and keeping its membership
+pub mod share_group_heartbeat_request {
+    include!("generated/ShareGroupHeartbeatRequest.rs");
+}
+/// ShareGroupHeartbeatResponse (KIP-932)
+pub mod share_group_heartbeat_response {
+    include!("generated/ShareGroupHeartbeatResponse.rs");
+}
+/// ShareGroupDescribeRequest (KIP-932), describing share groups and their members
+pub mod share_group_describe_request {
+    include!("generated/ShareGroupDescribeRequest.rs");
+}
+/// ShareGroupDescribeResponse (KIP-932)
+pub mod share_group_describe_response {
+    include!("generated/ShareGroupDescribeResponse.rs");
+}
+/// ShareFetchRequest (KIP-932), acquiring records for a member of a share group, and
+/// acknowledging the ones it delivered
+pub mod share_fetch_request {
+    include!("generated/ShareFetchRequest.rs");
+}
+/// ShareFetchResponse (KIP-932)
+pub mod share_fetch_response {
+    include!("generated/ShareFetchResponse.rs");
+}
+/// ShareAcknowledgeRequest (KIP-932), acknowledging records without fetching more
+pub mod share_acknowledge_request {
+    include!("generated/ShareAcknowledgeRequest.rs");
+}
+/// ShareAcknowledgeResponse (KIP-932)
+pub mod share_acknowledge_response {
+    include!("generated/ShareAcknowledgeResponse.rs");
+}
+/// AddRaftVoterRequest (KIP-853), adding a controller to the voters of the cluster metadata
+/// partition
+pub mod add_raft_voter_request {
+    include!("generated/AddRaftVoterRequest.rs");
+}
+/// AddRaftVoterResponse (KIP-853)
+pub mod add_raft_voter_response {
+    include!("generated/AddRaftVoterResponse.rs");
+}
+/// RemoveRaftVoterRequest (KIP-853), removing a controller from the voters
+pub mod remove_raft_voter_request {
+    include!("generated/RemoveRaftVoterRequest.rs");
+}
+/// RemoveRaftVoterResponse (KIP-853)
+pub mod remove_raft_voter_response {
+    include!("generated/RemoveRaftVoterResponse.rs");
+}
+/// UpdateRaftVoterRequest (KIP-853), which voters send the leader to update their endpoints and
+/// supported `kraft.version` range
+pub mod update_raft_voter_request {
+    include!("generated/UpdateRaftVoterRequest.rs");
+}
+/// UpdateRaftVoterResponse (KIP-853)
+pub mod update_raft_voter_response {
+    include!("generated/UpdateRaftVoterResponse.rs");
+}
+
+pub use self::add_raft_voter_request::AddRaftVoterRequest;
+pub use self::add_raft_voter_response::AddRaftVoterResponse;
+pub use self::describe_topic_partitions_request::DescribeTopicPartitionsRequest;
+pub use self::describe_topic_partitions_response::DescribeTopicPartitionsResponse;
+pub use self::get_telemetry_subscriptions_request::GetTelemetrySubscriptionsRequest;
+pub use self::get_telemetry_subscriptions_response::GetTelemetrySubscriptionsResponse;
+pub use self::list_client_metrics_resources_request::ListClientMetricsResourcesRequest;
+pub use self::list_client_metrics_resources_response::ListClientMetricsResourcesResponse;
+pub use self::push_telemetry_request::PushTelemetryRequest;
+pub use self::push_telemetry_response::PushTelemetryResponse;
+pub use self::remove_raft_voter_request::RemoveRaftVoterRequest;
+pub use self::remove_raft_voter_response::RemoveRaftVoterResponse;
+pub use self::share_acknowledge_request::ShareAcknowledgeRequest;
+pub use self::share_acknowledge_response::ShareAcknowledgeResponse;
+pub use self::share_fetch_request::ShareFetchRequest;
+pub use self::share_fetch_response::ShareFetchResponse;
+pub use self::share_group_describe_request::ShareGroupDescribeRequest;
+pub use self::share_group_describe_response::ShareGroupDescribeResponse;
+pub use self::share_group_heartbeat_request::ShareGroupHeartbeatRequest;
+pub use self::share_group_heartbeat_response::ShareGroupHeartbeatResponse;
+pub use self::update_raft_voter_request::UpdateRaftVoterRequest;
+pub use self::update_raft_voter_response::UpdateRaftVoterResponse;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::KafkaRequest;
+
+    /// The definitions of the messages generated here
+    #[cfg(feature = "codegen")]
+    const MESSAGES: &[&str] = &[
+        "AddRaftVoterRequest",
+        "AddRaftVoterResponse",
+        "DescribeTopicPartitionsRequest",
+        "DescribeTopicPartitionsResponse",
+        "GetTelemetrySubscriptionsRequest",
+        "GetTelemetrySubscriptionsResponse",
+        "ListClientMetricsResourcesRequest",
+        "ListClientMetricsResourcesResponse",
+        "PushTelemetryRequest",
+        "PushTelemetryResponse",
+        "RemoveRaftVoterRequest",
+        "RemoveRaftVoterResponse",
+        "ShareAcknowledgeRequest",
+        "ShareAcknowledgeResponse",
+        "ShareFetchRequest",
+        "ShareFetchResponse",
+        "ShareGroupDescribeRequest",
+        "ShareGroupDescribeResponse",
+        "ShareGroupHeartbeatRequest",
+        "ShareGroupHeartbeatResponse",
+        "UpdateRaftVoterRequest",
+        "UpdateRaftVoterResponse",
+    ];
+
+    #[cfg(feature = "codegen")]
+    #[test]
+    fn test_generated() {
+        use crate::codegen;
+
+        // regenerate with `KAFKA_SERDE_BLESS=1 cargo test --features codegen`
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/messages");
+        for name in MESSAGES {
+            let definition = format!("{}/definitions/{}.json", dir, name);
+            let generated = format!("{}/generated/{}.rs", dir, name);
+            if std::env::var_os("KAFKA_SERDE_BLESS").is_some() {
+                codegen::generate_file(&definition, &generated).unwrap();
+            }
+            let expected = codegen::generate(&std::fs::read_to_string(&definition).unwrap());
+            let actual = std::fs::read_to_string(&generated).unwrap();
+            assert!(expected.unwrap() == actual, "{} is out of date", generated);
+        }
+    }
+
+    #[test]
+    fn test_describe_topic_partitions() {
+        use describe_topic_partitions_request::{Cursor, TopicRequest};
+        use describe_topic_partitions_response::{
+            DescribeTopicPartitionsResponsePartition, DescribeTopicPartitionsResponseTopic,
+        };
+
+        assert_eq!(DescribeTopicPartitionsRequest::API_KEY, 75);
+        assert!(DescribeTopicPartitionsRequest::is_flexible(0));
+        let request = DescribeTopicPartitionsRequest {
+            topics: vec![TopicRequest { name: "t".into() }],
+            cursor: Some(Cursor {
+                topic_name: "t".into(),
+                partition_index: 2,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(request.response_partition_limit, 2000);
+        let mut buf = Vec::new();
+        request.encode(0, &mut buf).unwrap();
+        // the topics, the partition limit, then the cursor and its presence marker
+        assert_eq!(
+            buf,
+            [2, 2, b't', 0, 0, 0, 7, 0xd0, 1, 2, b't', 0, 0, 0, 2, 0, 0]
+        );
+        assert_eq!(
+            DescribeTopicPartitionsRequest::decode(0, &buf).unwrap(),
+            request
+        );
+
+        let response = DescribeTopicPartitionsResponse {
+            topics: vec![DescribeTopicPartitionsResponseTopic {
+                name: Some("t".into()),
+                topic_id: [7; 16],
+                partitions: vec![DescribeTopicPartitionsResponsePartition {
+                    leader_id: 1,
+                    replica_nodes: vec![1, 2],
+                    isr_nodes: vec![1],
+                    eligible_leader_replicas: Some(vec![2]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(response.topics[0].topic_authorized_operations, i32::MIN);
+        assert_eq!(response.topics[0].partitions[0].leader_epoch, -1);
+        buf.clear();
+        response.encode(0, &mut buf).unwrap();
+        let decoded = DescribeTopicPartitionsResponse::decode(0, &buf).unwrap();
+        assert_eq!(decoded, response);
+        assert_eq!(decoded.next_cursor, None);
+        assert!(DescribeTopicPartitionsResponse::decode(1, &buf).is_err());
+    }
+
+    #[test]
+    fn test_telemetry() {
+        use list_client_metrics_resources_response::ClientMetricsResource;
+
+        let request = GetTelemetrySubscriptionsRequest::default();
+        let mut buf = Vec::new();
+        request.encode(0, &mut buf).unwrap();
+        // a zero client instance id, asking the broker for one, and no tagged fields
+        assert_eq!(buf, [0; 17]);
+
+        let response = GetTelemetrySubscriptionsResponse::builder()
+            .client_instance_id([9; 16])
+            .subscription_id(3)
+            .accepted_compression_types([1i8, 4])
+            .push_interval_ms(30_000)
+            .telemetry_max_bytes(1 << 20)
+            .delta_temporality(true)
+            .requested_metrics(["org.apache.kafka.producer."])
+            .build(0)
+            .unwrap();
+        buf.clear();
+        response.encode(0, &mut buf).unwrap();
+        assert_eq!(
+            GetTelemetrySubscriptionsResponse::decode(0, &buf).unwrap(),
+            response
+        );
+
+        let request = PushTelemetryRequest {
+            client_instance_id: response.client_instance_id,
+            subscription_id: response.subscription_id,
+            compression_type: 4,
+            metrics: vec![0x0a, 0x00],
+            ..Default::default()
+        };
+        assert_eq!(PushTelemetryRequest::API_KEY, 72);
+        buf.clear();
+        request.encode(0, &mut buf).unwrap();
+        assert_eq!(PushTelemetryRequest::decode(0, &buf).unwrap(), request);
+        let response = PushTelemetryResponse::decode(0, &[0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(response, PushTelemetryResponse::default());
+
+        buf.clear();
+        ListClientMetricsResourcesRequest::default()
+            .encode(0, &mut buf)
+            .unwrap();
+        assert_eq!(buf, [0]);
+        let response = ListClientMetricsResourcesResponse {
+            client_metrics_resources: vec![ClientMetricsResource {
+                name: "producers".into(),
+            }],
+            ..Default::default()
+        };
+        buf.clear();
+        response.encode(0, &mut buf).unwrap();
+        assert_eq!(
+            ListClientMetricsResourcesResponse::decode(0, &buf).unwrap(),
+            response
+        );
+    }
+
+    #[test]
+    fn test_share_groups() {
+        use share_fetch_request::{AcknowledgementBatch, FetchPartition, FetchTopic};
+        use share_fetch_response::{AcquiredRecords, PartitionData, ShareFetchableTopicResponse};
+        use share_group_heartbeat_response::{Assignment, TopicPartitions};
+
+        let topic_id = [5; 16];
+        let heartbeat = ShareGroupHeartbeatRequest::builder()
+            .group_id("queue")
+            .member_id("m")
+            .subscribed_topic_names(vec!["t".to_owned()])
+            .build(0)
+            .unwrap();
+        assert_eq!(ShareGroupHeartbeatRequest::API_KEY, 76);
+        assert_eq!(heartbeat.rack_id, None);
+        let mut buf = Vec::new();
+        heartbeat.encode(0, &mut buf).unwrap();
+        assert_eq!(
+            ShareGroupHeartbeatRequest::decode(0, &buf).unwrap(),
+            heartbeat
+        );
+
+        let response = ShareGroupHeartbeatResponse {
+            member_epoch: 1,
+            heartbeat_interval_ms: 5_000,
+            assignment: Some(Assignment {
+                topic_partitions: vec![TopicPartitions {
+                    topic_id,
+                    partitions: vec![0, 1],
+                }],
+            }),
+            ..Default::default()
+        };
+        buf.clear();
+        response.encode(0, &mut buf).unwrap();
+        assert_eq!(
+            ShareGroupHeartbeatResponse::decode(0, &buf).unwrap(),
+            response
+        );
+
+        // fetching more records while accepting the first two delivered, and rejecting the third
+        let fetch = ShareFetchRequest {
+            group_id: Some("queue".into()),
+            member_id: Some("m".into()),
+            share_session_epoch: 1,
+            topics: vec![FetchTopic {
+                topic_id,
+                partitions: vec![FetchPartition {
+                    partition_index: 0,
+                    partition_max_bytes: 1 << 20,
+                    acknowledgement_batches: vec![AcknowledgementBatch {
+                        first_offset: 10,
+                        last_offset: 12,
+                        acknowledge_types: vec![1, 1, 3],
+                    }],
+                }],
+            }],
+            ..Default::default()
+        };
+        assert_eq!(fetch.max_bytes, i32::MAX);
+        buf.clear();
+        fetch.encode(0, &mut buf).unwrap();
+        assert_eq!(ShareFetchRequest::decode(0, &buf).unwrap(), fetch);
+
+        let response = ShareFetchResponse {
+            responses: vec![ShareFetchableTopicResponse {
+                topic_id,
+                partitions: vec![PartitionData {
+                    records: Some(vec![0; 8]),
+                    acquired_records: vec![AcquiredRecords {
+                        first_offset: 13,
+                        last_offset: 20,
+                        delivery_count: 1,
+                    }],
+                    ..Default::default()
+                }],
+            }],
+            ..Default::default()
+        };
+        buf.clear();
+        response.encode(0, &mut buf).unwrap();
+        assert_eq!(ShareFetchResponse::decode(0, &buf).unwrap(), response);
+
+        let acknowledge = ShareAcknowledgeRequest {
+            member_id: Some("m".into()),
+            share_session_epoch: -1,
+            ..Default::default()
+        };
+        buf.clear();
+        acknowledge.encode(0, &mut buf).unwrap();
+        // no group, the member, the epoch closing the session, no topics and no tagged fields
+        assert_eq!(buf, [0, 2, b'm', 0xff, 0xff, 0xff, 0xff, 1, 0]);
+        assert_eq!(
+            ShareAcknowledgeRequest::decode(0, &buf).unwrap(),
+            acknowledge
+        );
+        assert!(ShareAcknowledgeResponse::decode(0, &buf).is_err());
+    }
+
+    #[test]
+    fn test_raft_voters() {
+        use add_raft_voter_request::Listener;
+        use update_raft_voter_request::KRaftVersionFeature;
+        use update_raft_voter_response::CurrentLeader;
+
+        let directory_id = [3; 16];
+        let add = AddRaftVoterRequest::builder()
+            .cluster_id("cluster".to_owned())
+            .timeout_ms(30_000)
+            .voter_id(4)
+            .voter_directory_id(directory_id)
+            .listeners([Listener {
+                name: "CONTROLLER".into(),
+                host: "controller-4".into(),
+                port: 9093,
+            }])
+            .build(0)
+            .unwrap();
+        assert_eq!(AddRaftVoterRequest::API_KEY, 80);
+        let mut buf = Vec::new();
+        add.encode(0, &mut buf).unwrap();
+        assert_eq!(AddRaftVoterRequest::decode(0, &buf).unwrap(), add);
+        // the port is the last field of the listener, before its tagged fields and the ones of
+        // the request
+        assert_eq!(buf[buf.len() - 4..], [0x23, 0x85, 0, 0]);
+
+        let remove = RemoveRaftVoterRequest {
+            cluster_id: None,
+            voter_id: 4,
+            voter_directory_id: directory_id,
+        };
+        buf.clear();
+        remove.encode(0, &mut buf).unwrap();
+        assert_eq!(buf.len(), 1 + 4 + 16 + 1);
+        assert_eq!(RemoveRaftVoterRequest::decode(0, &buf).unwrap(), remove);
+        let response = RemoveRaftVoterResponse::decode(0, &[0, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(response.error_message, None);
+
+        let update = UpdateRaftVoterRequest {
+            current_leader_epoch: 7,
+            voter_id: 4,
+            voter_directory_id: directory_id,
+            k_raft_version_feature: KRaftVersionFeature {
+                min_supported_version: 0,
+                max_supported_version: 1,
+            },
+            ..Default::default()
+        };
+        buf.clear();
+        update.encode(0, &mut buf).unwrap();
+        assert_eq!(UpdateRaftVoterRequest::decode(0, &buf).unwrap(), update);
+
+        // the current leader is a tagged field
+        let response = UpdateRaftVoterResponse::decode(0, &[0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(response.current_leader.leader_id, -1);
+        let response = UpdateRaftVoterResponse {
+            error_code: 6,
+            current_leader: CurrentLeader {
+                leader_id: 1,
+                leader_epoch: 8,
+                host: "controller-1".into(),
+                port: 9093,
+            },
+            ..Default::default()
+        };
+        buf.clear();
+        response.encode(0, &mut buf).unwrap();
+        assert_eq!(UpdateRaftVoterResponse::decode(0, &buf).unwrap(), response);
+    }
+}
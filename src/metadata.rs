@@ -0,0 +1,547 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Cluster metadata
+//!
+//! A [`MetadataResponse`] decodes the body of a MetadataResponse, and a [`MetadataCache`] keeps
+//! what the latest ones said about the cluster, to answer which broker leads a partition.
+//!
+//! The cache tracks how old the metadata of each topic is, and clients mark topics stale when
+//! brokers answer that they no longer lead a partition. Lookups of missing or stale topics call
+//! the refresh hook of the cache, for the client to send a Metadata request for them.
+//!
+//! Versions 0 to 12 are supported.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::metadata::{MetadataCache, MetadataResponse};
+//! use std::sync::{mpsc, Mutex};
+//!
+//! let (refresh, topics_to_refresh) = mpsc::channel();
+//! let refresh = Mutex::new(refresh);
+//! let mut cache = MetadataCache::new().on_refresh(move |topic: &str| {
+//!     let _ = refresh.lock().unwrap().send(topic.to_owned());
+//! });
+//!
+//! // nothing is known about the topic yet
+//! assert!(cache.leader("events", 0).is_none());
+//! assert_eq!(topics_to_refresh.try_recv().unwrap(), "events");
+//!
+//! fn on_metadata(cache: &mut MetadataCache, body: &[u8]) -> kafka_serde::Result<()> {
+//!     cache.update(MetadataResponse::decode(12, body)?);
+//!     Ok(())
+//! }
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::schema::{take_field, take_field_or, Field, FromValue, KafkaValue, Schema, Type};
+
+/// The API key of Metadata
+pub const METADATA_KEY: i16 = 3;
+
+/// The last version of Metadata known
+const MAX_VERSION: i16 = 12;
+
+/// The error code of topics and partitions the broker doesn't know
+const UNKNOWN_TOPIC_OR_PARTITION: i16 = 3;
+
+/// The schema of MetadataResponse, in the versions supported
+fn schema() -> &'static Schema {
+    static SCHEMA: OnceLock<Schema> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        let broker = Type::Struct(vec![
+            Field::new("NodeId", Type::Int32),
+            Field::new("Host", Type::String),
+            Field::new("Port", Type::Int32),
+            Field::new("Rack", Type::String)
+                .versions(1..)
+                .nullable_versions(1..),
+        ]);
+        let nodes = || Type::Array(Box::new(Type::Int32));
+        let partition = Type::Struct(vec![
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("PartitionIndex", Type::Int32),
+            Field::new("LeaderId", Type::Int32),
+            Field::new("LeaderEpoch", Type::Int32).versions(7..),
+            Field::new("ReplicaNodes", nodes()),
+            Field::new("IsrNodes", nodes()),
+            Field::new("OfflineReplicas", nodes()).versions(5..),
+        ]);
+        let topic = Type::Struct(vec![
+            Field::new("ErrorCode", Type::Int16),
+            Field::new("Name", Type::String).nullable_versions(12..),
+            Field::new("TopicId", Type::Uuid).versions(10..),
+            Field::new("IsInternal", Type::Bool).versions(1..),
+            Field::new("Partitions", Type::Array(Box::new(partition))),
+            Field::new("TopicAuthorizedOperations", Type::Int32).versions(8..),
+        ]);
+        Schema::new(
+            "MetadataResponse",
+            vec![
+                Field::new("ThrottleTimeMs", Type::Int32).versions(3..),
+                Field::new("Brokers", Type::Array(Box::new(broker))),
+                Field::new("ClusterId", Type::String)
+                    .versions(2..)
+                    .nullable_versions(2..),
+                Field::new("ControllerId", Type::Int32).versions(1..),
+                Field::new("Topics", Type::Array(Box::new(topic))),
+                Field::new("ClusterAuthorizedOperations", Type::Int32).versions(8..=10),
+            ],
+        )
+        .valid_versions(0..=MAX_VERSION)
+        .flexible_versions(9..)
+    })
+}
+
+/// A decoded MetadataResponse
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct MetadataResponse {
+    /// How long the response was throttled for, in milliseconds
+    pub throttle_time_ms: i32,
+    /// The brokers of the cluster
+    pub brokers: Vec<Broker>,
+    /// The cluster id, if the broker has one
+    pub cluster_id: Option<String>,
+    /// The id of the controller, -1 if there is none
+    pub controller_id: i32,
+    /// The topics asked for, or all of them
+    pub topics: Vec<TopicMetadata>,
+}
+
+/// A broker of a [`MetadataResponse`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Broker {
+    /// The broker id
+    pub node_id: i32,
+    /// The host name of the broker
+    pub host: String,
+    /// The port the broker listens on
+    pub port: i32,
+    /// The rack of the broker, if it is in one
+    pub rack: Option<String>,
+}
+
+/// A topic of a [`MetadataResponse`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct TopicMetadata {
+    /// The error of the topic, 0 if there was none
+    pub error_code: i16,
+    /// The topic name, null if the topic was asked for by id
+    pub name: Option<String>,
+    /// The topic id, all zeros in versions without it
+    pub topic_id: [u8; 16],
+    /// Whether the topic is internal to the brokers
+    pub is_internal: bool,
+    /// The partitions of the topic
+    pub partitions: Vec<PartitionMetadata>,
+}
+
+/// A partition of a [`TopicMetadata`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct PartitionMetadata {
+    /// The error of the partition, 0 if there was none
+    pub error_code: i16,
+    /// The partition index
+    pub index: i32,
+    /// The id of the leader, -1 if there is none
+    pub leader_id: i32,
+    /// The epoch of the leader, -1 in versions without it
+    pub leader_epoch: i32,
+    /// The ids of the replicas
+    pub replica_nodes: Vec<i32>,
+    /// The ids of the in-sync replicas
+    pub isr_nodes: Vec<i32>,
+    /// The ids of the replicas that are offline
+    pub offline_replicas: Vec<i32>,
+}
+
+impl MetadataResponse {
+    /// Decodes the body of a MetadataResponse in `version`, from 0 to 12
+    pub fn decode(version: i16, body: &[u8]) -> Result<MetadataResponse> {
+        Self::from_value(schema().decode(version, body)?)
+    }
+}
+
+impl FromValue for MetadataResponse {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(MetadataResponse {
+            throttle_time_ms: take_field(&mut fields, "ThrottleTimeMs")?,
+            brokers: take_field(&mut fields, "Brokers")?,
+            cluster_id: take_field(&mut fields, "ClusterId")?,
+            controller_id: take_field_or(&mut fields, "ControllerId", -1)?,
+            topics: take_field(&mut fields, "Topics")?,
+        })
+    }
+}
+
+impl FromValue for Broker {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(Broker {
+            node_id: take_field(&mut fields, "NodeId")?,
+            host: take_field(&mut fields, "Host")?,
+            port: take_field(&mut fields, "Port")?,
+            rack: take_field(&mut fields, "Rack")?,
+        })
+    }
+}
+
+impl FromValue for TopicMetadata {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(TopicMetadata {
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            name: take_field(&mut fields, "Name")?,
+            topic_id: take_field(&mut fields, "TopicId")?,
+            is_internal: take_field(&mut fields, "IsInternal")?,
+            partitions: take_field(&mut fields, "Partitions")?,
+        })
+    }
+}
+
+impl FromValue for PartitionMetadata {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        let mut fields = value.into_fields()?;
+        Ok(PartitionMetadata {
+            error_code: take_field(&mut fields, "ErrorCode")?,
+            index: take_field(&mut fields, "PartitionIndex")?,
+            leader_id: take_field(&mut fields, "LeaderId")?,
+            leader_epoch: take_field_or(&mut fields, "LeaderEpoch", -1)?,
+            replica_nodes: take_field(&mut fields, "ReplicaNodes")?,
+            isr_nodes: take_field(&mut fields, "IsrNodes")?,
+            offline_replicas: take_field(&mut fields, "OfflineReplicas")?,
+        })
+    }
+}
+
+type RefreshFn = Box<dyn Fn(&str) + Send + Sync>;
+
+/// The metadata of a topic in a [`MetadataCache`]
+#[derive(Debug, Clone)]
+struct CachedTopic {
+    metadata: TopicMetadata,
+    // when the metadata was received, unless the cache doesn't expire it
+    updated: Option<Instant>,
+    // marked stale by the client
+    invalidated: bool,
+}
+
+/// The latest metadata of a cluster: its brokers, and the leaders of the partitions of its
+/// topics
+///
+/// Metadata goes stale after [`max_age`](MetadataCache::max_age), 5 minutes by default like the
+/// `metadata.max.age.ms` of the Java client, or when [`invalidate`](MetadataCache::invalidate)d.
+/// Stale metadata is still returned, as it is usually still right, but lookups of stale or
+/// missing topics call the refresh hook.
+pub struct MetadataCache {
+    cluster_id: Option<String>,
+    controller_id: i32,
+    brokers: HashMap<i32, Broker>,
+    topics: HashMap<String, CachedTopic>,
+    max_age: Option<Duration>,
+    refresh: Option<RefreshFn>,
+}
+
+impl fmt::Debug for MetadataCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetadataCache")
+            .field("cluster_id", &self.cluster_id)
+            .field("controller_id", &self.controller_id)
+            .field("brokers", &self.brokers)
+            .field("topics", &self.topics)
+            .field("max_age", &self.max_age)
+            .finish()
+    }
+}
+
+impl Default for MetadataCache {
+    fn default() -> Self {
+        MetadataCache {
+            cluster_id: None,
+            controller_id: -1,
+            brokers: HashMap::new(),
+            topics: HashMap::new(),
+            max_age: Some(Duration::from_secs(300)),
+            refresh: None,
+        }
+    }
+}
+
+impl MetadataCache {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long the metadata of a topic stays fresh. `None` keeps it fresh until it is
+    /// invalidated, without reading the clock, for targets without one like
+    /// `wasm32-unknown-unknown`
+    pub fn max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the hook called with the name of the topics whose metadata is looked up while
+    /// missing or stale, e.g. to schedule a Metadata request for them
+    ///
+    /// The hook is called on every such lookup, until the topic is updated: it should be quick,
+    /// and coalesce the refreshes it triggers.
+    pub fn on_refresh(mut self, refresh: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.refresh = Some(Box::new(refresh));
+        self
+    }
+
+    /// Ingests a MetadataResponse
+    ///
+    /// The brokers of the response replace those of the cache, and its topics replace those of
+    /// the same name. Topics the broker doesn't know are removed, and those with another error
+    /// are marked stale. Topics not in the response are kept as they are, as requests may only
+    /// ask for some of them.
+    pub fn update(&mut self, response: MetadataResponse) {
+        let updated = self.max_age.map(|_| Instant::now());
+        self.cluster_id = response.cluster_id;
+        self.controller_id = response.controller_id;
+        if !response.brokers.is_empty() {
+            self.brokers = response
+                .brokers
+                .into_iter()
+                .map(|broker| (broker.node_id, broker))
+                .collect();
+        }
+        for topic in response.topics {
+            let name = match topic.name {
+                Some(ref name) => name.clone(),
+                None => continue,
+            };
+            match topic.error_code {
+                0 => {
+                    let cached = CachedTopic {
+                        metadata: topic,
+                        updated,
+                        invalidated: false,
+                    };
+                    self.topics.insert(name, cached);
+                }
+                UNKNOWN_TOPIC_OR_PARTITION => {
+                    self.topics.remove(&name);
+                }
+                _ => self.invalidate(&name),
+            }
+        }
+    }
+
+    /// Marks the metadata of `topic` stale, e.g. after a broker answered that it doesn't lead
+    /// one of its partitions anymore
+    pub fn invalidate(&mut self, topic: &str) {
+        if let Some(cached) = self.topics.get_mut(topic) {
+            cached.invalidated = true;
+        }
+    }
+
+    /// The cluster id, if the broker has one
+    pub fn cluster_id(&self) -> Option<&str> {
+        self.cluster_id.as_deref()
+    }
+
+    /// The controller of the cluster, if there is one and it is known
+    pub fn controller(&self) -> Option<&Broker> {
+        self.broker(self.controller_id)
+    }
+
+    /// The broker with the id `node_id`, if it is known
+    pub fn broker(&self, node_id: i32) -> Option<&Broker> {
+        self.brokers.get(&node_id)
+    }
+
+    /// The brokers of the cluster, in no particular order
+    pub fn brokers(&self) -> impl Iterator<Item = &Broker> {
+        self.brokers.values()
+    }
+
+    /// The metadata of `topic`, if it is known. Calls the refresh hook if it is missing or
+    /// stale
+    pub fn topic(&self, topic: &str) -> Option<&TopicMetadata> {
+        let cached = self.topics.get(topic);
+        if cached.is_none_or(|cached| self.is_expired(cached)) {
+            if let Some(ref refresh) = self.refresh {
+                refresh(topic);
+            }
+        }
+        cached.map(|cached| &cached.metadata)
+    }
+
+    /// The metadata of `partition` of `topic`, if it is known. Calls the refresh hook if the
+    /// topic is missing or stale
+    pub fn partition(&self, topic: &str, partition: i32) -> Option<&PartitionMetadata> {
+        self.topic(topic)?
+            .partitions
+            .iter()
+            .find(|metadata| metadata.index == partition)
+    }
+
+    /// The broker leading `partition` of `topic`, if it is known. Calls the refresh hook if the
+    /// topic is missing or stale
+    pub fn leader(&self, topic: &str, partition: i32) -> Option<&Broker> {
+        self.broker(self.partition(topic, partition)?.leader_id)
+    }
+
+    /// Whether the metadata of `topic` is missing or stale
+    pub fn is_stale(&self, topic: &str) -> bool {
+        self.topics
+            .get(topic)
+            .is_none_or(|cached| self.is_expired(cached))
+    }
+
+    /// The names of the topics whose metadata is stale, in no particular order
+    pub fn stale_topics(&self) -> Vec<&str> {
+        self.topics
+            .iter()
+            .filter(|(_, cached)| self.is_expired(cached))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    fn is_expired(&self, cached: &CachedTopic) -> bool {
+        cached.invalidated
+            || match (self.max_age, cached.updated) {
+                (Some(max_age), Some(updated)) => updated.elapsed() >= max_age,
+                // the max age was set after the update
+                (Some(_), None) => true,
+                (None, _) => false,
+            }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn response(version: i16, leader_id: i32, error_code: i16) -> Vec<u8> {
+        let broker = |node_id: i32, host: &str| {
+            KafkaValue::Struct(vec![
+                ("NodeId".to_owned(), KafkaValue::Int32(node_id)),
+                ("Host".to_owned(), KafkaValue::String(host.to_owned())),
+                ("Port".to_owned(), KafkaValue::Int32(9092)),
+                ("Rack".to_owned(), KafkaValue::Null),
+            ])
+        };
+        let nodes = KafkaValue::Array(vec![KafkaValue::Int32(1), KafkaValue::Int32(2)]);
+        let partition = KafkaValue::Struct(vec![
+            ("ErrorCode".to_owned(), KafkaValue::Int16(0)),
+            ("PartitionIndex".to_owned(), KafkaValue::Int32(0)),
+            ("LeaderId".to_owned(), KafkaValue::Int32(leader_id)),
+            ("LeaderEpoch".to_owned(), KafkaValue::Int32(4)),
+            ("ReplicaNodes".to_owned(), nodes.clone()),
+            ("IsrNodes".to_owned(), nodes),
+            ("OfflineReplicas".to_owned(), KafkaValue::Array(Vec::new())),
+        ]);
+        let topic = KafkaValue::Struct(vec![
+            ("ErrorCode".to_owned(), KafkaValue::Int16(error_code)),
+            ("Name".to_owned(), KafkaValue::String("events".to_owned())),
+            ("TopicId".to_owned(), KafkaValue::Uuid([7; 16])),
+            ("IsInternal".to_owned(), KafkaValue::Bool(false)),
+            ("Partitions".to_owned(), KafkaValue::Array(vec![partition])),
+            (
+                "TopicAuthorizedOperations".to_owned(),
+                KafkaValue::Int32(i32::MIN),
+            ),
+        ]);
+        let response = KafkaValue::Struct(vec![
+            ("ThrottleTimeMs".to_owned(), KafkaValue::Int32(0)),
+            (
+                "Brokers".to_owned(),
+                KafkaValue::Array(vec![broker(1, "a"), broker(2, "b")]),
+            ),
+            ("ClusterId".to_owned(), KafkaValue::String("c".to_owned())),
+            ("ControllerId".to_owned(), KafkaValue::Int32(2)),
+            ("Topics".to_owned(), KafkaValue::Array(vec![topic])),
+            (
+                "ClusterAuthorizedOperations".to_owned(),
+                KafkaValue::Int32(i32::MIN),
+            ),
+        ]);
+        let mut buf = Vec::new();
+        schema().encode(version, &response, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_decode() {
+        for version in [0, 1, 7, 9, 10, 12] {
+            let response = MetadataResponse::decode(version, &response(version, 1, 0)).unwrap();
+            assert_eq!(response.brokers.len(), 2);
+            assert_eq!(response.controller_id, if version >= 1 { 2 } else { -1 });
+            let topic = &response.topics[0];
+            assert_eq!(topic.name.as_deref(), Some("events"));
+            assert_eq!(
+                topic.topic_id,
+                if version >= 10 { [7; 16] } else { [0; 16] }
+            );
+            assert_eq!(topic.partitions[0].isr_nodes, [1, 2]);
+            assert_eq!(
+                topic.partitions[0].leader_epoch,
+                if version >= 7 { 4 } else { -1 }
+            );
+        }
+    }
+
+    #[test]
+    fn test_cache() {
+        let refreshed = Arc::new(Mutex::new(Vec::new()));
+        let hook = refreshed.clone();
+        let mut cache = MetadataCache::new()
+            .on_refresh(move |topic: &str| hook.lock().unwrap().push(topic.to_owned()));
+        assert!(cache.leader("events", 0).is_none());
+        assert_eq!(*refreshed.lock().unwrap(), ["events"]);
+
+        let decode = |leader_id, error_code| {
+            MetadataResponse::decode(12, &response(12, leader_id, error_code)).unwrap()
+        };
+        cache.update(decode(1, 0));
+        assert_eq!(cache.leader("events", 0).unwrap().host, "a");
+        assert!(cache.leader("events", 1).is_none());
+        assert_eq!(cache.controller().unwrap().host, "b");
+        assert_eq!(cache.cluster_id(), Some("c"));
+        assert!(!cache.is_stale("events"));
+        assert_eq!(refreshed.lock().unwrap().len(), 1);
+
+        // stale metadata is still returned
+        cache.invalidate("events");
+        assert_eq!(cache.stale_topics(), ["events"]);
+        assert_eq!(cache.leader("events", 0).unwrap().host, "a");
+        assert_eq!(refreshed.lock().unwrap().len(), 2);
+        cache.update(decode(2, 0));
+        assert_eq!(cache.leader("events", 0).unwrap().host, "b");
+        assert!(cache.stale_topics().is_empty());
+
+        // leader not available
+        cache.update(decode(-1, 5));
+        assert!(cache.is_stale("events"));
+        assert_eq!(cache.leader("events", 0).unwrap().host, "b");
+        cache.update(decode(-1, UNKNOWN_TOPIC_OR_PARTITION));
+        assert!(cache.topic("events").is_none());
+    }
+
+    #[test]
+    fn test_max_age() {
+        let mut cache = MetadataCache::new().max_age(Some(Duration::ZERO));
+        cache.update(MetadataResponse::decode(1, &response(1, 1, 0)).unwrap());
+        assert!(cache.is_stale("events"));
+
+        let mut cache = MetadataCache::new().max_age(None);
+        cache.update(MetadataResponse::decode(1, &response(1, 1, 0)).unwrap());
+        assert!(!cache.is_stale("events"));
+    }
+}
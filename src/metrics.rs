@@ -0,0 +1,170 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Protocol metrics
+//!
+//! Services embedding this crate can monitor their protocol layer by installing a [`Metrics`]
+//! implementation with [`set_metrics`], which forwards what it is told to their metrics library.
+//! It hears about:
+//! - every payload encoded by [`to_vec`](crate::to_vec), [`to_writer`](crate::to_writer),
+//!   `to_buf` and [`FrameWriter`](crate::FrameWriter), or by [`Schema::encode`]
+//! - every payload decoded by the [`Decoder`](crate::Decoder) and its shortcuts, or by
+//!   [`Schema::decode`], and the category of the errors of those that couldn't be
+//! - the requests sent and responses received by client connections, per API key
+//!
+//! Until metrics are set, reporting costs a single atomic load.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::metrics::{self, Metrics};
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! struct DecodedBytes(AtomicUsize);
+//!
+//! impl Metrics for DecodedBytes {
+//!     fn decoded(&self, bytes: usize) {
+//!         self.0.fetch_add(bytes, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! static DECODED: DecodedBytes = DecodedBytes(AtomicUsize::new(0));
+//!
+//! metrics::set_metrics(&DECODED).unwrap();
+//! let _: i32 = kafka_serde::from_bytes(&[0, 0, 0, 1]).unwrap();
+//! assert!(DECODED.0.load(Ordering::Relaxed) >= 4);
+//! ```
+//!
+//! [`Schema::encode`]: crate::schema::Schema::encode
+//! [`Schema::decode`]: crate::schema::Schema::decode
+use std::sync::OnceLock;
+
+use crate::error::{ErrorCategory, ErrorKind, Result};
+
+/// Receives the metrics of the protocol layer. Every method does nothing by default
+///
+/// Methods are called on the thread doing the work, right after it is done: they should be
+/// quick, like incrementing counters.
+pub trait Metrics: Send + Sync {
+    /// A payload was encoded into `bytes` bytes
+    fn encoded(&self, bytes: usize) {
+        let _ = bytes;
+    }
+
+    /// A payload was decoded from `bytes` bytes
+    fn decoded(&self, bytes: usize) {
+        let _ = bytes;
+    }
+
+    /// A payload could not be decoded, because of an error in `category`
+    fn decode_failed(&self, category: ErrorCategory) {
+        let _ = category;
+    }
+
+    /// A request to the API `api_key` was sent, in a frame of `bytes` bytes, size included
+    fn request_sent(&self, api_key: i16, bytes: usize) {
+        let _ = (api_key, bytes);
+    }
+
+    /// The response to a request to the API `api_key` was received, in a frame of `bytes`
+    /// bytes, size included
+    fn response_received(&self, api_key: i16, bytes: usize) {
+        let _ = (api_key, bytes);
+    }
+}
+
+static METRICS: OnceLock<&'static dyn Metrics> = OnceLock::new();
+
+/// Installs `metrics` for the rest of the process, failing if metrics were already set
+pub fn set_metrics(metrics: &'static dyn Metrics) -> Result<()> {
+    METRICS
+        .set(metrics)
+        .map_err(|_| ErrorKind::Custom("metrics are already set".into()).into())
+}
+
+#[inline]
+fn with_metrics(f: impl FnOnce(&dyn Metrics)) {
+    if let Some(metrics) = METRICS.get() {
+        f(*metrics)
+    }
+}
+
+/// Reports an encoding of `bytes` bytes, if it succeeded
+#[inline]
+pub(crate) fn report_encode<T>(result: &Result<T>, bytes: usize) {
+    if result.is_ok() {
+        with_metrics(|metrics| metrics.encoded(bytes));
+    }
+}
+
+/// Reports a decoding that read `bytes` bytes if it succeeded, or its error
+#[inline]
+pub(crate) fn report_decode<T>(result: &Result<T>, bytes: usize) {
+    with_metrics(|metrics| match *result {
+        Ok(_) => metrics.decoded(bytes),
+        Err(ref err) => metrics.decode_failed(err.category()),
+    });
+}
+
+#[inline]
+pub(crate) fn report_request(api_key: i16, bytes: usize) {
+    with_metrics(|metrics| metrics.request_sent(api_key, bytes));
+}
+
+#[inline]
+pub(crate) fn report_response(api_key: i16, bytes: usize) {
+    with_metrics(|metrics| metrics.response_received(api_key, bytes));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        // what was reported by the current thread, as tests run concurrently
+        static REPORTED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    struct Recorder;
+
+    impl Recorder {
+        fn push(event: String) {
+            REPORTED.with(|reported| reported.borrow_mut().push(event));
+        }
+
+        fn take() -> Vec<String> {
+            REPORTED.with(|reported| reported.take())
+        }
+    }
+
+    impl Metrics for Recorder {
+        fn encoded(&self, bytes: usize) {
+            Recorder::push(format!("encoded {}", bytes));
+        }
+
+        fn decoded(&self, bytes: usize) {
+            Recorder::push(format!("decoded {}", bytes));
+        }
+
+        fn decode_failed(&self, category: ErrorCategory) {
+            Recorder::push(format!("failed {:?}", category));
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        set_metrics(&Recorder).unwrap();
+        assert!(set_metrics(&Recorder).is_err());
+
+        crate::to_vec(&(1i32, 2i16)).unwrap();
+        crate::to_writer(Vec::new(), &1i64).unwrap();
+        let _: i16 = crate::from_bytes(&[0, 1, 0xff]).unwrap();
+        assert!(crate::from_bytes::<i32>(&[0, 1]).is_err());
+        assert_eq!(
+            Recorder::take(),
+            ["encoded 10", "encoded 8", "decoded 2", "failed Protocol"]
+        );
+    }
+}
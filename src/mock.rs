@@ -0,0 +1,328 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # A mock broker for tests
+//!
+//! A [`MockBroker`] listens on a local port and answers requests the way it is told to, for
+//! clients to be tested without a Kafka cluster. Each API it supports gets a function that
+//! builds the response from the request, which can be decoded with
+//! [`CapturedRequest::body`]. Requests to the other APIs close the connection, as brokers do.
+//!
+//! Each connection is served by a thread of its own. The broker keeps the requests it received,
+//! for tests to check what the client sent.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::client::{BlockingConnection, Request};
+//! use kafka_serde::mock::MockBroker;
+//! use kafka_serde::KafkaRequest;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct HeartbeatRequest {
+//!     group_id: String,
+//!     generation_id: i32,
+//!     member_id: String,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct HeartbeatResponse {
+//!     throttle_time_ms: i32,
+//!     error_code: i16,
+//! }
+//!
+//! impl KafkaRequest for HeartbeatRequest {
+//!     const API_KEY: i16 = 12;
+//!     const MIN_VERSION: i16 = 1;
+//!     const MAX_VERSION: i16 = 3;
+//!     type Response = HeartbeatResponse;
+//! }
+//!
+//! // members of generations other than 5 are told to rejoin
+//! let broker = MockBroker::new()
+//!     .respond::<HeartbeatRequest>(|request| {
+//!         let heartbeat: HeartbeatRequest = request.body()?;
+//!         let error_code = if heartbeat.generation_id == 5 { 0 } else { 27 };
+//!         Ok(HeartbeatResponse { throttle_time_ms: 0, error_code })
+//!     })
+//!     .start()
+//!     .unwrap();
+//!
+//! let mut connection = BlockingConnection::connect(broker.addr()).unwrap();
+//! let heartbeat = HeartbeatRequest {
+//!     group_id: "group".into(),
+//!     generation_id: 4,
+//!     member_id: "member".into(),
+//! };
+//! let response = connection.send(&Request::message(1, heartbeat).unwrap()).unwrap();
+//! assert_eq!(response.error_code, 27);
+//! assert_eq!(broker.requests()[0].header.api_key, 12);
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use serde::Serialize;
+
+use crate::capture::{self, CapturedRequest};
+use crate::client;
+use crate::error::{ErrorKind, Result};
+use crate::header;
+use crate::KafkaRequest;
+
+type Respond = Box<dyn Fn(&CapturedRequest) -> Result<Vec<u8>> + Send + Sync>;
+
+/// How the broker answers the requests of an API
+struct Handler {
+    first_flexible_version: Option<i16>,
+    respond: Respond,
+}
+
+impl Handler {
+    fn is_flexible(&self, version: i16) -> bool {
+        self.first_flexible_version
+            .is_some_and(|first| version >= first)
+    }
+}
+
+/// A broker answering requests with the responses of the functions given for their API
+#[derive(Default)]
+pub struct MockBroker {
+    handlers: HashMap<i16, Handler>,
+}
+
+impl fmt::Debug for MockBroker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut api_keys: Vec<_> = self.handlers.keys().collect();
+        api_keys.sort();
+        f.debug_struct("MockBroker")
+            .field("api_keys", &api_keys)
+            .finish()
+    }
+}
+
+impl MockBroker {
+    /// Creates a broker that doesn't support any API
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers requests to the API of `Q` with what `respond` returns for them. Errors close
+    /// the connection
+    pub fn respond<Q>(
+        self,
+        respond: impl Fn(&CapturedRequest) -> Result<Q::Response> + Send + Sync + 'static,
+    ) -> Self
+    where
+        Q: KafkaRequest,
+        Q::Response: Serialize,
+    {
+        self.respond_raw(Q::API_KEY, Q::FIRST_FLEXIBLE_VERSION, move |request| {
+            crate::to_vec(&respond(request)?)
+        })
+    }
+
+    /// Answers requests to the API `api_key` with the response bodies `respond` encodes for
+    /// them, e.g. canned ones. Errors close the connection
+    ///
+    /// Headers are flexible from `first_flexible_version` on, if the API has flexible versions.
+    pub fn respond_raw(
+        mut self,
+        api_key: i16,
+        first_flexible_version: Option<i16>,
+        respond: impl Fn(&CapturedRequest) -> Result<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        let handler = Handler {
+            first_flexible_version,
+            respond: Box::new(respond),
+        };
+        self.handlers.insert(api_key, handler);
+        self
+    }
+
+    /// Starts listening on a free port of the loopback interface
+    pub fn start(self) -> Result<MockBrokerHandle> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            handlers: self.handlers,
+            requests: Mutex::new(Vec::new()),
+            shutdown: AtomicBool::new(false),
+        });
+        let accept = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shared.shutdown.load(Ordering::Acquire) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let shared = shared.clone();
+                        // failures close the connection, which is all the client sees
+                        thread::spawn(move || shared.serve(stream));
+                    }
+                }
+            })
+        };
+        Ok(MockBrokerHandle {
+            addr,
+            shared,
+            accept: Some(accept),
+        })
+    }
+}
+
+/// What the threads of a running broker share
+struct Shared {
+    handlers: HashMap<i16, Handler>,
+    requests: Mutex<Vec<CapturedRequest>>,
+    shutdown: AtomicBool,
+}
+
+impl Shared {
+    fn serve(&self, mut stream: TcpStream) -> Result<()> {
+        let mut size = [0; 4];
+        loop {
+            match stream.read_exact(&mut size) {
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                result => result?,
+            }
+            let size = i32::from_be_bytes(size);
+            if size < 0 {
+                return Err(ErrorKind::InvalidLength(size).into());
+            }
+            let mut frame = Vec::new();
+            client::read_frame(&mut stream, size as usize, &mut frame)?;
+            let request = capture::read_request(&frame, |api_key, api_version| {
+                self.handlers
+                    .get(&api_key)
+                    .is_some_and(|handler| handler.is_flexible(api_version))
+            })?;
+            self.requests.lock().unwrap().push(request.clone());
+
+            let header = &request.header;
+            let handler = self
+                .handlers
+                .get(&header.api_key)
+                .ok_or_else(|| ErrorKind::Custom(format!("unsupported API {}", header.api_key)))?;
+            let body = (handler.respond)(&request)?;
+            let mut response = vec![0; 4];
+            response.extend_from_slice(&header.correlation_id.to_be_bytes());
+            let flexible = handler.is_flexible(header.api_version);
+            if header::response_header_version(header.api_key, flexible) >= 1 {
+                // no tagged fields
+                response.push(0);
+            }
+            response.extend_from_slice(&body);
+            let size = (response.len() - 4) as i32;
+            response[..4].copy_from_slice(&size.to_be_bytes());
+            stream.write_all(&response)?;
+        }
+    }
+}
+
+/// A running [`MockBroker`], which stops listening when dropped
+///
+/// Connections that are open then are served until the client closes them.
+pub struct MockBrokerHandle {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    accept: Option<JoinHandle<()>>,
+}
+
+impl fmt::Debug for MockBrokerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockBrokerHandle")
+            .field("addr", &self.addr)
+            .finish()
+    }
+}
+
+impl MockBrokerHandle {
+    /// The address the broker listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The requests received so far, in the order they were read
+    pub fn requests(&self) -> Vec<CapturedRequest> {
+        self.shared.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockBrokerHandle {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        // wake the accepting thread up, for it to see the broker is shutting down
+        let _ = TcpStream::connect(self.addr);
+        if let Some(accept) = self.accept.take() {
+            let _ = accept.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::API_VERSIONS_KEY;
+    use crate::client::{BlockingConnection, Request};
+
+    struct MetadataRequest;
+
+    impl KafkaRequest for MetadataRequest {
+        const API_KEY: i16 = 3;
+        const MIN_VERSION: i16 = 0;
+        const MAX_VERSION: i16 = 12;
+        const FIRST_FLEXIBLE_VERSION: Option<i16> = Some(9);
+        type Response = i32;
+    }
+
+    fn broker() -> MockBrokerHandle {
+        MockBroker::new()
+            .respond::<MetadataRequest>(|request| Ok(request.body::<i32>()? * 2))
+            .respond_raw(API_VERSIONS_KEY, Some(3), |_| Ok(vec![0, 35]))
+            .start()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_respond() {
+        let broker = broker();
+        let mut connection = BlockingConnection::connect(broker.addr())
+            .unwrap()
+            .client_id("test");
+        for version in [1, 9] {
+            let request = Request::<_, i32>::new(3, version, 21i32).flexible(version >= 9);
+            assert_eq!(connection.send(&request).unwrap(), 42);
+        }
+        let api_versions = Request::<_, i16>::new(18, 3, 0i8).flexible(true);
+        assert_eq!(connection.send(&api_versions).unwrap(), 35);
+
+        let requests = broker.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[1].header.api_version, 9);
+        assert_eq!(requests[1].header.client_id.as_deref(), Some("test"));
+        assert_eq!(requests[1].body, [0, 0, 0, 21]);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let broker = broker();
+        let mut connection = BlockingConnection::connect(broker.addr()).unwrap();
+        match *connection
+            .send(&Request::<_, i32>::new(1, 0, 0i32))
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::Io(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert_eq!(broker.requests()[0].header.api_key, 1);
+    }
+}
@@ -0,0 +1,89 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Partitioning records by key
+//!
+//! The Java producer writes the records that have a key to the partition the murmur2 hash of the
+//! key picks. Producers built on this crate must pick the same partitions for the same keys,
+//! or the records of a key end up spread over several partitions, out of order, and compacted
+//! topics keep a value per partition instead of one per key.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::partitioner::partition_for_key;
+//!
+//! assert_eq!(partition_for_key(b"user-42", 12), partition_for_key(b"user-42", 12));
+//! assert!((0..12).contains(&partition_for_key(b"user-42", 12)));
+//! ```
+
+/// The seed of the murmur2 hash of the Java client
+const SEED: u32 = 0x9747_b28c;
+const M: u32 = 0x5bd1_e995;
+const R: u32 = 24;
+
+/// Hashes `data` like `Utils.murmur2` of the Java client does
+pub fn murmur2(data: &[u8]) -> i32 {
+    let mut h = SEED ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        for (i, byte) in rest.iter().enumerate() {
+            h ^= (*byte as u32) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+    h as i32
+}
+
+/// The partition, out of `num_partitions`, that the default partitioner of the Java client
+/// picks for records with `key`
+///
+/// # Panics
+///
+/// If `num_partitions` is not positive.
+pub fn partition_for_key(key: &[u8], num_partitions: i32) -> i32 {
+    assert!(num_partitions > 0, "no partitions to pick from");
+    // like `Utils.toPositive`, which clears the sign bit rather than taking the absolute value
+    (murmur2(key) & 0x7fff_ffff) % num_partitions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_murmur2() {
+        // the values of the tests of the Java client
+        assert_eq!(murmur2(b"21"), -973932308);
+        assert_eq!(murmur2(b"foobar"), -790332482);
+        assert_eq!(murmur2(b"a-little-bit-long-string"), -985981536);
+        assert_eq!(murmur2(b"a-little-bit-longer-string"), -1486304829);
+        assert_eq!(
+            murmur2(b"lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8"),
+            -58897971
+        );
+        assert_eq!(murmur2(b"abc"), 479470107);
+    }
+
+    #[test]
+    fn test_partition_for_key() {
+        assert_eq!(partition_for_key(b"foobar", 1), 0);
+        // -790332482 & 0x7fffffff = 1357151166
+        assert_eq!(partition_for_key(b"foobar", 10), 6);
+        // 1173551340 with the sign bit cleared, while its absolute value would give 6
+        assert_eq!(partition_for_key(b"21", 7), 3);
+    }
+}
@@ -0,0 +1,315 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Produce requests
+//!
+//! A [`ProduceRequestBuilder`] turns the records to write to a partition into a complete
+//! ProduceRequest frame, ready to be written to a broker: size, request header, and a body whose
+//! single record batch holds the records, stamped with the current time by default.
+//!
+//! It covers the common case of producing to one partition, without idempotence. Requests to
+//! several partitions, or with batches built by hand, can use the [`records`](crate::records)
+//! module with a message struct generated from the ProduceRequest definition.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::produce::ProduceRequestBuilder;
+//! use kafka_serde::records::{Compression, Header, Headers};
+//!
+//! let headers: Headers = vec![Header::new("source", Some(b"example".to_vec()))].into();
+//! let records = vec![
+//!     (Some("key"), Some("first"), headers),
+//!     (None, Some("second"), Headers::new()),
+//! ];
+//! let frame = ProduceRequestBuilder::new()
+//!     .acks(1)
+//!     .compression(Compression::None)
+//!     .client_id("example")
+//!     .build(1, "events", 0, records)
+//!     .unwrap();
+//! assert_eq!(&frame[4..8], [0, 0, 0, 8]); // Produce v8
+//! ```
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{ErrorKind, Result};
+use crate::header;
+use crate::records::{Compression, Headers, RecordBatchBuilder};
+use crate::schema::{Field, KafkaValue, Schema, Type};
+use crate::ser::FrameWriter;
+
+/// The API key of Produce
+pub const PRODUCE_KEY: i16 = 0;
+
+/// The first version of Produce with record batches
+const MIN_VERSION: i16 = 3;
+/// The last version of Produce naming topics, which later versions identify by their id
+const MAX_VERSION: i16 = 12;
+
+/// The schema of ProduceRequest, in the versions supported
+fn schema() -> &'static Schema {
+    static SCHEMA: OnceLock<Schema> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        let partition = Type::Struct(vec![
+            Field::new("Index", Type::Int32),
+            Field::new("Records", Type::Records).nullable_versions(..),
+        ]);
+        let topic = Type::Struct(vec![
+            Field::new("Name", Type::String),
+            Field::new("PartitionData", Type::Array(Box::new(partition))),
+        ]);
+        Schema::new(
+            "ProduceRequest",
+            vec![
+                Field::new("TransactionalId", Type::String).nullable_versions(..),
+                Field::new("Acks", Type::Int16),
+                Field::new("TimeoutMs", Type::Int32),
+                Field::new("TopicData", Type::Array(Box::new(topic))),
+            ],
+        )
+        .valid_versions(MIN_VERSION..=MAX_VERSION)
+        .flexible_versions(9..)
+    })
+}
+
+/// Builds ProduceRequest frames writing records to a single partition
+///
+/// Defaults to version 8, the last version before flexible ones, waiting for all in-sync
+/// replicas for up to 30 seconds, without compression.
+#[derive(Debug, Clone)]
+pub struct ProduceRequestBuilder {
+    api_version: i16,
+    acks: i16,
+    timeout_ms: i32,
+    transactional_id: Option<String>,
+    client_id: Option<String>,
+    compression: Compression,
+    timestamp: Option<i64>,
+}
+
+impl Default for ProduceRequestBuilder {
+    fn default() -> Self {
+        ProduceRequestBuilder {
+            api_version: 8,
+            acks: -1,
+            timeout_ms: 30_000,
+            transactional_id: None,
+            client_id: None,
+            compression: Compression::None,
+            timestamp: None,
+        }
+    }
+}
+
+impl ProduceRequestBuilder {
+    /// Creates a builder with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The version of the request, from 3 to 12
+    pub fn api_version(mut self, api_version: i16) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// How many replicas must have the records before the broker answers: 0 for none, not even
+    /// the leader, 1 for the leader, and -1 for all in-sync replicas. Defaults to -1
+    ///
+    /// Brokers don't answer requests with acks=0 at all: their frame is written to the broker,
+    /// without reading a response after it. Connections sending them as a
+    /// [`Request`](crate::client::Request) do the same, with a response of `()`.
+    pub fn acks(mut self, acks: i16) -> Self {
+        self.acks = acks;
+        self
+    }
+
+    /// How long the broker waits for the replicas. Defaults to 30 seconds
+    pub fn timeout_ms(mut self, timeout_ms: i32) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// The transactional id of the producer, if it writes in a transaction
+    pub fn transactional_id(mut self, transactional_id: impl Into<String>) -> Self {
+        self.transactional_id = Some(transactional_id.into());
+        self
+    }
+
+    /// The client id of the request header
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    /// The codec compressing the record batch. Defaults to [`Compression::None`]
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// The timestamp of the records, in milliseconds since the epoch. Defaults to the time the
+    /// request is built, which needs a clock: set it on targets without one, like
+    /// `wasm32-unknown-unknown`
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builds the frame of a request with `correlation_id`, writing `records`, as keys, values
+    /// and headers, to `partition` of `topic`. Fails if there are no records
+    pub fn build<K, V>(
+        &self,
+        correlation_id: i32,
+        topic: &str,
+        partition: i32,
+        records: impl IntoIterator<Item = (Option<K>, Option<V>, Headers)>,
+    ) -> Result<Vec<u8>>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let schema = schema();
+        let flexible = schema.is_flexible(self.api_version);
+        // brokers take a single batch per partition
+        let mut batch = RecordBatchBuilder::new()
+            .max_batch_size(usize::MAX)
+            .compression(self.compression);
+        let timestamp = self.timestamp.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_millis() as i64)
+        });
+        for (key, value, headers) in records {
+            let key = key.map(|key| key.as_ref().to_vec());
+            let value = value.map(|value| value.as_ref().to_vec());
+            batch.append(timestamp, key, value, headers);
+        }
+        let mut records = Vec::new();
+        match batch.build().first() {
+            Some(batch) => batch.encode(&mut records)?,
+            None => return Err(ErrorKind::Custom("no records to produce".to_owned()).into()),
+        }
+
+        let partition = KafkaValue::Struct(vec![
+            ("Index".to_owned(), KafkaValue::Int32(partition)),
+            ("Records".to_owned(), KafkaValue::Records(records)),
+        ]);
+        let topic = KafkaValue::Struct(vec![
+            ("Name".to_owned(), KafkaValue::String(topic.to_owned())),
+            (
+                "PartitionData".to_owned(),
+                KafkaValue::Array(vec![partition]),
+            ),
+        ]);
+        let transactional_id = match self.transactional_id {
+            Some(ref id) => KafkaValue::String(id.clone()),
+            None => KafkaValue::Null,
+        };
+        let request = KafkaValue::Struct(vec![
+            ("TransactionalId".to_owned(), transactional_id),
+            ("Acks".to_owned(), KafkaValue::Int16(self.acks)),
+            ("TimeoutMs".to_owned(), KafkaValue::Int32(self.timeout_ms)),
+            ("TopicData".to_owned(), KafkaValue::Array(vec![topic])),
+        ]);
+
+        let mut frame = FrameWriter::new();
+        frame.size_prefixed(|frame| {
+            header::write_request_header(
+                frame.buf_mut(),
+                PRODUCE_KEY,
+                self.api_version,
+                flexible,
+                correlation_id,
+                self.client_id.as_deref(),
+            )?;
+            schema.encode(self.api_version, &request, frame.buf_mut())
+        })?;
+        Ok(frame.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::header::split_request;
+    use crate::records::{Header, RecordBatch};
+
+    type Entry = (Option<&'static str>, Option<Vec<u8>>, Headers);
+
+    fn records() -> Vec<Entry> {
+        vec![
+            (Some("k"), Some(b"a".to_vec()), Headers::new()),
+            (None, None, vec![Header::new("h", Some(vec![1]))].into()),
+        ]
+    }
+
+    #[test]
+    fn test_build() {
+        for version in [3, 8, 9, 12] {
+            let frame = ProduceRequestBuilder::new()
+                .api_version(version)
+                .transactional_id("tx")
+                .client_id("client")
+                .timestamp(1_600_000_000_000)
+                .build(7, "topic", 2, records())
+                .unwrap();
+            let size = i32::from_be_bytes([frame[0], frame[1], frame[2], frame[3]]);
+            assert_eq!(size as usize, frame.len() - 4);
+
+            let (header, body) = split_request(&frame[4..], |_, version| version >= 9).unwrap();
+            assert_eq!(header.api_key, PRODUCE_KEY);
+            assert_eq!(header.api_version, version);
+            assert_eq!(header.correlation_id, 7);
+            assert_eq!(header.client_id.as_deref(), Some("client"));
+
+            let request = schema().decode(version, body).unwrap();
+            assert_eq!(
+                request.get("TransactionalId").and_then(|id| id.as_str()),
+                Some("tx")
+            );
+            assert_eq!(request.get("Acks").and_then(|acks| acks.as_i64()), Some(-1));
+            let topic = &request.get("TopicData").unwrap().as_array().unwrap()[0];
+            assert_eq!(
+                topic.get("Name").and_then(|name| name.as_str()),
+                Some("topic")
+            );
+            let partition = &topic.get("PartitionData").unwrap().as_array().unwrap()[0];
+            assert_eq!(partition.get("Index").and_then(|i| i.as_i64()), Some(2));
+            let records = match partition.get("Records") {
+                Some(KafkaValue::Records(records)) => records,
+                records => panic!("unexpected records {:?}", records),
+            };
+            let batch = RecordBatch::decode(records).unwrap();
+            assert_eq!(batch.records.len(), 2);
+            assert_eq!(batch.records[0].key.as_deref(), Some(&b"k"[..]));
+            assert_eq!(
+                batch.records[1].headers.get("h").unwrap().value,
+                Some(vec![1])
+            );
+            assert_eq!(batch.producer_id, -1);
+            assert_eq!(batch.max_timestamp, 1_600_000_000_000);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let builder = ProduceRequestBuilder::new();
+        let none: Vec<(Option<&str>, Option<&str>, Headers)> = Vec::new();
+        match *builder.build(1, "topic", 0, none).unwrap_err().kind() {
+            ErrorKind::Custom(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert!(builder
+            .clone()
+            .api_version(2)
+            .build(1, "topic", 0, records())
+            .is_err());
+        assert!(builder
+            .api_version(13)
+            .build(1, "topic", 0, records())
+            .is_err());
+    }
+}
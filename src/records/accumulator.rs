@@ -0,0 +1,253 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::{Compression, Headers, RecordBatch, RecordBatchBuilder, DEFAULT_MAX_BATCH_SIZE};
+
+/// A batch of records ready to be produced to a partition, see [`RecordAccumulator`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadyBatch {
+    /// The topic to produce to
+    pub topic: String,
+    /// The partition to produce to
+    pub partition: i32,
+    /// The records
+    pub batch: RecordBatch,
+}
+
+/// The records appended to a partition and not handed over yet
+#[derive(Debug)]
+struct Pending {
+    builder: RecordBatchBuilder,
+    // when the first record of the batch being filled was appended
+    since: Instant,
+}
+
+/// Groups records into batches per partition, like the Java producer
+///
+/// A batch is ready once it reaches the batch size, or once its first record lingered for the
+/// linger time, to give the records that follow a chance to share its request. Ready batches
+/// are handed over by [`ready`](RecordAccumulator::ready), for the caller to produce them, e.g.
+/// one request per broker with the batches of the partitions it leads.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::records::{Headers, RecordAccumulator};
+/// use std::time::Duration;
+///
+/// let mut accumulator = RecordAccumulator::new()
+///     .batch_size(1024)
+///     .linger(Duration::from_millis(5));
+/// accumulator.append("events", 0, 1_600_000_000_000, None, Some(b"a".to_vec()), Headers::new());
+/// accumulator.append("events", 1, 1_600_000_000_000, None, Some(b"b".to_vec()), Headers::new());
+///
+/// // the batches are neither full nor lingered for long enough
+/// assert!(accumulator.ready().is_empty());
+/// std::thread::sleep(Duration::from_millis(5));
+/// assert_eq!(accumulator.ready().len(), 2);
+/// assert!(accumulator.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct RecordAccumulator {
+    batch_size: usize,
+    linger: Duration,
+    compression: Compression,
+    pending: HashMap<(String, i32), Pending>,
+    // the batches that filled up, in order
+    full: Vec<ReadyBatch>,
+}
+
+impl Default for RecordAccumulator {
+    fn default() -> Self {
+        RecordAccumulator {
+            batch_size: DEFAULT_MAX_BATCH_SIZE,
+            linger: Duration::from_millis(5),
+            compression: Compression::None,
+            pending: HashMap::new(),
+            full: Vec::new(),
+        }
+    }
+}
+
+impl RecordAccumulator {
+    /// Creates an accumulator with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The encoded size of batches, before compression, from which they are ready. Defaults to
+    /// [`DEFAULT_MAX_BATCH_SIZE`], like the `batch.size` of the Java producer
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// How long batches wait for more records before they are ready. Defaults to 5
+    /// milliseconds, like the `linger.ms` of the Java producer
+    pub fn linger(mut self, linger: Duration) -> Self {
+        self.linger = linger;
+        self
+    }
+
+    /// The codec used to compress the batches. Defaults to [`Compression::None`]
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Appends a record to `partition` of `topic`, with the given timestamp in milliseconds
+    /// since the epoch
+    pub fn append(
+        &mut self,
+        topic: &str,
+        partition: i32,
+        timestamp: i64,
+        key: Option<Vec<u8>>,
+        value: Option<Vec<u8>>,
+        headers: Headers,
+    ) {
+        let (batch_size, compression) = (self.batch_size, self.compression);
+        let pending = self
+            .pending
+            .entry((topic.to_owned(), partition))
+            .or_insert_with(|| Pending {
+                builder: RecordBatchBuilder::new()
+                    .max_batch_size(batch_size)
+                    .compression(compression),
+                since: Instant::now(),
+            });
+        pending.builder.append(timestamp, key, value, headers);
+        let full = pending.builder.take_full();
+        if !full.is_empty() {
+            // the record starts a new batch
+            pending.since = Instant::now();
+        }
+        self.full.extend(full.into_iter().map(|batch| ReadyBatch {
+            topic: topic.to_owned(),
+            partition,
+            batch,
+        }));
+    }
+
+    /// Hands over the batches that are full, then those that lingered for long enough, oldest
+    /// first
+    pub fn ready(&mut self) -> Vec<ReadyBatch> {
+        let now = Instant::now();
+        let linger = self.linger;
+        self.take(|pending| now.saturating_duration_since(pending.since) >= linger)
+    }
+
+    /// Hands over all batches, ready or not, e.g. before shutting down
+    pub fn flush(&mut self) -> Vec<ReadyBatch> {
+        self.take(|_| true)
+    }
+
+    /// When the next batch will be ready, if there are records, for the caller to call
+    /// [`ready`](RecordAccumulator::ready) then. In the past if some batches are ready
+    pub fn next_ready(&self) -> Option<Instant> {
+        if !self.full.is_empty() {
+            return Some(Instant::now());
+        }
+        self.pending
+            .values()
+            .map(|pending| pending.since + self.linger)
+            .min()
+    }
+
+    /// Whether there are no records left to hand over
+    pub fn is_empty(&self) -> bool {
+        self.full.is_empty() && self.pending.is_empty()
+    }
+
+    fn take(&mut self, mut is_ready: impl FnMut(&Pending) -> bool) -> Vec<ReadyBatch> {
+        let mut lingered: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| is_ready(pending))
+            .map(|(key, pending)| (pending.since, key.clone()))
+            .collect();
+        lingered.sort();
+
+        let mut ready = std::mem::take(&mut self.full);
+        for (_, key) in lingered {
+            let pending = match self.pending.remove(&key) {
+                Some(pending) => pending,
+                None => continue,
+            };
+            let (topic, partition) = key;
+            ready.extend(pending.builder.build().into_iter().map(|batch| ReadyBatch {
+                topic: topic.clone(),
+                partition,
+                batch,
+            }));
+        }
+        ready
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::records::RECORD_BATCH_OVERHEAD;
+
+    fn append(accumulator: &mut RecordAccumulator, partition: i32, value: u8) {
+        accumulator.append(
+            "topic",
+            partition,
+            1000 + value as i64,
+            None,
+            Some(vec![value; 20]),
+            Headers::new(),
+        );
+    }
+
+    #[test]
+    fn test_batch_size() {
+        let mut accumulator = RecordAccumulator::new()
+            .batch_size(RECORD_BATCH_OVERHEAD + 60)
+            .linger(Duration::from_secs(3600));
+        for value in 0..5 {
+            append(&mut accumulator, 0, value);
+        }
+        append(&mut accumulator, 1, 9);
+
+        // two batches of two records are full, the last record of each partition lingers
+        let ready = accumulator.ready();
+        let records: Vec<_> = ready
+            .iter()
+            .map(|ready| (ready.partition, ready.batch.records.len()))
+            .collect();
+        assert_eq!(records, [(0, 2), (0, 2)]);
+        assert_eq!(ready[1].batch.base_timestamp, 1002);
+        assert!(accumulator.ready().is_empty());
+        assert!(accumulator.next_ready().unwrap() > Instant::now());
+
+        let flushed = accumulator.flush();
+        let mut records: Vec<_> = flushed
+            .iter()
+            .map(|ready| (ready.partition, ready.batch.records[0].value.clone()))
+            .collect();
+        records.sort();
+        assert_eq!(records, [(0, Some(vec![4; 20])), (1, Some(vec![9; 20]))]);
+        assert!(accumulator.is_empty());
+        assert_eq!(accumulator.next_ready(), None);
+    }
+
+    #[test]
+    fn test_linger() {
+        let mut accumulator = RecordAccumulator::new().linger(Duration::ZERO);
+        append(&mut accumulator, 0, 0);
+        append(&mut accumulator, 0, 1);
+        append(&mut accumulator, 2, 2);
+        let ready = accumulator.ready();
+        assert_eq!(ready.len(), 2);
+        assert_eq!(ready[0].partition, 0);
+        assert_eq!(ready[0].batch.records.len(), 2);
+        assert_eq!(ready[1].topic, "topic");
+        assert!(accumulator.is_empty());
+    }
+}
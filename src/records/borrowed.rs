@@ -0,0 +1,301 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::batch::{batch_prefix, put_crc_covered_header, write_batch};
+use super::crc::crc32c_append;
+use super::{nullable_bytes_size, varint_len_size, BatchHeader, Compression, HeaderRef};
+use crate::error::Result;
+use crate::{ser, varint};
+use std::io::{self, IoSlice};
+
+/// Slices shorter than this are copied: a separate I/O buffer costs more than copying them
+const MIN_BORROWED_LEN: usize = 64;
+
+/// A record whose key, value and headers are borrowed from the caller, to be produced in a
+/// [`BorrowedBatch`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BorrowedRecord<'a> {
+    /// Record attributes. Unused by the protocol so far
+    pub attributes: i8,
+    /// Timestamp, relative to the batch's base timestamp
+    pub timestamp_delta: i64,
+    /// Offset, relative to the batch's base offset
+    pub offset_delta: i32,
+    /// The record key
+    pub key: Option<&'a [u8]>,
+    /// The record value
+    pub value: Option<&'a [u8]>,
+    /// The record headers
+    pub headers: &'a [HeaderRef<'a>],
+}
+
+impl BorrowedRecord<'_> {
+    fn body_size(&self) -> usize {
+        std::mem::size_of::<i8>()
+            + varint::varlong_size(self.timestamp_delta)
+            + varint::varint_size(self.offset_delta)
+            + nullable_bytes_size(self.key)
+            + nullable_bytes_size(self.value)
+            + varint_len_size(self.headers.len())
+            + self
+                .headers
+                .iter()
+                .map(|h| nullable_bytes_size(Some(h.key.as_bytes())) + nullable_bytes_size(h.value))
+                .sum::<usize>()
+    }
+}
+
+/// A v2 record batch whose records are borrowed from the caller, and written without being
+/// copied
+///
+/// Encoding an uncompressed batch only writes the framing (lengths, offsets, the batch header)
+/// into a buffer: keys and values are handed to the writer as they are, with vectored writes.
+/// Compressed batches can't avoid the copy, and are encoded like a
+/// [`RecordBatch`](super::RecordBatch).
+///
+/// # Examples
+/// ```
+/// use kafka_serde::records::{BorrowedBatch, BorrowedRecord, RecordBatch};
+///
+/// let payload = vec![0u8; 4096];
+/// let batch = BorrowedBatch {
+///     header: RecordBatch::default().header().unwrap(),
+///     records: vec![BorrowedRecord {
+///         value: Some(&payload),
+///         ..Default::default()
+///     }],
+/// };
+///
+/// let mut buf = Vec::new();
+/// batch.encode(&mut buf).unwrap();
+/// let decoded = RecordBatch::decode(&buf).unwrap();
+/// assert_eq!(decoded.records[0].value.as_deref(), Some(&payload[..]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorrowedBatch<'a> {
+    /// The fixed-size fields of the batch. The record count is ignored: the number of records
+    /// is written instead
+    pub header: BatchHeader,
+    /// The records in this batch
+    pub records: Vec<BorrowedRecord<'a>>,
+}
+
+impl BorrowedBatch<'_> {
+    /// Encodes the batch into `writer`, computing the checksum over the borrowed data in place
+    pub fn encode<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        let header = BatchHeader {
+            record_count: ser::len_i32("records", self.records.len())?,
+            ..self.header
+        };
+        let compression = header.compression()?;
+
+        let mut segments = Segments::default();
+        if compression == Compression::None {
+            put_crc_covered_header(&header, &mut segments.scratch);
+        }
+        for record in &self.records {
+            segments.put_record(record)?;
+        }
+        if compression != Compression::None {
+            // compression copies the records anyway
+            let records = compression.compress(&segments.to_vec())?;
+            return write_batch(&header, &records, writer);
+        }
+
+        let slices = segments.io_slices();
+        let body_len = slices.iter().map(|s| s.len()).sum();
+        let crc = slices.iter().fold(0, |crc, s| crc32c_append(crc, s));
+        let prefix = batch_prefix(&header, body_len, crc)?;
+        let mut all = Vec::with_capacity(slices.len() + 1);
+        all.push(IoSlice::new(&prefix));
+        all.extend(slices);
+        write_all_vectored(writer, &mut all)?;
+        Ok(())
+    }
+}
+
+/// Encoded data made of the bytes written to `scratch`, with borrowed slices in between
+#[derive(Default)]
+struct Segments<'a> {
+    scratch: Vec<u8>,
+    // the borrowed slices, and where they go in scratch
+    borrowed: Vec<(usize, &'a [u8])>,
+}
+
+impl<'a> Segments<'a> {
+    fn put_bytes(&mut self, bytes: Option<&'a [u8]>) -> Result<()> {
+        match bytes {
+            Some(bytes) if bytes.len() >= MIN_BORROWED_LEN => {
+                varint::write_varint(&mut self.scratch, ser::len_i32("bytes", bytes.len())?)?;
+                self.borrowed.push((self.scratch.len(), bytes));
+                Ok(())
+            }
+            bytes => super::write_nullable_bytes(&mut self.scratch, bytes),
+        }
+    }
+
+    fn put_record(&mut self, record: &BorrowedRecord<'a>) -> Result<()> {
+        varint::write_varint(
+            &mut self.scratch,
+            ser::len_i32("record", record.body_size())?,
+        )?;
+        self.scratch.push(record.attributes as u8);
+        varint::write_varlong(&mut self.scratch, record.timestamp_delta)?;
+        varint::write_varint(&mut self.scratch, record.offset_delta)?;
+        self.put_bytes(record.key)?;
+        self.put_bytes(record.value)?;
+        varint::write_varint(
+            &mut self.scratch,
+            ser::len_i32("headers", record.headers.len())?,
+        )?;
+        for header in record.headers {
+            self.put_bytes(Some(header.key.as_bytes()))?;
+            self.put_bytes(header.value)?;
+        }
+        Ok(())
+    }
+
+    fn io_slices(&self) -> Vec<IoSlice<'_>> {
+        let mut slices = Vec::with_capacity(self.borrowed.len() * 2 + 1);
+        let mut start = 0;
+        for &(pos, bytes) in &self.borrowed {
+            slices.push(IoSlice::new(&self.scratch[start..pos]));
+            slices.push(IoSlice::new(bytes));
+            start = pos;
+        }
+        slices.push(IoSlice::new(&self.scratch[start..]));
+        slices
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        self.io_slices()
+            .iter()
+            .flat_map(|s| s.iter())
+            .copied()
+            .collect()
+    }
+}
+
+/// Writes all of `slices`, like `write_all` does for a single buffer
+fn write_all_vectored<W: io::Write>(
+    writer: &mut W,
+    mut slices: &mut [IoSlice<'_>],
+) -> io::Result<()> {
+    IoSlice::advance_slices(&mut slices, 0);
+    while !slices.is_empty() {
+        match writer.write_vectored(slices) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => IoSlice::advance_slices(&mut slices, n),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::records::{Header, Headers, Record, RecordBatch};
+
+    fn sample<'a>(large: &'a [u8], headers: &'a [HeaderRef<'a>]) -> BorrowedBatch<'a> {
+        BorrowedBatch {
+            header: RecordBatch {
+                base_offset: 10,
+                last_offset_delta: 1,
+                ..Default::default()
+            }
+            .header()
+            .unwrap(),
+            records: vec![
+                BorrowedRecord {
+                    key: Some(b"k"),
+                    value: Some(large),
+                    headers,
+                    ..Default::default()
+                },
+                BorrowedRecord {
+                    offset_delta: 1,
+                    timestamp_delta: 5,
+                    value: None,
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    fn owned(large: &[u8]) -> RecordBatch {
+        RecordBatch {
+            base_offset: 10,
+            last_offset_delta: 1,
+            records: vec![
+                Record {
+                    key: Some(b"k".to_vec()),
+                    value: Some(large.to_vec()),
+                    headers: Headers::from(vec![Header::new("h", Some(large.to_vec()))]),
+                    ..Default::default()
+                },
+                Record {
+                    offset_delta: 1,
+                    timestamp_delta: 5,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// Accepts at most 7 bytes per write, to exercise partial vectored writes
+    struct Trickle(Vec<u8>);
+
+    impl io::Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let len = buf.len().min(7);
+            self.0.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_same_as_owned() {
+        let large: Vec<u8> = (0..200).collect();
+        let headers = [HeaderRef {
+            key: "h",
+            value: Some(&large),
+        }];
+        let mut expected = Vec::new();
+        owned(&large).encode(&mut expected).unwrap();
+
+        let mut buf = Vec::new();
+        sample(&large, &headers).encode(&mut buf).unwrap();
+        assert_eq!(buf, expected);
+
+        let mut trickle = Trickle(Vec::new());
+        sample(&large, &headers).encode(&mut trickle).unwrap();
+        assert_eq!(trickle.0, expected);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_compressed() {
+        let large: Vec<u8> = (0..200).collect();
+        let headers = [HeaderRef {
+            key: "h",
+            value: Some(&large),
+        }];
+        let mut batch = sample(&large, &headers);
+        batch.header.attributes = Compression::Gzip.apply(batch.header.attributes);
+        let mut buf = Vec::new();
+        batch.encode(&mut buf).unwrap();
+        assert_eq!(
+            RecordBatch::decode(&buf).unwrap(),
+            owned(&large).with_compression(Compression::Gzip)
+        );
+    }
+}
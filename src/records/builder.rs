@@ -0,0 +1,311 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::{
+    Compression, Headers, Record, RecordBatch, RecordSerializer, TimestampType,
+    RECORD_BATCH_OVERHEAD,
+};
+use crate::error::Result;
+
+/// Default upper bound for the encoded size of a batch, matching the Java producer's `batch.size`
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 16 * 1024;
+
+/// Accumulates records into one or more [`RecordBatch`]es
+///
+/// The builder tracks the encoded size of the batch being filled, and starts a new one whenever
+/// appending a record would take it over the maximum batch size. A record that is larger than
+/// the maximum on its own still gets a batch of its own. Sizes are computed before compression,
+/// so compressed batches will usually be smaller than the limit.
+///
+/// Offsets and timestamps are stored as deltas from the first record of each batch, and the
+/// max timestamp of each batch is computed from its records.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::records::{Headers, RecordBatchBuilder};
+///
+/// let mut builder = RecordBatchBuilder::new().max_batch_size(128);
+/// for i in 0..10u8 {
+///     builder.append(1_600_000_000_000, None, Some(vec![i; 20]), Headers::new());
+/// }
+/// let batches = builder.build();
+/// assert!(batches.len() > 1);
+/// assert_eq!(batches.iter().map(|b| b.records.len()).sum::<usize>(), 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecordBatchBuilder {
+    max_batch_size: usize,
+    compression: Compression,
+    timestamp_type: TimestampType,
+    next_offset: i64,
+    current: RecordBatch,
+    current_size: usize,
+    done: Vec<RecordBatch>,
+}
+
+impl Default for RecordBatchBuilder {
+    fn default() -> Self {
+        RecordBatchBuilder {
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            compression: Compression::None,
+            timestamp_type: TimestampType::CreateTime,
+            next_offset: 0,
+            current: RecordBatch::default(),
+            current_size: RECORD_BATCH_OVERHEAD,
+            done: Vec::new(),
+        }
+    }
+}
+
+impl RecordBatchBuilder {
+    /// Creates a builder with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum encoded size of each batch. Defaults to [`DEFAULT_MAX_BATCH_SIZE`]
+    pub fn max_batch_size(mut self, size: usize) -> Self {
+        self.max_batch_size = size;
+        self
+    }
+
+    /// The codec used to compress the batches. Defaults to [`Compression::None`]
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// What the record timestamps represent. Defaults to [`TimestampType::CreateTime`]
+    pub fn timestamp_type(mut self, timestamp_type: TimestampType) -> Self {
+        self.timestamp_type = timestamp_type;
+        self
+    }
+
+    /// The offset of the first record. Defaults to 0, which is what producers send
+    ///
+    /// Following batches continue the numbering where the previous one left off.
+    pub fn base_offset(mut self, offset: i64) -> Self {
+        self.next_offset = offset;
+        self.current.base_offset = offset;
+        self
+    }
+
+    /// Appends a record with the given timestamp, in milliseconds since the epoch
+    pub fn append(
+        &mut self,
+        timestamp: i64,
+        key: Option<Vec<u8>>,
+        value: Option<Vec<u8>>,
+        headers: Headers,
+    ) {
+        let mut record = self.record_for(timestamp, key, value, headers);
+        let mut size = record.encoded_size();
+        if !self.current.records.is_empty() && self.current_size + size > self.max_batch_size {
+            self.close_current();
+            record = self.record_for(timestamp, record.key, record.value, record.headers);
+            size = record.encoded_size();
+        }
+
+        if self.current.records.is_empty() {
+            self.current.base_timestamp = timestamp;
+            self.current.max_timestamp = timestamp;
+        }
+        self.current.max_timestamp = self.current.max_timestamp.max(timestamp);
+        self.current.last_offset_delta = record.offset_delta;
+        self.current.records.push(record);
+        self.current_size += size;
+        self.next_offset += 1;
+    }
+
+    /// Appends a record whose key and value are encoded with `keys` and `values`
+    pub fn append_with<K, V, KS, VS>(
+        &mut self,
+        keys: &KS,
+        values: &VS,
+        timestamp: i64,
+        key: Option<&K>,
+        value: Option<&V>,
+        headers: Headers,
+    ) -> Result<()>
+    where
+        K: ?Sized,
+        V: ?Sized,
+        KS: RecordSerializer<K>,
+        VS: RecordSerializer<V>,
+    {
+        let key = key.map(|key| keys.serialize(key)).transpose()?;
+        let value = value.map(|value| values.serialize(value)).transpose()?;
+        self.append(timestamp, key, value, headers);
+        Ok(())
+    }
+
+    /// Encoded size of the batch currently being filled, before compression
+    pub fn current_batch_size(&self) -> usize {
+        self.current_size
+    }
+
+    /// Whether no records were appended yet
+    pub fn is_empty(&self) -> bool {
+        self.done.is_empty() && self.current.records.is_empty()
+    }
+
+    /// Takes the batches that are full, leaving the one being filled
+    pub(crate) fn take_full(&mut self) -> Vec<RecordBatch> {
+        std::mem::take(&mut self.done)
+    }
+
+    /// Finishes the last batch and returns all batches, in order
+    pub fn build(mut self) -> Vec<RecordBatch> {
+        if !self.current.records.is_empty() {
+            self.close_current();
+        }
+        self.done
+    }
+
+    fn record_for(
+        &self,
+        timestamp: i64,
+        key: Option<Vec<u8>>,
+        value: Option<Vec<u8>>,
+        headers: Headers,
+    ) -> Record {
+        let (timestamp_delta, offset_delta) = if self.current.records.is_empty() {
+            (0, 0)
+        } else {
+            (
+                timestamp - self.current.base_timestamp,
+                (self.next_offset - self.current.base_offset) as i32,
+            )
+        };
+        Record {
+            attributes: 0,
+            timestamp_delta,
+            offset_delta,
+            key,
+            value,
+            headers,
+        }
+    }
+
+    fn close_current(&mut self) {
+        let next = RecordBatch {
+            base_offset: self.next_offset,
+            ..Default::default()
+        };
+        let mut batch = std::mem::replace(&mut self.current, next);
+        batch.attributes = self.timestamp_type.apply(self.compression.id() as i16);
+        self.done.push(batch);
+        self.current_size = RECORD_BATCH_OVERHEAD;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::records::Header;
+
+    #[test]
+    fn test_single_batch() {
+        let mut builder = RecordBatchBuilder::new().base_offset(10);
+        builder.append(
+            1000,
+            Some(b"k".to_vec()),
+            Some(b"a".to_vec()),
+            Headers::new(),
+        );
+        builder.append(990, None, Some(b"b".to_vec()), Headers::new());
+        builder.append(
+            1005,
+            None,
+            None,
+            vec![Header::new("h", Some(vec![1]))].into(),
+        );
+        let size = builder.current_batch_size();
+        let batches = builder.build();
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+        assert_eq!(batch.base_offset, 10);
+        assert_eq!(batch.last_offset_delta, 2);
+        assert_eq!(batch.base_timestamp, 1000);
+        assert_eq!(batch.max_timestamp, 1005);
+        let deltas: Vec<_> = batch
+            .records
+            .iter()
+            .map(|r| (r.offset_delta, r.timestamp_delta))
+            .collect();
+        assert_eq!(deltas, [(0, 0), (1, -10), (2, 5)]);
+
+        let mut buf = Vec::new();
+        batch.encode(&mut buf).unwrap();
+        assert_eq!(buf.len(), size);
+    }
+
+    #[test]
+    fn test_split() {
+        let mut builder = RecordBatchBuilder::new().max_batch_size(RECORD_BATCH_OVERHEAD + 60);
+        for i in 0..5 {
+            builder.append(2000 + i, None, Some(vec![0u8; 20]), Headers::new());
+        }
+        let batches = builder.build();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].records.len(), 2);
+        assert_eq!(batches[1].base_offset, 2);
+        assert_eq!(batches[1].base_timestamp, 2002);
+        assert_eq!(batches[1].records[0].offset_delta, 0);
+        assert_eq!(batches[1].records[0].timestamp_delta, 0);
+        assert_eq!(batches[2].base_offset, 4);
+        assert_eq!(batches[2].last_offset_delta, 0);
+
+        for batch in &batches {
+            let mut buf = Vec::new();
+            batch.encode(&mut buf).unwrap();
+            assert!(buf.len() <= RECORD_BATCH_OVERHEAD + 60);
+        }
+    }
+
+    #[test]
+    fn test_oversized_record() {
+        let mut builder = RecordBatchBuilder::new().max_batch_size(100);
+        builder.append(0, None, Some(vec![1]), Headers::new());
+        builder.append(0, None, Some(vec![0u8; 500]), Headers::new());
+        builder.append(0, None, Some(vec![2]), Headers::new());
+        let batches = builder.build();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[1].records[0].value.as_ref().unwrap().len(), 500);
+    }
+
+    #[test]
+    fn test_timestamp_type() {
+        let mut builder = RecordBatchBuilder::new().timestamp_type(TimestampType::LogAppendTime);
+        builder.append(5, None, None, Headers::new());
+        builder.append(7, None, None, Headers::new());
+        let batches = builder.build();
+        assert_eq!(batches[0].timestamp_type(), TimestampType::LogAppendTime);
+        assert_eq!(batches[0].max_timestamp, 7);
+        assert_eq!(batches[0].timestamp_of(&batches[0].records[0]), 7);
+    }
+
+    #[test]
+    fn test_empty() {
+        let builder = RecordBatchBuilder::new();
+        assert!(builder.is_empty());
+        assert!(builder.build().is_empty());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_compression() {
+        let mut builder = RecordBatchBuilder::new().compression(Compression::Gzip);
+        builder.append(0, None, Some(vec![0u8; 1000]), Headers::new());
+        let batches = builder.build();
+        assert_eq!(batches[0].compression().unwrap(), Compression::Gzip);
+
+        let mut buf = Vec::new();
+        batches[0].encode(&mut buf).unwrap();
+        assert!(buf.len() < 1000);
+        assert_eq!(RecordBatch::decode(&buf).unwrap(), batches[0]);
+    }
+}
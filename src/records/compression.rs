@@ -0,0 +1,162 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use crate::error::{ErrorKind, Result};
+#[cfg(feature = "gzip")]
+use std::io::Write;
+use std::io::{self, Read};
+
+/// Compression codecs, as stored in the lowest three bits of the record attributes
+///
+/// Codecs are enabled through cargo features of the same name. Decoding data that uses a codec
+/// that is not enabled fails with [`ErrorKind::UnsupportedCompression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Compression {
+    /// No compression
+    None,
+    /// gzip (feature `gzip`)
+    Gzip,
+    /// snappy (feature `snappy`)
+    Snappy,
+    /// lz4
+    Lz4,
+    /// zstd (feature `zstd`)
+    Zstd,
+}
+
+const CODEC_MASK: i16 = 0x07;
+
+impl Compression {
+    /// Extracts the codec from a set of record attributes
+    pub fn from_attributes(attributes: i16) -> Result<Compression> {
+        match attributes & CODEC_MASK {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Gzip),
+            2 => Ok(Compression::Snappy),
+            3 => Ok(Compression::Lz4),
+            4 => Ok(Compression::Zstd),
+            id => Err(ErrorKind::UnsupportedCompression(id as i8).into()),
+        }
+    }
+
+    /// The codec id, as stored in the record attributes
+    pub fn id(self) -> i8 {
+        match self {
+            Compression::None => 0,
+            Compression::Gzip => 1,
+            Compression::Snappy => 2,
+            Compression::Lz4 => 3,
+            Compression::Zstd => 4,
+        }
+    }
+
+    /// Returns `attributes` with the codec bits set to this codec
+    pub fn apply(self, attributes: i16) -> i16 {
+        (attributes & !CODEC_MASK) | self.id() as i16
+    }
+
+    /// Compresses `data` with this codec
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut enc = flate2::write::GzEncoder::new(
+                    Vec::with_capacity(data.len() / 2),
+                    flate2::Compression::default(),
+                );
+                enc.write_all(data)?;
+                Ok(enc.finish()?)
+            }
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => super::snappy::compress(data),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+            codec => Err(ErrorKind::UnsupportedCompression(codec.id()).into()),
+        }
+    }
+
+    /// Decompresses `data`, which was compressed with this codec
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() * 2);
+        self.decompress_reader(data)?.read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    /// Returns a reader that decompresses `data` as it is read
+    ///
+    /// This bounds the memory needed to go through large batches, and lets callers stop early
+    /// without paying for decompressing the rest.
+    pub fn decompress_reader<'a>(self, data: &'a [u8]) -> Result<Box<dyn io::Read + 'a>> {
+        match self {
+            Compression::None => Ok(Box::new(data)),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Ok(Box::new(flate2::read::GzDecoder::new(data))),
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => super::snappy::reader(data),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Box::new(zstd::Decoder::with_buffer(data)?)),
+            codec => Err(ErrorKind::UnsupportedCompression(codec.id()).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_attributes() {
+        assert_eq!(Compression::from_attributes(0).unwrap(), Compression::None);
+        assert_eq!(
+            Compression::from_attributes(0x11).unwrap(),
+            Compression::Gzip
+        );
+        assert_eq!(Compression::from_attributes(4).unwrap(), Compression::Zstd);
+        assert!(Compression::from_attributes(7).is_err());
+        for id in 0..5 {
+            assert_eq!(Compression::from_attributes(id).unwrap().id(), id as i8);
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip() {
+        let compressed = Compression::Gzip.compress(b"kafka kafka kafka").unwrap();
+        let out = Compression::Gzip.decompress(&compressed).unwrap();
+        assert_eq!(out, b"kafka kafka kafka");
+    }
+
+    #[cfg(feature = "snappy")]
+    #[test]
+    fn test_snappy() {
+        let compressed = Compression::Snappy.compress(b"kafka kafka kafka").unwrap();
+        let out = Compression::Snappy.decompress(&compressed).unwrap();
+        assert_eq!(out, b"kafka kafka kafka");
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        let compressed = Compression::Zstd.compress(b"kafka kafka kafka").unwrap();
+        let out = Compression::Zstd.decompress(&compressed).unwrap();
+        assert_eq!(out, b"kafka kafka kafka");
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(Compression::Zstd.apply(0x09), 0x0c);
+        assert_eq!(Compression::None.apply(0x0a), 0x08);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        match *Compression::from_attributes(6).unwrap_err().kind() {
+            ErrorKind::UnsupportedCompression(6) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
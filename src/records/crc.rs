@@ -0,0 +1,110 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! CRC32C (Castagnoli), which protects v2 record batches, and CRC32 (IEEE), which protects
+//! legacy messages.
+//!
+//! The table-driven implementation here is portable but slow on large batches. The `crc32c`
+//! feature swaps it for one using the CRC instructions of the CPU, when it has them.
+
+const CASTAGNOLI: u32 = 0x82f6_3b78;
+const IEEE: u32 = 0xedb8_8320;
+
+const fn make_table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ polynomial
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+static TABLE: [u32; 256] = make_table(CASTAGNOLI);
+static IEEE_TABLE: [u32; 256] = make_table(IEEE);
+
+/// Computes the CRC32C checksum of `data`
+pub(crate) fn crc32c(data: &[u8]) -> u32 {
+    crc32c_append(0, data)
+}
+
+/// Extends `crc`, the checksum of some data, into the checksum of that data followed by `data`
+#[cfg(feature = "crc32c")]
+pub(crate) fn crc32c_append(crc: u32, data: &[u8]) -> u32 {
+    crc32c::crc32c_append(crc, data)
+}
+
+/// Extends `crc`, the checksum of some data, into the checksum of that data followed by `data`
+#[cfg(not(feature = "crc32c"))]
+pub(crate) fn crc32c_append(crc: u32, data: &[u8]) -> u32 {
+    software_crc32c_append(crc, data)
+}
+
+#[cfg_attr(feature = "crc32c", allow(dead_code))]
+fn software_crc32c_append(crc: u32, data: &[u8]) -> u32 {
+    table_append(&TABLE, crc, data)
+}
+
+/// Computes the CRC32 checksum of `data`, as found in legacy messages
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    table_append(&IEEE_TABLE, 0, data)
+}
+
+fn table_append(table: &[u32; 256], crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc = table[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+        assert_eq!(crc32c(&[0u8; 32]), 0x8a91_36aa);
+    }
+
+    #[test]
+    fn test_ieee_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(&[0u8; 32]), 0x190a_55ad);
+    }
+
+    #[test]
+    fn test_matches_software() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        for len in [0, 1, 7, 8, 9, 63, 64, 1000] {
+            assert_eq!(
+                crc32c(&data[..len]),
+                software_crc32c_append(0, &data[..len])
+            );
+        }
+    }
+
+    #[test]
+    fn test_append() {
+        let data = b"123456789";
+        for split in 0..data.len() {
+            let crc = crc32c_append(crc32c(&data[..split]), &data[split..]);
+            assert_eq!(crc, 0xe306_9283);
+        }
+    }
+}
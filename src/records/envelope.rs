@@ -0,0 +1,123 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use crate::error::{ErrorKind, Result};
+
+/// The byte that starts the payloads framed for Confluent's Schema Registry
+pub const SCHEMA_REGISTRY_MAGIC: u8 = 0;
+
+/// The size of the framing of Schema Registry: the magic byte, then the `i32` schema id
+const ENVELOPE_SIZE: usize = 5;
+
+/// A record key or value in the wire format of Confluent's Schema Registry: a magic byte and
+/// the id of the payload schema, followed by the payload
+///
+/// Producers and consumers using the serializers of Schema Registry clients frame their
+/// payloads this way, so that readers can fetch the schema the payload was written with.
+/// Protobuf payloads start with the indexes of their message type, which are left in the
+/// payload.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::records::SchemaEnvelope;
+///
+/// let mut value = Vec::new();
+/// SchemaEnvelope::new(42, b"{\"a\":1}").encode(&mut value);
+/// assert_eq!(value[..5], [0, 0, 0, 0, 42]);
+///
+/// let envelope = SchemaEnvelope::decode(&value).unwrap();
+/// assert_eq!(envelope.schema_id, 42);
+/// assert_eq!(envelope.payload, b"{\"a\":1}");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaEnvelope<'a> {
+    /// The id of the payload schema in the registry
+    pub schema_id: i32,
+    /// The serialized payload
+    pub payload: &'a [u8],
+}
+
+impl<'a> SchemaEnvelope<'a> {
+    /// Frames `payload`, serialized with the schema `schema_id`
+    pub fn new(schema_id: i32, payload: &'a [u8]) -> Self {
+        SchemaEnvelope { schema_id, payload }
+    }
+
+    /// Reads the framing of `buf`, borrowing the payload from it
+    ///
+    /// Fails with [`ErrorKind::UnsupportedMagic`] if `buf` doesn't start with
+    /// [`SCHEMA_REGISTRY_MAGIC`], e.g. because it was produced without a registry.
+    pub fn decode(buf: &'a [u8]) -> Result<SchemaEnvelope<'a>> {
+        if buf.len() < ENVELOPE_SIZE {
+            return Err(ErrorKind::NeedMoreBytes(ENVELOPE_SIZE - buf.len()).into());
+        }
+        if buf[0] != SCHEMA_REGISTRY_MAGIC {
+            return Err(ErrorKind::UnsupportedMagic(buf[0] as i8).into());
+        }
+        let mut schema_id = [0; 4];
+        schema_id.copy_from_slice(&buf[1..ENVELOPE_SIZE]);
+        Ok(SchemaEnvelope {
+            schema_id: i32::from_be_bytes(schema_id),
+            payload: &buf[ENVELOPE_SIZE..],
+        })
+    }
+
+    /// Appends the framing and the payload to `buf`
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        buf.reserve(ENVELOPE_SIZE + self.payload.len());
+        buf.push(SCHEMA_REGISTRY_MAGIC);
+        buf.extend_from_slice(&self.schema_id.to_be_bytes());
+        buf.extend_from_slice(self.payload);
+    }
+
+    /// Returns the framing and the payload in a new vector, e.g. for [`Record::value`]
+    ///
+    /// [`Record::value`]: super::Record::value
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.encode(&mut buf);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::records::{Record, RecordBatch};
+
+    #[test]
+    fn test_roundtrip() {
+        let envelope = SchemaEnvelope::new(0x0102_0304, b"payload");
+        let buf = envelope.to_vec();
+        assert_eq!(buf[..5], [0, 1, 2, 3, 4]);
+        assert_eq!(SchemaEnvelope::decode(&buf).unwrap(), envelope);
+        assert_eq!(SchemaEnvelope::decode(&buf[..5]).unwrap().payload, b"");
+
+        let batch = RecordBatch {
+            records: vec![Record {
+                value: Some(buf),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut encoded = Vec::new();
+        batch.encode(&mut encoded).unwrap();
+        let decoded = RecordBatch::decode(&encoded).unwrap();
+        let value = decoded.records[0].value.as_deref().unwrap();
+        assert_eq!(SchemaEnvelope::decode(value).unwrap(), envelope);
+    }
+
+    #[test]
+    fn test_invalid() {
+        match *SchemaEnvelope::decode(&[0, 0, 1]).unwrap_err().kind() {
+            ErrorKind::NeedMoreBytes(2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        match *SchemaEnvelope::decode(b"{\"a\":1}").unwrap_err().kind() {
+            ErrorKind::UnsupportedMagic(0x7b) => {} // '{'
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
@@ -0,0 +1,438 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::{nullable_bytes_size, read_nullable_bytes, varint_len_size, write_nullable_bytes};
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::{ser, varint};
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::io;
+
+/// A record header: a string key and an optional, opaque value
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Header {
+    /// The header key. Keys are not unique within a record
+    pub key: String,
+    /// The header value, `None` if the header was sent with a null value
+    pub value: Option<Vec<u8>>,
+}
+
+impl Header {
+    /// Creates a new header
+    pub fn new<K: Into<String>>(key: K, value: Option<Vec<u8>>) -> Self {
+        Header {
+            key: key.into(),
+            value,
+        }
+    }
+
+    fn read(de: &mut KafkaDeserializer<'_>) -> Result<Header> {
+        HeaderRef::read(de).map(|h| h.to_owned())
+    }
+
+    fn write<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        write_nullable_bytes(writer, Some(self.key.as_bytes()))?;
+        write_nullable_bytes(writer, self.value.as_deref())
+    }
+
+    fn encoded_size(&self) -> usize {
+        nullable_bytes_size(Some(self.key.as_bytes())) + nullable_bytes_size(self.value.as_deref())
+    }
+}
+
+/// A record header borrowing its key and value from the buffer it was decoded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderRef<'a> {
+    /// The header key
+    pub key: &'a str,
+    /// The header value, `None` if the header was sent with a null value
+    pub value: Option<&'a [u8]>,
+}
+
+impl<'a> HeaderRef<'a> {
+    /// Copies the key and value into an owned [`Header`]
+    pub fn to_owned(&self) -> Header {
+        Header {
+            key: self.key.to_owned(),
+            value: self.value.map(|v| v.to_vec()),
+        }
+    }
+
+    fn read(de: &mut KafkaDeserializer<'a>) -> Result<HeaderRef<'a>> {
+        let key = match read_nullable_bytes(de)? {
+            Some(key) => std::str::from_utf8(key)?,
+            None => return Err(ErrorKind::Custom("null header key".into()).into()),
+        };
+        let value = read_nullable_bytes(de)?;
+        Ok(HeaderRef { key, value })
+    }
+}
+
+/// Iterator over the headers of a [`RecordRef`](super::RecordRef), decoding them as it goes
+///
+/// Decoding errors are yielded as they happen, after which the iterator stops.
+pub struct HeaderRefs<'a> {
+    de: KafkaDeserializer<'a>,
+    remaining: Option<i32>,
+}
+
+impl<'a> HeaderRefs<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        HeaderRefs {
+            de: KafkaDeserializer::new(buf),
+            remaining: None,
+        }
+    }
+
+    fn read_next(&mut self) -> Result<Option<HeaderRef<'a>>> {
+        let remaining = match self.remaining {
+            Some(remaining) => remaining,
+            None => {
+                let count = self.de.read_varint()?;
+                if count < 0 {
+                    return Err(ErrorKind::InvalidLength(count).into());
+                }
+                count
+            }
+        };
+        if remaining == 0 {
+            self.remaining = Some(0);
+            return Ok(None);
+        }
+        self.remaining = Some(remaining - 1);
+        HeaderRef::read(&mut self.de).map(Some)
+    }
+}
+
+impl std::fmt::Debug for HeaderRefs<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeaderRefs")
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+impl<'a> Iterator for HeaderRefs<'a> {
+    type Item = Result<HeaderRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_next() {
+            Ok(header) => header.map(Ok),
+            Err(err) => {
+                self.remaining = Some(0);
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// The ordered list of headers attached to a record
+///
+/// On the wire this is a `varint` count followed by each header's key and value, both prefixed
+/// by their `varint` lengths.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::records::{Header, Headers};
+///
+/// let mut headers = Headers::new();
+/// headers.push(Header::new("traceparent", Some(b"00-abc-01".to_vec())));
+///
+/// let mut buf = Vec::new();
+/// headers.encode(&mut buf).unwrap();
+/// let decoded = Headers::decode(&buf).unwrap();
+/// assert_eq!(decoded, headers);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Headers(Vec<Header>);
+
+impl Headers {
+    /// Creates an empty list of headers
+    pub fn new() -> Self {
+        Headers(Vec::new())
+    }
+
+    /// Appends a header, keeping any existing headers with the same key
+    pub fn push(&mut self, header: Header) {
+        self.0.push(header);
+    }
+
+    /// Returns the last header with the given key, if any
+    pub fn get(&self, key: &str) -> Option<&Header> {
+        self.0.iter().rev().find(|h| h.key == key)
+    }
+
+    /// Returns all headers with the given key, in order
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Header> + 'a {
+        self.0.iter().filter(move |h| h.key == key)
+    }
+
+    /// Iterates over all headers, in order
+    pub fn iter(&self) -> std::slice::Iter<'_, Header> {
+        self.0.iter()
+    }
+
+    /// Number of headers
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no headers
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Encodes the headers into `writer`
+    pub fn encode<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        varint::write_varint(writer, ser::len_i32("headers", self.0.len())?)?;
+        for header in &self.0 {
+            header.write(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a list of headers from the beginning of `buf`
+    pub fn decode(buf: &[u8]) -> Result<Headers> {
+        Headers::read(&mut KafkaDeserializer::new(buf))
+    }
+
+    pub(crate) fn read(de: &mut KafkaDeserializer<'_>) -> Result<Headers> {
+        let count = de.read_varint()?;
+        if count < 0 {
+            return Err(ErrorKind::InvalidLength(count).into());
+        }
+        let mut headers = Vec::new();
+        for _ in 0..count {
+            headers.push(Header::read(de)?);
+        }
+        Ok(Headers(headers))
+    }
+
+    /// How many bytes the headers take once encoded
+    pub fn encoded_size(&self) -> usize {
+        varint_len_size(self.0.len()) + self.0.iter().map(Header::encoded_size).sum::<usize>()
+    }
+}
+
+impl From<Vec<Header>> for Headers {
+    fn from(headers: Vec<Header>) -> Self {
+        Headers(headers)
+    }
+}
+
+impl From<Headers> for Vec<Header> {
+    fn from(headers: Headers) -> Self {
+        headers.0
+    }
+}
+
+/// Keeps the headers in order, with their duplicate keys and null values
+impl From<Vec<(String, Option<Vec<u8>>)>> for Headers {
+    fn from(headers: Vec<(String, Option<Vec<u8>>)>) -> Self {
+        headers
+            .into_iter()
+            .map(|(key, value)| Header { key, value })
+            .collect()
+    }
+}
+
+/// Keeps the headers in order, with their duplicate keys and null values
+impl From<Headers> for Vec<(String, Option<Vec<u8>>)> {
+    fn from(headers: Headers) -> Self {
+        headers
+            .into_iter()
+            .map(|header| (header.key, header.value))
+            .collect()
+    }
+}
+
+/// Sorts the headers by key, for records with the same headers to be encoded the same
+impl<S: BuildHasher> From<HashMap<String, Vec<u8>, S>> for Headers {
+    fn from(headers: HashMap<String, Vec<u8>, S>) -> Self {
+        let mut headers: Vec<_> = headers
+            .into_iter()
+            .map(|(key, value)| Header::new(key, Some(value)))
+            .collect();
+        headers.sort_by(|a, b| a.key.cmp(&b.key));
+        Headers(headers)
+    }
+}
+
+/// Maps each key to the value of its last header, like [`Headers::get`] finds it. Keys whose
+/// last header has a null value are left out
+impl<S: BuildHasher + Default> From<Headers> for HashMap<String, Vec<u8>, S> {
+    fn from(headers: Headers) -> Self {
+        let mut map = HashMap::default();
+        for header in headers {
+            match header.value {
+                Some(value) => map.insert(header.key, value),
+                None => map.remove(&header.key),
+            };
+        }
+        map
+    }
+}
+
+impl std::iter::FromIterator<Header> for Headers {
+    fn from_iter<I: IntoIterator<Item = Header>>(iter: I) -> Self {
+        Headers(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Headers {
+    type Item = Header;
+    type IntoIter = std::vec::IntoIter<Header>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Headers {
+    type Item = &'a Header;
+    type IntoIter = std::slice::Iter<'a, Header>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_headers() {
+        let headers: Headers =
+            vec![Header::new("a", Some(vec![1, 2])), Header::new("bc", None)].into();
+        let mut buf = Vec::new();
+        headers.encode(&mut buf).unwrap();
+        assert_eq!(buf, [0x04, 0x02, b'a', 0x04, 1, 2, 0x04, b'b', b'c', 0x01]);
+        assert_eq!(buf.len(), headers.encoded_size());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let headers: Headers = vec![
+            Header::new("k", Some(vec![])),
+            Header::new("k", None),
+            Header::new("", Some(b"value".to_vec())),
+        ]
+        .into_iter()
+        .collect();
+        let mut buf = Vec::new();
+        headers.encode(&mut buf).unwrap();
+        assert_eq!(Headers::decode(&buf).unwrap(), headers);
+    }
+
+    #[test]
+    fn test_pairs() {
+        let pairs = vec![
+            ("k".to_owned(), Some(vec![1])),
+            ("other".to_owned(), None),
+            ("k".to_owned(), Some(vec![2])),
+        ];
+        let headers = Headers::from(pairs.clone());
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get("k").unwrap().value, Some(vec![2]));
+        assert_eq!(Vec::<(String, Option<Vec<u8>>)>::from(headers), pairs);
+    }
+
+    #[test]
+    fn test_map() {
+        let headers: Headers = vec![
+            Header::new("k", Some(vec![1])),
+            Header::new("null", Some(vec![3])),
+            Header::new("k", Some(vec![2])),
+            Header::new("null", None),
+        ]
+        .into();
+        let map: HashMap<String, Vec<u8>> = headers.into();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["k"], [2]);
+
+        let mut map = HashMap::new();
+        map.insert("b".to_owned(), vec![2]);
+        map.insert("a".to_owned(), vec![1]);
+        let headers = Headers::from(map);
+        let keys: Vec<_> = headers.iter().map(|header| header.key.as_str()).collect();
+        assert_eq!(keys, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut buf = Vec::new();
+        Headers::new().encode(&mut buf).unwrap();
+        assert_eq!(buf, [0x00]);
+        assert!(Headers::decode(&buf).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let headers: Headers = vec![
+            Header::new("k", Some(vec![1])),
+            Header::new("other", None),
+            Header::new("k", Some(vec![2])),
+        ]
+        .into();
+        assert_eq!(headers.get("k").unwrap().value, Some(vec![2]));
+        assert_eq!(headers.get_all("k").count(), 2);
+        assert!(headers.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_header_refs() {
+        let headers: Headers =
+            vec![Header::new("a", Some(vec![1, 2])), Header::new("bc", None)].into();
+        let mut buf = Vec::new();
+        headers.encode(&mut buf).unwrap();
+        let refs: Vec<_> = HeaderRefs::new(&buf).map(|h| h.unwrap()).collect();
+        assert_eq!(
+            refs,
+            [
+                HeaderRef {
+                    key: "a",
+                    value: Some(&[1, 2])
+                },
+                HeaderRef {
+                    key: "bc",
+                    value: None
+                }
+            ]
+        );
+        assert_eq!(refs[0].to_owned(), headers.iter().next().unwrap().clone());
+
+        let results: Vec<_> = HeaderRefs::new(&buf[..buf.len() - 2]).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn test_null_key() {
+        let data = [0x02, 0x01, 0x01];
+        assert!(Headers::decode(&data).is_err());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = [0x02, 0x02, b'a', 0x08, 1];
+        match *Headers::decode(&data).unwrap_err().kind() {
+            ErrorKind::NeedMoreBytes(3) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_negative_count() {
+        // a varint of -2
+        match *Headers::decode(&[0x03]).unwrap_err().kind() {
+            ErrorKind::InvalidLength(-2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
@@ -0,0 +1,328 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::batch::write_batch;
+use super::{BatchHeader, Compression, Record, RecordRef};
+use crate::de::KafkaDeserializer;
+use crate::error::Result;
+use std::io;
+
+/// A record batch whose records are decoded on demand
+///
+/// Created by [`BatchDecoder::decode_lazy`](super::BatchDecoder::decode_lazy). Consumers that
+/// only look at a few records of each batch (e.g. filtering by key) avoid paying for the ones
+/// they skip. Uncompressed records are read straight from the input buffer, and compressed ones
+/// are decompressed incrementally as the iteration progresses.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::records::{BatchDecoder, Record};
+///
+/// fn first_with_key(buf: &[u8], key: &[u8]) -> kafka_serde::Result<Option<Record>> {
+///     let batch = BatchDecoder::new().decode_lazy(buf)?;
+///     for record in batch.records() {
+///         let record = record?;
+///         if record.key.as_deref() == Some(key) {
+///             return Ok(Some(record));
+///         }
+///     }
+///     Ok(None)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LazyBatch<'a> {
+    /// The batch header
+    pub header: BatchHeader,
+    compression: Compression,
+    records: &'a [u8],
+}
+
+impl<'a> LazyBatch<'a> {
+    pub(crate) fn new(header: BatchHeader, compression: Compression, records: &'a [u8]) -> Self {
+        LazyBatch {
+            header,
+            compression,
+            records,
+        }
+    }
+
+    /// Iterates over the records of this batch, decoding each one as it is reached
+    pub fn records(&self) -> Records<'a> {
+        let source = match self.compression {
+            Compression::None => Source::Slice(KafkaDeserializer::new(self.records)),
+            codec => match codec.decompress_reader(self.records) {
+                Ok(reader) => Source::Stream(reader, Vec::new()),
+                Err(err) => Source::Failed(Some(err)),
+            },
+        };
+        Records {
+            source,
+            remaining: self.header.record_count,
+        }
+    }
+
+    /// Iterates over the records of this batch without copying them, if the batch is not
+    /// compressed
+    ///
+    /// Keys, values and headers borrow from the buffer the batch was decoded from, which keeps
+    /// consumer hot paths free of allocations. Compressed batches can't be borrowed from and
+    /// return `None`: use [`records`](Self::records) for those.
+    pub fn record_refs(&self) -> Option<RecordRefs<'a>> {
+        match self.compression {
+            Compression::None => Some(RecordRefs {
+                de: KafkaDeserializer::new(self.records),
+                remaining: self.header.record_count,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Re-encodes this batch into `writer`, with its records compressed with `compression`
+    ///
+    /// The records are recompressed as opaque bytes, without being decoded, so they come out
+    /// exactly as they went in. Attributes, batch length and checksum are recomputed, all the
+    /// other header fields are kept.
+    pub fn transcode<W: io::Write>(&self, compression: Compression, writer: &mut W) -> Result<()> {
+        let header = BatchHeader {
+            attributes: compression.apply(self.header.attributes),
+            ..self.header
+        };
+        if compression == self.compression {
+            return write_batch(&header, self.records, writer);
+        }
+        let records = self.compression.decompress(self.records)?;
+        match compression {
+            Compression::None => write_batch(&header, &records, writer),
+            codec => write_batch(&header, &codec.compress(&records)?, writer),
+        }
+    }
+}
+
+/// Iterator over the records of an uncompressed [`LazyBatch`], borrowing from its buffer
+///
+/// Decoding errors are yielded as they happen, after which the iterator stops.
+pub struct RecordRefs<'a> {
+    de: KafkaDeserializer<'a>,
+    remaining: i32,
+}
+
+impl std::fmt::Debug for RecordRefs<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordRefs")
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+impl<'a> Iterator for RecordRefs<'a> {
+    type Item = Result<RecordRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining <= 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let record = RecordRef::read(&mut self.de);
+        if record.is_err() {
+            self.remaining = 0;
+        }
+        Some(record)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining.max(0) as usize))
+    }
+}
+
+enum Source<'a> {
+    Slice(KafkaDeserializer<'a>),
+    Stream(Box<dyn io::Read + 'a>, Vec<u8>),
+    Failed(Option<crate::Error>),
+}
+
+/// Iterator over the records of a [`LazyBatch`]
+///
+/// Decoding errors are yielded as they happen, after which the iterator stops.
+pub struct Records<'a> {
+    source: Source<'a>,
+    remaining: i32,
+}
+
+impl std::fmt::Debug for Records<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Records")
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+impl Iterator for Records<'_> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining <= 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let record = match &mut self.source {
+            Source::Slice(de) => Record::read(de),
+            Source::Stream(reader, buf) => Record::read_from(reader, buf),
+            Source::Failed(err) => Err(err.take().unwrap()),
+        };
+        if record.is_err() {
+            self.remaining = 0;
+        }
+        Some(record)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining.max(0) as usize))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::records::{BatchDecoder, Headers, RecordBatchBuilder};
+
+    fn encoded(values: &[&[u8]]) -> Vec<u8> {
+        let mut builder = RecordBatchBuilder::new().base_offset(50);
+        for (i, v) in values.iter().enumerate() {
+            builder.append(
+                100 + i as i64,
+                Some(vec![i as u8]),
+                Some(v.to_vec()),
+                Headers::new(),
+            );
+        }
+        let mut buf = Vec::new();
+        builder.build()[0].encode(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_lazy_matches_eager() {
+        let buf = encoded(&[b"a", b"bb", b"ccc"]);
+        let lazy = BatchDecoder::new().decode_lazy(&buf).unwrap();
+        let eager = BatchDecoder::new().decode(&buf).unwrap();
+        assert_eq!(lazy.header, eager.header().unwrap());
+        let records: Vec<_> = lazy.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, eager.records);
+        assert_eq!(lazy.header.offset_of(&records[2]), 52);
+        assert_eq!(lazy.header.timestamp_of(&records[2]), 102);
+    }
+
+    #[test]
+    fn test_partial_iteration() {
+        let buf = encoded(&[b"a", b"bb", b"ccc"]);
+        let lazy = BatchDecoder::new().decode_lazy(&buf).unwrap();
+        let mut records = lazy.records();
+        assert_eq!(records.size_hint(), (0, Some(3)));
+        let first = records.next().unwrap().unwrap();
+        assert_eq!(first.value.as_deref(), Some(&b"a"[..]));
+        assert_eq!(records.size_hint(), (0, Some(2)));
+    }
+
+    #[test]
+    fn test_stops_after_error() {
+        let mut buf = encoded(&[b"a", b"bb"]);
+        // claim a third record that is not there
+        buf[60] = 3;
+        let lazy = BatchDecoder::new()
+            .verify_crc(false)
+            .decode_lazy(&buf)
+            .unwrap();
+        let results: Vec<_> = lazy.records().collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_record_refs() {
+        let buf = encoded(&[b"a", b"bb"]);
+        let lazy = BatchDecoder::new().decode_lazy(&buf).unwrap();
+        let refs: Vec<_> = lazy.record_refs().unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[1].value, Some(&b"bb"[..]));
+        assert_eq!(refs[1].offset(&lazy.header), 51);
+        // the value points into the input buffer
+        let start = buf.as_ptr() as usize;
+        let value = refs[1].value.unwrap().as_ptr() as usize;
+        assert!(value > start && value < start + buf.len());
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_transcode() {
+        use crate::records::{Compression, Record, RecordBatch};
+
+        let batch = RecordBatch {
+            records: vec![
+                Record {
+                    value: Some(vec![7u8; 500]),
+                    ..Default::default()
+                };
+                3
+            ],
+            ..Default::default()
+        }
+        .with_compression(Compression::Gzip);
+        let mut buf = Vec::new();
+        batch.encode(&mut buf).unwrap();
+
+        let mut zstd = Vec::new();
+        let lazy = BatchDecoder::new().decode_lazy(&buf).unwrap();
+        lazy.transcode(Compression::Zstd, &mut zstd).unwrap();
+        let decoded = RecordBatch::decode(&zstd).unwrap();
+        assert_eq!(decoded.compression().unwrap(), Compression::Zstd);
+        assert_eq!(decoded.records, batch.records);
+
+        let mut plain = Vec::new();
+        let lazy = BatchDecoder::new().decode_lazy(&zstd).unwrap();
+        lazy.transcode(Compression::None, &mut plain).unwrap();
+        let mut expected = Vec::new();
+        batch
+            .clone()
+            .with_compression(Compression::None)
+            .encode(&mut expected)
+            .unwrap();
+        assert_eq!(plain, expected);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_compressed() {
+        use crate::records::Compression;
+
+        let mut builder = RecordBatchBuilder::new().compression(Compression::Gzip);
+        builder.append(0, None, Some(b"zipped".to_vec()), Headers::new());
+        let mut buf = Vec::new();
+        builder.build()[0].encode(&mut buf).unwrap();
+
+        let lazy = BatchDecoder::new().decode_lazy(&buf).unwrap();
+        let record = lazy.records().next().unwrap().unwrap();
+        assert_eq!(record.value.as_deref(), Some(&b"zipped"[..]));
+        assert!(lazy.record_refs().is_none());
+    }
+
+    #[cfg(feature = "snappy")]
+    #[test]
+    fn test_streaming_early_exit() {
+        use crate::records::Compression;
+
+        let mut builder = RecordBatchBuilder::new()
+            .compression(Compression::Snappy)
+            .max_batch_size(usize::MAX);
+        for i in 0..10_000u32 {
+            builder.append(0, None, Some(i.to_be_bytes().to_vec()), Headers::new());
+        }
+        let mut buf = Vec::new();
+        builder.build()[0].encode(&mut buf).unwrap();
+        let lazy = BatchDecoder::new().decode_lazy(&buf).unwrap();
+        let firsts: Vec<_> = lazy.records().take(3).map(|r| r.unwrap()).collect();
+        assert_eq!(firsts[2].value.as_deref(), Some(&2u32.to_be_bytes()[..]));
+        let all = lazy.records().collect::<crate::Result<Vec<_>>>().unwrap();
+        assert_eq!(all.len(), 10_000);
+    }
+}
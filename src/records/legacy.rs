@@ -0,0 +1,524 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Legacy message sets, used by magic v0 and v1 (pre-0.11) data.
+use std::io;
+
+use super::crc::crc32;
+use super::{Compression, TimestampType};
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::ser;
+
+// offset and message size come before the message
+const LOG_OVERHEAD: usize = 12;
+
+/// A message in the legacy (magic v0 and v1) format
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct LegacyMessage {
+    /// The absolute offset of this message
+    pub offset: i64,
+    /// The message format version, 0 or 1
+    pub magic: i8,
+    /// The message attributes. The lowest three bits hold the compression codec
+    pub attributes: i8,
+    /// The message timestamp. Only present for magic v1
+    pub timestamp: Option<i64>,
+    /// The message key
+    pub key: Option<Vec<u8>>,
+    /// The message value
+    pub value: Option<Vec<u8>>,
+}
+
+impl LegacyMessage {
+    /// The codec used to compress the inner messages, if this is a wrapper message
+    pub fn compression(&self) -> Result<Compression> {
+        Compression::from_attributes(self.attributes as i16)
+    }
+
+    /// What the timestamp represents. Only meaningful for magic v1
+    pub fn timestamp_type(&self) -> TimestampType {
+        TimestampType::from_attributes(self.attributes as i16)
+    }
+
+    fn read(
+        de: &mut KafkaDeserializer<'_>,
+        offset: i64,
+        verify_crc: bool,
+    ) -> Result<LegacyMessage> {
+        let expected = de.read_u32()?;
+        // unlike that of record batches, the checksum is a CRC32 (IEEE), of the rest of the
+        // message
+        if verify_crc {
+            let computed = crc32(de.peek_remaining());
+            if computed != expected {
+                return Err(ErrorKind::CrcMismatch { expected, computed }.into());
+            }
+        }
+        let magic = de.read_i8()?;
+        if magic > 1 {
+            return Err(ErrorKind::UnsupportedMagic(magic).into());
+        }
+        let attributes = de.read_i8()?;
+        let timestamp = if magic == 1 {
+            Some(de.read_i64()?)
+        } else {
+            None
+        };
+        let key = de.read_nullable_bytes()?.map(|k| k.to_vec());
+        let value = de.read_nullable_bytes()?.map(|v| v.to_vec());
+        Ok(LegacyMessage {
+            offset,
+            magic,
+            attributes,
+            timestamp,
+            key,
+            value,
+        })
+    }
+
+    /// Appends the message to `buf`, with `offset` rather than its own, computing its size and
+    /// checksum
+    fn write(&self, offset: i64, buf: &mut Vec<u8>) -> Result<()> {
+        if !(0..=1).contains(&self.magic) {
+            return Err(ErrorKind::UnsupportedMagic(self.magic).into());
+        }
+        let start = buf.len();
+        buf.extend_from_slice(&offset.to_be_bytes());
+        // the size and checksum are patched in once the rest is written
+        buf.extend_from_slice(&[0; 8]);
+        buf.push(self.magic as u8);
+        buf.push(self.attributes as u8);
+        if self.magic == 1 {
+            buf.extend_from_slice(&self.timestamp.unwrap_or(-1).to_be_bytes());
+        }
+        ser::write_nullable_bytes(buf, self.key.as_deref())?;
+        ser::write_nullable_bytes(buf, self.value.as_deref())?;
+
+        let size = ser::len_i32("message", buf.len() - start - LOG_OVERHEAD)?;
+        let crc = crc32(&buf[start + LOG_OVERHEAD + 4..]);
+        buf[start + 8..start + LOG_OVERHEAD].copy_from_slice(&size.to_be_bytes());
+        buf[start + LOG_OVERHEAD..start + LOG_OVERHEAD + 4].copy_from_slice(&crc.to_be_bytes());
+        Ok(())
+    }
+}
+
+/// A sequence of legacy messages
+///
+/// Compressed data is stored as a single wrapper message whose value is itself a compressed
+/// message set. Decoding unwraps those transparently: the inner messages take the place of the
+/// wrapper, with their absolute offsets reconstructed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct MessageSet {
+    /// The messages, in offset order
+    pub messages: Vec<LegacyMessage>,
+}
+
+impl MessageSet {
+    /// Decodes a message set that spans all of `buf`, failing with [`ErrorKind::CrcMismatch`]
+    /// if one of its messages, or of the messages compressed in them, was corrupted
+    pub fn decode(buf: &[u8]) -> Result<MessageSet> {
+        Self::decode_with(buf, true)
+    }
+
+    /// Decodes a message set that spans all of `buf`, without verifying the checksums of its
+    /// messages, e.g. for data that is trusted or was already verified
+    pub fn decode_unverified(buf: &[u8]) -> Result<MessageSet> {
+        Self::decode_with(buf, false)
+    }
+
+    /// Encodes the messages into `writer`, in the format of their magic, as Produce v0 to v2
+    /// send them and brokers older than 0.11 store them
+    ///
+    /// The messages must not be compressed wrappers: see
+    /// [`encode_compressed`](MessageSet::encode_compressed) to compress them.
+    ///
+    /// # Examples
+    /// ```
+    /// use kafka_serde::records::{LegacyMessage, MessageSet};
+    ///
+    /// let set = MessageSet {
+    ///     messages: vec![LegacyMessage {
+    ///         offset: 0,
+    ///         magic: 1,
+    ///         timestamp: Some(1_600_000_000_000),
+    ///         value: Some(b"value".to_vec()),
+    ///         ..LegacyMessage::default()
+    ///     }],
+    /// };
+    /// let mut buf = Vec::new();
+    /// set.encode(&mut buf).unwrap();
+    /// assert_eq!(MessageSet::decode(&buf).unwrap(), set);
+    /// ```
+    pub fn encode<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut buf = Vec::new();
+        for message in &self.messages {
+            if message.compression()? != Compression::None {
+                return Err(ErrorKind::Custom("message already compressed".into()).into());
+            }
+            message.write(message.offset, &mut buf)?;
+        }
+        writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// Encodes the messages into `writer` as a single wrapper message, whose value is the
+    /// message set they make, compressed with `compression`
+    ///
+    /// The messages must all have the same magic, which the wrapper takes. The wrapper has the
+    /// offset of the last message, and in v1 its largest timestamp, while the messages it
+    /// wraps have their offsets relative to the first one, as decoding expects. Zstd is not
+    /// allowed in legacy messages.
+    pub fn encode_compressed<W: io::Write>(
+        &self,
+        compression: Compression,
+        writer: &mut W,
+    ) -> Result<()> {
+        let (first, last) = match (self.messages.first(), self.messages.last()) {
+            (Some(first), Some(last)) if compression != Compression::None => (first, last),
+            _ => return self.encode(writer),
+        };
+        if compression == Compression::Zstd {
+            return Err(ErrorKind::UnsupportedCompression(compression.id()).into());
+        }
+        let magic = first.magic;
+        if self.messages.iter().any(|message| message.magic != magic) {
+            return Err(ErrorKind::Custom("messages of different magics".into()).into());
+        }
+
+        let mut inner = Vec::new();
+        for message in &self.messages {
+            if message.compression()? != Compression::None {
+                return Err(ErrorKind::Custom("message already compressed".into()).into());
+            }
+            let offset = if magic == 0 {
+                message.offset
+            } else {
+                message.offset - first.offset
+            };
+            message.write(offset, &mut inner)?;
+        }
+        let wrapper = LegacyMessage {
+            offset: last.offset,
+            magic,
+            attributes: compression.id(),
+            timestamp: self.messages.iter().filter_map(|m| m.timestamp).max(),
+            key: None,
+            value: Some(compression.compress(&inner)?),
+        };
+        let mut buf = Vec::new();
+        wrapper.write(wrapper.offset, &mut buf)?;
+        writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    fn decode_with(buf: &[u8], verify_crc: bool) -> Result<MessageSet> {
+        let mut messages = Vec::new();
+        let mut de = KafkaDeserializer::new(buf);
+        read_set(&mut de, &mut messages, false, verify_crc)?;
+        Ok(MessageSet { messages })
+    }
+}
+
+fn read_set(
+    de: &mut KafkaDeserializer<'_>,
+    out: &mut Vec<LegacyMessage>,
+    inner: bool,
+    verify_crc: bool,
+) -> Result<()> {
+    while de.remaining() > 0 {
+        let offset = de.read_i64()?;
+        let size = de.read_i32()?;
+        if size < 0 {
+            return Err(ErrorKind::InvalidLength(size).into());
+        }
+        let mut body = KafkaDeserializer::new(de.slice(size as usize)?);
+        let message = LegacyMessage::read(&mut body, offset, verify_crc)?;
+
+        let compression = message.compression()?;
+        if compression == Compression::None {
+            out.push(message);
+            continue;
+        }
+        if inner {
+            return Err(ErrorKind::Custom("nested compressed message".into()).into());
+        }
+        let value = message
+            .value
+            .as_deref()
+            .ok_or_else(|| ErrorKind::Custom("compressed message without a value".into()))?;
+        let decompressed = compression.decompress(value)?;
+        let first = out.len();
+        read_set(
+            &mut KafkaDeserializer::new(&decompressed),
+            out,
+            true,
+            verify_crc,
+        )?;
+        fixup_inner(&message, &mut out[first..]);
+    }
+    Ok(())
+}
+
+/// Applies the wrapper's offset and timestamp rules to its freshly decoded inner messages
+///
+/// In v0 the inner messages carry their absolute offsets. From v1 on they carry offsets relative
+/// to the first inner message, and the wrapper holds the absolute offset of the last one. If the
+/// wrapper uses log append time, its timestamp overrides the inner ones.
+fn fixup_inner(wrapper: &LegacyMessage, inner: &mut [LegacyMessage]) {
+    if wrapper.magic == 0 {
+        return;
+    }
+    let last_relative = inner.last().map(|m| m.offset).unwrap_or(0);
+    let base = wrapper.offset - last_relative;
+    let log_append_time = wrapper.timestamp_type() == TimestampType::LogAppendTime;
+    for message in inner {
+        message.offset += base;
+        if log_append_time {
+            message.timestamp = wrapper.timestamp;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(offset: i64, magic: i8, attributes: i8, value: &[u8]) -> Vec<u8> {
+        message_at(offset, magic, attributes, 1000, value)
+    }
+
+    fn message_at(offset: i64, magic: i8, attributes: i8, timestamp: i64, value: &[u8]) -> Vec<u8> {
+        let mut body = vec![magic as u8, attributes as u8];
+        if magic == 1 {
+            body.extend_from_slice(&timestamp.to_be_bytes());
+        }
+        body.extend_from_slice(&(-1i32).to_be_bytes());
+        body.extend_from_slice(&(value.len() as i32).to_be_bytes());
+        body.extend_from_slice(value);
+        let crc = crc32(&body);
+        body.splice(0..0, crc.to_be_bytes());
+
+        let mut out = offset.to_be_bytes().to_vec();
+        out.extend_from_slice(&(body.len() as i32).to_be_bytes());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    #[test]
+    fn test_decode_plain() {
+        let mut data = message(10, 0, 0, b"a");
+        data.extend(message(11, 1, 0, b"bc"));
+        let set = MessageSet::decode(&data).unwrap();
+        assert_eq!(set.messages.len(), 2);
+        assert_eq!(set.messages[0].offset, 10);
+        assert_eq!(set.messages[0].timestamp, None);
+        assert_eq!(set.messages[0].key, None);
+        assert_eq!(set.messages[0].value.as_deref(), Some(&b"a"[..]));
+        assert_eq!(set.messages[1].offset, 11);
+        assert_eq!(set.messages[1].timestamp, Some(1000));
+        assert_eq!(set.messages[1].value.as_deref(), Some(&b"bc"[..]));
+    }
+
+    #[test]
+    fn test_decode_empty() {
+        assert!(MessageSet::decode(&[]).unwrap().messages.is_empty());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = message(10, 0, 0, b"abc");
+        assert!(MessageSet::decode(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_crc_mismatch() {
+        let mut data = message(10, 1, 0, b"abc");
+        // the checksum of the message after offset, size and crc, as zlib computes it
+        assert_eq!(data[12..16], 0x6125_26e0u32.to_be_bytes());
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        match *MessageSet::decode(&data).unwrap_err().kind() {
+            ErrorKind::CrcMismatch { expected, computed } => assert_ne!(expected, computed),
+            ref e => panic!("unexpected error {}", e),
+        }
+        let set = MessageSet::decode_unverified(&data).unwrap();
+        assert_eq!(set.messages[0].value.as_deref(), Some(&b"abb"[..]));
+    }
+
+    fn legacy(offset: i64, magic: i8, timestamp: i64, value: &[u8]) -> LegacyMessage {
+        LegacyMessage {
+            offset,
+            magic,
+            attributes: 0,
+            timestamp: (magic == 1).then_some(timestamp),
+            key: None,
+            value: Some(value.to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let set = MessageSet {
+            messages: vec![legacy(10, 0, 0, b"a"), legacy(11, 1, 1000, b"bc")],
+        };
+        let mut buf = Vec::new();
+        set.encode(&mut buf).unwrap();
+        let mut expected = message(10, 0, 0, b"a");
+        expected.extend(message(11, 1, 0, b"bc"));
+        assert_eq!(buf, expected);
+        assert_eq!(MessageSet::decode(&buf).unwrap(), set);
+
+        let wrapper = LegacyMessage {
+            attributes: 1,
+            ..legacy(0, 1, 0, b"")
+        };
+        let set = MessageSet {
+            messages: vec![wrapper],
+        };
+        assert!(set.encode(&mut Vec::new()).is_err());
+        assert!(set
+            .encode_compressed(Compression::Gzip, &mut Vec::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_encode_compressed_errors() {
+        let set = MessageSet {
+            messages: vec![legacy(0, 0, 0, b"a"), legacy(1, 1, 1000, b"b")],
+        };
+        match *set
+            .encode_compressed(Compression::Zstd, &mut Vec::new())
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::UnsupportedCompression(4) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        #[cfg(feature = "gzip")]
+        match *set
+            .encode_compressed(Compression::Gzip, &mut Vec::new())
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::Custom(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        // nothing to compress
+        let mut buf = Vec::new();
+        MessageSet::default()
+            .encode_compressed(Compression::Gzip, &mut buf)
+            .unwrap();
+        assert!(buf.is_empty());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_encode_compressed() {
+        for magic in 0..=1 {
+            let set = MessageSet {
+                messages: vec![
+                    legacy(100, magic, 3000, b"a"),
+                    legacy(101, magic, 2000, b"b"),
+                    legacy(102, magic, 1000, b"c"),
+                ],
+            };
+            let mut buf = Vec::new();
+            set.encode_compressed(Compression::Gzip, &mut buf).unwrap();
+
+            let mut de = KafkaDeserializer::new(&buf);
+            assert_eq!(de.read_i64().unwrap(), 102);
+            let size = de.read_i32().unwrap() as usize;
+            let wrapper =
+                LegacyMessage::read(&mut KafkaDeserializer::new(&buf[12..12 + size]), 102, true)
+                    .unwrap();
+            assert_eq!(wrapper.compression().unwrap(), Compression::Gzip);
+            assert_eq!(wrapper.timestamp, (magic == 1).then_some(3000));
+
+            assert_eq!(MessageSet::decode(&buf).unwrap(), set);
+        }
+    }
+
+    #[test]
+    fn test_negative_size() {
+        let mut data = 10i64.to_be_bytes().to_vec();
+        data.extend_from_slice(&(-2i32).to_be_bytes());
+        match *MessageSet::decode(&data).unwrap_err().kind() {
+            ErrorKind::InvalidLength(-2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_magic_v2() {
+        let data = message(10, 2, 0, b"abc");
+        match *MessageSet::decode(&data).unwrap_err().kind() {
+            ErrorKind::UnsupportedMagic(2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_unsupported_codec() {
+        let data = message(10, 0, 7, b"abc");
+        assert!(MessageSet::decode(&data).is_err());
+    }
+
+    #[cfg(feature = "gzip")]
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        Compression::Gzip.compress(data).unwrap()
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_nested_v0() {
+        let mut inner = message(5, 0, 0, b"a");
+        inner.extend(message(6, 0, 0, b"b"));
+        let data = message(6, 0, 1, &gzip(&inner));
+
+        let set = MessageSet::decode(&data).unwrap();
+        assert_eq!(set.messages.len(), 2);
+        assert_eq!(set.messages[0].offset, 5);
+        assert_eq!(set.messages[1].offset, 6);
+        assert_eq!(set.messages[1].value.as_deref(), Some(&b"b"[..]));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_nested_v1_relative_offsets() {
+        let mut inner = message(0, 1, 0, b"a");
+        inner.extend(message(1, 1, 0, b"b"));
+        inner.extend(message(2, 1, 0, b"c"));
+        let mut data = message(102, 1, 1, &gzip(&inner));
+        data.extend(message(103, 1, 0, b"d"));
+
+        let set = MessageSet::decode(&data).unwrap();
+        let offsets: Vec<i64> = set.messages.iter().map(|m| m.offset).collect();
+        assert_eq!(offsets, [100, 101, 102, 103]);
+        assert_eq!(set.messages[2].value.as_deref(), Some(&b"c"[..]));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_nested_v1_log_append_time() {
+        let inner = message(0, 1, 0, b"a");
+        let attributes = TimestampType::LogAppendTime.apply(1) as i8;
+        let data = message_at(7, 1, attributes, 2000, &gzip(&inner));
+
+        let set = MessageSet::decode(&data).unwrap();
+        assert_eq!(set.messages[0].offset, 7);
+        assert_eq!(set.messages[0].timestamp, Some(2000));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_doubly_nested() {
+        let inner = message(0, 1, 1, &gzip(&message(0, 1, 0, b"a")));
+        let data = message(0, 1, 1, &gzip(&inner));
+        assert!(MessageSet::decode(&data).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Kafka records
+//!
+//! The record format doesn't map well into serde: lengths and counts are zig-zag encoded
+//! variable-length integers, so the types in this module come with their own `encode` and
+//! `decode` methods instead.
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::{ser, varint};
+use std::convert::TryFrom;
+use std::io;
+
+mod accumulator;
+mod batch;
+mod borrowed;
+mod builder;
+mod compression;
+mod crc;
+mod envelope;
+mod header;
+mod lazy;
+mod legacy;
+mod payload;
+mod record;
+#[cfg(feature = "snappy")]
+mod snappy;
+mod timestamp;
+
+pub use self::accumulator::{ReadyBatch, RecordAccumulator};
+pub(crate) use self::batch::split_first;
+pub use self::batch::{
+    BatchDecoder, BatchHeader, RecordBatch, RECORD_BATCH_MAGIC, RECORD_BATCH_OVERHEAD,
+};
+pub use self::borrowed::{BorrowedBatch, BorrowedRecord};
+pub use self::builder::{RecordBatchBuilder, DEFAULT_MAX_BATCH_SIZE};
+pub use self::compression::Compression;
+pub(crate) use self::crc::crc32c;
+pub use self::envelope::{SchemaEnvelope, SCHEMA_REGISTRY_MAGIC};
+pub use self::header::{Header, HeaderRef, HeaderRefs, Headers};
+pub use self::lazy::{LazyBatch, RecordRefs, Records};
+pub use self::legacy::{LegacyMessage, MessageSet};
+#[cfg(feature = "json")]
+pub use self::payload::Json;
+pub use self::payload::{RawBytes, RecordDeserializer, RecordSerializer, Utf8};
+pub use self::record::{Record, RecordRef};
+pub use self::timestamp::TimestampType;
+
+/// Reads a byte array prefixed by its `varint` length, where a length of -1 means null
+pub(crate) fn read_nullable_bytes<'de>(
+    de: &mut KafkaDeserializer<'de>,
+) -> Result<Option<&'de [u8]>> {
+    let len = de.read_varint()?;
+    match len {
+        -1 => Ok(None),
+        len if len < 0 => Err(ErrorKind::InvalidLength(len).into()),
+        len => Ok(Some(de.slice(len as usize)?)),
+    }
+}
+
+/// Writes a byte array prefixed by its `varint` length, where `None` is written as -1
+pub(crate) fn write_nullable_bytes<W: io::Write>(
+    writer: &mut W,
+    bytes: Option<&[u8]>,
+) -> Result<()> {
+    match bytes {
+        None => varint::write_varint(writer, -1),
+        Some(bytes) => {
+            varint::write_varint(writer, ser::len_i32("bytes", bytes.len())?)?;
+            writer.write_all(bytes)?;
+            Ok(())
+        }
+    }
+}
+
+/// How many bytes `bytes` take once written by [`write_nullable_bytes`]
+pub(crate) fn nullable_bytes_size(bytes: Option<&[u8]>) -> usize {
+    match bytes {
+        None => varint::varint_size(-1),
+        Some(bytes) => varint_len_size(bytes.len()) + bytes.len(),
+    }
+}
+
+/// How many bytes the `varint` of length `len` takes. Lengths over `i32::MAX` fail to encode,
+/// their size is that of the largest one
+pub(crate) fn varint_len_size(len: usize) -> usize {
+    varint::varint_size(i32::try_from(len).unwrap_or(i32::MAX))
+}
@@ -0,0 +1,160 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use crate::error::{ErrorKind, Result};
+
+/// Encodes the keys or values of records from application types
+///
+/// Implemented by [`RawBytes`], [`Utf8`] and, with the `json` feature, `Json`. Used by
+/// [`RecordBatchBuilder::append_with`](super::RecordBatchBuilder::append_with).
+///
+/// # Examples
+/// ```
+/// use kafka_serde::records::{RecordDeserializer, RecordSerializer, Utf8};
+///
+/// let bytes = Utf8.serialize("café").unwrap();
+/// let value: String = Utf8.deserialize(&bytes).unwrap();
+/// assert_eq!(value, "café");
+/// ```
+pub trait RecordSerializer<T: ?Sized> {
+    /// Encodes `value` into the bytes of a record key or value
+    fn serialize(&self, value: &T) -> Result<Vec<u8>>;
+}
+
+/// Decodes the keys or values of records into application types
+///
+/// Implemented by [`RawBytes`], [`Utf8`] and, with the `json` feature, `Json`. Used by
+/// [`Record::key_with`](super::Record::key_with) and the like.
+pub trait RecordDeserializer<T> {
+    /// Decodes the bytes of a record key or value
+    fn deserialize(&self, bytes: &[u8]) -> Result<T>;
+}
+
+/// Payloads kept as they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawBytes;
+
+impl RecordSerializer<[u8]> for RawBytes {
+    fn serialize(&self, value: &[u8]) -> Result<Vec<u8>> {
+        Ok(value.to_vec())
+    }
+}
+
+impl RecordSerializer<Vec<u8>> for RawBytes {
+    fn serialize(&self, value: &Vec<u8>) -> Result<Vec<u8>> {
+        Ok(value.clone())
+    }
+}
+
+impl RecordDeserializer<Vec<u8>> for RawBytes {
+    fn deserialize(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Payloads holding UTF-8 text, which fail to decode if they are not valid UTF-8
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Utf8;
+
+impl RecordSerializer<str> for Utf8 {
+    fn serialize(&self, value: &str) -> Result<Vec<u8>> {
+        Ok(value.as_bytes().to_vec())
+    }
+}
+
+impl RecordSerializer<String> for Utf8 {
+    fn serialize(&self, value: &String) -> Result<Vec<u8>> {
+        Ok(value.as_bytes().to_vec())
+    }
+}
+
+impl RecordDeserializer<String> for Utf8 {
+    fn deserialize(&self, bytes: &[u8]) -> Result<String> {
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(err) => Err(ErrorKind::InvalidStringEncoding(err).into()),
+        }
+    }
+}
+
+/// Payloads holding JSON documents, through serde_json
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl<T: serde::Serialize + ?Sized> RecordSerializer<T> for Json {
+    fn serialize(&self, value: &T) -> Result<Vec<u8>> {
+        serde_json::to_vec(value).map_err(|err| ErrorKind::Custom(err.to_string()).into())
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: serde::de::DeserializeOwned> RecordDeserializer<T> for Json {
+    fn deserialize(&self, bytes: &[u8]) -> Result<T> {
+        serde_json::from_slice(bytes).map_err(|err| ErrorKind::Custom(err.to_string()).into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builtins() {
+        assert_eq!(RawBytes.serialize(&b"\xff"[..]).unwrap(), [0xff]);
+        assert_eq!(RawBytes.serialize(&vec![1]).unwrap(), [1]);
+        let raw: Vec<u8> = RawBytes.deserialize(&[0xff]).unwrap();
+        assert_eq!(raw, [0xff]);
+
+        assert_eq!(Utf8.serialize(&"a".to_owned()).unwrap(), b"a");
+        let err = RecordDeserializer::<String>::deserialize(&Utf8, &[0xff]).unwrap_err();
+        match *err.kind() {
+            ErrorKind::InvalidStringEncoding(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        use crate::records::{Headers, RecordBatchBuilder};
+
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Event {
+            id: u32,
+        }
+
+        let mut builder = RecordBatchBuilder::new();
+        builder
+            .append_with(
+                &Utf8,
+                &Json,
+                0,
+                Some("key"),
+                Some(&Event { id: 3 }),
+                Headers::new(),
+            )
+            .unwrap();
+        builder
+            .append_with::<str, Event, _, _>(&Utf8, &Json, 0, None, None, Headers::new())
+            .unwrap();
+        let batch = &builder.build()[0];
+        let record = &batch.records[0];
+        assert_eq!(record.value.as_deref(), Some(&br#"{"id":3}"#[..]));
+        assert_eq!(record.key_with(&Utf8).unwrap().unwrap(), "key");
+        assert_eq!(
+            record.value_with::<Event, _>(&Json).unwrap().unwrap(),
+            Event { id: 3 }
+        );
+        assert!(batch.records[1].value_with::<Event, _>(&Json).is_none());
+
+        let err = RecordDeserializer::<Event>::deserialize(&Json, b"{").unwrap_err();
+        match *err.kind() {
+            ErrorKind::Custom(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
@@ -0,0 +1,357 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use super::{
+    nullable_bytes_size, read_nullable_bytes, varint_len_size, write_nullable_bytes, BatchHeader,
+    HeaderRefs, Headers, RecordDeserializer, TimestampType,
+};
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::{ser, varint};
+use std::io::{self, Read};
+
+/// A single record inside a [`RecordBatch`](super::RecordBatch)
+///
+/// Offsets and timestamps are stored as deltas from the batch's base offset and base timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Record {
+    /// Record attributes. Unused by the protocol so far
+    pub attributes: i8,
+    /// Timestamp, relative to the batch's base timestamp
+    pub timestamp_delta: i64,
+    /// Offset, relative to the batch's base offset
+    pub offset_delta: i32,
+    /// The record key
+    pub key: Option<Vec<u8>>,
+    /// The record value
+    pub value: Option<Vec<u8>>,
+    /// The record headers
+    pub headers: Headers,
+}
+
+impl Record {
+    /// Decodes the key with `deserializer`, unless it is null
+    pub fn key_with<T, D: RecordDeserializer<T>>(&self, deserializer: &D) -> Option<Result<T>> {
+        self.key.as_deref().map(|key| deserializer.deserialize(key))
+    }
+
+    /// Decodes the value with `deserializer`, unless it is null
+    pub fn value_with<T, D: RecordDeserializer<T>>(&self, deserializer: &D) -> Option<Result<T>> {
+        self.value
+            .as_deref()
+            .map(|value| deserializer.deserialize(value))
+    }
+
+    fn body_size(&self) -> usize {
+        std::mem::size_of::<i8>()
+            + varint::varlong_size(self.timestamp_delta)
+            + varint::varint_size(self.offset_delta)
+            + nullable_bytes_size(self.key.as_deref())
+            + nullable_bytes_size(self.value.as_deref())
+            + self.headers.encoded_size()
+    }
+
+    /// How many bytes the record takes once encoded, including its length prefix
+    pub fn encoded_size(&self) -> usize {
+        let body = self.body_size();
+        varint_len_size(body) + body
+    }
+
+    pub(crate) fn write<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        varint::write_varint(writer, ser::len_i32("record", self.body_size())?)?;
+        writer.write_all(&self.attributes.to_be_bytes())?;
+        varint::write_varlong(writer, self.timestamp_delta)?;
+        varint::write_varint(writer, self.offset_delta)?;
+        write_nullable_bytes(writer, self.key.as_deref())?;
+        write_nullable_bytes(writer, self.value.as_deref())?;
+        self.headers.encode(writer)
+    }
+
+    pub(crate) fn read(de: &mut KafkaDeserializer<'_>) -> Result<Record> {
+        let len = de.read_varint()?;
+        if len < 0 {
+            return Err(ErrorKind::InvalidLength(len).into());
+        }
+        Record::read_body(&mut KafkaDeserializer::new(de.slice(len as usize)?))
+    }
+
+    /// Reads a record from a stream, using `buf` as scratch space for its body
+    pub(crate) fn read_from<R: io::Read>(reader: &mut R, buf: &mut Vec<u8>) -> Result<Record> {
+        let len = varint::read_varint(reader)?;
+        if len < 0 {
+            return Err(ErrorKind::InvalidLength(len).into());
+        }
+        // the body is allocated as it is read, rather than from a length that may be bogus
+        buf.clear();
+        reader.by_ref().take(len as u64).read_to_end(buf)?;
+        if buf.len() < len as usize {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Record::read_body(&mut KafkaDeserializer::new(buf))
+    }
+
+    fn read_body(body: &mut KafkaDeserializer<'_>) -> Result<Record> {
+        let attributes = body.read_i8()?;
+        let timestamp_delta = body.read_varlong()?;
+        let offset_delta = body.read_varint()?;
+        let key = read_nullable_bytes(body)?.map(|k| k.to_vec());
+        let value = read_nullable_bytes(body)?.map(|v| v.to_vec());
+        let headers = Headers::read(body)?;
+        Ok(Record {
+            attributes,
+            timestamp_delta,
+            offset_delta,
+            key,
+            value,
+            headers,
+        })
+    }
+}
+
+/// A record borrowing its key, value and headers from the buffer it was decoded from
+///
+/// Obtained from [`LazyBatch::record_refs`](super::LazyBatch::record_refs), for uncompressed
+/// batches. Headers are only decoded when iterated over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordRef<'a> {
+    /// Record attributes
+    pub attributes: i8,
+    /// Timestamp, relative to the batch's base timestamp
+    pub timestamp_delta: i64,
+    /// Offset, relative to the batch's base offset
+    pub offset_delta: i32,
+    /// The record key
+    pub key: Option<&'a [u8]>,
+    /// The record value
+    pub value: Option<&'a [u8]>,
+    headers: &'a [u8],
+}
+
+impl<'a> RecordRef<'a> {
+    /// Iterates over the record headers
+    pub fn headers(&self) -> HeaderRefs<'a> {
+        HeaderRefs::new(self.headers)
+    }
+
+    /// The absolute offset of this record, given the header of the batch it belongs to
+    pub fn offset(&self, batch: &BatchHeader) -> i64 {
+        batch.base_offset + self.offset_delta as i64
+    }
+
+    /// The absolute timestamp of this record, given the header of the batch it belongs to
+    pub fn timestamp(&self, batch: &BatchHeader) -> i64 {
+        match batch.timestamp_type() {
+            TimestampType::CreateTime => batch.base_timestamp + self.timestamp_delta,
+            TimestampType::LogAppendTime => batch.max_timestamp,
+        }
+    }
+
+    /// Decodes the key with `deserializer`, unless it is null
+    pub fn key_with<T, D: RecordDeserializer<T>>(&self, deserializer: &D) -> Option<Result<T>> {
+        self.key.map(|key| deserializer.deserialize(key))
+    }
+
+    /// Decodes the value with `deserializer`, unless it is null
+    pub fn value_with<T, D: RecordDeserializer<T>>(&self, deserializer: &D) -> Option<Result<T>> {
+        self.value.map(|value| deserializer.deserialize(value))
+    }
+
+    /// Copies the record into an owned [`Record`]
+    pub fn to_owned(&self) -> Result<Record> {
+        Ok(Record {
+            attributes: self.attributes,
+            timestamp_delta: self.timestamp_delta,
+            offset_delta: self.offset_delta,
+            key: self.key.map(|k| k.to_vec()),
+            value: self.value.map(|v| v.to_vec()),
+            headers: self
+                .headers()
+                .map(|h| h.map(|h| h.to_owned()))
+                .collect::<Result<Headers>>()?,
+        })
+    }
+
+    pub(crate) fn read(de: &mut KafkaDeserializer<'a>) -> Result<RecordRef<'a>> {
+        let len = de.read_varint()?;
+        if len < 0 {
+            return Err(ErrorKind::InvalidLength(len).into());
+        }
+        let mut body = KafkaDeserializer::new(de.slice(len as usize)?);
+        let attributes = body.read_i8()?;
+        let timestamp_delta = body.read_varlong()?;
+        let offset_delta = body.read_varint()?;
+        let key = read_nullable_bytes(&mut body)?;
+        let value = read_nullable_bytes(&mut body)?;
+        let headers = body.slice(body.remaining())?;
+        Ok(RecordRef {
+            attributes,
+            timestamp_delta,
+            offset_delta,
+            key,
+            value,
+            headers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::records::Header;
+
+    #[test]
+    fn test_encode_record() {
+        let record = Record {
+            attributes: 0,
+            timestamp_delta: 1,
+            offset_delta: 2,
+            key: None,
+            value: Some(b"v".to_vec()),
+            headers: Headers::new(),
+        };
+        let mut buf = Vec::new();
+        record.write(&mut buf).unwrap();
+        assert_eq!(buf, [0x0e, 0x00, 0x02, 0x04, 0x01, 0x02, b'v', 0x00]);
+        assert_eq!(buf.len(), record.encoded_size());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let record = Record {
+            attributes: 0,
+            timestamp_delta: -5,
+            offset_delta: 300,
+            key: Some(b"key".to_vec()),
+            value: None,
+            headers: vec![Header::new("h", Some(vec![1]))].into(),
+        };
+        let mut buf = Vec::new();
+        record.write(&mut buf).unwrap();
+        assert_eq!(buf.len(), record.encoded_size());
+        let decoded = Record::read(&mut KafkaDeserializer::new(&buf)).unwrap();
+        assert_eq!(decoded, record);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_roundtrip() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut seed = 1u64;
+        for _ in 0..64 {
+            let bytes: Vec<u8> = (0..256)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    (seed >> 56) as u8
+                })
+                .collect();
+            let record = Record::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            let mut buf = Vec::new();
+            record.write(&mut buf).unwrap();
+            assert_eq!(buf.len(), record.encoded_size());
+            let decoded = Record::read(&mut KafkaDeserializer::new(&buf)).unwrap();
+            assert_eq!(decoded, record);
+        }
+    }
+
+    #[test]
+    fn test_read_from_stream() {
+        let record = Record {
+            key: Some(b"key".to_vec()),
+            value: Some(b"value".to_vec()),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        record.write(&mut buf).unwrap();
+        record.write(&mut buf).unwrap();
+
+        let mut reader = &buf[..];
+        let mut scratch = Vec::new();
+        assert_eq!(
+            Record::read_from(&mut reader, &mut scratch).unwrap(),
+            record
+        );
+        assert_eq!(
+            Record::read_from(&mut reader, &mut scratch).unwrap(),
+            record
+        );
+        match *Record::read_from(&mut reader, &mut scratch)
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::Io(ref err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        // a record claiming a gigabyte fails once the stream ends, not allocating it
+        let mut buf = Vec::new();
+        varint::write_varint(&mut buf, 1 << 30).unwrap();
+        buf.push(0);
+        assert!(Record::read_from(&mut &buf[..], &mut scratch).is_err());
+        assert!(scratch.capacity() < 1 << 20);
+    }
+
+    #[test]
+    fn test_record_ref() {
+        let record = Record {
+            timestamp_delta: 3,
+            offset_delta: 4,
+            key: Some(b"key".to_vec()),
+            value: None,
+            headers: vec![Header::new("h", Some(vec![1]))].into(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        record.write(&mut buf).unwrap();
+        let r = RecordRef::read(&mut KafkaDeserializer::new(&buf)).unwrap();
+        assert_eq!(r.key, Some(&b"key"[..]));
+        assert_eq!(r.value, None);
+        assert_eq!(r.headers().next().unwrap().unwrap().key, "h");
+        assert_eq!(r.to_owned().unwrap(), record);
+
+        let batch = BatchHeader {
+            base_offset: 10,
+            partition_leader_epoch: 0,
+            attributes: 0,
+            last_offset_delta: 4,
+            base_timestamp: 100,
+            max_timestamp: 103,
+            producer_id: -1,
+            producer_epoch: -1,
+            base_sequence: -1,
+            record_count: 1,
+        };
+        assert_eq!(r.offset(&batch), 14);
+        assert_eq!(r.timestamp(&batch), 103);
+    }
+
+    #[test]
+    fn test_length_exceeds_body() {
+        // the record claims 2 bytes, but its fields need more than that
+        let data = [0x04, 0x00, 0x02, 0x04, 0x01];
+        assert!(Record::read(&mut KafkaDeserializer::new(&data)).is_err());
+    }
+
+    #[test]
+    fn test_negative_length() {
+        // a varint of -2
+        let data = [0x03, 0x00];
+        match *Record::read(&mut KafkaDeserializer::new(&data))
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::InvalidLength(-2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        match *RecordRef::read(&mut KafkaDeserializer::new(&data))
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::InvalidLength(-2) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
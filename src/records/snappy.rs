@@ -0,0 +1,197 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Snappy compression, including the xerial framing used by the Java client.
+//!
+//! The Java client doesn't emit raw snappy: it wraps the data in the framing of xerial's
+//! `SnappyOutputStream`, a 16-byte header followed by blocks, each prefixed by its big-endian
+//! `int32` compressed length. Other clients emit raw snappy, so decoding accepts both.
+use crate::error::{ErrorKind, Result};
+use std::convert::TryInto;
+use std::io;
+
+const XERIAL_MAGIC: [u8; 8] = [0x82, b'S', b'N', b'A', b'P', b'P', b'Y', 0];
+const XERIAL_VERSION: i32 = 1;
+const XERIAL_COMPATIBLE_VERSION: i32 = 1;
+const XERIAL_HEADER_LEN: usize = 16;
+// matches the block size the Java client uses when compressing record sets
+const XERIAL_BLOCK_SIZE: usize = 32 * 1024;
+// the most snappy expands its input: a copy of up to 64 bytes takes 3 bytes of it
+const MAX_EXPANSION: usize = 22;
+
+/// Compresses `data` with xerial framing, as the Java client does
+pub(crate) fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = snap::raw::Encoder::new();
+    let mut out = Vec::with_capacity(XERIAL_HEADER_LEN + data.len() / 2);
+    out.extend_from_slice(&XERIAL_MAGIC);
+    out.extend_from_slice(&XERIAL_VERSION.to_be_bytes());
+    out.extend_from_slice(&XERIAL_COMPATIBLE_VERSION.to_be_bytes());
+    for block in data.chunks(XERIAL_BLOCK_SIZE) {
+        let compressed = encoder.compress_vec(block).map_err(io::Error::from)?;
+        out.extend_from_slice(&(compressed.len() as i32).to_be_bytes());
+        out.extend_from_slice(&compressed);
+    }
+    Ok(out)
+}
+
+/// Returns a reader over the decompressed contents of `data`, which may or may not use xerial
+/// framing
+///
+/// Xerial-framed data is decompressed one block at a time as it is read. Raw snappy can't be
+/// split, so it is decompressed at once.
+pub(crate) fn reader<'a>(data: &'a [u8]) -> Result<Box<dyn io::Read + 'a>> {
+    if data.len() < XERIAL_HEADER_LEN || data[..XERIAL_MAGIC.len()] != XERIAL_MAGIC {
+        decompressed_len(data)?;
+        let raw = snap::raw::Decoder::new()
+            .decompress_vec(data)
+            .map_err(io::Error::from)?;
+        return Ok(Box::new(io::Cursor::new(raw)));
+    }
+    Ok(Box::new(XerialReader {
+        rest: &data[XERIAL_HEADER_LEN..],
+        decoder: snap::raw::Decoder::new(),
+        block: Vec::new(),
+        pos: 0,
+    }))
+}
+
+/// The size `block` decompresses to, as its header says, which is only trusted if the block can
+/// expand that much: the buffer it decompresses into is allocated from it
+fn decompressed_len(block: &[u8]) -> Result<usize> {
+    let len = snap::raw::decompress_len(block).map_err(io::Error::from)?;
+    if len > block.len().saturating_mul(MAX_EXPANSION) {
+        return Err(ErrorKind::Custom(format!(
+            "snappy block of {} bytes claims to decompress to {}",
+            block.len(),
+            len
+        ))
+        .into());
+    }
+    Ok(len)
+}
+
+struct XerialReader<'a> {
+    rest: &'a [u8],
+    decoder: snap::raw::Decoder,
+    block: Vec<u8>,
+    pos: usize,
+}
+
+impl XerialReader<'_> {
+    fn next_block(&mut self) -> Result<()> {
+        if self.rest.len() < 4 {
+            return Err(ErrorKind::NeedMoreBytes(4 - self.rest.len()).into());
+        }
+        let len = i32::from_be_bytes(self.rest[..4].try_into().unwrap());
+        self.rest = &self.rest[4..];
+        if len < 0 {
+            return Err(ErrorKind::InvalidLength(len).into());
+        }
+        let len = len as usize;
+        if self.rest.len() < len {
+            return Err(ErrorKind::NeedMoreBytes(len - self.rest.len()).into());
+        }
+        let (block, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        self.block.resize(decompressed_len(block)?, 0);
+        let written = self
+            .decoder
+            .decompress(block, &mut self.block)
+            .map_err(io::Error::from)?;
+        self.block.truncate(written);
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl io::Read for XerialReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.block.len() {
+            if self.rest.is_empty() {
+                return Ok(0);
+            }
+            self.next_block()?;
+        }
+        let n = buf.len().min(self.block.len() - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+
+    fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        reader(data)?.read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_xerial_roundtrip() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let compressed = compress(&data).unwrap();
+        assert_eq!(&compressed[..8], &XERIAL_MAGIC);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_xerial_blocks() {
+        let data = vec![7u8; XERIAL_BLOCK_SIZE + 1];
+        let compressed = compress(&data).unwrap();
+        let first = i32::from_be_bytes(compressed[16..20].try_into().unwrap()) as usize;
+        // a second block follows the first one
+        assert!(compressed.len() > XERIAL_HEADER_LEN + 4 + first + 4);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_raw_snappy() {
+        let raw = snap::raw::Encoder::new()
+            .compress_vec(b"raw raw raw")
+            .unwrap();
+        assert_eq!(decompress(&raw).unwrap(), b"raw raw raw");
+    }
+
+    #[test]
+    fn test_expansion() {
+        let zeros = vec![0; 1 << 20];
+        let raw = snap::raw::Encoder::new().compress_vec(&zeros).unwrap();
+        assert_eq!(decompress(&raw).unwrap(), zeros);
+        assert_eq!(decompress(&compress(&zeros).unwrap()).unwrap(), zeros);
+
+        // a gigabyte out of 6 bytes
+        let bogus = [0x80, 0x80, 0x80, 0x80, 0x04, 0x00];
+        assert!(decompress(&bogus).is_err());
+        let mut framed = compress(&[]).unwrap();
+        framed.extend_from_slice(&(bogus.len() as i32).to_be_bytes());
+        framed.extend_from_slice(&bogus);
+        assert!(decompress(&framed).is_err());
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(decompress(&compress(&[]).unwrap()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reads_block_by_block() {
+        let data: Vec<u8> = (0..3 * XERIAL_BLOCK_SIZE).map(|i| (i % 13) as u8).collect();
+        let compressed = compress(&data).unwrap();
+        let mut reader = reader(&compressed).unwrap();
+        let mut first = vec![0u8; 10];
+        reader.read_exact(&mut first).unwrap();
+        assert_eq!(first, &data[..10]);
+    }
+
+    #[test]
+    fn test_truncated_block() {
+        let compressed = compress(b"kafka kafka kafka").unwrap();
+        assert!(decompress(&compressed[..compressed.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+
+const TIMESTAMP_TYPE_MASK: i16 = 0x08;
+
+/// What the timestamps of a batch (or legacy message) represent, stored in bit 3 of its attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum TimestampType {
+    /// Timestamps were set by the producer when the records were created
+    #[default]
+    CreateTime,
+    /// The broker overwrote the timestamps with the time it appended the records to the log
+    ///
+    /// In a v2 batch, all records then share the batch's max timestamp.
+    LogAppendTime,
+}
+
+impl TimestampType {
+    /// Extracts the timestamp type from a set of attributes
+    pub fn from_attributes(attributes: i16) -> TimestampType {
+        if attributes & TIMESTAMP_TYPE_MASK != 0 {
+            TimestampType::LogAppendTime
+        } else {
+            TimestampType::CreateTime
+        }
+    }
+
+    /// Returns `attributes` with the timestamp type bit set to this type
+    pub fn apply(self, attributes: i16) -> i16 {
+        match self {
+            TimestampType::CreateTime => attributes & !TIMESTAMP_TYPE_MASK,
+            TimestampType::LogAppendTime => attributes | TIMESTAMP_TYPE_MASK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(TimestampType::from_attributes(0), TimestampType::CreateTime);
+        assert_eq!(
+            TimestampType::from_attributes(0x09),
+            TimestampType::LogAppendTime
+        );
+        assert_eq!(TimestampType::LogAppendTime.apply(0x01), 0x09);
+        assert_eq!(TimestampType::CreateTime.apply(0x19), 0x11);
+    }
+}
@@ -0,0 +1,1459 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Message schemas known at runtime
+//!
+//! A [`Schema`] describes a message like the JSON definitions of Apache Kafka do: its fields,
+//! their types, and the versions they exist in. Strings, bytes and arrays switch to their compact
+//! encodings in the flexible versions of the message, which also end every structure with tagged
+//! fields.
+//!
+//! [`Schema::decode`] walks a message following its schema, into a [`KafkaValue`]. This lets
+//! generic tools, like proxies and auditors, handle messages they have no Rust struct for. With
+//! the `json` feature, `to_json` decodes a message into a `serde_json::Value` instead, to dump
+//! payloads in logs and tests. [`Schema::validate`] checks a message, or a whole frame, more
+//! strictly than decoding does, to vet encoders and third-party traffic.
+//!
+//! [`Schema::decode_projected`] only decodes the fields a [`Projection`] selects, skipping the
+//! others without allocating, e.g. to read the error codes of a large MetadataResponse.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::schema::{Field, Schema, Type};
+//!
+//! let schema = Schema::new(
+//!     "FindCoordinatorRequest",
+//!     vec![
+//!         Field::new("key", Type::String),
+//!         Field::new("key_type", Type::Int8).versions(1..),
+//!     ],
+//! )
+//! .valid_versions(0..=3)
+//! .flexible_versions(3..);
+//! assert!(schema.is_flexible(3));
+//!
+//! let request = schema.decode(1, &[0, 1, b'g', 0]).unwrap();
+//! assert_eq!(request.get("key").and_then(|key| key.as_str()), Some("g"));
+//! assert_eq!(request.get("key_type").and_then(|key| key.as_i64()), Some(0));
+//!
+//! # #[cfg(feature = "json")]
+//! # {
+//! let json = kafka_serde::schema::to_json(&schema, 1, &[0, 1, b'g', 0]).unwrap();
+//! assert_eq!(json, serde_json::json!({ "key": "g", "key_type": 0 }));
+//! # }
+//! ```
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::{header, metrics, ser, varint};
+
+/// The type of a field, as named in the JSON definitions of Apache Kafka
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// `bool`, a single byte
+    Bool,
+    /// `int8`
+    Int8,
+    /// `int16`
+    Int16,
+    /// `uint16`
+    Uint16,
+    /// `int32`
+    Int32,
+    /// `uint32`
+    Uint32,
+    /// `int64`
+    Int64,
+    /// `float64`
+    Float64,
+    /// `string`, with an `i16` length, or a compact one in flexible versions
+    String,
+    /// `bytes`, with an `i32` length, or a compact one in flexible versions
+    Bytes,
+    /// `records`, a record set encoded like bytes
+    Records,
+    /// `uuid`, 16 bytes
+    Uuid,
+    /// `[]T`, an array of elements of the given type
+    Array(Box<Type>),
+    /// A structure made of the given fields
+    Struct(Vec<Field>),
+}
+
+/// A field of a message or of a structure nested in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// The field name
+    pub name: String,
+    /// The field type
+    pub ty: Type,
+    /// The versions the field is in
+    pub versions: RangeInclusive<i16>,
+    /// The versions in which the field can be null. Only strings, bytes, arrays and structs
+    /// can be
+    pub nullable_versions: RangeInclusive<i16>,
+    /// The tag of the field, if it is a tagged field of the flexible versions
+    pub tag: Option<u32>,
+}
+
+impl Field {
+    /// Creates a field that is in all versions, and never null
+    pub fn new(name: impl Into<String>, ty: Type) -> Self {
+        Field {
+            name: name.into(),
+            ty,
+            versions: 0..=i16::MAX,
+            #[allow(clippy::reversed_empty_ranges)]
+            nullable_versions: 1..=0,
+            tag: None,
+        }
+    }
+
+    /// Sets the versions the field is in, e.g. `3..` for a field added in v3
+    pub fn versions(mut self, versions: impl RangeBounds<i16>) -> Self {
+        self.versions = inclusive(versions);
+        self
+    }
+
+    /// Sets the versions in which the field can be null
+    pub fn nullable_versions(mut self, versions: impl RangeBounds<i16>) -> Self {
+        self.nullable_versions = inclusive(versions);
+        self
+    }
+
+    /// Makes the field a tagged field, only found among the tagged fields of the flexible
+    /// versions
+    pub fn tag(mut self, tag: u32) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+}
+
+/// A request or response, or any other versioned structure like the values of internal topics
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// The message name, e.g. `FetchRequest`
+    pub name: String,
+    /// The versions that can be decoded
+    pub valid_versions: RangeInclusive<i16>,
+    /// The versions using compact encodings and tagged fields
+    pub flexible_versions: RangeInclusive<i16>,
+    /// The message fields, in the order they are encoded in
+    pub fields: Vec<Field>,
+}
+
+impl Schema {
+    /// Creates a schema valid in all versions, none of which are flexible
+    pub fn new(name: impl Into<String>, fields: Vec<Field>) -> Self {
+        Schema {
+            name: name.into(),
+            valid_versions: 0..=i16::MAX,
+            #[allow(clippy::reversed_empty_ranges)]
+            flexible_versions: 1..=0,
+            fields,
+        }
+    }
+
+    /// Sets the versions that can be decoded
+    pub fn valid_versions(mut self, versions: impl RangeBounds<i16>) -> Self {
+        self.valid_versions = inclusive(versions);
+        self
+    }
+
+    /// Sets the versions using compact encodings and tagged fields, e.g. `9..`
+    pub fn flexible_versions(mut self, versions: impl RangeBounds<i16>) -> Self {
+        self.flexible_versions = inclusive(versions);
+        self
+    }
+
+    /// Whether `version` uses compact encodings and tagged fields
+    pub fn is_flexible(&self, version: i16) -> bool {
+        self.flexible_versions.contains(&version)
+    }
+
+    fn check_version(&self, version: i16) -> Result<()> {
+        if self.valid_versions.contains(&version) {
+            Ok(())
+        } else {
+            Err(ErrorKind::Custom(format!("unsupported {} version: {}", self.name, version)).into())
+        }
+    }
+
+    /// Fails unless `version` is valid, and the top-level fields named `fields` are all in it,
+    /// as generated builders check for the fields they were given
+    pub fn check_fields(&self, version: i16, fields: &[&str]) -> Result<()> {
+        self.check_version(version)?;
+        for name in fields {
+            let field = self.fields.iter().find(|field| field.name == *name);
+            if !field.is_some_and(|field| field.versions.contains(&version)) {
+                return Err(ErrorKind::Custom(format!(
+                    "{} has no field {} in version {}",
+                    self.name, name, version
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The fields to decode with [`Schema::decode_projected`]
+///
+/// Fields are selected by their path, made of the names of the fields leading to them separated
+/// by dots, like `Topics.Partitions.ErrorCode`: the elements of arrays are walked through. The
+/// structures and arrays of structures selected by name are decoded whole.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::schema::Projection;
+///
+/// let projection = Projection::new()
+///     .field("ThrottleTimeMs")
+///     .field("Topics.ErrorCode")
+///     .field("Topics.Partitions.ErrorCode");
+/// assert!(projection.contains("Topics.Partitions"));
+/// assert!(!projection.contains("Topics.Name"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Projection {
+    // the fields selected, with what is selected in them, or none for all of them
+    fields: Option<Vec<(String, Projection)>>,
+}
+
+/// The projection selecting all fields
+static ALL: Projection = Projection { fields: None };
+
+impl Projection {
+    /// A projection selecting no fields
+    pub fn new() -> Self {
+        Projection {
+            fields: Some(Vec::new()),
+        }
+    }
+
+    /// Selects the field at `path`
+    pub fn field(mut self, path: &str) -> Self {
+        let mut projection = &mut self;
+        for name in path.split('.') {
+            let fields = match projection.fields {
+                Some(ref mut fields) => fields,
+                // everything is already selected
+                None => return self,
+            };
+            let index = match fields.iter().position(|(field, _)| field == name) {
+                Some(index) => index,
+                None => {
+                    fields.push((name.to_owned(), Projection::new()));
+                    fields.len() - 1
+                }
+            };
+            projection = &mut fields[index].1;
+        }
+        projection.fields = None;
+        self
+    }
+
+    /// Whether the field at `path`, or some of the fields in it, are selected
+    pub fn contains(&self, path: &str) -> bool {
+        let mut projection = self;
+        for name in path.split('.') {
+            match projection.select(name) {
+                Some(nested) => projection = nested,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// What is selected in the field `name`, if anything
+    fn select(&self, name: &str) -> Option<&Projection> {
+        match self.fields {
+            Some(ref fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, nested)| nested),
+            None => Some(&ALL),
+        }
+    }
+}
+
+/// Turns any range of versions into an inclusive one, open ends going as far as versions go
+fn inclusive(versions: impl RangeBounds<i16>) -> RangeInclusive<i16> {
+    let start = match versions.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match versions.end_bound() {
+        Bound::Included(&end) => end,
+        Bound::Excluded(&end) => end.saturating_sub(1),
+        Bound::Unbounded => i16::MAX,
+    };
+    start..=end
+}
+
+/// A value decoded by following a [`Schema`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum KafkaValue {
+    /// A null string, bytes, array or struct
+    Null,
+    /// A `bool`
+    Bool(bool),
+    /// An `int8`
+    Int8(i8),
+    /// An `int16`
+    Int16(i16),
+    /// A `uint16`
+    Uint16(u16),
+    /// An `int32`
+    Int32(i32),
+    /// A `uint32`
+    Uint32(u32),
+    /// An `int64`
+    Int64(i64),
+    /// A `float64`
+    Float64(f64),
+    /// A `string`
+    String(String),
+    /// `bytes`
+    Bytes(Vec<u8>),
+    /// A record set, still encoded
+    Records(Vec<u8>),
+    /// A `uuid`
+    Uuid([u8; 16]),
+    /// An array
+    Array(Vec<KafkaValue>),
+    /// A structure, with its fields in the order they were decoded in. Fields that are not in
+    /// the decoded version, and tagged fields that were not set, are left out
+    Struct(Vec<(String, KafkaValue)>),
+}
+
+impl KafkaValue {
+    /// The value of the field `name`, if this is a structure that has it
+    pub fn get(&self, name: &str) -> Option<&KafkaValue> {
+        match *self {
+            KafkaValue::Struct(ref fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// The value, if it is an integer of any width
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            KafkaValue::Int8(value) => Some(value.into()),
+            KafkaValue::Int16(value) => Some(value.into()),
+            KafkaValue::Uint16(value) => Some(value.into()),
+            KafkaValue::Int32(value) => Some(value.into()),
+            KafkaValue::Uint32(value) => Some(value.into()),
+            KafkaValue::Int64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value, if it is a string
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            KafkaValue::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The elements, if this is an array
+    pub fn as_array(&self) -> Option<&[KafkaValue]> {
+        match *self {
+            KafkaValue::Array(ref elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// The fields, if this is a structure
+    pub fn into_fields(self) -> Result<Vec<(String, KafkaValue)>> {
+        match self {
+            KafkaValue::Struct(fields) => Ok(fields),
+            value => Err(mismatch("a struct", &value)),
+        }
+    }
+}
+
+impl Schema {
+    /// Decodes `buf`, a message of this schema in `version`
+    ///
+    /// Unknown tagged fields are skipped. Errors are wrapped in [`ErrorKind::Decode`], like
+    /// those of [`from_bytes`](crate::from_bytes).
+    pub fn decode(&self, version: i16, buf: &[u8]) -> Result<KafkaValue> {
+        self.check_version(version)?;
+        let mut walker = Walker::new(buf, version, self.is_flexible(version), false);
+        let result = walker.walk(&self.fields, &ALL);
+        metrics::report_decode(&result, walker.de.position());
+        result
+    }
+
+    /// Decodes the fields of `buf`, a message of this schema in `version`, that `projection`
+    /// selects
+    ///
+    /// The other fields are skipped, following their lengths: their strings and bytes are
+    /// neither copied nor checked. Structures only hold the fields selected in them.
+    ///
+    /// # Examples
+    /// ```
+    /// use kafka_serde::schema::{Field, KafkaValue, Projection, Schema, Type};
+    ///
+    /// let schema = Schema::new(
+    ///     "DeleteTopicsResponse",
+    ///     vec![Field::new(
+    ///         "Responses",
+    ///         Type::Array(Box::new(Type::Struct(vec![
+    ///             Field::new("Name", Type::String),
+    ///             Field::new("ErrorCode", Type::Int16),
+    ///         ]))),
+    ///     )],
+    /// );
+    /// let buf = [0, 0, 0, 2, 0, 1, b'a', 0, 0, 0, 1, b'b', 0, 3];
+    /// let projection = Projection::new().field("Responses.ErrorCode");
+    /// let response = schema.decode_projected(0, &buf, &projection).unwrap();
+    /// let codes: Vec<_> = response.get("Responses").and_then(|r| r.as_array()).unwrap()
+    ///     .iter()
+    ///     .map(|response| response.get("ErrorCode").and_then(|code| code.as_i64()))
+    ///     .collect();
+    /// assert_eq!(codes, [Some(0), Some(3)]);
+    /// ```
+    pub fn decode_projected(
+        &self,
+        version: i16,
+        buf: &[u8],
+        projection: &Projection,
+    ) -> Result<KafkaValue> {
+        self.check_version(version)?;
+        let mut walker = Walker::new(buf, version, self.is_flexible(version), false);
+        let result = walker.walk(&self.fields, projection);
+        metrics::report_decode(&result, walker.de.position());
+        result
+    }
+
+    /// Checks that `buf` is exactly a message of this schema in `version`, e.g. the output of
+    /// an encoder written by hand or traffic captured from another client
+    ///
+    /// This is stricter than [`decode`](Schema::decode), which lets messages be followed by
+    /// other bytes and tagged fields come in any order: the message must take up all of `buf`,
+    /// and tagged fields must come in increasing tag order, as the Java implementation writes
+    /// them. The first violation found fails with an [`ErrorKind::Decode`], holding its offset
+    /// and the path of the field it is in.
+    ///
+    /// # Examples
+    /// ```
+    /// use kafka_serde::schema::{Field, Schema, Type};
+    /// use kafka_serde::ErrorKind;
+    ///
+    /// let schema = Schema::new(
+    ///     "ListGroupsResponse",
+    ///     vec![
+    ///         Field::new("ErrorCode", Type::Int16),
+    ///         Field::new(
+    ///             "Groups",
+    ///             Type::Array(Box::new(Type::Struct(vec![
+    ///                 Field::new("GroupId", Type::String),
+    ///                 Field::new("ProtocolType", Type::String),
+    ///             ]))),
+    ///         ),
+    ///     ],
+    /// );
+    /// assert!(schema.validate(0, &[0, 0, 0, 0, 0, 1, 0, 1, b'g', 0, 0]).is_ok());
+    ///
+    /// // the protocol type is 2 bytes long, but only 1 is left
+    /// let err = schema.validate(0, &[0, 0, 0, 0, 0, 1, 0, 1, b'g', 0, 2, b'c']).unwrap_err();
+    /// match *err.kind() {
+    ///     ErrorKind::Decode { offset, ref path, .. } => {
+    ///         assert_eq!((offset, path.as_str()), (9, "Groups[0].ProtocolType"));
+    ///     }
+    ///     ref e => panic!("unexpected error {}", e),
+    /// }
+    /// ```
+    pub fn validate(&self, version: i16, buf: &[u8]) -> Result<()> {
+        self.validate_from(version, buf, 0)
+    }
+
+    /// Checks a request frame, stripped of its size, whose body must be a message of this
+    /// schema in the version of its header, like [`validate`](Schema::validate) does
+    ///
+    /// Offsets in errors are from the start of the frame.
+    pub fn validate_request(&self, frame: &[u8]) -> Result<()> {
+        let (header, body) = header::split_request(frame, |_, version| self.is_flexible(version))?;
+        self.validate_from(header.api_version, frame, frame.len() - body.len())
+    }
+
+    /// Checks a response frame, stripped of its size, whose header is in `header_version` and
+    /// whose body must be a message of this schema in `version`, like
+    /// [`validate`](Schema::validate) does
+    ///
+    /// Offsets in errors are from the start of the frame.
+    pub fn validate_response(&self, version: i16, header_version: i16, frame: &[u8]) -> Result<()> {
+        let (_, body) = header::split_response(frame, header_version)?;
+        self.validate_from(version, frame, frame.len() - body.len())
+    }
+
+    /// Checks the message starting at `start` in `buf`, and ending with it
+    fn validate_from(&self, version: i16, buf: &[u8], start: usize) -> Result<()> {
+        self.check_version(version)?;
+        let mut walker = Walker::new(buf, version, self.is_flexible(version), true);
+        walker.de.slice(start)?;
+        walker.walk(&self.fields, &ALL)?;
+        if walker.de.remaining() > 0 {
+            walker.de.expect("end of message");
+            let err = ErrorKind::Custom(format!(
+                "{} bytes follow the message",
+                walker.de.remaining()
+            ));
+            return Err(walker.de.locate(err.into()));
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a message field by field, following its schema
+struct Walker<'de> {
+    de: KafkaDeserializer<'de>,
+    version: i16,
+    flexible: bool,
+    // whether to check what decoding lets through, see `Schema::validate`
+    strict: bool,
+    // fields and indexes leading to the value that failed, innermost first, like `.Name` or
+    // `[2]`. Only filled in as an error unwinds
+    path: Vec<String>,
+}
+
+impl<'de> Walker<'de> {
+    fn new(buf: &'de [u8], version: i16, flexible: bool, strict: bool) -> Self {
+        Walker {
+            de: KafkaDeserializer::new(buf),
+            version,
+            flexible,
+            strict,
+            path: Vec::new(),
+        }
+    }
+
+    /// Reads the fields of a message made of `fields` that `projection` selects, with its
+    /// errors located
+    fn walk(&mut self, fields: &[Field], projection: &Projection) -> Result<KafkaValue> {
+        self.read_struct(fields, projection).map_err(|err| {
+            let path: String = self.path.drain(..).rev().collect();
+            let path = path.strip_prefix('.').unwrap_or(&path).to_owned();
+            self.de.locate_at(err, path)
+        })
+    }
+
+    fn read_struct(&mut self, fields: &[Field], projection: &Projection) -> Result<KafkaValue> {
+        let mut values = match projection.fields {
+            Some(ref selected) => Vec::with_capacity(selected.len()),
+            None => Vec::with_capacity(fields.len()),
+        };
+        let version = self.version;
+        let present = fields.iter().filter(move |f| f.versions.contains(&version));
+        for field in present.clone().filter(|f| f.tag.is_none()) {
+            let nullable = field.nullable_versions.contains(&self.version);
+            match projection.select(&field.name) {
+                Some(nested) => {
+                    let value = self.read_field(field, nullable, nested)?;
+                    values.push((field.name.clone(), value));
+                }
+                None => self.skip_field(field, nullable)?,
+            }
+        }
+        if !self.flexible {
+            return Ok(KafkaValue::Struct(values));
+        }
+
+        self.de.expect("tagged fields");
+        let mut last_tag = None;
+        for _ in 0..self.de.read_unsigned_varint()? {
+            self.de.expect("tag");
+            let tag = self.de.read_unsigned_varint()?;
+            if self.strict && last_tag.is_some_and(|last| tag <= last) {
+                return Err(ErrorKind::Custom(format!(
+                    "tagged field {} follows tagged field {}",
+                    tag,
+                    last_tag.unwrap_or_default()
+                ))
+                .into());
+            }
+            last_tag = Some(tag);
+            self.de.expect("tagged field size");
+            let size = self.de.read_unsigned_varint()? as usize;
+            let remaining = self.de.remaining();
+            let field = present.clone().find(|f| f.tag == Some(tag));
+            match field.and_then(|field| Some((field, projection.select(&field.name)?))) {
+                Some((field, nested)) => {
+                    let nullable = field.nullable_versions.contains(&self.version);
+                    let value = self.read_field(field, nullable, nested)?;
+                    if remaining - self.de.remaining() != size {
+                        return Err(ErrorKind::Custom(format!(
+                            "tagged field {} is not {} bytes long",
+                            field.name, size
+                        ))
+                        .into());
+                    }
+                    values.push((field.name.clone(), value));
+                }
+                None => {
+                    self.de.expect(if field.is_some() {
+                        "tagged field"
+                    } else {
+                        "unknown tagged field"
+                    });
+                    self.de.slice(size)?;
+                }
+            }
+        }
+        Ok(KafkaValue::Struct(values))
+    }
+
+    fn read_field(
+        &mut self,
+        field: &Field,
+        nullable: bool,
+        projection: &Projection,
+    ) -> Result<KafkaValue> {
+        let value = self.read_value(&field.ty, nullable, projection);
+        if value.is_err() {
+            self.path.push(format!(".{}", field.name));
+        }
+        value
+    }
+
+    fn skip_field(&mut self, field: &Field, nullable: bool) -> Result<()> {
+        let skipped = self.skip_value(&field.ty, nullable);
+        if skipped.is_err() {
+            self.path.push(format!(".{}", field.name));
+        }
+        skipped
+    }
+
+    /// Reads a value of type `ty`, only with the fields `projection` selects in structures
+    fn read_value(
+        &mut self,
+        ty: &Type,
+        nullable: bool,
+        projection: &Projection,
+    ) -> Result<KafkaValue> {
+        let value = match *ty {
+            Type::Bool => {
+                self.de.expect("bool");
+                match self.de.read_u8()? {
+                    0 => KafkaValue::Bool(false),
+                    1 => KafkaValue::Bool(true),
+                    value => return Err(ErrorKind::InvalidBoolEncoding(value).into()),
+                }
+            }
+            Type::Int8 => {
+                self.de.expect("i8");
+                KafkaValue::Int8(self.de.read_i8()?)
+            }
+            Type::Int16 => {
+                self.de.expect("i16");
+                KafkaValue::Int16(self.de.read_i16()?)
+            }
+            Type::Uint16 => {
+                self.de.expect("u16");
+                KafkaValue::Uint16(self.de.read_u16()?)
+            }
+            Type::Int32 => {
+                self.de.expect("i32");
+                KafkaValue::Int32(self.de.read_i32()?)
+            }
+            Type::Uint32 => {
+                self.de.expect("u32");
+                KafkaValue::Uint32(self.de.read_u32()?)
+            }
+            Type::Int64 => {
+                self.de.expect("i64");
+                KafkaValue::Int64(self.de.read_i64()?)
+            }
+            Type::Float64 => {
+                self.de.expect("f64");
+                KafkaValue::Float64(f64::from_bits(self.de.read_u64()?))
+            }
+            Type::String => {
+                self.de.expect("string");
+                let s = if self.flexible {
+                    self.de.read_compact_nullable_string()?
+                } else {
+                    self.de.read_nullable_string()?
+                };
+                match s {
+                    Some(s) => KafkaValue::String(s.to_owned()),
+                    None => return null(nullable, "string"),
+                }
+            }
+            Type::Bytes | Type::Records => {
+                self.de.expect("bytes");
+                let bytes = if self.flexible {
+                    self.de.read_compact_nullable_bytes()?
+                } else {
+                    self.de.read_nullable_bytes()?
+                };
+                match bytes {
+                    Some(bytes) if *ty == Type::Records => KafkaValue::Records(bytes.to_vec()),
+                    Some(bytes) => KafkaValue::Bytes(bytes.to_vec()),
+                    None => return null(nullable, "bytes"),
+                }
+            }
+            Type::Uuid => {
+                self.de.expect("uuid");
+                let mut uuid = [0; 16];
+                uuid.copy_from_slice(self.de.slice(16)?);
+                KafkaValue::Uuid(uuid)
+            }
+            Type::Array(ref element) => {
+                let len = match self.read_array_len()? {
+                    Some(len) => len,
+                    None => return null(nullable, "array"),
+                };
+                // elements are read one by one, so a bogus length fails once the bytes run out
+                let mut elements = Vec::new();
+                for index in 0..len {
+                    match self.read_value(element, false, projection) {
+                        Ok(value) => elements.push(value),
+                        Err(err) => {
+                            self.path.push(format!("[{}]", index));
+                            return Err(err);
+                        }
+                    }
+                }
+                KafkaValue::Array(elements)
+            }
+            Type::Struct(ref fields) if nullable => {
+                self.de.expect("struct presence");
+                match self.de.read_i8()? {
+                    -1 => KafkaValue::Null,
+                    1 => self.read_struct(fields, projection)?,
+                    marker => return Err(invalid_presence(marker)),
+                }
+            }
+            Type::Struct(ref fields) => self.read_struct(fields, projection)?,
+        };
+        Ok(value)
+    }
+
+    /// Reads past a value of type `ty`, only checking what is needed to find where it ends
+    fn skip_value(&mut self, ty: &Type, nullable: bool) -> Result<()> {
+        match *ty {
+            Type::Bool => self.skip("bool", 1),
+            Type::Int8 => self.skip("i8", 1),
+            Type::Int16 => self.skip("i16", 2),
+            Type::Uint16 => self.skip("u16", 2),
+            Type::Int32 => self.skip("i32", 4),
+            Type::Uint32 => self.skip("u32", 4),
+            Type::Int64 => self.skip("i64", 8),
+            Type::Float64 => self.skip("f64", 8),
+            Type::Uuid => self.skip("uuid", 16),
+            Type::String | Type::Bytes | Type::Records => {
+                let what = if *ty == Type::String {
+                    "string"
+                } else {
+                    "bytes"
+                };
+                self.de.expect(what);
+                let bytes = if self.flexible {
+                    self.de.read_compact_nullable_bytes()?
+                } else if *ty == Type::String {
+                    match self.de.read_i16()? {
+                        -1 => None,
+                        len if len < 0 => return Err(ErrorKind::InvalidLength(len.into()).into()),
+                        len => Some(self.de.slice(len as usize)?),
+                    }
+                } else {
+                    self.de.read_nullable_bytes()?
+                };
+                match bytes {
+                    Some(_) => Ok(()),
+                    None => null(nullable, what).map(drop),
+                }
+            }
+            Type::Array(ref element) => {
+                let len = match self.read_array_len()? {
+                    Some(len) => len,
+                    None => return null(nullable, "array").map(drop),
+                };
+                for index in 0..len {
+                    if let Err(err) = self.skip_value(element, false) {
+                        self.path.push(format!("[{}]", index));
+                        return Err(err);
+                    }
+                }
+                Ok(())
+            }
+            Type::Struct(ref fields) if nullable => {
+                self.de.expect("struct presence");
+                match self.de.read_i8()? {
+                    -1 => Ok(()),
+                    1 => self.read_struct(fields, &Projection::new()).map(drop),
+                    marker => Err(invalid_presence(marker)),
+                }
+            }
+            Type::Struct(ref fields) => self.read_struct(fields, &Projection::new()).map(drop),
+        }
+    }
+
+    fn skip(&mut self, expected: &'static str, len: usize) -> Result<()> {
+        self.de.expect(expected);
+        self.de.slice(len).map(drop)
+    }
+
+    /// Reads the length of an array, or a compact one in flexible versions, `None` if null
+    fn read_array_len(&mut self) -> Result<Option<usize>> {
+        self.de.expect("array length");
+        if self.flexible {
+            self.de.read_compact_array_len()
+        } else {
+            match self.de.read_i32()? {
+                -1 => Ok(None),
+                len if len < 0 => Err(ErrorKind::InvalidLength(len).into()),
+                len => Ok(Some(len as usize)),
+            }
+        }
+    }
+}
+
+fn invalid_presence(marker: i8) -> crate::Error {
+    ErrorKind::Custom(format!("invalid struct presence: {}", marker)).into()
+}
+
+fn null(nullable: bool, what: &str) -> Result<KafkaValue> {
+    if nullable {
+        Ok(KafkaValue::Null)
+    } else {
+        Err(ErrorKind::Custom(format!("unexpected null {}", what)).into())
+    }
+}
+
+impl Schema {
+    /// Encodes `value`, a message of this schema in `version`, at the end of `buf`
+    ///
+    /// `value` must be a structure holding all the fields that are in `version`, except tagged
+    /// fields, which are only written when they are there. Fields that are not in `version` are
+    /// ignored.
+    pub fn encode(&self, version: i16, value: &KafkaValue, buf: &mut Vec<u8>) -> Result<()> {
+        self.check_version(version)?;
+        let writer = ValueWriter {
+            version,
+            flexible: self.is_flexible(version),
+        };
+        let start = buf.len();
+        let result = writer
+            .write_struct(&self.fields, value, buf)
+            .inspect_err(|_| buf.truncate(start));
+        metrics::report_encode(&result, buf.len() - start);
+        result
+    }
+}
+
+/// Encodes a message field by field, following its schema
+struct ValueWriter {
+    version: i16,
+    flexible: bool,
+}
+
+impl ValueWriter {
+    fn write_struct(&self, fields: &[Field], value: &KafkaValue, buf: &mut Vec<u8>) -> Result<()> {
+        if !matches!(*value, KafkaValue::Struct(_)) {
+            return Err(mismatch("a struct", value));
+        }
+        let version = self.version;
+        let present = fields.iter().filter(move |f| f.versions.contains(&version));
+        for field in present.clone().filter(|f| f.tag.is_none()) {
+            let nullable = field.nullable_versions.contains(&self.version);
+            match value.get(&field.name) {
+                Some(value) => self.write_value(&field.ty, nullable, value, buf)?,
+                None => {
+                    return Err(ErrorKind::Custom(format!("missing field {}", field.name)).into())
+                }
+            }
+        }
+        if !self.flexible {
+            return Ok(());
+        }
+
+        let mut tagged: Vec<_> = present
+            .filter_map(|f| Some((f.tag?, f, value.get(&f.name)?)))
+            .collect();
+        tagged.sort_by_key(|&(tag, _, _)| tag);
+        varint::write_unsigned_varint(buf, tagged.len() as u32)?;
+        let mut field_buf = Vec::new();
+        for (tag, field, value) in tagged {
+            let nullable = field.nullable_versions.contains(&self.version);
+            field_buf.clear();
+            self.write_value(&field.ty, nullable, value, &mut field_buf)?;
+            varint::write_unsigned_varint(buf, tag)?;
+            varint::write_unsigned_varint(buf, field_buf.len() as u32)?;
+            buf.extend_from_slice(&field_buf);
+        }
+        Ok(())
+    }
+
+    fn write_value(
+        &self,
+        ty: &Type,
+        nullable: bool,
+        value: &KafkaValue,
+        buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        if *value == KafkaValue::Null {
+            return self.write_null(ty, nullable, buf);
+        }
+        match (ty, value) {
+            (Type::Bool, &KafkaValue::Bool(value)) => buf.push(value as u8),
+            (Type::Int8, &KafkaValue::Int8(value)) => buf.extend_from_slice(&value.to_be_bytes()),
+            (Type::Int16, &KafkaValue::Int16(value)) => buf.extend_from_slice(&value.to_be_bytes()),
+            (Type::Uint16, &KafkaValue::Uint16(value)) => {
+                buf.extend_from_slice(&value.to_be_bytes())
+            }
+            (Type::Int32, &KafkaValue::Int32(value)) => buf.extend_from_slice(&value.to_be_bytes()),
+            (Type::Uint32, &KafkaValue::Uint32(value)) => {
+                buf.extend_from_slice(&value.to_be_bytes())
+            }
+            (Type::Int64, &KafkaValue::Int64(value)) => buf.extend_from_slice(&value.to_be_bytes()),
+            (Type::Float64, &KafkaValue::Float64(value)) => {
+                buf.extend_from_slice(&value.to_be_bytes())
+            }
+            (Type::String, KafkaValue::String(s)) if self.flexible => {
+                ser::write_compact_nullable_string(buf, Some(s))?
+            }
+            (Type::String, KafkaValue::String(s)) => ser::write_string(buf, s)?,
+            (
+                Type::Bytes | Type::Records,
+                KafkaValue::Bytes(bytes) | KafkaValue::Records(bytes),
+            ) if self.flexible => ser::write_compact_nullable_bytes(buf, Some(bytes))?,
+            (
+                Type::Bytes | Type::Records,
+                KafkaValue::Bytes(bytes) | KafkaValue::Records(bytes),
+            ) => ser::write_nullable_bytes(buf, Some(bytes))?,
+            (Type::Uuid, KafkaValue::Uuid(uuid)) => buf.extend_from_slice(uuid),
+            (Type::Array(element), KafkaValue::Array(elements)) => {
+                if self.flexible {
+                    ser::write_compact_array_len(buf, Some(elements.len()))?;
+                } else {
+                    ser::write_array_len(buf, elements.len())?;
+                }
+                for value in elements {
+                    self.write_value(element, false, value, buf)?;
+                }
+            }
+            (Type::Struct(fields), KafkaValue::Struct(_)) => {
+                if nullable {
+                    buf.push(1);
+                }
+                self.write_struct(fields, value, buf)?;
+            }
+            (ty, value) => return Err(mismatch(&format!("{:?}", ty), value)),
+        }
+        Ok(())
+    }
+
+    fn write_null(&self, ty: &Type, nullable: bool, buf: &mut Vec<u8>) -> Result<()> {
+        if !nullable {
+            return Err(ErrorKind::Custom(format!("unexpected null {:?}", ty)).into());
+        }
+        match *ty {
+            Type::String if self.flexible => ser::write_compact_nullable_string(buf, None),
+            Type::String => ser::write_nullable_string(buf, None),
+            Type::Bytes | Type::Records if self.flexible => {
+                ser::write_compact_nullable_bytes(buf, None)
+            }
+            Type::Bytes | Type::Records => ser::write_nullable_bytes(buf, None),
+            Type::Array(_) if self.flexible => ser::write_compact_array_len(buf, None),
+            Type::Array(_) => {
+                buf.extend_from_slice(&(-1i32).to_be_bytes());
+                Ok(())
+            }
+            Type::Struct(_) => {
+                buf.push(-1i8 as u8);
+                Ok(())
+            }
+            ref ty => Err(ErrorKind::Custom(format!("unexpected null {:?}", ty)).into()),
+        }
+    }
+}
+
+fn mismatch(expected: &str, value: &KafkaValue) -> crate::Error {
+    ErrorKind::Custom(format!("expected {}, found {:?}", expected, value)).into()
+}
+
+/// Types that can be read out of a [`KafkaValue`], like the structs generated from the message
+/// definitions of Apache Kafka
+pub trait FromValue: Sized {
+    /// Reads `value`, failing if it doesn't hold this type
+    fn from_value(value: KafkaValue) -> Result<Self>;
+}
+
+/// Types that can be turned into a [`KafkaValue`], to be encoded with [`Schema::encode`]
+pub trait ToValue {
+    /// Converts `self` into a value
+    fn to_value(&self) -> KafkaValue;
+}
+
+/// Messages described by a [`Schema`], like the structs generated from the message definitions
+/// of Apache Kafka
+pub trait Message: FromValue + ToValue {
+    /// The schema of the message
+    fn schema() -> &'static Schema;
+}
+
+macro_rules! impl_value {
+    ($($t:ty => $variant:ident,)*) => {
+        $(
+            impl FromValue for $t {
+                fn from_value(value: KafkaValue) -> Result<Self> {
+                    match value {
+                        KafkaValue::$variant(value) => Ok(value),
+                        value => Err(mismatch(stringify!($t), &value)),
+                    }
+                }
+            }
+
+            impl ToValue for $t {
+                fn to_value(&self) -> KafkaValue {
+                    KafkaValue::$variant(self.clone())
+                }
+            }
+        )*
+    };
+}
+
+impl_value! {
+    bool => Bool,
+    i8 => Int8,
+    i16 => Int16,
+    u16 => Uint16,
+    i32 => Int32,
+    u32 => Uint32,
+    i64 => Int64,
+    f64 => Float64,
+    String => String,
+    [u8; 16] => Uuid,
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        match value {
+            KafkaValue::Bytes(bytes) | KafkaValue::Records(bytes) => Ok(bytes),
+            value => Err(mismatch("bytes", &value)),
+        }
+    }
+}
+
+impl ToValue for Vec<u8> {
+    fn to_value(&self) -> KafkaValue {
+        KafkaValue::Bytes(self.clone())
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        match value {
+            KafkaValue::Array(elements) => elements.into_iter().map(T::from_value).collect(),
+            value => Err(mismatch("an array", &value)),
+        }
+    }
+}
+
+impl<T: ToValue> ToValue for Vec<T> {
+    fn to_value(&self) -> KafkaValue {
+        KafkaValue::Array(self.iter().map(T::to_value).collect())
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: KafkaValue) -> Result<Self> {
+        match value {
+            KafkaValue::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> KafkaValue {
+        self.as_ref().map_or(KafkaValue::Null, T::to_value)
+    }
+}
+
+/// Takes the field `name` out of the fields of a decoded structure, or its default value if
+/// it wasn't there, as fields are left out of the versions they are not in
+pub fn take_field<T: FromValue + Default>(
+    fields: &mut Vec<(String, KafkaValue)>,
+    name: &str,
+) -> Result<T> {
+    take_field_or(fields, name, T::default())
+}
+
+/// Takes the field `name` out of the fields of a decoded structure, or `default` if it wasn't
+/// there, for fields whose definition sets their default value
+pub fn take_field_or<T: FromValue>(
+    fields: &mut Vec<(String, KafkaValue)>,
+    name: &str,
+    default: T,
+) -> Result<T> {
+    match fields.iter().position(|(field, _)| field == name) {
+        Some(index) => T::from_value(fields.swap_remove(index).1),
+        None => Ok(default),
+    }
+}
+
+/// Decodes `buf`, a message of `schema` in `version`, into JSON
+///
+/// Structures become objects, and integers numbers. Bytes, records and UUIDs become hex strings.
+/// See [`Schema::decode`].
+#[cfg(feature = "json")]
+pub fn to_json(schema: &Schema, version: i16, buf: &[u8]) -> Result<serde_json::Value> {
+    schema.decode(version, buf).map(serde_json::Value::from)
+}
+
+#[cfg(feature = "json")]
+impl From<KafkaValue> for serde_json::Value {
+    fn from(value: KafkaValue) -> Self {
+        use serde_json::Value;
+
+        match value {
+            KafkaValue::Null => Value::Null,
+            KafkaValue::Bool(value) => Value::from(value),
+            KafkaValue::Int8(value) => Value::from(value),
+            KafkaValue::Int16(value) => Value::from(value),
+            KafkaValue::Uint16(value) => Value::from(value),
+            KafkaValue::Int32(value) => Value::from(value),
+            KafkaValue::Uint32(value) => Value::from(value),
+            KafkaValue::Int64(value) => Value::from(value),
+            KafkaValue::Float64(value) => Value::from(value),
+            KafkaValue::String(value) => Value::from(value),
+            KafkaValue::Bytes(bytes) | KafkaValue::Records(bytes) => Value::from(hex(&bytes)),
+            KafkaValue::Uuid(uuid) => {
+                let hex = hex(&uuid);
+                Value::from(format!(
+                    "{}-{}-{}-{}-{}",
+                    &hex[..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..]
+                ))
+            }
+            KafkaValue::Array(elements) => {
+                Value::Array(elements.into_iter().map(Value::from).collect())
+            }
+            KafkaValue::Struct(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(out, "{:02x}", byte);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn api_versions_response() -> Schema {
+        Schema::new(
+            "ApiVersionsResponse",
+            vec![
+                Field::new("error_code", Type::Int16),
+                Field::new(
+                    "api_keys",
+                    Type::Array(Box::new(Type::Struct(vec![
+                        Field::new("api_key", Type::Int16),
+                        Field::new("min_version", Type::Int16),
+                        Field::new("max_version", Type::Int16),
+                    ]))),
+                ),
+                Field::new("throttle_time_ms", Type::Int32).versions(1..),
+                Field::new("finalized_features_epoch", Type::Int64)
+                    .versions(3..)
+                    .tag(1),
+            ],
+        )
+        .valid_versions(0..=3)
+        .flexible_versions(3..)
+    }
+
+    #[test]
+    fn test_decode() {
+        let schema = api_versions_response();
+        let v3 = [
+            0, 0, // error code
+            2, 0, 18, 0, 0, 0, 3, 0, // one api key, no tagged fields
+            0, 0, 0, 0, // throttle time
+            2, // two tagged fields
+            1, 8, 0, 0, 0, 0, 0, 0, 0, 7, // finalized features epoch
+            9, 1, 0xff, // unknown
+        ];
+        let api_key = KafkaValue::Struct(vec![
+            ("api_key".into(), KafkaValue::Int16(18)),
+            ("min_version".into(), KafkaValue::Int16(0)),
+            ("max_version".into(), KafkaValue::Int16(3)),
+        ]);
+        assert_eq!(
+            schema.decode(3, &v3).unwrap(),
+            KafkaValue::Struct(vec![
+                ("error_code".into(), KafkaValue::Int16(0)),
+                ("api_keys".into(), KafkaValue::Array(vec![api_key])),
+                ("throttle_time_ms".into(), KafkaValue::Int32(0)),
+                ("finalized_features_epoch".into(), KafkaValue::Int64(7)),
+            ])
+        );
+
+        assert!(schema.decode(4, &v3).is_err());
+        let err = schema.decode(3, &v3[..12]).unwrap_err();
+        assert!(err.is_incomplete());
+        match *err.kind() {
+            ErrorKind::Decode {
+                offset, expected, ..
+            } => assert_eq!((offset, expected), (10, "i32")),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_decode_projected() {
+        let schema = api_versions_response();
+        let v3 = [
+            0, 5, // error code
+            3, // two api keys
+            0, 18, 0, 0, 0, 3, 0, // no tagged fields
+            0, 3, 0, 0, 0, 12, 0, //
+            0, 0, 0, 0, // throttle time
+            2, // two tagged fields
+            1, 8, 0, 0, 0, 0, 0, 0, 0, 7, // finalized features epoch
+            9, 1, 0xff, // unknown
+        ];
+        let projection = Projection::new().field("api_keys.max_version");
+        let api_key =
+            |max| KafkaValue::Struct(vec![("max_version".into(), KafkaValue::Int16(max))]);
+        assert_eq!(
+            schema.decode_projected(3, &v3, &projection).unwrap(),
+            KafkaValue::Struct(vec![(
+                "api_keys".into(),
+                KafkaValue::Array(vec![api_key(3), api_key(12)])
+            )])
+        );
+
+        let projection = Projection::new()
+            .field("error_code")
+            .field("finalized_features_epoch")
+            .field("api_keys.max_version")
+            .field("api_keys");
+        let decoded = schema.decode_projected(3, &v3, &projection).unwrap();
+        assert_eq!(decoded.get("error_code"), Some(&KafkaValue::Int16(5)));
+        assert_eq!(decoded.get("throttle_time_ms"), None);
+        assert_eq!(
+            decoded.get("finalized_features_epoch"),
+            Some(&KafkaValue::Int64(7))
+        );
+        assert_eq!(
+            decoded.get("api_keys"),
+            schema.decode(3, &v3).unwrap().get("api_keys")
+        );
+
+        // skipped fields are still read past
+        let err = schema
+            .decode_projected(3, &v3[..14], &Projection::new().field("throttle_time_ms"))
+            .unwrap_err();
+        match *err.kind() {
+            ErrorKind::Decode {
+                offset, ref path, ..
+            } => assert_eq!((offset, path.as_str()), (14, "api_keys[1].max_version")),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_skip_unchecked() {
+        let schema = Schema::new(
+            "Example",
+            vec![
+                Field::new("Name", Type::String),
+                Field::new("Nested", Type::Struct(vec![Field::new("Flag", Type::Bool)]))
+                    .nullable_versions(..),
+                Field::new("Count", Type::Int32),
+            ],
+        );
+        // the name isn't UTF-8, and the flag isn't a bool, but neither are decoded
+        let buf = [0, 2, 0xc3, 0x28, 1, 2, 0, 0, 0, 9];
+        assert!(schema.decode(0, &buf).is_err());
+        let count = schema
+            .decode_projected(0, &buf, &Projection::new().field("Count"))
+            .unwrap();
+        assert_eq!(
+            count,
+            KafkaValue::Struct(vec![("Count".into(), KafkaValue::Int32(9))])
+        );
+        assert!(Projection::new().field("Nested").contains("Nested.Flag"));
+    }
+
+    #[test]
+    fn test_encode() {
+        let schema = api_versions_response();
+        let v3 = [
+            0, 0, // error code
+            2, 0, 18, 0, 0, 0, 3, 0, // one api key, no tagged fields
+            0, 0, 0, 0, // throttle time
+            1, // one tagged field
+            1, 8, 0, 0, 0, 0, 0, 0, 0, 7, // finalized features epoch
+        ];
+        let value = schema.decode(3, &v3).unwrap();
+        let mut buf = vec![0xff];
+        schema.encode(3, &value, &mut buf).unwrap();
+        assert_eq!(buf[1..], v3);
+
+        // v0 leaves out the fields it doesn't have
+        buf.clear();
+        schema.encode(0, &value, &mut buf).unwrap();
+        assert_eq!(buf, [0, 0, 0, 0, 0, 1, 0, 18, 0, 0, 0, 3]);
+
+        let missing = KafkaValue::Struct(vec![("error_code".into(), KafkaValue::Int16(0))]);
+        assert!(schema.encode(0, &missing, &mut buf).is_err());
+        assert_eq!(buf.len(), 12);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_to_json() {
+        use serde_json::json;
+
+        let schema = api_versions_response();
+        let v0 = [0, 0, 0, 0, 0, 1, 0, 18, 0, 0, 0, 3];
+        assert_eq!(
+            to_json(&schema, 0, &v0).unwrap(),
+            json!({
+                "error_code": 0,
+                "api_keys": [{ "api_key": 18, "min_version": 0, "max_version": 3 }],
+            })
+        );
+
+        let v3 = [
+            0, 0, // error code
+            2, 0, 18, 0, 0, 0, 3, 0, // one api key, no tagged fields
+            0, 0, 0, 0, // throttle time
+            2, // two tagged fields
+            1, 8, 0, 0, 0, 0, 0, 0, 0, 7, // finalized features epoch
+            9, 1, 0xff, // unknown
+        ];
+        assert_eq!(
+            to_json(&schema, 3, &v3).unwrap(),
+            json!({
+                "error_code": 0,
+                "api_keys": [{ "api_key": 18, "min_version": 0, "max_version": 3 }],
+                "throttle_time_ms": 0,
+                "finalized_features_epoch": 7,
+            })
+        );
+
+        assert!(to_json(&schema, 4, &v3).is_err());
+        let err = to_json(&schema, 3, &v3[..12]).unwrap_err();
+        assert!(err.is_incomplete());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_nullable() {
+        use serde_json::json;
+
+        let schema = Schema::new(
+            "Nullable",
+            vec![
+                Field::new("name", Type::String).nullable_versions(1..),
+                Field::new("id", Type::Uuid),
+                Field::new("data", Type::Bytes).nullable_versions(..),
+            ],
+        );
+        let mut buf = vec![0xff, 0xff];
+        buf.extend(0..16u8);
+        buf.extend_from_slice(&[0, 0, 0, 2, 0xab, 0xcd]);
+        assert_eq!(
+            to_json(&schema, 1, &buf).unwrap(),
+            json!({
+                "name": null,
+                "id": "00010203-0405-0607-0809-0a0b0c0d0e0f",
+                "data": "abcd",
+            })
+        );
+        assert!(to_json(&schema, 0, &buf).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        fn located(err: crate::Error) -> (usize, &'static str, String) {
+            match *err.kind() {
+                ErrorKind::Decode {
+                    offset,
+                    expected,
+                    ref path,
+                    ..
+                } => (offset, expected, path.clone()),
+                ref e => panic!("unexpected error {}", e),
+            }
+        }
+
+        let schema = api_versions_response();
+        let v3 = [
+            0, 0, // error code
+            2, 0, 18, 0, 0, 0, 3, 0, // one api key, no tagged fields
+            0, 0, 0, 0, // throttle time
+            2, // two tagged fields
+            1, 8, 0, 0, 0, 0, 0, 0, 0, 7, // finalized features epoch
+            9, 1, 0xff, // unknown
+        ];
+        schema.validate(3, &v3).unwrap();
+        let err = schema.validate(3, &v3[..8]).unwrap_err();
+        assert_eq!(
+            located(err),
+            (7, "i16", "api_keys[0].max_version".to_owned())
+        );
+
+        let mut trailing = v3.to_vec();
+        trailing.push(0);
+        assert!(schema.decode(3, &trailing).is_ok());
+        let err = schema.validate(3, &trailing).unwrap_err();
+        assert_eq!(located(err), (28, "end of message", String::new()));
+
+        let mut unordered = v3[..15].to_vec();
+        unordered.extend_from_slice(&[9, 1, 0xff, 1, 8, 0, 0, 0, 0, 0, 0, 0, 7]);
+        assert!(schema.decode(3, &unordered).is_ok());
+        let err = schema.validate(3, &unordered).unwrap_err();
+        assert_eq!(located(err), (18, "tag", String::new()));
+
+        // offsets are from the start of frames
+        let mut frame = vec![0, 0, 0, 7];
+        frame.extend_from_slice(&v3);
+        schema.validate_response(3, 0, &frame).unwrap();
+        let err = schema.validate_response(3, 0, &frame[..16]).unwrap_err();
+        assert_eq!(located(err), (14, "i32", "throttle_time_ms".to_owned()));
+
+        let schema = Schema::new(
+            "FindCoordinatorRequest",
+            vec![Field::new("key", Type::String)],
+        );
+        let mut frame = vec![0, 10, 0, 0, 0, 0, 0, 7, 0, 1, b'c', 0, 1, b'g'];
+        schema.validate_request(&frame).unwrap();
+        frame.push(0);
+        let err = schema.validate_request(&frame).unwrap_err();
+        assert_eq!(located(err), (14, "end of message", String::new()));
+    }
+}
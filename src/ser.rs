@@ -3,18 +3,71 @@
 //
 // This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
 // Datadog, Inc.
-use crate::error::{Error, Result};
+use crate::error::{Error, ErrorKind, Result};
+use crate::{metrics, varint};
 use serde::{ser, Serialize};
+use std::any;
+use std::convert::TryFrom;
 use std::io;
 
 struct KafkaSerializer<W> {
     writer: W,
 }
 
+/// Where the serializer writes to
+trait Output {
+    fn put(&mut self, bytes: &[u8]) -> Result<()>;
+}
+
+/// Any writer, whose I/O errors are passed on
+struct IoOutput<W> {
+    writer: W,
+    written: usize,
+}
+
+impl<W: io::Write> Output for IoOutput<W> {
+    #[inline]
+    fn put(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes)?;
+        self.written += bytes.len();
+        Ok(())
+    }
+}
+
+// appending to a vector can't fail, so it skips the io::Write plumbing and its error checks
+impl Output for &mut Vec<u8> {
+    #[inline]
+    fn put(&mut self, bytes: &[u8]) -> Result<()> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Any in-memory buffer, failing instead of panicking once it is full
+#[cfg(feature = "bytes")]
+struct BufMutOutput<'a, B> {
+    buf: &'a mut B,
+    written: usize,
+}
+
+#[cfg(feature = "bytes")]
+impl<B: bytes::BufMut> Output for BufMutOutput<'_, B> {
+    #[inline]
+    fn put(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.buf.remaining_mut() < bytes.len() {
+            return Err(io::Error::from(io::ErrorKind::WriteZero).into());
+        }
+        self.buf.put_slice(bytes);
+        self.written += bytes.len();
+        Ok(())
+    }
+}
+
 /// Serializes a kafka payload into a I/O stream
 ///
 /// Often times the kafka protocol will require the message to be manipulated after serializing
 /// (for instance to add the size to the beginning), so the initial stream is returned.
+/// [`FrameWriter`] takes care of sizes when serializing into memory.
 ///
 /// # Examples
 /// ```
@@ -43,27 +96,406 @@ struct KafkaSerializer<W> {
 /// x.set_position(0u64);
 /// x.write(&sz.to_be_bytes()).unwrap(); // writes the size to the beginning of the payload
 /// ```
-
 #[inline]
 pub fn to_writer<W, T>(writer: W, value: &T) -> Result<W>
 where
     T: Serialize,
     W: io::Write,
 {
-    let mut serializer = KafkaSerializer { writer };
-    value.serialize(&mut serializer)?;
-    Ok(serializer.writer)
+    let mut serializer = KafkaSerializer {
+        writer: IoOutput { writer, written: 0 },
+    };
+    let result = value.serialize(&mut serializer);
+    metrics::report_encode(&result, serializer.writer.written);
+    result?;
+    Ok(serializer.writer.writer)
+}
+
+/// Serializes a kafka payload at the end of a [`bytes::BufMut`], like the `BytesMut` of tokio
+/// codecs
+///
+/// Buffers that can't grow fail with an [`ErrorKind::Io`] once they are full, with whatever
+/// fit written.
+///
+/// # Examples
+/// ```
+/// use bytes::BytesMut;
+///
+/// let mut buf = BytesMut::new();
+/// kafka_serde::to_buf(&mut buf, &1i32).unwrap();
+/// assert_eq!(buf, [0, 0, 0, 1][..]);
+/// ```
+#[cfg(feature = "bytes")]
+pub fn to_buf<B, T>(buf: &mut B, value: &T) -> Result<()>
+where
+    T: Serialize,
+    B: bytes::BufMut,
+{
+    let mut serializer = KafkaSerializer {
+        writer: BufMutOutput { buf, written: 0 },
+    };
+    let result = value.serialize(&mut serializer);
+    metrics::report_encode(&result, serializer.writer.written);
+    result
+}
+
+/// Serializes a kafka payload into a new vector
+///
+/// This is faster than [`to_writer`] with a `Vec<u8>` or a `Cursor`, as it appends to the
+/// vector directly.
+///
+/// # Examples
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct ResponseHeader {
+///     correlation_id: i32,
+/// }
+///
+/// let data = kafka_serde::to_vec(&ResponseHeader { correlation_id: 1 }).unwrap();
+/// assert_eq!(data, [0, 0, 0, 1]);
+/// ```
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut buf = Vec::new();
+    let result = append_to_vec(&mut buf, value);
+    metrics::report_encode(&result, buf.len());
+    result?;
+    Ok(buf)
+}
+
+/// Serializes `value` at the end of `buf`
+///
+/// If serialization fails, `buf` is left with whatever was written before the error.
+pub(crate) fn append_to_vec<T>(buf: &mut Vec<u8>, value: &T) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = KafkaSerializer { writer: buf };
+    value.serialize(&mut serializer)
+}
+
+/// Types whose encoding always takes the same number of bytes, known at compile time
+///
+/// Headers and acknowledgements are often made of fixed-width integers only. Implementing this
+/// trait for them lets [`FrameWriter::fixed_size_frame`] write their size prefix upfront and
+/// reserve exactly the room they need. The size of a struct is the sum of the sizes of its
+/// fields.
+///
+/// # Examples
+/// ```
+/// use kafka_serde::{FixedSize, FrameWriter};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct HeartbeatResponse {
+///     throttle_time_ms: i32,
+///     error_code: i16,
+/// }
+///
+/// impl FixedSize for HeartbeatResponse {
+///     const ENCODED_SIZE: usize = i32::ENCODED_SIZE + i16::ENCODED_SIZE;
+/// }
+///
+/// let mut frame = FrameWriter::new();
+/// frame.fixed_size_frame(&HeartbeatResponse { throttle_time_ms: 0, error_code: 0 }).unwrap();
+/// assert_eq!(frame.as_bytes()[..4], [0, 0, 0, 6]);
+/// ```
+pub trait FixedSize {
+    /// How many bytes every value of the type is encoded into
+    const ENCODED_SIZE: usize;
+}
+
+macro_rules! impl_fixed_size {
+    ($($t:ty),*) => {
+        $(
+            impl FixedSize for $t {
+                const ENCODED_SIZE: usize = std::mem::size_of::<$t>();
+            }
+        )*
+    };
+}
+
+impl_fixed_size!(i8, u8, i16, u16, i32, u32, i64, u64, f64);
+
+impl FixedSize for bool {
+    const ENCODED_SIZE: usize = 1;
+}
+
+impl<T: FixedSize + ?Sized> FixedSize for &T {
+    const ENCODED_SIZE: usize = T::ENCODED_SIZE;
+}
+
+/// Serializes kafka payloads into memory, filling in size prefixes as it goes
+///
+/// [`FrameWriter::size_prefixed`] reserves room for an `i32` size, runs a closure that writes
+/// what the size is about, then writes in the size of what the closure wrote. Scopes can be
+/// nested, e.g. for a request frame embedding a length-prefixed payload, and everything is
+/// written in a single pass.
+///
+/// The buffer is kept when cleared, so a writer that is reused for every request stops
+/// allocating once its buffer has grown to fit the largest one. See [`FrameWriter::encode`].
+///
+/// # Examples
+/// ```
+/// use kafka_serde::FrameWriter;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct RequestHeader {
+///     api_key: i16,
+///     api_version: i16,
+///     correlation_id: i32,
+///     client_id: &'static str,
+/// }
+///
+/// let header = RequestHeader {
+///     api_key: 18,
+///     api_version: 0,
+///     correlation_id: 1,
+///     client_id: "",
+/// };
+/// let mut frame = FrameWriter::new();
+/// frame.size_prefixed(|frame| frame.serialize(&header)).unwrap();
+/// let frame = frame.into_inner();
+/// assert_eq!(frame[..4], [0, 0, 0, 10]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FrameWriter {
+    buf: Vec<u8>,
+}
+
+impl FrameWriter {
+    /// Creates an empty writer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a writer appending to `buf`
+    pub fn with_buffer(buf: Vec<u8>) -> Self {
+        FrameWriter { buf }
+    }
+
+    /// Serializes `value` at the end of the buffer
+    pub fn serialize<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        let start = self.buf.len();
+        let result = append_to_vec(&mut self.buf, value);
+        metrics::report_encode(&result, self.buf.len() - start);
+        result
+    }
+
+    /// Serializes `value` in place of what was written before, returning its bytes
+    ///
+    /// # Examples
+    /// ```
+    /// use kafka_serde::FrameWriter;
+    ///
+    /// let mut frame = FrameWriter::new();
+    /// for correlation_id in 0..10i32 {
+    ///     let bytes = frame.encode(&correlation_id).unwrap();
+    ///     assert_eq!(bytes, correlation_id.to_be_bytes());
+    /// }
+    /// ```
+    pub fn encode<T: Serialize>(&mut self, value: &T) -> Result<&[u8]> {
+        self.clear();
+        self.serialize(value)?;
+        Ok(&self.buf)
+    }
+
+    /// Discards what was written, keeping the buffer for what comes next
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    /// Writes what `f` writes, prefixed by its size as an `i32`
+    ///
+    /// If `f` fails, what it wrote is discarded along with the room reserved for the size.
+    pub fn size_prefixed<F, R>(&mut self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        let start = self.buf.len();
+        self.buf.extend_from_slice(&[0; 4]);
+        let size = f(self).and_then(|out| Ok((out, len_i32("frame", self.buf.len() - start - 4)?)));
+        match size {
+            Ok((out, size)) => {
+                self.buf[start..start + 4].copy_from_slice(&size.to_be_bytes());
+                Ok(out)
+            }
+            Err(err) => {
+                self.buf.truncate(start);
+                Err(err)
+            }
+        }
+    }
+
+    /// Writes `value` prefixed by its size, which is known upfront: nothing is patched after the
+    /// fact, and the buffer grows at most once
+    ///
+    /// Fails without writing anything if `value` doesn't take [`FixedSize::ENCODED_SIZE`] bytes,
+    /// as a wrong size would corrupt the stream.
+    pub fn fixed_size_frame<T: FixedSize + Serialize>(&mut self, value: &T) -> Result<()> {
+        let start = self.buf.len();
+        self.buf.reserve(4 + T::ENCODED_SIZE);
+        self.buf
+            .extend_from_slice(&(T::ENCODED_SIZE as i32).to_be_bytes());
+        let written = self.serialize(value).map(|()| self.buf.len() - start - 4);
+        match written {
+            Ok(size) if size == T::ENCODED_SIZE => Ok(()),
+            Ok(size) => {
+                self.buf.truncate(start);
+                Err(ErrorKind::Custom(format!(
+                    "{} bytes written for a fixed size of {}",
+                    size,
+                    T::ENCODED_SIZE
+                ))
+                .into())
+            }
+            Err(err) => {
+                self.buf.truncate(start);
+                Err(err)
+            }
+        }
+    }
+
+    /// The bytes written so far
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// The buffer, for what writes frames without serde
+    pub(crate) fn buf_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+
+    /// Consumes the writer, returning the buffer
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+impl io::Write for FrameWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.buf.write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn overflow(what: &'static str, len: usize, max: usize) -> Error {
+    ErrorKind::LengthOverflow { what, len, max }.into()
+}
+
+/// The `i16` length of a string, failing if it is longer than that
+fn string_len(s: &str) -> Result<i16> {
+    i16::try_from(s.len()).map_err(|_| overflow("string", s.len(), i16::MAX as usize))
+}
+
+/// The `i32` length of bytes, or element count of an array, failing if it is larger than that
+pub(crate) fn len_i32(what: &'static str, len: usize) -> Result<i32> {
+    i32::try_from(len).map_err(|_| overflow(what, len, i32::MAX as usize))
+}
+
+/// The length plus one of compact bytes, strings and arrays, failing if it is larger than
+/// what their `u32` varint holds
+fn compact_len(what: &'static str, len: usize) -> Result<u32> {
+    match u32::try_from(len) {
+        Ok(len) if len < u32::MAX => Ok(len + 1),
+        _ => Err(overflow(what, len, u32::MAX as usize - 1)),
+    }
+}
+
+/// Writes a `NULLABLE_STRING`, prefixed by its `i16` length, where `None` is written as -1
+pub(crate) fn write_nullable_string<W: io::Write>(writer: &mut W, s: Option<&str>) -> Result<()> {
+    match s {
+        None => writer.write_all(&(-1i16).to_be_bytes())?,
+        Some(s) => {
+            writer.write_all(&string_len(s)?.to_be_bytes())?;
+            writer.write_all(s.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a `STRING`, prefixed by its `i16` length
+pub(crate) fn write_string<W: io::Write>(writer: &mut W, s: &str) -> Result<()> {
+    write_nullable_string(writer, Some(s))
+}
+
+/// Writes `NULLABLE_BYTES`, prefixed by their `i32` length, where `None` is written as -1
+pub(crate) fn write_nullable_bytes<W: io::Write>(
+    writer: &mut W,
+    bytes: Option<&[u8]>,
+) -> Result<()> {
+    match bytes {
+        None => writer.write_all(&(-1i32).to_be_bytes())?,
+        Some(bytes) => {
+            writer.write_all(&len_i32("bytes", bytes.len())?.to_be_bytes())?;
+            writer.write_all(bytes)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the `i32` element count of an `ARRAY`
+pub(crate) fn write_array_len<W: io::Write>(writer: &mut W, len: usize) -> Result<()> {
+    writer.write_all(&len_i32("array", len)?.to_be_bytes())?;
+    Ok(())
+}
+
+/// Writes a `COMPACT_NULLABLE_STRING`, prefixed by its length plus one, where `None` is 0
+pub(crate) fn write_compact_nullable_string<W: io::Write>(
+    writer: &mut W,
+    s: Option<&str>,
+) -> Result<()> {
+    write_compact_nullable_bytes(writer, s.map(str::as_bytes))
+}
+
+/// Writes `COMPACT_NULLABLE_BYTES`, prefixed by their length plus one, where `None` is 0
+pub(crate) fn write_compact_nullable_bytes<W: io::Write>(
+    writer: &mut W,
+    bytes: Option<&[u8]>,
+) -> Result<()> {
+    match bytes {
+        None => varint::write_unsigned_varint(writer, 0)?,
+        Some(bytes) => {
+            varint::write_unsigned_varint(writer, compact_len("bytes", bytes.len())?)?;
+            writer.write_all(bytes)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the element count of a `COMPACT_ARRAY` plus one, where `None`, a null array, is 0
+pub(crate) fn write_compact_array_len<W: io::Write>(
+    writer: &mut W,
+    len: Option<usize>,
+) -> Result<()> {
+    let len = match len {
+        Some(len) => compact_len("array", len)?,
+        None => 0,
+    };
+    varint::write_unsigned_varint(writer, len)
 }
 
 impl<'a, W> ser::Serializer for &'a mut KafkaSerializer<W>
 where
-    W: io::Write,
+    W: Output,
 {
     type Ok = ();
 
     type Error = Error;
 
-    type SerializeSeq = Self;
+    type SerializeSeq = SeqSerializer<'a, W>;
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
     type SerializeTupleVariant = Self;
@@ -84,42 +516,42 @@ where
     // will be serialized the same. Other formats, especially compact binary
     // formats, may need independent logic for the different sizes.
     fn serialize_i8(self, v: i8) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
     fn serialize_i16(self, v: i16) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
     fn serialize_i32(self, v: i32) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
     fn serialize_u8(self, v: u8) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
     fn serialize_u16(self, v: u16) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
     fn serialize_u32(self, v: u32) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
@@ -128,7 +560,7 @@ where
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
-        self.writer.write_all(&v.to_be_bytes())?;
+        self.writer.put(&v.to_be_bytes())?;
         Ok(())
     }
 
@@ -137,13 +569,15 @@ where
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        self.writer.write_all(&((v.len() as i16).to_be_bytes()))?;
-        self.writer.write_all(v.as_bytes())?;
+        self.writer.put(&string_len(v)?.to_be_bytes())?;
+        self.writer.put(v.as_bytes())?;
         Ok(())
     }
 
-    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
-        type_not_supported!("bytes")
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.writer.put(&len_i32("bytes", v.len())?.to_be_bytes())?;
+        self.writer.put(v)?;
+        Ok(())
     }
 
     fn serialize_none(self) -> Result<()> {
@@ -195,13 +629,23 @@ where
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        let len = len.unwrap() as i32;
-        self.writer.write_all(&len.to_be_bytes())?;
-        Ok(self)
+        match len {
+            Some(len) => {
+                self.writer.put(&len_i32("array", len)?.to_be_bytes())?;
+                Ok(SeqSerializer {
+                    serializer: self,
+                    buffered: None,
+                })
+            }
+            None => Ok(SeqSerializer {
+                serializer: self,
+                buffered: Some((Vec::new(), 0)),
+            }),
+        }
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
-        self.writer.write_all(&((len as i32).to_be_bytes()))?;
+        self.writer.put(&len_i32("array", len)?.to_be_bytes())?;
         Ok(self)
     }
 
@@ -223,8 +667,13 @@ where
         type_not_supported!("ser-tuple-variant")
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        type_not_supported!("ser-map")
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        // structs with `#[serde(flatten)]` fields serialize as maps of unknown length, whose
+        // values are written in order like the fields of structs. Actual maps have a length
+        match len {
+            None => Ok(self),
+            Some(_) => type_not_supported!("ser-map"),
+        }
     }
 
     fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
@@ -242,9 +691,19 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeSeq for &'a mut KafkaSerializer<W>
+/// Serializes the elements of a sequence, after its length
+///
+/// Sequences whose length is not known upfront, like those of iterators that filter, are
+/// buffered until they end, to write their length first.
+pub(crate) struct SeqSerializer<'a, W> {
+    serializer: &'a mut KafkaSerializer<W>,
+    // the elements serialized so far and how many there are, when the length comes last
+    buffered: Option<(Vec<u8>, usize)>,
+}
+
+impl<W> ser::SerializeSeq for SeqSerializer<'_, W>
 where
-    W: io::Write,
+    W: Output,
 {
     type Ok = ();
     type Error = Error;
@@ -253,17 +712,30 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        match self.buffered {
+            None => value.serialize(&mut *self.serializer),
+            Some((ref mut buf, ref mut len)) => {
+                append_to_vec(buf, value)?;
+                *len += 1;
+                Ok(())
+            }
+        }
     }
 
     fn end(self) -> Result<()> {
+        if let Some((buf, len)) = self.buffered {
+            self.serializer
+                .writer
+                .put(&len_i32("array", len)?.to_be_bytes())?;
+            self.serializer.writer.put(&buf)?;
+        }
         Ok(())
     }
 }
 
-impl<'a, W> ser::SerializeTuple for &'a mut KafkaSerializer<W>
+impl<W> ser::SerializeTuple for &mut KafkaSerializer<W>
 where
-    W: io::Write,
+    W: Output,
 {
     type Ok = ();
     type Error = Error;
@@ -280,9 +752,9 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeTupleStruct for &'a mut KafkaSerializer<W>
+impl<W> ser::SerializeTupleStruct for &mut KafkaSerializer<W>
 where
-    W: io::Write,
+    W: Output,
 {
     type Ok = ();
     type Error = Error;
@@ -299,9 +771,9 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeTupleVariant for &'a mut KafkaSerializer<W>
+impl<W> ser::SerializeTupleVariant for &mut KafkaSerializer<W>
 where
-    W: io::Write,
+    W: Output,
 {
     type Ok = ();
     type Error = Error;
@@ -318,28 +790,40 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeMap for &'a mut KafkaSerializer<W>
+impl<W> ser::SerializeMap for &mut KafkaSerializer<W>
 where
-    W: io::Write,
+    W: Output,
 {
     type Ok = ();
     type Error = Error;
 
+    // only the structs with flattened fields get here, and their fields go through
+    // `serialize_entry`. Keys serialized on their own are from maps, which would lose them
     fn serialize_key<T>(&mut self, _key: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        type_not_supported!("ser-map-trait")
+        type_not_supported!("ser-map")
     }
 
-    // It doesn't make a difference whether the colon is printed at the end of
-    // `serialize_key` or at the beginning of `serialize_value`. In this case
-    // the code is a bit simpler having it here.
-    fn serialize_value<T>(&mut self, _value: &T) -> Result<()>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        type_not_supported!("ser-map-trait")
+        value.serialize(&mut **self)
+    }
+
+    // the keys of fields are `&'static str`, whose names are not written. The entries of maps
+    // flattened in the struct come here too, with keys of other types, e.g. `String`, and fail
+    fn serialize_entry<K, V>(&mut self, _key: &K, value: &V) -> Result<()>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        if any::type_name::<K>() != any::type_name::<str>() {
+            return type_not_supported!("ser-map");
+        }
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<()> {
@@ -347,9 +831,9 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeStruct for &'a mut KafkaSerializer<W>
+impl<W> ser::SerializeStruct for &mut KafkaSerializer<W>
 where
-    W: io::Write,
+    W: Output,
 {
     type Ok = ();
     type Error = Error;
@@ -366,9 +850,9 @@ where
     }
 }
 
-impl<'a, W> ser::SerializeStructVariant for &'a mut KafkaSerializer<W>
+impl<W> ser::SerializeStructVariant for &mut KafkaSerializer<W>
 where
-    W: io::Write,
+    W: Output,
 {
     type Ok = ();
     type Error = Error;
@@ -417,6 +901,154 @@ mod test {
         test_integer!(i64);
     }
 
+    #[test]
+    fn test_to_vec() {
+        #[derive(Serialize)]
+        struct Dummy {
+            a: i16,
+            b: &'static str,
+            c: Vec<u32>,
+            d: Option<i8>,
+        }
+
+        let dummy = Dummy {
+            a: 3,
+            b: "abc",
+            c: vec![1, 2],
+            d: Some(-1),
+        };
+        let expected = to_writer(Vec::new(), &dummy).unwrap();
+        assert_eq!(to_vec(&dummy).unwrap(), expected);
+        assert!(to_vec(&'c').is_err());
+    }
+
+    #[test]
+    fn test_size_prefixed() {
+        let mut frame = FrameWriter::with_buffer(vec![0xff]);
+        frame
+            .size_prefixed(|frame| {
+                frame.serialize(&1i16)?;
+                frame.size_prefixed(|frame| frame.serialize(&"ab"))?;
+                frame.size_prefixed(|_| Ok(()))
+            })
+            .unwrap();
+        assert_eq!(
+            frame.as_bytes(),
+            [0xff, 0, 0, 0, 14, 0, 1, 0, 0, 0, 4, 0, 2, b'a', b'b', 0, 0, 0, 0]
+        );
+
+        let err = frame.size_prefixed(|frame| {
+            frame.serialize(&1i32)?;
+            frame.serialize(&Some(vec![()]))
+        });
+        assert!(err.is_err());
+        assert_eq!(frame.as_bytes().len(), 19);
+
+        let capacity = frame.buf.capacity();
+        assert_eq!(frame.encode(&7i16).unwrap(), [0, 7]);
+        assert_eq!(frame.buf.capacity(), capacity);
+    }
+
+    #[derive(Serialize)]
+    struct Ack {
+        correlation_id: i32,
+        error_code: i16,
+        retriable: bool,
+    }
+
+    impl FixedSize for Ack {
+        const ENCODED_SIZE: usize = i32::ENCODED_SIZE + i16::ENCODED_SIZE + bool::ENCODED_SIZE;
+    }
+
+    #[derive(Serialize)]
+    struct NotFixed(&'static str);
+
+    impl FixedSize for NotFixed {
+        const ENCODED_SIZE: usize = 2;
+    }
+
+    #[test]
+    fn test_fixed_size_frame() {
+        let ack = Ack {
+            correlation_id: 1,
+            error_code: 2,
+            retriable: true,
+        };
+        assert_eq!(to_vec(&ack).unwrap().len(), Ack::ENCODED_SIZE);
+
+        let mut expected = FrameWriter::new();
+        expected
+            .size_prefixed(|frame| frame.serialize(&ack))
+            .unwrap();
+        let mut frame = FrameWriter::new();
+        frame.fixed_size_frame(&ack).unwrap();
+        assert_eq!(frame.as_bytes(), expected.as_bytes());
+
+        assert!(frame.fixed_size_frame(&NotFixed("abc")).is_err());
+        assert_eq!(frame.as_bytes(), expected.as_bytes());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_to_buf() {
+        let mut buf = bytes::BytesMut::from(&[0xff][..]);
+        to_buf(&mut buf, &"ab").unwrap();
+        assert_eq!(buf[..], [0xff, 0, 2, b'a', b'b']);
+
+        let mut array = [0u8; 3];
+        let mut full = &mut array[..];
+        match *to_buf(&mut full, &7i32).unwrap_err().kind() {
+            ErrorKind::Io(ref err) => assert_eq!(err.kind(), io::ErrorKind::WriteZero),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Blobs<'a> {
+        #[serde(with = "serde_bytes")]
+        owned: Vec<u8>,
+        #[serde(with = "serde_bytes")]
+        borrowed: &'a [u8],
+        buf: serde_bytes::ByteBuf,
+    }
+
+    #[test]
+    fn test_serde_bytes() {
+        let blobs = Blobs {
+            owned: vec![1, 2],
+            borrowed: &[3],
+            buf: serde_bytes::ByteBuf::from(vec![]),
+        };
+        let data = to_vec(&blobs).unwrap();
+        assert_eq!(data, [0, 0, 0, 2, 1, 2, 0, 0, 0, 1, 3, 0, 0, 0, 0]);
+        assert_eq!(crate::from_bytes::<Blobs<'_>>(&data).unwrap(), blobs);
+    }
+
+    /// The even numbers of a range, whose count is unknown until they are all filtered
+    struct Evens(std::ops::Range<i16>);
+
+    impl Serialize for Evens {
+        fn serialize<S: ser::Serializer>(
+            &self,
+            serializer: S,
+        ) -> std::result::Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.clone().filter(|i| i % 2 == 0))
+        }
+    }
+
+    #[test]
+    fn test_unsized_seq() {
+        assert_eq!(
+            to_vec(&Evens(0..5)).unwrap(),
+            [0, 0, 0, 3, 0, 0, 0, 2, 0, 4]
+        );
+        assert_eq!(to_vec(&Evens(1..2)).unwrap(), [0, 0, 0, 0]);
+
+        let nested = vec![Evens(0..3), Evens(1..2)];
+        let writer = to_writer(Vec::new(), &nested).unwrap();
+        assert_eq!(writer, [0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 0]);
+    }
+
     #[test]
     fn test_strings() {
         let x = io::Cursor::new(vec![]);
@@ -447,6 +1079,70 @@ mod test {
         assert_eq!(c[1], 0);
     }
 
+    #[test]
+    fn test_string_overflow() {
+        let s = "a".repeat(i16::MAX as usize);
+        assert_eq!(to_vec(&s).unwrap().len(), s.len() + 2);
+
+        let s = "a".repeat(i16::MAX as usize + 1);
+        let mut buf = vec![0xff];
+        let err = append_to_vec(&mut buf, &s).unwrap_err();
+        assert_eq!(err.category(), crate::ErrorCategory::Limit);
+        match *err.kind() {
+            ErrorKind::LengthOverflow {
+                what: "string",
+                len: 32768,
+                max: 32767,
+            } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert_eq!(buf, [0xff]);
+
+        let mut buf = Vec::new();
+        assert!(write_nullable_string(&mut buf, Some(&s)).is_err());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_length_overflow() {
+        struct Huge;
+
+        impl Serialize for Huge {
+            fn serialize<S: ser::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                use serde::ser::SerializeSeq;
+                // no element is serialized: the length must be rejected first
+                serializer.serialize_seq(Some(i32::MAX as usize + 1))?.end()
+            }
+        }
+
+        let mut buf = vec![0xff];
+        let err = append_to_vec(&mut buf, &Huge).unwrap_err();
+        match *err.kind() {
+            ErrorKind::LengthOverflow {
+                what: "array",
+                len: 0x8000_0000,
+                max: 0x7fff_ffff,
+            } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert_eq!(buf, [0xff]);
+
+        assert!(write_array_len(&mut buf, i32::MAX as usize + 1).is_err());
+        assert!(write_compact_array_len(&mut buf, Some(u32::MAX as usize)).is_err());
+        assert_eq!(buf, [0xff]);
+        write_compact_array_len(&mut buf, Some(u32::MAX as usize - 1)).unwrap();
+        assert_eq!(buf, [0xff, 0xff, 0xff, 0xff, 0xff, 0x0f]);
+
+        assert_eq!(len_i32("bytes", i32::MAX as usize).unwrap(), i32::MAX);
+        match *len_i32("bytes", usize::MAX).unwrap_err().kind() {
+            ErrorKind::LengthOverflow { what: "bytes", .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
     #[test]
     fn test_list() {
         let x = io::Cursor::new(vec![]);
@@ -480,6 +1176,87 @@ mod test {
         assert_eq!(c[4], 2);
     }
 
+    #[test]
+    fn test_flatten() {
+        #[derive(Serialize)]
+        struct Header {
+            api_key: i16,
+            api_version: i16,
+        }
+        #[derive(Serialize)]
+        struct Body {
+            n: i32,
+        }
+        #[derive(Serialize)]
+        struct Flattened {
+            #[serde(flatten)]
+            header: Header,
+            #[serde(flatten)]
+            body: Body,
+        }
+        #[derive(Serialize)]
+        struct Nested {
+            header: Header,
+            body: Body,
+        }
+
+        let header = || Header {
+            api_key: 18,
+            api_version: 3,
+        };
+        let flattened = to_vec(&Flattened {
+            header: header(),
+            body: Body { n: 7 },
+        })
+        .unwrap();
+        assert_eq!(flattened, [0, 18, 0, 3, 0, 0, 0, 7]);
+        let nested = Nested {
+            header: header(),
+            body: Body { n: 7 },
+        };
+        assert_eq!(flattened, to_vec(&nested).unwrap());
+
+        let map: std::collections::HashMap<i32, i32> = vec![(1, 2)].into_iter().collect();
+        let err = to_vec(&map).unwrap_err();
+        match *err.kind() {
+            ErrorKind::TypeNotSupported("ser-map") => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        // a map of unknown length, whose keys would be lost
+        struct Unsized;
+        impl Serialize for Unsized {
+            fn serialize<S: ser::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.collect_map((0..3).filter(|i| i % 2 == 0).map(|i| (i, i)))
+            }
+        }
+        let err = to_vec(&Unsized).unwrap_err();
+        match *err.kind() {
+            ErrorKind::TypeNotSupported("ser-map") => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        // a flattened map, whose keys would be lost
+        #[derive(Serialize)]
+        struct Extra {
+            n: i32,
+            #[serde(flatten)]
+            extra: std::collections::HashMap<String, i32>,
+        }
+        let extra = Extra {
+            n: 7,
+            extra: vec![("m".to_owned(), 8)].into_iter().collect(),
+        };
+        let err = to_vec(&extra).unwrap_err();
+        match *err.kind() {
+            ErrorKind::TypeNotSupported("ser-map") => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
     #[test]
     fn test_enum_repr() {
         #[derive(Serialize_repr)]
@@ -0,0 +1,153 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Byte fields decoded as [`Bytes`] slices of the buffer they are decoded from.
+//!
+//! Use it on `Bytes` fields with `#[serde(with = "kafka_serde::shared_bytes")]`. When the payload
+//! is decoded with [`Decoder::decode_shared`](crate::Decoder::decode_shared), the fields share
+//! the reference-counted buffer of the payload instead of copying from it: they own their data
+//! without a lifetime tying them to the buffer, and can be sent to other tasks or threads.
+//! Otherwise, they are copied.
+//!
+//! # Examples
+//! ```
+//! use bytes::Bytes;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Record {
+//!     offset: i64,
+//!     #[serde(with = "kafka_serde::shared_bytes")]
+//!     value: Bytes,
+//! }
+//!
+//! let buf = Bytes::from_static(&[0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 2, b'h', b'i']);
+//! let record: Record = kafka_serde::Decoder::new().decode_shared(&buf).unwrap();
+//! assert_eq!(record.value, "hi");
+//! assert_eq!(record.value.as_ptr(), buf[12..].as_ptr());
+//! ```
+use bytes::Bytes;
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+use std::cell::RefCell;
+use std::fmt;
+
+thread_local! {
+    // the buffer of the decode running on this thread, if its byte fields can share it
+    static SOURCE: RefCell<Option<Bytes>> = const { RefCell::new(None) };
+}
+
+/// Lets byte fields share `source` for as long as it lives, restoring what the thread did
+/// before on drop
+pub(crate) struct SharedScope {
+    previous: Option<Bytes>,
+}
+
+impl SharedScope {
+    pub(crate) fn enter(source: &Bytes) -> Self {
+        let previous = SOURCE.with(|current| current.replace(Some(source.clone())));
+        SharedScope { previous }
+    }
+}
+
+impl Drop for SharedScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SOURCE.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Slices `bytes` out of the buffer being decoded if it comes from there, or copies it
+fn share(bytes: &[u8]) -> Bytes {
+    SOURCE.with(|source| match *source.borrow() {
+        Some(ref source) if contains(source, bytes) => source.slice_ref(bytes),
+        _ => Bytes::copy_from_slice(bytes),
+    })
+}
+
+fn contains(source: &[u8], bytes: &[u8]) -> bool {
+    let range = source.as_ptr_range();
+    range.start <= bytes.as_ptr() && bytes.as_ptr_range().end <= range.end
+}
+
+/// Serializes `bytes` as `BYTES`, prefixed by their `i32` length
+pub fn serialize<S: Serializer>(bytes: &Bytes, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(bytes)
+}
+
+/// Deserializes `BYTES` as a slice of the buffer being decoded, or as a copy
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+    struct SharedVisitor;
+
+    impl<'de> Visitor<'de> for SharedVisitor {
+        type Value = Bytes;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("bytes")
+        }
+
+        fn visit_borrowed_bytes<E: de::Error>(self, bytes: &'de [u8]) -> Result<Bytes, E> {
+            Ok(share(bytes))
+        }
+
+        fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Bytes, E> {
+            Ok(Bytes::copy_from_slice(bytes))
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Bytes, E> {
+            Ok(Bytes::from(bytes))
+        }
+    }
+
+    deserializer.deserialize_bytes(SharedVisitor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Fetched {
+        partition: i32,
+        #[serde(with = "super")]
+        records: Bytes,
+    }
+
+    #[test]
+    fn test_shared() {
+        let fetched = Fetched {
+            partition: 3,
+            records: Bytes::from_static(b"records"),
+        };
+        let buf = Bytes::from(crate::to_vec(&fetched).unwrap());
+
+        let shared: Fetched = crate::Decoder::new().decode_shared(&buf).unwrap();
+        assert_eq!(shared, fetched);
+        assert_eq!(shared.records.as_ptr(), buf[8..].as_ptr());
+        drop(buf);
+        assert_eq!(shared.records, "records");
+
+        let buf = crate::to_vec(&fetched).unwrap();
+        let copied: Fetched = crate::from_bytes(&buf).unwrap();
+        assert_eq!(copied, fetched);
+        assert_ne!(copied.records.as_ptr(), buf[8..].as_ptr());
+    }
+
+    #[test]
+    fn test_scope() {
+        let source = Bytes::from_static(b"abc");
+        {
+            let _scope = SharedScope::enter(&source);
+            assert_eq!(share(&source[1..]).as_ptr(), source[1..].as_ptr());
+            {
+                let _nested = SharedScope::enter(&Bytes::from_static(b"xyz"));
+                assert_ne!(share(&source[1..]).as_ptr(), source[1..].as_ptr());
+            }
+            assert_eq!(share(&source[1..]).as_ptr(), source[1..].as_ptr());
+        }
+        assert_ne!(share(&source[1..]).as_ptr(), source[1..].as_ptr());
+    }
+}
@@ -0,0 +1,287 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use std::fs;
+use std::path::Path;
+
+/// The size of the entries of `.index` files: a relative offset and a position
+const OFFSET_ENTRY_SIZE: usize = 8;
+/// The size of the entries of `.timeindex` files: a timestamp and a relative offset
+const TIME_ENTRY_SIZE: usize = 12;
+/// The timestamp time index lookups return when the index has no entry before theirs, like
+/// `RecordBatch.NO_TIMESTAMP` of the Java client
+const NO_TIMESTAMP: i64 = -1;
+
+/// An entry of an [`OffsetIndex`]: the position in the segment of the batch holding an offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OffsetPosition {
+    /// The offset
+    pub offset: i64,
+    /// The position in the `.log` file of the batch holding it
+    pub position: u32,
+}
+
+/// An entry of a [`TimeIndex`]: the offset from which the records of a segment reach a
+/// timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampOffset {
+    /// The largest timestamp in the segment up to the offset
+    pub timestamp: i64,
+    /// The offset
+    pub offset: i64,
+}
+
+/// A `.index` file, mapping offsets to the positions of their batches in the `.log` file of
+/// the same segment
+///
+/// Brokers add an entry every `index.interval.bytes` of appended batches, so the index is
+/// sparse: [`lookup`](OffsetIndex::lookup) gives the position to start scanning the segment
+/// from, see [`Segment::batches_from`](super::Segment::batches_from).
+///
+/// # Examples
+/// ```no_run
+/// use kafka_serde::storage::{OffsetIndex, Segment};
+///
+/// let index = OffsetIndex::open("my-topic-0/00000000000000000000.index")?;
+/// let segment = Segment::open("my-topic-0/00000000000000000000.log")?;
+/// let start = index.lookup(1234).position as usize;
+/// for batch in segment.batches_from(start) {
+///     let batch = batch?;
+///     if batch.header.base_offset + batch.header.last_offset_delta as i64 >= 1234 {
+///         // the batch holding offset 1234
+///         break;
+///     }
+/// }
+/// # Ok::<(), kafka_serde::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetIndex {
+    base_offset: i64,
+    entries: Vec<OffsetPosition>,
+}
+
+impl OffsetIndex {
+    /// Reads the index file at `path`, whose name gives the base offset of its segment
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<OffsetIndex> {
+        let (base_offset, data) = read(path.as_ref())?;
+        OffsetIndex::decode(base_offset, &data)
+    }
+
+    /// Decodes the index of the segment starting at `base_offset`
+    pub fn decode(base_offset: i64, buf: &[u8]) -> Result<OffsetIndex> {
+        let mut de = entries(buf, OFFSET_ENTRY_SIZE, "offset")?;
+        let mut entries = Vec::with_capacity(buf.len() / OFFSET_ENTRY_SIZE);
+        while de.remaining() > 0 {
+            let relative_offset = de.read_i32()?;
+            let position = de.read_u32()?;
+            if !entries.is_empty() && relative_offset == 0 && position == 0 {
+                // the space brokers preallocate for the entries of active segments
+                break;
+            }
+            entries.push(OffsetPosition {
+                offset: base_offset + relative_offset as i64,
+                position,
+            });
+        }
+        Ok(OffsetIndex {
+            base_offset,
+            entries,
+        })
+    }
+
+    /// The offset of the first record of the segment
+    pub fn base_offset(&self) -> i64 {
+        self.base_offset
+    }
+
+    /// The entries of the index, by increasing offset
+    pub fn entries(&self) -> &[OffsetPosition] {
+        &self.entries
+    }
+
+    /// The entry with the largest offset up to `offset`, or the start of the segment if there is
+    /// none, like the `lookup` of brokers
+    pub fn lookup(&self, offset: i64) -> OffsetPosition {
+        let count = self.entries.partition_point(|entry| entry.offset <= offset);
+        match count {
+            0 => OffsetPosition {
+                offset: self.base_offset,
+                position: 0,
+            },
+            count => self.entries[count - 1],
+        }
+    }
+}
+
+/// A `.timeindex` file, mapping timestamps to the offsets from which the records of a segment
+/// reach them
+///
+/// Brokers add an entry along with the ones of the [`OffsetIndex`] of the segment, when the
+/// largest timestamp of the segment grew since the previous one. Timestamps and offsets are
+/// both increasing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeIndex {
+    base_offset: i64,
+    entries: Vec<TimestampOffset>,
+}
+
+impl TimeIndex {
+    /// Reads the time index file at `path`, whose name gives the base offset of its segment
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<TimeIndex> {
+        let (base_offset, data) = read(path.as_ref())?;
+        TimeIndex::decode(base_offset, &data)
+    }
+
+    /// Decodes the time index of the segment starting at `base_offset`
+    pub fn decode(base_offset: i64, buf: &[u8]) -> Result<TimeIndex> {
+        let mut de = entries(buf, TIME_ENTRY_SIZE, "time")?;
+        let mut entries = Vec::with_capacity(buf.len() / TIME_ENTRY_SIZE);
+        while de.remaining() > 0 {
+            let timestamp = de.read_i64()?;
+            let relative_offset = de.read_i32()?;
+            if !entries.is_empty() && timestamp == 0 && relative_offset == 0 {
+                // the space brokers preallocate for the entries of active segments
+                break;
+            }
+            entries.push(TimestampOffset {
+                timestamp,
+                offset: base_offset + relative_offset as i64,
+            });
+        }
+        Ok(TimeIndex {
+            base_offset,
+            entries,
+        })
+    }
+
+    /// The offset of the first record of the segment
+    pub fn base_offset(&self) -> i64 {
+        self.base_offset
+    }
+
+    /// The entries of the index, by increasing timestamp
+    pub fn entries(&self) -> &[TimestampOffset] {
+        &self.entries
+    }
+
+    /// The entry with the largest timestamp up to `timestamp`, or the start of the segment
+    /// without a timestamp if there is none, like the `lookup` of brokers
+    ///
+    /// The records from `timestamp` on are at or after the offset of the entry, to be looked up
+    /// in the [`OffsetIndex`].
+    pub fn lookup(&self, timestamp: i64) -> TimestampOffset {
+        let count = self
+            .entries
+            .partition_point(|entry| entry.timestamp <= timestamp);
+        match count {
+            0 => TimestampOffset {
+                timestamp: NO_TIMESTAMP,
+                offset: self.base_offset,
+            },
+            count => self.entries[count - 1],
+        }
+    }
+}
+
+/// Reads the index file at `path`, along with the base offset its name gives
+fn read(path: &Path) -> Result<(i64, Vec<u8>)> {
+    let base_offset = super::base_offset(path).ok_or_else(|| {
+        ErrorKind::Custom(format!(
+            "index file not named after an offset: {}",
+            path.display()
+        ))
+    })?;
+    Ok((base_offset, fs::read(path)?))
+}
+
+/// A deserializer over the entries of an index, failing if `buf` doesn't hold whole entries
+fn entries<'a>(buf: &'a [u8], entry_size: usize, index: &str) -> Result<KafkaDeserializer<'a>> {
+    if buf.len() % entry_size != 0 {
+        return Err(ErrorKind::Custom(format!(
+            "corrupt {} index: {} bytes are not a multiple of the entry size {}",
+            index,
+            buf.len(),
+            entry_size
+        ))
+        .into());
+    }
+    Ok(KafkaDeserializer::new(buf))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn offset_index() -> Vec<u8> {
+        let mut buf = Vec::new();
+        for (relative_offset, position) in [(0i32, 0u32), (10, 4096), (25, 8192)] {
+            buf.extend_from_slice(&relative_offset.to_be_bytes());
+            buf.extend_from_slice(&position.to_be_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_offset_index() {
+        let index = OffsetIndex::decode(100, &offset_index()).unwrap();
+        assert_eq!(index.entries().len(), 3);
+        let at = |offset, position| OffsetPosition { offset, position };
+        assert_eq!(index.lookup(99), at(100, 0));
+        assert_eq!(index.lookup(100), at(100, 0));
+        assert_eq!(index.lookup(109), at(100, 0));
+        assert_eq!(index.lookup(110), at(110, 4096));
+        assert_eq!(index.lookup(1000), at(125, 8192));
+
+        // preallocated space is left out
+        let mut buf = offset_index();
+        buf.resize(buf.len() + 4 * OFFSET_ENTRY_SIZE, 0);
+        assert_eq!(OffsetIndex::decode(100, &buf).unwrap(), index);
+
+        let empty = OffsetIndex::decode(100, &[0; 16]).unwrap();
+        assert_eq!(empty.entries(), [at(100, 0)]);
+        assert_eq!(empty.lookup(150), at(100, 0));
+
+        let err = OffsetIndex::decode(100, &buf[..buf.len() - 1]).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Custom(ref msg) => assert!(msg.starts_with("corrupt offset index")),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_time_index() {
+        let mut buf = Vec::new();
+        for (timestamp, relative_offset) in [(1_000i64, 3i32), (2_000, 12), (5_000, 40)] {
+            buf.extend_from_slice(&timestamp.to_be_bytes());
+            buf.extend_from_slice(&relative_offset.to_be_bytes());
+        }
+        buf.resize(buf.len() + TIME_ENTRY_SIZE, 0);
+        let index = TimeIndex::decode(100, &buf).unwrap();
+        assert_eq!(index.entries().len(), 3);
+        let at = |timestamp, offset| TimestampOffset { timestamp, offset };
+        assert_eq!(index.lookup(999), at(NO_TIMESTAMP, 100));
+        assert_eq!(index.lookup(1_000), at(1_000, 103));
+        assert_eq!(index.lookup(4_999), at(2_000, 112));
+        assert_eq!(index.lookup(i64::MAX), at(5_000, 140));
+
+        assert!(TimeIndex::decode(100, &buf[..TIME_ENTRY_SIZE + 4]).is_err());
+    }
+
+    #[test]
+    fn test_open() {
+        let err = TimeIndex::open("my-topic-0/segment.timeindex").unwrap_err();
+        match *err.kind() {
+            ErrorKind::Custom(ref msg) => {
+                assert_eq!(
+                    msg,
+                    "index file not named after an offset: my-topic-0/segment.timeindex"
+                )
+            }
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+}
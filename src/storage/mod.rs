@@ -0,0 +1,71 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # On-disk log files
+//!
+//! Brokers store each partition in a directory of segments: a `.log` file holding record
+//! batches as they were appended, named after the offset of its first record, along with the
+//! `.index` and `.timeindex` files that locate offsets and timestamps in it. Snapshots of the
+//! states of the producers of the partition sit next to them. This module reads those files,
+//! for offline inspection, recovery and analytics tools to work on the data of a partition
+//! without a running broker.
+//!
+//! # Examples
+//! ```no_run
+//! use kafka_serde::storage::Segment;
+//!
+//! let segment = Segment::open("my-topic-0/00000000000000000000.log")?;
+//! for batch in segment.batches() {
+//!     let batch = batch?;
+//!     println!("{} records from offset {}", batch.header.record_count, batch.header.base_offset);
+//! }
+//! # Ok::<(), kafka_serde::Error>(())
+//! ```
+use std::path::Path;
+
+mod index;
+mod segment;
+mod snapshot;
+
+pub use self::index::{OffsetIndex, OffsetPosition, TimeIndex, TimestampOffset};
+pub use self::segment::{Batches, Segment};
+pub use self::snapshot::{ProducerEntry, ProducerSnapshot};
+
+/// The number of digits of the offsets segment files are named after
+const FILE_NAME_DIGITS: usize = 20;
+
+/// The base offset of the segment a file belongs to, from a file name like
+/// `00000000000000000042.log`
+///
+/// Returns `None` if the name is not a zero-padded offset followed by an extension.
+pub fn base_offset<P: AsRef<Path>>(path: P) -> Option<i64> {
+    let stem = path.as_ref().file_stem()?.to_str()?;
+    if stem.len() != FILE_NAME_DIGITS || !stem.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    stem.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base_offset() {
+        assert_eq!(base_offset("00000000000000000000.log"), Some(0));
+        assert_eq!(
+            base_offset("/data/t-0/00000000000000001234.index"),
+            Some(1234)
+        );
+        assert_eq!(
+            base_offset("09223372036854775807.timeindex"),
+            Some(i64::MAX)
+        );
+        assert_eq!(base_offset("1234.log"), None);
+        assert_eq!(base_offset("0000000000000000000a.log"), None);
+        assert_eq!(base_offset("99999999999999999999.log"), None);
+        assert_eq!(base_offset("leader-epoch-checkpoint"), None);
+    }
+}
@@ -0,0 +1,296 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use crate::error::Result;
+use crate::records::{split_first, BatchDecoder, LazyBatch};
+use std::fs;
+use std::path::Path;
+
+/// A `.log` segment file, holding the record batches of a partition from its base offset on
+///
+/// The file is read whole into memory by [`open`](Segment::open), or memory-mapped by
+/// [`map`](Segment::map) with the `mmap` feature, and its batches are decoded on demand as
+/// [`batches`](Segment::batches) iterates over them.
+///
+/// Segments written by brokers before Kafka 0.11 hold legacy message sets instead, which fail
+/// to decode with [`ErrorKind::UnsupportedMagic`](crate::ErrorKind::UnsupportedMagic).
+#[derive(Debug)]
+pub struct Segment {
+    base_offset: Option<i64>,
+    data: Data,
+}
+
+#[derive(Debug)]
+enum Data {
+    Owned(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Segment {
+    /// Reads the segment file at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Segment> {
+        let path = path.as_ref();
+        Ok(Segment {
+            base_offset: super::base_offset(path),
+            data: Data::Owned(fs::read(path)?),
+        })
+    }
+
+    /// Memory-maps the segment file at `path`, for segments too large to be read whole
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified while it is mapped, as the memory of the segment would
+    /// change under the batches borrowing it. Active segments, which brokers append to, and
+    /// segments a broker may truncate or delete, should be copied or [`open`](Segment::open)ed
+    /// instead.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn map<P: AsRef<Path>>(path: P) -> Result<Segment> {
+        let path = path.as_ref();
+        let file = fs::File::open(path)?;
+        Ok(Segment {
+            base_offset: super::base_offset(path),
+            data: Data::Mapped(memmap2::Mmap::map(&file)?),
+        })
+    }
+
+    /// A segment holding `data`, as read from a file with an unknown base offset
+    pub fn new(data: Vec<u8>) -> Segment {
+        Segment {
+            base_offset: None,
+            data: Data::Owned(data),
+        }
+    }
+
+    /// The offset of the first record of the segment, from the name of its file
+    ///
+    /// `None` when the file is not named after an offset, like the copies of segments.
+    pub fn base_offset(&self) -> Option<i64> {
+        self.base_offset
+    }
+
+    /// The bytes of the segment
+    pub fn as_bytes(&self) -> &[u8] {
+        match self.data {
+            Data::Owned(ref data) => data,
+            #[cfg(feature = "mmap")]
+            Data::Mapped(ref data) => data,
+        }
+    }
+
+    /// Iterates over the record batches of the segment, verifying their checksums
+    pub fn batches(&self) -> Batches<'_> {
+        self.batches_with(BatchDecoder::new())
+    }
+
+    /// Iterates over the record batches of the segment, decoded with `decoder`
+    pub fn batches_with(&self, decoder: BatchDecoder) -> Batches<'_> {
+        Batches {
+            decoder,
+            buf: self.as_bytes(),
+            position: 0,
+            done: false,
+        }
+    }
+
+    /// Iterates over the record batches of the segment from `position`, like the position an
+    /// [`OffsetIndex`](super::OffsetIndex) gives for an offset
+    ///
+    /// `position` must be the start of a batch. The iteration is empty if it is past the end of
+    /// the segment.
+    pub fn batches_from(&self, position: usize) -> Batches<'_> {
+        Batches {
+            position,
+            ..self.batches()
+        }
+    }
+}
+
+/// An iterator over the record batches of a [`Segment`]
+///
+/// A broker that stops while appending to a segment can leave its last batch cut short. Like
+/// [`BatchDecoder::decode_all`] does, the iteration ends before such a batch. It also ends
+/// after the first batch that fails to decode, and [`position`](Batches::position) then tells
+/// where the valid batches of the segment end, which is where brokers truncate it on recovery.
+#[derive(Debug, Clone)]
+pub struct Batches<'a> {
+    decoder: BatchDecoder,
+    buf: &'a [u8],
+    position: usize,
+    done: bool,
+}
+
+impl Batches<'_> {
+    /// The position in the segment of the next batch, past the batches iterated over so far
+    ///
+    /// Once the iteration ends, it is the size of the segment if all of its batches decoded,
+    /// or the position of the batch that failed to decode or was cut short.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a> Iterator for Batches<'a> {
+    type Item = Result<LazyBatch<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let buf = self.buf.get(self.position..).unwrap_or_default();
+        let batch = match split_first(buf) {
+            Ok(Some((batch, _))) => batch,
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err));
+            }
+        };
+        match self.decoder.decode_lazy(batch) {
+            Ok(decoded) => {
+                self.position += batch.len();
+                Some(Ok(decoded))
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::records::{Record, RecordBatch};
+    use crate::ErrorKind;
+    use std::path::PathBuf;
+
+    fn batch(base_offset: i64, values: &[&[u8]]) -> RecordBatch {
+        let records = values
+            .iter()
+            .enumerate()
+            .map(|(delta, value)| Record {
+                offset_delta: delta as i32,
+                value: Some(value.to_vec()),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        RecordBatch {
+            base_offset,
+            last_offset_delta: records.len() as i32 - 1,
+            records,
+            ..Default::default()
+        }
+    }
+
+    fn segment() -> Vec<u8> {
+        let mut buf = Vec::new();
+        batch(42, &[b"a", b"b"]).encode(&mut buf).unwrap();
+        batch(44, &[b"c"]).encode(&mut buf).unwrap();
+        buf
+    }
+
+    fn offsets(segment: &Segment) -> Vec<i64> {
+        let mut offsets = Vec::new();
+        for batch in segment.batches() {
+            let batch = batch.unwrap();
+            for record in batch.records() {
+                offsets.push(batch.header.offset_of(&record.unwrap()));
+            }
+        }
+        offsets
+    }
+
+    /// A file in the temporary directory, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, data: &[u8]) -> TempFile {
+            let dir = std::env::temp_dir().join(format!("kafka-serde-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join(name);
+            fs::write(&path, data).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_open() {
+        let file = TempFile::new("00000000000000000042.log", &segment());
+        let segment = Segment::open(&file.0).unwrap();
+        assert_eq!(segment.base_offset(), Some(42));
+        assert_eq!(offsets(&segment), [42, 43, 44]);
+
+        let file = TempFile::new("empty.log", &[]);
+        let segment = Segment::open(&file.0).unwrap();
+        assert_eq!(segment.base_offset(), None);
+        assert_eq!(segment.batches().count(), 0);
+
+        let err = Segment::open("/nonexistent/00000000000000000000.log").unwrap_err();
+        match *err.kind() {
+            ErrorKind::Io(_) => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_map() {
+        let file = TempFile::new("00000000000000000043.log", &segment());
+        let segment = unsafe { Segment::map(&file.0) }.unwrap();
+        assert_eq!(segment.base_offset(), Some(43));
+        assert_eq!(offsets(&segment), [42, 43, 44]);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = segment();
+        let mut first = Vec::new();
+        batch(42, &[b"a", b"b"]).encode(&mut first).unwrap();
+        let segment = Segment::new(data[..data.len() - 1].to_vec());
+        let mut batches = segment.batches();
+        assert_eq!(batches.next().unwrap().unwrap().header.base_offset, 42);
+        assert!(batches.next().is_none());
+        assert_eq!(batches.position(), first.len());
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let mut data = segment();
+        let mut first = Vec::new();
+        batch(42, &[b"a", b"b"]).encode(&mut first).unwrap();
+        let len = data.len();
+        data[len - 1] ^= 1;
+        let segment = Segment::new(data);
+        let mut batches = segment.batches();
+        assert_eq!(batches.next().unwrap().unwrap().header.base_offset, 42);
+        match *batches.next().unwrap().unwrap_err().kind() {
+            ErrorKind::CrcMismatch { .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        assert!(batches.next().is_none());
+        assert_eq!(batches.position(), first.len());
+
+        // batches can be iterated from a position, like the one of an index
+        let mut batches = segment.batches_from(first.len());
+        assert!(batches.next().unwrap().is_err());
+        assert_eq!(segment.batches_from(len + 1).count(), 0);
+
+        // the checksums can be left unverified
+        let batches = segment.batches_with(BatchDecoder::new().verify_crc(false));
+        assert_eq!(batches.count(), 2);
+    }
+}
@@ -0,0 +1,242 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+use crate::de::KafkaDeserializer;
+use crate::error::{ErrorKind, Result};
+use crate::records::crc32c;
+use std::fs;
+use std::path::Path;
+
+/// The version of the snapshots written by brokers since Kafka 0.11
+const SNAPSHOT_VERSION: i16 = 1;
+/// The size of each producer entry
+const ENTRY_SIZE: usize = 46;
+
+/// The state of a producer, as of the offset of a [`ProducerSnapshot`]
+///
+/// Brokers keep it to reject the duplicates and the batches out of order of idempotent
+/// producers, which fail with `OutOfOrderSequence` when their sequence doesn't follow
+/// [`last_sequence`](ProducerEntry::last_sequence).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProducerEntry {
+    /// The producer id
+    pub producer_id: i64,
+    /// The producer epoch
+    pub producer_epoch: i16,
+    /// The sequence of the last record the producer appended
+    pub last_sequence: i32,
+    /// The offset of the last record the producer appended
+    pub last_offset: i64,
+    /// The offset delta of the last record in its batch, from the first one
+    pub offset_delta: i32,
+    /// The largest timestamp of the last batch the producer appended
+    pub timestamp: i64,
+    /// The epoch of the transaction coordinator that last wrote a transaction marker for the
+    /// producer, or -1
+    pub coordinator_epoch: i32,
+    /// The first offset of the transaction the producer has open, if it has one
+    pub current_txn_first_offset: Option<i64>,
+}
+
+impl ProducerEntry {
+    /// The sequence of the first record of the last batch the producer appended
+    pub fn first_sequence(&self) -> i32 {
+        self.last_sequence.wrapping_sub(self.offset_delta)
+    }
+
+    /// The offset of the first record of the last batch the producer appended
+    pub fn first_offset(&self) -> i64 {
+        self.last_offset - self.offset_delta as i64
+    }
+}
+
+/// A `.snapshot` file, holding the state of the producers that appended to a partition, as of
+/// the offset the file is named after
+///
+/// Brokers take these snapshots as segments roll, and when they stop, to rebuild the producer
+/// states from the snapshot and the segments after its offset instead of from the whole log.
+///
+/// # Examples
+/// ```no_run
+/// use kafka_serde::storage::ProducerSnapshot;
+///
+/// let snapshot = ProducerSnapshot::open("my-topic-0/00000000000000001234.snapshot")?;
+/// for producer in snapshot.entries() {
+///     println!(
+///         "producer {} epoch {}: sequences {}..={}",
+///         producer.producer_id,
+///         producer.producer_epoch,
+///         producer.first_sequence(),
+///         producer.last_sequence
+///     );
+/// }
+/// # Ok::<(), kafka_serde::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducerSnapshot {
+    offset: Option<i64>,
+    entries: Vec<ProducerEntry>,
+}
+
+impl ProducerSnapshot {
+    /// Reads the snapshot file at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ProducerSnapshot> {
+        let path = path.as_ref();
+        let mut snapshot = ProducerSnapshot::decode(&fs::read(path)?)?;
+        snapshot.offset = super::base_offset(path);
+        Ok(snapshot)
+    }
+
+    /// Decodes a snapshot, verifying its checksum
+    pub fn decode(buf: &[u8]) -> Result<ProducerSnapshot> {
+        let mut de = KafkaDeserializer::new(buf);
+        let version = de.read_i16()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(ErrorKind::Custom(format!(
+                "unsupported producer snapshot version: {}",
+                version
+            ))
+            .into());
+        }
+        let expected = de.read_u32()?;
+        let computed = crc32c(de.peek_remaining());
+        if expected != computed {
+            return Err(ErrorKind::CrcMismatch { expected, computed }.into());
+        }
+
+        let count = de.read_array_len()?;
+        let mut entries = Vec::with_capacity(count.min(de.remaining() / ENTRY_SIZE));
+        for _ in 0..count {
+            entries.push(ProducerEntry {
+                producer_id: de.read_i64()?,
+                producer_epoch: de.read_i16()?,
+                last_sequence: de.read_i32()?,
+                last_offset: de.read_i64()?,
+                offset_delta: de.read_i32()?,
+                timestamp: de.read_i64()?,
+                coordinator_epoch: de.read_i32()?,
+                current_txn_first_offset: match de.read_i64()? {
+                    -1 => None,
+                    offset => Some(offset),
+                },
+            });
+        }
+        Ok(ProducerSnapshot {
+            offset: None,
+            entries,
+        })
+    }
+
+    /// The offset the snapshot was taken at, from the name of its file
+    ///
+    /// The states are those of the producers up to the record before it. `None` when the file
+    /// is not named after an offset, or the snapshot was decoded from bytes.
+    pub fn offset(&self) -> Option<i64> {
+        self.offset
+    }
+
+    /// The states of the producers
+    pub fn entries(&self) -> &[ProducerEntry] {
+        &self.entries
+    }
+
+    /// The state of the producer with `producer_id`, if the snapshot has it
+    pub fn producer(&self, producer_id: i64) -> Option<&ProducerEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.producer_id == producer_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The size of the version and checksum that start snapshots
+    const ENTRIES_OFFSET: usize = 6;
+
+    fn entry() -> ProducerEntry {
+        ProducerEntry {
+            producer_id: 4000,
+            producer_epoch: 2,
+            last_sequence: 14,
+            last_offset: 1_209,
+            offset_delta: 4,
+            timestamp: 1_600_000_000_000,
+            coordinator_epoch: -1,
+            current_txn_first_offset: None,
+        }
+    }
+
+    fn encode(version: i16, entries: &[ProducerEntry]) -> Vec<u8> {
+        let mut body = (entries.len() as i32).to_be_bytes().to_vec();
+        for entry in entries {
+            body.extend_from_slice(&entry.producer_id.to_be_bytes());
+            body.extend_from_slice(&entry.producer_epoch.to_be_bytes());
+            body.extend_from_slice(&entry.last_sequence.to_be_bytes());
+            body.extend_from_slice(&entry.last_offset.to_be_bytes());
+            body.extend_from_slice(&entry.offset_delta.to_be_bytes());
+            body.extend_from_slice(&entry.timestamp.to_be_bytes());
+            body.extend_from_slice(&entry.coordinator_epoch.to_be_bytes());
+            let first_offset = entry.current_txn_first_offset.unwrap_or(-1);
+            body.extend_from_slice(&first_offset.to_be_bytes());
+        }
+        let mut buf = version.to_be_bytes().to_vec();
+        buf.extend_from_slice(&crc32c(&body).to_be_bytes());
+        buf.extend_from_slice(&body);
+        buf
+    }
+
+    #[test]
+    fn test_decode() {
+        let transactional = ProducerEntry {
+            producer_id: 4001,
+            coordinator_epoch: 7,
+            current_txn_first_offset: Some(1_100),
+            ..entry()
+        };
+        let buf = encode(1, &[entry(), transactional]);
+        assert_eq!(buf.len(), ENTRIES_OFFSET + 4 + 2 * ENTRY_SIZE);
+        let snapshot = ProducerSnapshot::decode(&buf).unwrap();
+        assert_eq!(snapshot.entries(), [entry(), transactional]);
+        assert_eq!(snapshot.offset(), None);
+
+        let producer = snapshot.producer(4000).unwrap();
+        assert_eq!(producer.first_sequence(), 10);
+        assert_eq!(producer.first_offset(), 1_205);
+        assert_eq!(snapshot.producer(4002), None);
+
+        let empty = ProducerSnapshot::decode(&encode(1, &[])).unwrap();
+        assert!(empty.entries().is_empty());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut buf = encode(1, &[entry()]);
+        let len = buf.len();
+        buf[len - 1] ^= 1;
+        match *ProducerSnapshot::decode(&buf).unwrap_err().kind() {
+            ErrorKind::CrcMismatch { .. } => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        match *ProducerSnapshot::decode(&encode(2, &[entry()]))
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::Custom(ref msg) => {
+                assert_eq!(msg, "unsupported producer snapshot version: 2")
+            }
+            ref e => panic!("unexpected error {}", e),
+        }
+
+        // a count of entries larger than the snapshot
+        let mut buf = encode(1, &[entry()]);
+        buf[ENTRIES_OFFSET..ENTRIES_OFFSET + 4].copy_from_slice(&i32::MAX.to_be_bytes());
+        let crc = crc32c(&buf[ENTRIES_OFFSET..]);
+        buf[2..ENTRIES_OFFSET].copy_from_slice(&crc.to_be_bytes());
+        assert!(ProducerSnapshot::decode(&buf).unwrap_err().is_incomplete());
+    }
+}
@@ -0,0 +1,238 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Proptest strategies
+//!
+//! Strategies generating values the protocol can carry, for property-based tests of what is
+//! built on this crate: strings and bytes within the bounds of their length prefixes, nullable
+//! values, arrays, records, and the versions of a [`Schema`].
+//!
+//! [`message`] generates values of any [`Schema`] in a version: fields that are in it, nulls
+//! where they are allowed, tagged fields set or not, and arrays of nested structures. Whatever
+//! it generates must encode, then decode back to itself.
+//!
+//! Sizes are kept small for tests to run quickly: strings of up to 32 characters, bytes of up
+//! to 64, and arrays of up to 4 elements.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::schema::{Field, Schema, Type};
+//! use kafka_serde::strategy;
+//! use proptest::prelude::*;
+//!
+//! let schema = Schema::new(
+//!     "HeartbeatResponse",
+//!     vec![
+//!         Field::new("ThrottleTimeMs", Type::Int32).versions(1..),
+//!         Field::new("ErrorCode", Type::Int16),
+//!     ],
+//! )
+//! .valid_versions(0..=4)
+//! .flexible_versions(4..);
+//!
+//! proptest!(|((version, value) in strategy::versioned_message(&schema))| {
+//!     let mut buf = Vec::new();
+//!     schema.encode(version, &value, &mut buf).unwrap();
+//!     prop_assert_eq!(schema.decode(version, &buf).unwrap(), value);
+//! });
+//! ```
+use std::iter::FromIterator;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::records::{Header, Headers, Record};
+use crate::schema::{Field, KafkaValue, Schema, Type};
+
+/// The most characters of generated strings
+const MAX_STRING_CHARS: usize = 32;
+/// The most bytes of generated byte arrays
+const MAX_BYTES: usize = 64;
+/// The most elements of generated arrays
+const MAX_ELEMENTS: usize = 4;
+
+/// Strings of up to 32 characters, well within the `i16` length of protocol strings
+pub fn string() -> BoxedStrategy<String> {
+    vec(any::<char>(), 0..=MAX_STRING_CHARS)
+        .prop_map(String::from_iter)
+        .boxed()
+}
+
+/// Byte arrays of up to 64 bytes
+pub fn bytes() -> BoxedStrategy<Vec<u8>> {
+    vec(any::<u8>(), 0..=MAX_BYTES).boxed()
+}
+
+/// The values of `strategy`, or null a quarter of the time
+pub fn nullable<S: Strategy + 'static>(strategy: S) -> BoxedStrategy<Option<S::Value>>
+where
+    S::Value: Clone,
+{
+    prop_oneof![1 => Just(None), 3 => strategy.prop_map(Some)].boxed()
+}
+
+/// Arrays of up to 4 values of `element`
+pub fn array<S: Strategy + 'static>(element: S) -> BoxedStrategy<Vec<S::Value>> {
+    vec(element, 0..=MAX_ELEMENTS).boxed()
+}
+
+/// The valid versions of `schema`
+pub fn version(schema: &Schema) -> BoxedStrategy<i16> {
+    schema.valid_versions.clone().boxed()
+}
+
+/// Values of `schema` in `version`
+pub fn message(schema: &Schema, version: i16) -> BoxedStrategy<KafkaValue> {
+    fields(&schema.fields, version, schema.is_flexible(version))
+}
+
+/// Valid versions of `schema`, with values of the schema in them
+pub fn versioned_message(schema: &Schema) -> BoxedStrategy<(i16, KafkaValue)> {
+    let schema = schema.clone();
+    version(&schema)
+        .prop_flat_map(move |version| (Just(version), message(&schema, version)))
+        .boxed()
+}
+
+/// Record headers, of up to 4 headers with nullable values
+pub fn headers() -> BoxedStrategy<Headers> {
+    array((string(), nullable(bytes())))
+        .prop_map(|headers| {
+            let headers: Vec<_> = headers
+                .into_iter()
+                .map(|(key, value)| Header::new(key, value))
+                .collect();
+            Headers::from(headers)
+        })
+        .boxed()
+}
+
+/// Records of a v2 record batch, with nullable keys and values
+pub fn record() -> BoxedStrategy<Record> {
+    (
+        any::<i64>(),
+        any::<i32>(),
+        nullable(bytes()),
+        nullable(bytes()),
+        headers(),
+    )
+        .prop_map(
+            |(timestamp_delta, offset_delta, key, value, headers)| Record {
+                attributes: 0,
+                timestamp_delta,
+                offset_delta,
+                key,
+                value,
+                headers,
+            },
+        )
+        .boxed()
+}
+
+/// Structures made of `fields`, in `version`, in the order they are decoded in: the fields
+/// that are not tagged, then the tagged fields that are set, by tag
+fn fields(fields: &[Field], version: i16, flexible: bool) -> BoxedStrategy<KafkaValue> {
+    let mut present: Vec<_> = fields
+        .iter()
+        .filter(|field| field.versions.contains(&version))
+        .filter(|field| flexible || field.tag.is_none())
+        .collect();
+    present.sort_by_key(|field| field.tag);
+    let values: Vec<_> = present
+        .into_iter()
+        .map(|field| {
+            let name = field.name.clone();
+            let nullable = field.nullable_versions.contains(&version);
+            let value = value(&field.ty, nullable, version, flexible)
+                .prop_map(move |value| Some((name.clone(), value)));
+            match field.tag {
+                Some(_) => prop_oneof![Just(None), value].boxed(),
+                None => value.boxed(),
+            }
+        })
+        .collect();
+    values
+        .prop_map(|values| KafkaValue::Struct(values.into_iter().flatten().collect()))
+        .boxed()
+}
+
+fn value(ty: &Type, nullable: bool, version: i16, flexible: bool) -> BoxedStrategy<KafkaValue> {
+    let value = match *ty {
+        Type::Bool => any::<bool>().prop_map(KafkaValue::Bool).boxed(),
+        Type::Int8 => any::<i8>().prop_map(KafkaValue::Int8).boxed(),
+        Type::Int16 => any::<i16>().prop_map(KafkaValue::Int16).boxed(),
+        Type::Uint16 => any::<u16>().prop_map(KafkaValue::Uint16).boxed(),
+        Type::Int32 => any::<i32>().prop_map(KafkaValue::Int32).boxed(),
+        Type::Uint32 => any::<u32>().prop_map(KafkaValue::Uint32).boxed(),
+        Type::Int64 => any::<i64>().prop_map(KafkaValue::Int64).boxed(),
+        // NaN is not equal to itself
+        Type::Float64 => (-1e12..1e12).prop_map(KafkaValue::Float64).boxed(),
+        Type::String => string().prop_map(KafkaValue::String).boxed(),
+        Type::Bytes => bytes().prop_map(KafkaValue::Bytes).boxed(),
+        Type::Records => bytes().prop_map(KafkaValue::Records).boxed(),
+        Type::Uuid => any::<[u8; 16]>().prop_map(KafkaValue::Uuid).boxed(),
+        Type::Array(ref element) => array(value(element, false, version, flexible))
+            .prop_map(KafkaValue::Array)
+            .boxed(),
+        Type::Struct(ref nested) => fields(nested, version, flexible),
+    };
+    let can_be_null = matches!(
+        *ty,
+        Type::String | Type::Bytes | Type::Records | Type::Array(_) | Type::Struct(_)
+    );
+    if nullable && can_be_null {
+        prop_oneof![1 => Just(KafkaValue::Null), 3 => value].boxed()
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::de::KafkaDeserializer;
+
+    fn schema() -> Schema {
+        let entry = Type::Struct(vec![
+            Field::new("Name", Type::String),
+            Field::new("Id", Type::Uuid).versions(1..),
+            Field::new("Weight", Type::Float64),
+            Field::new("Data", Type::Bytes).nullable_versions(2..),
+            Field::new("Note", Type::String)
+                .tag(1)
+                .nullable_versions(..),
+        ]);
+        Schema::new(
+            "Example",
+            vec![
+                Field::new("Flag", Type::Bool),
+                Field::new("Entries", Type::Array(Box::new(entry.clone()))),
+                Field::new("Nested", entry).nullable_versions(..),
+                Field::new("Records", Type::Records).versions(2..),
+                Field::new("Count", Type::Uint32).tag(0),
+            ],
+        )
+        .valid_versions(0..=3)
+        .flexible_versions(3..)
+    }
+
+    proptest! {
+        #[test]
+        fn test_message_roundtrip((version, value) in versioned_message(&schema())) {
+            let schema = schema();
+            let mut buf = Vec::new();
+            schema.encode(version, &value, &mut buf).unwrap();
+            prop_assert_eq!(schema.decode(version, &buf).unwrap(), value);
+        }
+
+        #[test]
+        fn test_record_roundtrip(record in record()) {
+            let mut buf = Vec::new();
+            record.write(&mut buf).unwrap();
+            prop_assert_eq!(buf.len(), record.encoded_size());
+            prop_assert_eq!(Record::read(&mut KafkaDeserializer::new(&buf)).unwrap(), record);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Values selected by a discriminant
+//!
+//! Kafka payloads don't say which type they hold: a value decoded before them does, like the API
+//! key and version of a request header, or the version that starts the keys of internal topics.
+//! The format is not self-describing, so serde's tagged enums can't decode them.
+//!
+//! [`DeserializeVariant`] decodes a type, usually an enum, in the way a discriminant selects.
+//! [`VariantSeed`] is the [`DeserializeSeed`] doing so, for visitors decoding the discriminant
+//! then the value, and [`Tagged`] is a discriminant followed by the value it selects, for
+//! messages that are just that.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::tagged::{DeserializeVariant, Tagged};
+//! use serde::de::{Deserializer, Error};
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct OffsetCommitKey {
+//!     group: String,
+//!     topic: String,
+//!     partition: i32,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct GroupMetadataKey {
+//!     group: String,
+//! }
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum OffsetsKey {
+//!     OffsetCommit(OffsetCommitKey),
+//!     GroupMetadata(GroupMetadataKey),
+//! }
+//!
+//! impl<'de> DeserializeVariant<'de, i16> for OffsetsKey {
+//!     fn deserialize_variant<D>(version: &i16, deserializer: D) -> Result<Self, D::Error>
+//!     where
+//!         D: Deserializer<'de>,
+//!     {
+//!         match *version {
+//!             0 | 1 => OffsetCommitKey::deserialize(deserializer).map(OffsetsKey::OffsetCommit),
+//!             2 => GroupMetadataKey::deserialize(deserializer).map(OffsetsKey::GroupMetadata),
+//!             version => Err(D::Error::custom(format!("unknown key version {}", version))),
+//!         }
+//!     }
+//! }
+//!
+//! let data = [0x00, 0x02, 0x00, 0x01, b'g'];
+//! let key: Tagged<i16, OffsetsKey> = kafka_serde::from_bytes(&data).unwrap();
+//! assert_eq!(key.discriminant, 2);
+//! assert_eq!(
+//!     key.value,
+//!     OffsetsKey::GroupMetadata(GroupMetadataKey { group: "g".into() })
+//! );
+//! ```
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+/// A type decoded in the way a discriminant decoded before it selects
+///
+/// Implementations usually match on the discriminant, and decode the type of the variant it
+/// selects. Discriminants that select nothing are errors, or variants the caller skips.
+pub trait DeserializeVariant<'de, K>: Sized {
+    /// Decodes the value `discriminant` selects from `deserializer`
+    fn deserialize_variant<D>(discriminant: &K, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Decodes a `T` in the way a discriminant selects, see [`DeserializeVariant`]
+///
+/// Visitors of structs holding a discriminant then the value it selects, possibly with other
+/// fields in between, pass it to [`SeqAccess::next_element_seed`] once they have the
+/// discriminant.
+pub struct VariantSeed<'k, K, T> {
+    discriminant: &'k K,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'k, K, T> VariantSeed<'k, K, T> {
+    /// A seed decoding the variant of `T` selected by `discriminant`
+    pub fn new(discriminant: &'k K) -> Self {
+        VariantSeed {
+            discriminant,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K: fmt::Debug, T> fmt::Debug for VariantSeed<'_, K, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("VariantSeed")
+            .field("discriminant", self.discriminant)
+            .finish()
+    }
+}
+
+impl<'de, K, T> DeserializeSeed<'de> for VariantSeed<'_, K, T>
+where
+    T: DeserializeVariant<'de, K>,
+{
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize_variant(self.discriminant, deserializer)
+    }
+}
+
+/// A discriminant, followed by the value it selects
+///
+/// It is encoded like a struct of the two, and the value is decoded with [`VariantSeed`]. To
+/// encode it, enums over the values are usually `#[serde(untagged)]`, to be written as the
+/// variant they hold.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tagged<K, T> {
+    /// What selects the type of the value
+    pub discriminant: K,
+    /// The value
+    pub value: T,
+}
+
+impl<K: Serialize, T: Serialize> Serialize for Tagged<K, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Tagged", 2)?;
+        state.serialize_field("discriminant", &self.discriminant)?;
+        state.serialize_field("value", &self.value)?;
+        state.end()
+    }
+}
+
+impl<'de, K, T> Deserialize<'de> for Tagged<K, T>
+where
+    K: Deserialize<'de>,
+    T: DeserializeVariant<'de, K>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TaggedVisitor<K, T>(PhantomData<fn() -> (K, T)>);
+
+        impl<'de, K, T> Visitor<'de> for TaggedVisitor<K, T>
+        where
+            K: Deserialize<'de>,
+            T: DeserializeVariant<'de, K>,
+        {
+            type Value = Tagged<K, T>;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("a discriminant followed by the value it selects")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let discriminant: K = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let value = seq
+                    .next_element_seed(VariantSeed::new(&discriminant))?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(Tagged {
+                    discriminant,
+                    value,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct(
+            "Tagged",
+            &["discriminant", "value"],
+            TaggedVisitor(PhantomData),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{from_bytes, to_vec, ErrorKind};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct HeartbeatRequest {
+        group_id: String,
+        generation_id: i32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct ListGroupsRequest {}
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Api {
+        key: i16,
+        version: i16,
+    }
+
+    #[derive(Debug, PartialEq, Serialize)]
+    #[serde(untagged)]
+    enum Request {
+        Heartbeat(HeartbeatRequest),
+        ListGroups(ListGroupsRequest),
+    }
+
+    // routed by API key and version
+    impl<'de> DeserializeVariant<'de, Api> for Request {
+        fn deserialize_variant<D>(api: &Api, deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            match (api.key, api.version) {
+                (12, 0..=2) => HeartbeatRequest::deserialize(deserializer).map(Request::Heartbeat),
+                (16, 0..=2) => {
+                    ListGroupsRequest::deserialize(deserializer).map(Request::ListGroups)
+                }
+                (api_key, api_version) => Err(de::Error::custom(format!(
+                    "unsupported version {} of API {}",
+                    api_version, api_key
+                ))),
+            }
+        }
+    }
+
+    #[test]
+    fn test_tagged() {
+        let request = Tagged {
+            discriminant: Api {
+                key: 12,
+                version: 1,
+            },
+            value: Request::Heartbeat(HeartbeatRequest {
+                group_id: "g".into(),
+                generation_id: 3,
+            }),
+        };
+        let data = to_vec(&request).unwrap();
+        assert_eq!(data, [0, 12, 0, 1, 0, 1, b'g', 0, 0, 0, 3]);
+        assert_eq!(from_bytes::<Tagged<Api, Request>>(&data).unwrap(), request);
+
+        let data = [0, 16, 0, 2];
+        let request = from_bytes::<Tagged<Api, Request>>(&data).unwrap();
+        assert_eq!(request.value, Request::ListGroups(ListGroupsRequest {}));
+    }
+
+    #[test]
+    fn test_unknown_discriminant() {
+        let data = [0, 12, 0, 9, 0, 0];
+        let err = from_bytes::<Tagged<Api, Request>>(&data).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Decode { ref path, .. } if path == "value" => {}
+            ref e => panic!("unexpected error {}", e),
+        }
+        match *err.root_cause() {
+            ErrorKind::Custom(ref msg) => assert_eq!(msg, "unsupported version 9 of API 12"),
+            ref e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_variant_seed() {
+        // a struct with a field between the discriminant and the value it selects
+        #[derive(Debug, PartialEq)]
+        struct Frame {
+            api: Api,
+            correlation_id: i32,
+            request: Request,
+        }
+
+        impl<'de> Deserialize<'de> for Frame {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                struct FrameVisitor;
+
+                impl<'de> Visitor<'de> for FrameVisitor {
+                    type Value = Frame;
+
+                    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        fmt.write_str("a request frame")
+                    }
+
+                    fn visit_seq<A>(self, mut seq: A) -> Result<Frame, A::Error>
+                    where
+                        A: SeqAccess<'de>,
+                    {
+                        let missing = || de::Error::custom("missing field");
+                        let api = seq.next_element()?.ok_or_else(missing)?;
+                        let correlation_id = seq.next_element()?.ok_or_else(missing)?;
+                        let request = seq
+                            .next_element_seed(VariantSeed::new(&api))?
+                            .ok_or_else(missing)?;
+                        Ok(Frame {
+                            api,
+                            correlation_id,
+                            request,
+                        })
+                    }
+                }
+
+                deserializer.deserialize_struct(
+                    "Frame",
+                    &["api", "correlation_id", "request"],
+                    FrameVisitor,
+                )
+            }
+        }
+
+        let data = [0, 16, 0, 0, 0, 0, 0, 7];
+        assert_eq!(
+            from_bytes::<Frame>(&data).unwrap(),
+            Frame {
+                api: Api {
+                    key: 16,
+                    version: 0,
+                },
+                correlation_id: 7,
+                request: Request::ListGroups(ListGroupsRequest {}),
+            }
+        );
+    }
+}
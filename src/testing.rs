@@ -0,0 +1,332 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! # Assertions for the tests of message types
+//!
+//! [`assert_roundtrip`] checks that a message encodes into bytes that decode back into it, and
+//! that these bytes are encoded again the same way. The second check catches what comparing
+//! values misses, like fields that are decoded into their default value whatever was encoded.
+//! [`assert_serde_roundtrip`] does the same for types that are serialized with serde.
+//!
+//! Both return the encoded bytes, for tests to compare them with captured ones.
+//!
+//! With the `json` feature, `check_golden_vectors` checks message schemas against golden
+//! vectors: messages encoded by Apache Kafka, e.g. dumped from its own tests, along with the
+//! JSON they decode into. This lets users verify that the schemas of the APIs they depend on
+//! match the brokers they run. Vectors are written in JSON, as an array of
+//! ```json
+//! { "message": "HeartbeatResponse", "version": 1, "hex": "00000000001b",
+//!   "expected": { "ThrottleTimeMs": 0, "ErrorCode": 27 } }
+//! ```
+//! where `expected`, which follows `schema::to_json`, is optional.
+//!
+//! # Examples
+//! ```
+//! use kafka_serde::testing::assert_serde_roundtrip;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct HeartbeatResponse {
+//!     throttle_time_ms: i32,
+//!     error_code: i16,
+//! }
+//!
+//! let response = HeartbeatResponse {
+//!     throttle_time_ms: 0,
+//!     error_code: 27,
+//! };
+//! assert_eq!(assert_serde_roundtrip(&response), [0, 0, 0, 0, 0, 27]);
+//! ```
+use std::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(feature = "json")]
+use crate::capture;
+#[cfg(feature = "json")]
+use crate::error::{ErrorKind, Result};
+use crate::schema::Message;
+#[cfg(feature = "json")]
+use crate::schema::Schema;
+
+/// Encodes `value` in `version`, decodes it back and encodes it again, panicking if the decoded
+/// message or the bytes differ. Returns the encoded bytes
+///
+/// Fields that are not in `version` are not encoded, and must hold their default value for the
+/// decoded message to match.
+#[track_caller]
+pub fn assert_roundtrip<T>(value: &T, version: i16) -> Vec<u8>
+where
+    T: Message + PartialEq + Debug,
+{
+    let schema = T::schema();
+    let encode = |value: &T| {
+        let mut buf = Vec::new();
+        match schema.encode(version, &value.to_value(), &mut buf) {
+            Ok(()) => buf,
+            Err(err) => panic!("failed to encode {} v{}: {}", schema.name, version, err),
+        }
+    };
+    let buf = encode(value);
+    let decoded = match schema.decode(version, &buf).and_then(T::from_value) {
+        Ok(decoded) => decoded,
+        Err(err) => panic!("failed to decode {} v{}: {}", schema.name, version, err),
+    };
+    assert_eq!(
+        &decoded, value,
+        "{} v{} decoded into another message",
+        schema.name, version
+    );
+    assert_eq!(
+        encode(&decoded),
+        buf,
+        "{} v{} was encoded again differently",
+        schema.name,
+        version
+    );
+    buf
+}
+
+/// Serializes `value`, deserializes it back and serializes it again, panicking if the
+/// deserialized value or the bytes differ. Returns the serialized bytes
+#[track_caller]
+pub fn assert_serde_roundtrip<T>(value: &T) -> Vec<u8>
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let buf = match crate::to_vec(value) {
+        Ok(buf) => buf,
+        Err(err) => panic!("failed to serialize {:?}: {}", value, err),
+    };
+    let decoded: T = match crate::from_bytes(&buf) {
+        Ok(decoded) => decoded,
+        Err(err) => panic!("failed to deserialize {:?}: {}", value, err),
+    };
+    assert_eq!(&decoded, value, "deserialized into another value");
+    match crate::to_vec(&decoded) {
+        Ok(again) => assert_eq!(again, buf, "serialized again differently"),
+        Err(err) => panic!("failed to serialize {:?} again: {}", decoded, err),
+    }
+    buf
+}
+
+/// A message encoded by Apache Kafka, and the JSON it decodes into
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenVector {
+    /// The name of the message, like `HeartbeatResponse`
+    pub message: String,
+    /// The version the message is encoded in
+    pub version: i16,
+    /// The encoded message
+    pub bytes: Vec<u8>,
+    /// What the message decodes into, if known
+    pub expected: Option<serde_json::Value>,
+}
+
+#[cfg(feature = "json")]
+impl GoldenVector {
+    /// Reads golden vectors out of a JSON array
+    pub fn parse_all(json: &str) -> Result<Vec<GoldenVector>> {
+        #[derive(serde::Deserialize)]
+        struct Raw {
+            message: String,
+            version: i16,
+            hex: String,
+            expected: Option<serde_json::Value>,
+        }
+
+        let invalid = |what: String| ErrorKind::Custom(format!("invalid golden vectors: {}", what));
+        let vectors: Vec<Raw> =
+            serde_json::from_str(json).map_err(|err| invalid(err.to_string()))?;
+        vectors
+            .into_iter()
+            .map(|raw| {
+                let bytes = capture::decode_hex(raw.hex.as_bytes())
+                    .map_err(|err| invalid(format!("{} v{}: {}", raw.message, raw.version, err)))?;
+                Ok(GoldenVector {
+                    message: raw.message,
+                    version: raw.version,
+                    bytes,
+                    expected: raw.expected,
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that the message decodes with `schema` into the JSON expected, and that it
+    /// encodes back into the same bytes
+    pub fn check(&self, schema: &Schema) -> Result<()> {
+        let failed = |what: String| -> crate::Error {
+            ErrorKind::Custom(format!(
+                "golden vector of {} v{}: {}",
+                self.message, self.version, what
+            ))
+            .into()
+        };
+        let value = schema
+            .decode(self.version, &self.bytes)
+            .map_err(|err| failed(err.to_string()))?;
+        let mut bytes = Vec::with_capacity(self.bytes.len());
+        schema
+            .encode(self.version, &value, &mut bytes)
+            .map_err(|err| failed(err.to_string()))?;
+        if bytes != self.bytes {
+            return Err(failed(format!(
+                "encoded back into {:02x?}, instead of {:02x?}",
+                bytes, self.bytes
+            )));
+        }
+        match self.expected {
+            Some(ref expected) if *expected != serde_json::Value::from(value.clone()) => {
+                Err(failed(format!(
+                    "decoded into {}, instead of {}",
+                    serde_json::Value::from(value),
+                    expected
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Checks the golden vectors of `json` whose message has one of `schemas`, returning how many
+/// were checked. See [`GoldenVector::check`]
+#[cfg(feature = "json")]
+pub fn check_golden_vectors(json: &str, schemas: &[&Schema]) -> Result<usize> {
+    let mut checked = 0;
+    for vector in GoldenVector::parse_all(json)? {
+        if let Some(schema) = schemas.iter().find(|schema| schema.name == vector.message) {
+            vector.check(schema)?;
+            checked += 1;
+        }
+    }
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::OnceLock;
+
+    use super::*;
+    use crate::schema::{take_field, Field, FromValue, KafkaValue, Schema, ToValue, Type};
+    use crate::Result;
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Heartbeat {
+        generation_id: i32,
+        group_instance_id: Option<String>,
+    }
+
+    impl FromValue for Heartbeat {
+        fn from_value(value: KafkaValue) -> Result<Self> {
+            let mut fields = value.into_fields()?;
+            Ok(Self {
+                generation_id: take_field(&mut fields, "GenerationId")?,
+                group_instance_id: take_field(&mut fields, "GroupInstanceId")?,
+            })
+        }
+    }
+
+    impl ToValue for Heartbeat {
+        fn to_value(&self) -> KafkaValue {
+            KafkaValue::Struct(vec![
+                ("GenerationId".to_owned(), self.generation_id.to_value()),
+                (
+                    "GroupInstanceId".to_owned(),
+                    self.group_instance_id.to_value(),
+                ),
+            ])
+        }
+    }
+
+    impl Message for Heartbeat {
+        fn schema() -> &'static Schema {
+            static SCHEMA: OnceLock<Schema> = OnceLock::new();
+            SCHEMA.get_or_init(|| {
+                let fields = vec![
+                    Field::new("GenerationId", Type::Int32),
+                    Field::new("GroupInstanceId", Type::String)
+                        .versions(3..)
+                        .nullable_versions(3..),
+                ];
+                Schema::new("Heartbeat", fields)
+                    .valid_versions(0..=4)
+                    .flexible_versions(4..)
+            })
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let heartbeat = Heartbeat {
+            generation_id: 5,
+            group_instance_id: Some("i".to_owned()),
+        };
+        assert_eq!(assert_roundtrip(&heartbeat, 3), [0, 0, 0, 5, 0, 1, b'i']);
+        assert_eq!(assert_roundtrip(&heartbeat, 4), [0, 0, 0, 5, 2, b'i', 0]);
+
+        #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+        struct Header {
+            correlation_id: i32,
+            client_id: String,
+        }
+        let header = Header {
+            correlation_id: 5,
+            client_id: "i".to_owned(),
+        };
+        assert_eq!(assert_serde_roundtrip(&header), [0, 0, 0, 5, 0, 1, b'i']);
+    }
+
+    #[test]
+    #[should_panic(expected = "Heartbeat v2 decoded into another message")]
+    fn test_field_not_in_version() {
+        let heartbeat = Heartbeat {
+            generation_id: 5,
+            group_instance_id: Some("i".to_owned()),
+        };
+        assert_roundtrip(&heartbeat, 2);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_golden_vectors() {
+        let schema = Heartbeat::schema();
+        let vectors = r#"[
+            { "message": "Heartbeat", "version": 3, "hex": "00000005 0001 69",
+              "expected": { "GenerationId": 5, "GroupInstanceId": "i" } },
+            { "message": "Heartbeat", "version": 4, "hex": "00000005 00 00" },
+            { "message": "Metadata", "version": 0, "hex": "" }
+        ]"#;
+        assert_eq!(check_golden_vectors(vectors, &[schema]).unwrap(), 2);
+
+        let vectors = GoldenVector::parse_all(vectors).unwrap();
+        assert_eq!(vectors[1].bytes, [0, 0, 0, 5, 0, 0]);
+        let mut vector = vectors[0].clone();
+        vector.expected = Some(serde_json::json!({ "GenerationId": 4, "GroupInstanceId": "i" }));
+        let err = vector.check(schema).unwrap_err();
+        assert!(
+            err.to_string().contains("Heartbeat v3: decoded into"),
+            "{}",
+            err
+        );
+        // a trailing byte isn't encoded back
+        vector.bytes.push(0);
+        let err = vector.check(schema).unwrap_err();
+        assert!(err.to_string().contains("encoded back into"), "{}", err);
+
+        for vectors in [
+            r#"[{ "message": "Heartbeat", "version": 3, "hex": "0" }]"#,
+            r#"[{ "message": "Heartbeat", "version": 3, "hex": "zz" }]"#,
+            r#"{}"#,
+        ] {
+            match *GoldenVector::parse_all(vectors).unwrap_err().kind() {
+                ErrorKind::Custom(_) => {}
+                ref e => panic!("unexpected error {}", e),
+            }
+        }
+    }
+}
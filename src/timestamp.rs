@@ -0,0 +1,221 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Date and time fields encoded as kafka timestamps: `i64` milliseconds since the epoch.
+//!
+//! The modules here are meant for `#[serde(with = "...")]`. Kafka uses -1 for timestamps that
+//! are not set: the `_option` modules read it as `None`, and write `None` as -1, while the
+//! others read it as the epoch.
+//!
+//! # Examples
+//! ```
+//! # #[cfg(feature = "chrono")]
+//! # {
+//! use chrono::{DateTime, Utc};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct ListOffsetsPartition {
+//!     partition: i32,
+//!     #[serde(with = "kafka_serde::timestamp::chrono_option")]
+//!     timestamp: Option<DateTime<Utc>>,
+//! }
+//!
+//! let data = [0, 0, 0, 1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+//! let partition: ListOffsetsPartition = kafka_serde::from_bytes(&data).unwrap();
+//! assert_eq!(partition.timestamp, None);
+//! # }
+//! ```
+
+/// The timestamp of records and offsets whose time is not known
+const NO_TIMESTAMP: i64 = -1;
+
+/// `chrono::DateTime<Utc>` fields, reading -1 as the epoch
+#[cfg(feature = "chrono")]
+pub mod chrono {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn from_millis<E: serde::de::Error>(millis: i64) -> Result<DateTime<Utc>, E> {
+        DateTime::from_timestamp_millis(millis)
+            .ok_or_else(|| E::custom(format!("timestamp out of range: {}", millis)))
+    }
+
+    /// Serializes `time` as milliseconds since the epoch
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(time.timestamp_millis())
+    }
+
+    /// Deserializes milliseconds since the epoch, or -1 as the epoch
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        match i64::deserialize(deserializer)? {
+            super::NO_TIMESTAMP => Ok(DateTime::UNIX_EPOCH),
+            millis => from_millis(millis),
+        }
+    }
+}
+
+/// `Option<chrono::DateTime<Utc>>` fields, where -1 is `None`
+#[cfg(feature = "chrono")]
+pub mod chrono_option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Serializes `time` as milliseconds since the epoch, or `None` as -1
+    pub fn serialize<S: Serializer>(
+        time: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(time.map_or(super::NO_TIMESTAMP, |time| time.timestamp_millis()))
+    }
+
+    /// Deserializes milliseconds since the epoch, or -1 as `None`
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        match i64::deserialize(deserializer)? {
+            super::NO_TIMESTAMP => Ok(None),
+            millis => super::chrono::from_millis(millis).map(Some),
+        }
+    }
+}
+
+/// `time::OffsetDateTime` fields, reading -1 as the epoch
+#[cfg(feature = "time")]
+pub mod time {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    pub(super) fn to_millis(time: &OffsetDateTime) -> i64 {
+        // rounds towards the past, like chrono does
+        time.unix_timestamp_nanos().div_euclid(1_000_000) as i64
+    }
+
+    pub(super) fn from_millis<E: serde::de::Error>(millis: i64) -> Result<OffsetDateTime, E> {
+        OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000)
+            .map_err(|_| E::custom(format!("timestamp out of range: {}", millis)))
+    }
+
+    /// Serializes `time` as milliseconds since the epoch
+    pub fn serialize<S: Serializer>(
+        time: &OffsetDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(to_millis(time))
+    }
+
+    /// Deserializes milliseconds since the epoch, or -1 as the epoch
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OffsetDateTime, D::Error> {
+        match i64::deserialize(deserializer)? {
+            super::NO_TIMESTAMP => Ok(OffsetDateTime::UNIX_EPOCH),
+            millis => from_millis(millis),
+        }
+    }
+}
+
+/// `Option<time::OffsetDateTime>` fields, where -1 is `None`
+#[cfg(feature = "time")]
+pub mod time_option {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    /// Serializes `time` as milliseconds since the epoch, or `None` as -1
+    pub fn serialize<S: Serializer>(
+        time: &Option<OffsetDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(
+            time.as_ref()
+                .map_or(super::NO_TIMESTAMP, super::time::to_millis),
+        )
+    }
+
+    /// Deserializes milliseconds since the epoch, or -1 as `None`
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<OffsetDateTime>, D::Error> {
+        match i64::deserialize(deserializer)? {
+            super::NO_TIMESTAMP => Ok(None),
+            millis => super::time::from_millis(millis).map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Serialize};
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono() {
+        use chrono::{DateTime, Utc};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Times {
+            #[serde(with = "super::chrono")]
+            time: DateTime<Utc>,
+            #[serde(with = "super::chrono_option")]
+            maybe: Option<DateTime<Utc>>,
+        }
+
+        let times = Times {
+            time: DateTime::from_timestamp_millis(1_600_000_000_123).unwrap(),
+            maybe: DateTime::from_timestamp_millis(-5),
+        };
+        let data = crate::to_vec(&times).unwrap();
+        assert_eq!(data[..8], 1_600_000_000_123i64.to_be_bytes());
+        assert_eq!(data[8..], (-5i64).to_be_bytes());
+        assert_eq!(crate::from_bytes::<Times>(&data).unwrap(), times);
+
+        let data = [0xff; 16];
+        let times: Times = crate::from_bytes(&data).unwrap();
+        assert_eq!(times.time, DateTime::UNIX_EPOCH);
+        assert_eq!(times.maybe, None);
+        assert_eq!(crate::to_vec(&times).unwrap()[8..], data[8..]);
+
+        assert!(crate::from_bytes::<Times>(&[0x7f; 16]).is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time() {
+        use time::OffsetDateTime;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Times {
+            #[serde(with = "super::time")]
+            time: OffsetDateTime,
+            #[serde(with = "super::time_option")]
+            maybe: Option<OffsetDateTime>,
+        }
+
+        let millis = |millis: i64| {
+            OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000).unwrap()
+        };
+        let times = Times {
+            time: millis(1_600_000_000_123),
+            maybe: Some(millis(-5)),
+        };
+        let data = crate::to_vec(&times).unwrap();
+        assert_eq!(data[..8], 1_600_000_000_123i64.to_be_bytes());
+        assert_eq!(data[8..], (-5i64).to_be_bytes());
+        assert_eq!(crate::from_bytes::<Times>(&data).unwrap(), times);
+
+        let data = [0xff; 16];
+        let times: Times = crate::from_bytes(&data).unwrap();
+        assert_eq!(times.time, OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(times.maybe, None);
+        assert_eq!(crate::to_vec(&times).unwrap()[8..], data[8..]);
+
+        assert!(crate::from_bytes::<Times>(&[0x7f; 16]).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the
+// MIT/Apache-2.0 License, at your convenience
+//
+// This product includes software developed at Datadog (https://www.datadoghq.com/). Copyright 2021
+// Datadog, Inc.
+//! Variable-length zig-zag integers, as used by the v2 record format.
+//!
+//! They follow protobuf's encoding: the value is zig-zag encoded so that small negative numbers
+//! stay small, and then written 7 bits at a time, least significant group first.
+use crate::error::{ErrorKind, Result};
+use std::io;
+
+pub(crate) const MAX_VARINT_LEN: usize = 5;
+pub(crate) const MAX_VARLONG_LEN: usize = 10;
+
+fn encode_unsigned(mut value: u64, buf: &mut [u8; MAX_VARLONG_LEN]) -> usize {
+    let mut len = 0;
+    while value >= 0x80 {
+        buf[len] = (value as u8) | 0x80;
+        value >>= 7;
+        len += 1;
+    }
+    buf[len] = value as u8;
+    len + 1
+}
+
+fn zigzag64(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Writes a zig-zag encoded `varint`
+pub(crate) fn write_varint<W: io::Write>(writer: &mut W, value: i32) -> Result<()> {
+    write_varlong(writer, value as i64)
+}
+
+/// Writes a zig-zag encoded `varlong`
+pub(crate) fn write_varlong<W: io::Write>(writer: &mut W, value: i64) -> Result<()> {
+    let mut buf = [0u8; MAX_VARLONG_LEN];
+    let len = encode_unsigned(zigzag64(value), &mut buf);
+    writer.write_all(&buf[..len])?;
+    Ok(())
+}
+
+/// Writes an `UNSIGNED_VARINT`, as used by the flexible versions of the protocol, which isn't
+/// zig-zag encoded
+pub(crate) fn write_unsigned_varint<W: io::Write>(writer: &mut W, value: u32) -> Result<()> {
+    let mut buf = [0u8; MAX_VARLONG_LEN];
+    let len = encode_unsigned(value.into(), &mut buf);
+    writer.write_all(&buf[..len])?;
+    Ok(())
+}
+
+/// How many bytes `value` takes once encoded as a `varint`
+pub(crate) fn varint_size(value: i32) -> usize {
+    varlong_size(value as i64)
+}
+
+/// How many bytes `value` takes once encoded as a `varlong`
+pub(crate) fn varlong_size(value: i64) -> usize {
+    let mut value = zigzag64(value);
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+/// Reads a zig-zag encoded `varint` from a stream
+pub(crate) fn read_varint<R: io::Read>(reader: &mut R) -> Result<i32> {
+    let mut value = 0u64;
+    let mut byte = [0u8; 1];
+    for i in 0..MAX_VARINT_LEN {
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            if value > u32::MAX as u64 {
+                break;
+            }
+            return Ok(unzigzag(value) as i32);
+        }
+    }
+    Err(ErrorKind::InvalidVarint.into())
+}
+
+/// Reverses the zig-zag encoding of a decoded unsigned value
+pub(crate) fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode(value: i64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_varlong(&mut buf, value).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_unsigned_varint() {
+        let mut buf = Vec::new();
+        write_unsigned_varint(&mut buf, 1).unwrap();
+        write_unsigned_varint(&mut buf, 300).unwrap();
+        assert_eq!(buf, [0x01, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_small_values() {
+        assert_eq!(encode(0), [0x00]);
+        assert_eq!(encode(-1), [0x01]);
+        assert_eq!(encode(1), [0x02]);
+        assert_eq!(encode(-64), [0x7f]);
+        assert_eq!(encode(64), [0x80, 0x01]);
+    }
+
+    #[test]
+    fn test_sizes() {
+        for v in &[
+            0i64,
+            1,
+            -1,
+            63,
+            64,
+            -65,
+            8191,
+            8192,
+            i32::MAX as i64,
+            i64::MIN,
+        ] {
+            assert_eq!(encode(*v).len(), varlong_size(*v));
+        }
+        assert_eq!(varint_size(i32::MIN), MAX_VARINT_LEN);
+        assert_eq!(varlong_size(i64::MIN), MAX_VARLONG_LEN);
+    }
+
+    #[test]
+    fn test_varint_matches_varlong() {
+        for v in &[0i32, -1, 300, i32::MIN, i32::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, *v).unwrap();
+            assert_eq!(buf, encode(*v as i64));
+        }
+    }
+
+    #[test]
+    fn test_read_varint() {
+        let mut buf = Vec::new();
+        for v in &[0, -1, 64, i32::MAX, i32::MIN] {
+            write_varint(&mut buf, *v).unwrap();
+        }
+        let mut reader = &buf[..];
+        for v in &[0, -1, 64, i32::MAX, i32::MIN] {
+            assert_eq!(read_varint(&mut reader).unwrap(), *v);
+        }
+        assert!(read_varint(&mut reader).is_err());
+        let mut too_long = &[0xffu8, 0xff, 0xff, 0xff, 0xff, 0x01][..];
+        assert!(read_varint(&mut too_long).is_err());
+    }
+
+    #[test]
+    fn test_unzigzag() {
+        for v in &[0i64, 1, -1, 12345, -12345, i64::MAX, i64::MIN] {
+            assert_eq!(unzigzag(zigzag64(*v)), *v);
+        }
+    }
+}